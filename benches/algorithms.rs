@@ -0,0 +1,88 @@
+//! Signing and verification with every algorithm. The RSA and elliptic curve
+//! algorithms need the `openssl` feature.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use jwt::algorithm::any::{AnySigner, AnyVerifier};
+use jwt::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+
+const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+const CLAIMS: &str = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+const SECRET: &[u8] = b"a key that is at least sixty-four bytes long for hmac with sha512";
+
+fn keys() -> Vec<(AlgorithmType, AnySigner, AnyVerifier)> {
+    let mut keys = Vec::new();
+    for algorithm in [
+        AlgorithmType::Hs256,
+        AlgorithmType::Hs384,
+        AlgorithmType::Hs512,
+    ] {
+        keys.push((
+            algorithm,
+            AnySigner::hmac(algorithm, SECRET).unwrap(),
+            AnyVerifier::hmac(algorithm, SECRET).unwrap(),
+        ));
+    }
+
+    #[cfg(feature = "openssl")]
+    {
+        use jwt::PKeyWithDigest;
+        use openssl::ec::{EcGroup, EcKey};
+        use openssl::nid::Nid;
+        use openssl::pkey::PKey;
+        use openssl::rsa::Rsa;
+
+        let rsa = Rsa::generate(2048).unwrap();
+        for algorithm in [
+            AlgorithmType::Rs256,
+            AlgorithmType::Rs384,
+            AlgorithmType::Rs512,
+        ] {
+            let private = PKey::from_rsa(rsa.clone()).unwrap();
+            let public = PKey::public_key_from_pem(&private.public_key_to_pem().unwrap()).unwrap();
+            keys.push((
+                algorithm,
+                AnySigner::new(PKeyWithDigest::new(algorithm, private).unwrap()),
+                AnyVerifier::new(PKeyWithDigest::new(algorithm, public).unwrap()),
+            ));
+        }
+
+        for (algorithm, curve) in [
+            (AlgorithmType::Es256, Nid::X9_62_PRIME256V1),
+            (AlgorithmType::Es384, Nid::SECP384R1),
+            (AlgorithmType::Es512, Nid::SECP521R1),
+        ] {
+            let group = EcGroup::from_curve_name(curve).unwrap();
+            let private = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+            let public = PKey::public_key_from_pem(&private.public_key_to_pem().unwrap()).unwrap();
+            keys.push((
+                algorithm,
+                AnySigner::new(PKeyWithDigest::new(algorithm, private).unwrap()),
+                AnyVerifier::new(PKeyWithDigest::new(algorithm, public).unwrap()),
+            ));
+        }
+    }
+
+    keys
+}
+
+fn algorithms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("algorithms");
+    for (algorithm, signer, verifier) in keys() {
+        group.bench_function(format!("{} sign", algorithm), |b| {
+            b.iter(|| signer.sign(black_box(HEADER), black_box(CLAIMS)).unwrap())
+        });
+
+        let signature = signer.sign(HEADER, CLAIMS).unwrap();
+        group.bench_function(format!("{} verify", algorithm), |b| {
+            b.iter(|| {
+                verifier
+                    .verify(black_box(HEADER), black_box(CLAIMS), &signature)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, algorithms);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+//! Compares signing with a keyed `Hmac`, which is cloned for every token,
+//! with creating the `Hmac` from the key material for every token. Cloning
+//! copies the hash states of the padded key without allocating, and is faster
+//! than hashing the key again.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hmac::digest::{CtOutput, KeyInit};
+use hmac::{Hmac, Mac};
+use jwt::{SigningAlgorithm, VerifyingAlgorithm};
+use sha2::{Sha256, Sha512};
+
+const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+const CLAIMS: &str = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+const KEY: &[u8] = b"a key that is at least sixty-four bytes long for hmac with sha512";
+
+fn from_key<M: Mac + KeyInit>(key: &[u8]) -> CtOutput<M> {
+    let mut hmac = <M as KeyInit>::new_from_slice(key).unwrap();
+    hmac.update(HEADER.as_bytes());
+    hmac.update(b".");
+    hmac.update(CLAIMS.as_bytes());
+    hmac.finalize()
+}
+
+fn sign(c: &mut Criterion) {
+    let hs256: Hmac<Sha256> = Hmac::new_from_slice(KEY).unwrap();
+    let hs512: Hmac<Sha512> = Hmac::new_from_slice(KEY).unwrap();
+
+    let mut group = c.benchmark_group("hmac");
+    group.bench_function("hs256 sign", |b| {
+        b.iter(|| hs256.sign(black_box(HEADER), black_box(CLAIMS)).unwrap())
+    });
+    group.bench_function("hs256 sign from key", |b| {
+        b.iter(|| from_key::<Hmac<Sha256>>(black_box(KEY)))
+    });
+    group.bench_function("hs512 sign", |b| {
+        b.iter(|| hs512.sign(black_box(HEADER), black_box(CLAIMS)).unwrap())
+    });
+    group.bench_function("hs512 sign from key", |b| {
+        b.iter(|| from_key::<Hmac<Sha512>>(black_box(KEY)))
+    });
+
+    let signature = hs256.sign(HEADER, CLAIMS).unwrap();
+    group.bench_function("hs256 verify", |b| {
+        b.iter(|| {
+            VerifyingAlgorithm::verify(&hs256, black_box(HEADER), black_box(CLAIMS), &signature)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sign);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! Validation of the registered claims of verified tokens.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use jwt::validation::Validation;
+use jwt::RegisteredClaims;
+use serde_json::json;
+
+fn validation(c: &mut Criterion) {
+    let claims = json!({
+        "iss": "https://idp.example.com",
+        "sub": "someone",
+        "aud": ["billing", "orders"],
+        "exp": 2_000_000_000u64,
+        "nbf": 1_000_000_000u64,
+    });
+    let validation = Validation::new()
+        .issuer("https://idp.example.com")
+        .audience("orders")
+        .leeway(30)
+        .require_expiration(true);
+
+    let mut group = c.benchmark_group("validation");
+    group.bench_function("validate", |b| {
+        b.iter(|| {
+            validation
+                .validate(black_box(&claims), 1_500_000_000)
+                .unwrap()
+        })
+    });
+
+    let registered = RegisteredClaims {
+        expiration: Some(2_000_000_000),
+        not_before: Some(1_000_000_000),
+        ..Default::default()
+    };
+    group.bench_function("check time", |b| {
+        b.iter(|| black_box(&registered).check_time(1_500_000_000).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, validation);
+criterion_main!(benches);
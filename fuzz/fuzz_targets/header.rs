@@ -0,0 +1,13 @@
+//! Arbitrary encoded headers, the first part of a token that is decoded.
+
+#![no_main]
+
+use jwt::{FromBase64, Header, JoseHeader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = Header::from_base64(data) {
+        let _ = header.algorithm_type();
+        let _ = header.key_id();
+    }
+});
@@ -0,0 +1,23 @@
+//! Arbitrary token strings through every way of parsing them.
+
+#![no_main]
+
+use jwt::token::raw::{DecodeBuffers, RawToken};
+use jwt::{Claims, Header, Token};
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let token = match std::str::from_utf8(data) {
+        Ok(token) => token,
+        Err(_) => return,
+    };
+
+    let _ = RawToken::parse(token);
+    let _: Result<Token<Header, Claims, _>, _> = Token::parse_unverified(token);
+    let _: Result<Token<Header, Value, _>, _> = Token::parse_unverified(token);
+
+    let mut buffers = DecodeBuffers::new();
+    let _: Result<Token<Header, Value, _>, _> =
+        Token::parse_unverified_borrowed(token, &mut buffers);
+});
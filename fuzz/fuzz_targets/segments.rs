@@ -0,0 +1,30 @@
+//! Arbitrary bytes through the base64 and JSON layers of a segment: the size
+//! limits, the structural JSON checks and decoding.
+
+#![no_main]
+
+use jwt::token::limits::Limits;
+use jwt::token::raw::{Base64Mode, Component, RawToken};
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Limits::default().check_json(data);
+
+    let claims = match std::str::from_utf8(data) {
+        Ok(claims) => claims,
+        Err(_) => return,
+    };
+    let raw = RawToken {
+        header: "eyJhbGciOiJIUzI1NiJ9",
+        claims,
+        signature: "",
+    };
+    let lenient = Limits {
+        base64_mode: Base64Mode::Lenient,
+        ..Default::default()
+    };
+    let _ = raw.decode(Component::Claims);
+    let _: Result<Value, _> = raw.deserialize(Component::Claims);
+    let _: Result<Value, _> = raw.deserialize_with_limits(Component::Claims, &lenient);
+});
@@ -0,0 +1,22 @@
+//! Arbitrary token strings verified with a key, including tokens that name
+//! other algorithms or no algorithm at all.
+
+#![no_main]
+
+use hmac::{Hmac, Mac};
+use jwt::{Header, Token, VerifyWithKey};
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+use sha2::Sha256;
+
+fuzz_target!(|data: &[u8]| {
+    let token = match std::str::from_utf8(data) {
+        Ok(token) => token,
+        Err(_) => return,
+    };
+
+    let key: Hmac<Sha256> = Hmac::new_from_slice(b"fuzzing secret").unwrap();
+    let verified: Result<Token<Header, Value, _>, _> = token.verify_with_key(&key);
+    // Forging a signature by chance is not expected
+    assert!(verified.is_err());
+});
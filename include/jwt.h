@@ -0,0 +1,56 @@
+/* C interface of the jwt crate, built with the `ffi` feature. */
+
+#ifndef JWT_H
+#define JWT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum JwtStatus {
+    JWT_OK = 0,
+    JWT_NULL_ARGUMENT = 1,
+    JWT_INVALID_UTF8 = 2,
+    JWT_ALGORITHM_MISMATCH = 3,
+    JWT_UNSUPPORTED_ALGORITHM = 4,
+    JWT_INVALID_SIGNATURE = 5,
+    JWT_EXPIRED = 6,
+    JWT_NOT_YET_VALID = 7,
+    JWT_MALFORMED_TOKEN = 8,
+    JWT_INVALID_KEY = 9,
+    JWT_OTHER = 10,
+} JwtStatus;
+
+/*
+ * Every function writes a string to `out`: the result on JWT_OK, otherwise a
+ * description of the error. It has to be freed with jwt_string_free.
+ */
+
+/* Sign the JSON claims with an HMAC key, e.g. with algorithm "HS256". */
+JwtStatus jwt_sign(const char *algorithm,
+                   const uint8_t *key,
+                   size_t key_len,
+                   const char *claims_json,
+                   char **out);
+
+/* Verify a token with an HMAC key and check its exp and nbf claims. */
+JwtStatus jwt_verify(const char *algorithm,
+                     const uint8_t *key,
+                     size_t key_len,
+                     const char *token,
+                     char **out);
+
+/* Decode the claims of a token WITHOUT verifying the signature. */
+JwtStatus jwt_decode_claims_json(const char *token, char **out);
+
+/* Free a string returned by this library. Null is ignored. */
+void jwt_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* JWT_H */
@@ -0,0 +1,360 @@
+//! Integration with [actix-web](https://docs.rs/actix-web), enabled with the
+//! `actix-web` feature. [JwtAuth](struct.JwtAuth.html) is a middleware that
+//! rejects requests without a valid bearer token, and
+//! [Claims](struct.Claims.html) extracts the verified claims in handlers.
+//! The [BearerAuth](../bearer/struct.BearerAuth.html) is taken from the
+//! middleware, or from `web::Data` for routes without it.
+//! ## Examples
+//! ```
+//! use actix_web::{web, App};
+//! use jwt::actix::{Claims, JwtAuth};
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! async fn whoami(Claims(claims): Claims<RegisteredClaims>) -> String {
+//!     claims.subject.unwrap_or_default()
+//! }
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let app = App::new()
+//!     .wrap(JwtAuth::new(auth).skip_path("/health"))
+//!     .route("/health", web::get().to(|| async { "ok" }))
+//!     .route("/whoami", web::get().to(whoami));
+//! ```
+
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{ContentType, HeaderMap, HeaderValue, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::error::Error;
+use crate::policy::Policy;
+use crate::source::RequestParts;
+
+/// Extracts the verified claims of the bearer token.
+#[derive(Clone, Debug)]
+pub struct Claims<T = crate::Claims>(pub T);
+
+/// The claims verified by the middleware, kept in the request extensions.
+#[derive(Clone)]
+struct VerifiedClaims(Value);
+
+impl<T: DeserializeOwned> FromRequest for Claims<T> {
+    type Error = Rejection;
+    type Future = Ready<Result<Self, Rejection>>;
+
+    fn from_request(request: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let verified = request.extensions().get::<VerifiedClaims>().cloned();
+        let claims = match verified {
+            Some(VerifiedClaims(claims)) => {
+                serde_json::from_value(claims).map_err(|e| Rejection::InvalidToken(Error::Json(e)))
+            }
+            None => match request.app_data::<web::Data<BearerAuth>>() {
+                Some(auth) => auth
+                    .authenticate_request(&ActixParts(request.headers(), request.query_string())),
+                None => Err(Rejection::Unconfigured),
+            },
+        };
+        ready(claims.map(Claims))
+    }
+}
+
+impl ResponseError for Rejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = match self.body() {
+            Some(body) => HttpResponse::build(self.status_code())
+                .content_type(ContentType::json())
+                .body(body),
+            None => HttpResponse::new(self.status_code()),
+        };
+        if let Some(challenge) = self.www_authenticate() {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                response.headers_mut().insert(WWW_AUTHENTICATE, value);
+            }
+        }
+        response
+    }
+}
+
+type ErrorHandler = Arc<dyn Fn(&Rejection) -> HttpResponse>;
+
+/// A middleware that verifies the bearer token of every request, except for
+/// the skipped paths.
+#[derive(Clone)]
+pub struct JwtAuth {
+    auth: BearerAuth,
+    skip_paths: Vec<String>,
+    policy: Option<Arc<Policy>>,
+    error_handler: Option<ErrorHandler>,
+}
+
+impl JwtAuth {
+    pub fn new(auth: BearerAuth) -> Self {
+        JwtAuth {
+            auth,
+            skip_paths: Vec::new(),
+            policy: None,
+            error_handler: None,
+        }
+    }
+
+    /// Don't authenticate requests for the path and the paths below it.
+    pub fn skip_path(mut self, path: impl Into<String>) -> Self {
+        self.skip_paths
+            .push(path.into().trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Reject requests whose claims don't satisfy the
+    /// [policy](../policy/index.html) with `403 Forbidden`, e.g. on a
+    /// `web::resource` or `web::scope` to authorize its routes.
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Respond to rejected requests with a custom response, instead of an
+    /// empty one with the status and `WWW-Authenticate` header of the
+    /// rejection.
+    pub fn error_handler(mut self, handler: impl Fn(&Rejection) -> HttpResponse + 'static) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    fn skips(&self, path: &str) -> bool {
+        self.skip_paths.iter().any(|skip| {
+            path.strip_prefix(skip.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for JwtAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = JwtAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, ()>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuthMiddleware {
+            service: Rc::new(service),
+            config: self.clone(),
+        }))
+    }
+}
+
+/// The service created by [JwtAuth](struct.JwtAuth.html).
+pub struct JwtAuthMiddleware<S> {
+    service: Rc<S>,
+    config: JwtAuth,
+}
+
+impl<S, B> Service<ServiceRequest> for JwtAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        if !self.config.skips(request.path()) {
+            let parts = ActixParts(request.headers(), request.query_string());
+            let verified = match self.config.policy {
+                Some(ref policy) => self.config.auth.authorize_request::<Value>(&parts, policy),
+                None => self.config.auth.authenticate_request::<Value>(&parts),
+            };
+            match verified {
+                Ok(claims) => {
+                    request.extensions_mut().insert(VerifiedClaims(claims));
+                }
+                Err(rejection) => {
+                    let response = match self.config.error_handler {
+                        Some(ref handler) => handler(&rejection),
+                        None => rejection.error_response(),
+                    };
+                    let response = request.into_response(response).map_into_right_body();
+                    return Box::pin(ready(Ok(response)));
+                }
+            }
+        }
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move { Ok(service.call(request).await?.map_into_left_body()) })
+    }
+}
+
+struct ActixParts<'a>(&'a HeaderMap, &'a str);
+
+impl RequestParts for ActixParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        Some(self.1).filter(|query| !query.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use actix_web::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    use crate::actix::{Claims, JwtAuth};
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    async fn whoami(Claims(claims): Claims<RegisteredClaims>) -> String {
+        claims.subject.unwrap_or_default()
+    }
+
+    #[test]
+    pub fn middleware() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+
+        block_on(async {
+            let app = init_service(
+                App::new()
+                    .wrap(
+                        JwtAuth::new(auth)
+                            .skip_path("/health/")
+                            .error_handler(|_| HttpResponse::Forbidden().body("go away")),
+                    )
+                    .route("/health", web::get().to(|| async { "ok" }))
+                    .route("/whoami", web::get().to(whoami)),
+            )
+            .await;
+
+            let request = TestRequest::get()
+                .uri("/whoami")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request();
+            let response = call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(read_body(response).await, "someone");
+
+            let request = TestRequest::get().uri("/whoami").to_request();
+            let response = call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(read_body(response).await, "go away");
+
+            let request = TestRequest::get().uri("/health").to_request();
+            assert_eq!(call_service(&app, request).await.status(), StatusCode::OK);
+        });
+        Ok(())
+    }
+
+    #[test]
+    pub fn extractor_without_middleware() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+
+        block_on(async {
+            let app = init_service(
+                App::new()
+                    .app_data(web::Data::new(auth))
+                    .route("/whoami", web::get().to(whoami)),
+            )
+            .await;
+
+            let request = TestRequest::get().uri("/whoami").to_request();
+            let response = call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers().get(WWW_AUTHENTICATE).unwrap(), "Bearer");
+        });
+        Ok(())
+    }
+
+    #[test]
+    pub fn policy() -> Result<(), Error> {
+        use crate::policy::require_scope;
+        use serde_json::{json, Value};
+
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let reader = json!({"sub": "someone", "scope": "orders:read"}).sign_with_key(&signer)?;
+
+        block_on(async {
+            let app = init_service(
+                App::new()
+                    .service(
+                        web::resource("/orders")
+                            .wrap(JwtAuth::new(auth.clone()).policy(require_scope("orders:write")))
+                            .route(web::post().to(whoami)),
+                    )
+                    .service(
+                        web::resource("/whoami")
+                            .wrap(JwtAuth::new(auth).policy(require_scope("orders:read")))
+                            .route(web::get().to(whoami)),
+                    ),
+            )
+            .await;
+
+            let request = TestRequest::get()
+                .uri("/whoami")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", reader)))
+                .to_request();
+            assert_eq!(call_service(&app, request).await.status(), StatusCode::OK);
+
+            let request = TestRequest::post()
+                .uri("/orders")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", reader)))
+                .to_request();
+            let response = call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let body: Value = serde_json::from_slice(&read_body(response).await).unwrap();
+            assert_eq!(body["required"], json!({"scope": "orders:write"}));
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+//! Type erased algorithms, for holding keys of different algorithms in the
+//! same place, e.g. when the algorithm is only known from configuration at
+//! runtime.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::AlgorithmType;
+//!
+//! let verifiers = vec![
+//!     AnyVerifier::hmac(AlgorithmType::Hs256, b"first").unwrap(),
+//!     AnyVerifier::hmac(AlgorithmType::Hs512, b"second").unwrap(),
+//! ];
+//! ```
+
+use alloc::boxed::Box;
+#[cfg(feature = "hmac")]
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+#[cfg(feature = "hmac")]
+use hmac::{Hmac, KeyInit};
+#[cfg(feature = "hmac")]
+use sha2::{Sha256, Sha384, Sha512};
+
+#[cfg(feature = "hmac")]
+use crate::algorithm::rust_crypto::{check_hmac_key_length, KeyLengthPolicy};
+#[cfg(feature = "hmac")]
+use crate::algorithm::secret::{SecretBytes, SecretHmac};
+#[cfg(feature = "hmac")]
+use crate::algorithm::AlgorithmType;
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+#[cfg(any(feature = "hmac", feature = "openssl"))]
+use crate::jwk::KeyType;
+use crate::jwk::{Jwk, KeyOperation, KeyUsePolicy};
+
+/// A signing algorithm of any type.
+pub struct AnySigner(Box<dyn SigningAlgorithm + Send + Sync>);
+
+/// A verifying algorithm of any type.
+pub struct AnyVerifier(Box<dyn VerifyingAlgorithm + Send + Sync>);
+
+impl AnySigner {
+    pub fn new(algorithm: impl SigningAlgorithm + Send + Sync + 'static) -> Self {
+        AnySigner(Box::new(algorithm))
+    }
+
+    /// Create an HMAC signer for one of the `HS*` algorithms. The key-derived
+    /// state is wiped when the signer is dropped, but not the stack copy of
+    /// the padded secret, see [secret](../secret/index.html).
+    #[cfg(feature = "hmac")]
+    pub fn hmac(algorithm_type: AlgorithmType, secret: &[u8]) -> Result<Self, Error> {
+        AnySigner::hmac_with_policy(algorithm_type, secret, KeyLengthPolicy::Allow)
+    }
+
+    /// Create an HMAC signer that keeps the secret wrapped, see
+    /// [SecretHmac](../secret/struct.SecretHmac.html).
+    #[cfg(feature = "hmac")]
+    pub fn hmac_secret(
+        algorithm_type: AlgorithmType,
+        secret: impl Into<SecretBytes>,
+    ) -> Result<Self, Error> {
+        Ok(AnySigner::new(SecretHmac::new(algorithm_type, secret)?))
+    }
+
+    /// Create an HMAC signer, checking the length of the secret.
+    #[cfg(feature = "hmac")]
+    pub fn hmac_with_policy(
+        algorithm_type: AlgorithmType,
+        secret: &[u8],
+        policy: KeyLengthPolicy,
+    ) -> Result<Self, Error> {
+        check_hmac_key_length(algorithm_type, secret, policy)?;
+        let signer = match algorithm_type {
+            AlgorithmType::Hs256 => AnySigner::new(Hmac::<Sha256>::new_from_slice(secret)?),
+            AlgorithmType::Hs384 => AnySigner::new(Hmac::<Sha384>::new_from_slice(secret)?),
+            AlgorithmType::Hs512 => AnySigner::new(Hmac::<Sha512>::new_from_slice(secret)?),
+            other => return Err(Error::UnknownAlgorithmType(other.to_string())),
+        };
+
+        Ok(signer)
+    }
+
+    /// Create a signer from a JSON Web Key. Only symmetric keys are supported,
+    /// since the private parts of asymmetric keys are usually not published
+    /// as JWKs. Keys whose `use` or `key_ops` forbid signing are rejected.
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+        AnySigner::from_jwk_with_policy(jwk, KeyUsePolicy::Enforce)
+    }
+
+    /// Create a signer from a JSON Web Key, honoring or ignoring its `use`
+    /// and `key_ops`.
+    pub fn from_jwk_with_policy(jwk: &Jwk, policy: KeyUsePolicy) -> Result<Self, Error> {
+        jwk.check_operation(KeyOperation::Sign, policy)?;
+        match jwk.key_type {
+            #[cfg(feature = "hmac")]
+            KeyType::Octet => {
+                let secret = jwk.secret()?;
+                AnySigner::hmac(jwk.algorithm_type()?, &secret)
+            }
+            _ => Err(Error::InvalidJwk("unsupported key type")),
+        }
+    }
+}
+
+impl AnyVerifier {
+    pub fn new(algorithm: impl VerifyingAlgorithm + Send + Sync + 'static) -> Self {
+        AnyVerifier(Box::new(algorithm))
+    }
+
+    /// Create an HMAC verifier for one of the `HS*` algorithms. The key-derived
+    /// state is wiped when the verifier is dropped, but not the stack copy of
+    /// the padded secret, see [secret](../secret/index.html).
+    #[cfg(feature = "hmac")]
+    pub fn hmac(algorithm_type: AlgorithmType, secret: &[u8]) -> Result<Self, Error> {
+        AnyVerifier::hmac_with_policy(algorithm_type, secret, KeyLengthPolicy::Allow)
+    }
+
+    /// Create an HMAC verifier that keeps the secret wrapped, see
+    /// [SecretHmac](../secret/struct.SecretHmac.html).
+    #[cfg(feature = "hmac")]
+    pub fn hmac_secret(
+        algorithm_type: AlgorithmType,
+        secret: impl Into<SecretBytes>,
+    ) -> Result<Self, Error> {
+        Ok(AnyVerifier::new(SecretHmac::new(algorithm_type, secret)?))
+    }
+
+    /// Create an HMAC verifier, checking the length of the secret.
+    #[cfg(feature = "hmac")]
+    pub fn hmac_with_policy(
+        algorithm_type: AlgorithmType,
+        secret: &[u8],
+        policy: KeyLengthPolicy,
+    ) -> Result<Self, Error> {
+        check_hmac_key_length(algorithm_type, secret, policy)?;
+        let verifier = match algorithm_type {
+            AlgorithmType::Hs256 => AnyVerifier::new(Hmac::<Sha256>::new_from_slice(secret)?),
+            AlgorithmType::Hs384 => AnyVerifier::new(Hmac::<Sha384>::new_from_slice(secret)?),
+            AlgorithmType::Hs512 => AnyVerifier::new(Hmac::<Sha512>::new_from_slice(secret)?),
+            other => return Err(Error::UnknownAlgorithmType(other.to_string())),
+        };
+
+        Ok(verifier)
+    }
+
+    /// Create a verifier from a JSON Web Key. RSA and elliptic curve keys
+    /// require the `openssl` feature. Keys whose `use` or `key_ops` forbid
+    /// verifying, e.g. encryption keys, are rejected.
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+        AnyVerifier::from_jwk_with_policy(jwk, KeyUsePolicy::Enforce)
+    }
+
+    /// Create a verifier from a JSON Web Key, honoring or ignoring its `use`
+    /// and `key_ops`.
+    pub fn from_jwk_with_policy(jwk: &Jwk, policy: KeyUsePolicy) -> Result<Self, Error> {
+        jwk.check_operation(KeyOperation::Verify, policy)?;
+        match jwk.key_type {
+            #[cfg(feature = "hmac")]
+            KeyType::Octet => {
+                let secret = jwk.secret()?;
+                AnyVerifier::hmac(jwk.algorithm_type()?, &secret)
+            }
+            #[cfg(feature = "openssl")]
+            KeyType::Rsa | KeyType::EllipticCurve => Ok(AnyVerifier::new(
+                crate::algorithm::openssl::PKeyWithDigest::from_jwk_with_policy(
+                    jwk,
+                    KeyUsePolicy::Ignore,
+                )?,
+            )),
+            _ => Err(Error::InvalidJwk("unsupported key type")),
+        }
+    }
+}
+
+/// A verifying algorithm that is cheap to clone and can be shared between
+/// threads, e.g. as part of the application state of a web framework.
+#[derive(Clone)]
+pub struct SharedVerifier(Arc<dyn VerifyingAlgorithm + Send + Sync>);
+
+impl SharedVerifier {
+    pub fn new(algorithm: impl VerifyingAlgorithm + Send + Sync + 'static) -> Self {
+        SharedVerifier(Arc::new(algorithm))
+    }
+}
+
+impl From<AnyVerifier> for SharedVerifier {
+    fn from(verifier: AnyVerifier) -> Self {
+        SharedVerifier(Arc::from(verifier.0))
+    }
+}
+
+impl AsRef<dyn SigningAlgorithm> for AnySigner {
+    fn as_ref(&self) -> &(dyn SigningAlgorithm + 'static) {
+        &*self.0
+    }
+}
+
+impl AsRef<dyn VerifyingAlgorithm> for AnyVerifier {
+    fn as_ref(&self) -> &(dyn VerifyingAlgorithm + 'static) {
+        &*self.0
+    }
+}
+
+impl AsRef<dyn VerifyingAlgorithm> for SharedVerifier {
+    fn as_ref(&self) -> &(dyn VerifyingAlgorithm + 'static) {
+        &*self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    use hmac::Hmac;
+    use sha2::{Sha256, Sha384, Sha512};
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier, SharedVerifier};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+    use crate::error::Error;
+    use crate::jwk::{Jwk, KeyOperation, KeyUsePolicy};
+    use crate::token::signed::SignWithStore;
+    use crate::token::verified::VerifyWithStore;
+
+    #[test]
+    pub fn heterogeneous_store() -> Result<(), Error> {
+        let mut signers = BTreeMap::new();
+        signers.insert("first", AnySigner::hmac(AlgorithmType::Hs256, b"first")?);
+        signers.insert("second", AnySigner::hmac(AlgorithmType::Hs512, b"second")?);
+
+        let mut verifiers = BTreeMap::new();
+        verifiers.insert("first", AnyVerifier::hmac(AlgorithmType::Hs256, b"first")?);
+        verifiers.insert(
+            "second",
+            AnyVerifier::hmac(AlgorithmType::Hs512, b"second")?,
+        );
+
+        for key_id in ["first", "second"] {
+            let mut claims = BTreeMap::new();
+            claims.insert("sub", "someone");
+            let token = (key_id, claims).sign_with_store(&signers)?;
+
+            let claims: BTreeMap<String, String> = token.as_str().verify_with_store(&verifiers)?;
+            assert_eq!(claims["sub"], "someone");
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn from_jwk() -> Result<(), Error> {
+        let jwk: Jwk = serde_json::from_str(r#"{"kty":"oct","alg":"HS256","k":"c2VjcmV0"}"#)?;
+        let signer = AnySigner::from_jwk(&jwk)?;
+        let verifier = AnyVerifier::from_jwk(&jwk)?;
+
+        assert_eq!(signer.algorithm_type(), AlgorithmType::Hs256);
+        let signature = signer.sign("header", "claims")?;
+        assert!(verifier.verify("header", "claims", &signature)?);
+
+        let no_alg: Jwk = serde_json::from_str(r#"{"kty":"oct","k":"c2VjcmV0"}"#)?;
+        assert!(AnyVerifier::from_jwk(&no_alg).is_err());
+
+        let verify_only: Jwk = serde_json::from_str(
+            r#"{"kty":"oct","alg":"HS256","key_ops":["verify"],"k":"c2VjcmV0"}"#,
+        )?;
+        AnyVerifier::from_jwk(&verify_only)?;
+        assert!(matches!(
+            AnySigner::from_jwk(&verify_only),
+            Err(Error::KeyOperationNotPermitted(KeyOperation::Sign))
+        ));
+        AnySigner::from_jwk_with_policy(&verify_only, KeyUsePolicy::Ignore)?;
+
+        let encryption: Jwk =
+            serde_json::from_str(r#"{"kty":"oct","alg":"HS256","use":"enc","k":"c2VjcmV0"}"#)?;
+        assert!(matches!(
+            AnyVerifier::from_jwk(&encryption),
+            Err(Error::KeyOperationNotPermitted(KeyOperation::Verify))
+        ));
+        Ok(())
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    pub fn send_sync() {
+        assert_send_sync::<Hmac<Sha256>>();
+        assert_send_sync::<Hmac<Sha384>>();
+        assert_send_sync::<Hmac<Sha512>>();
+        assert_send_sync::<AnySigner>();
+        assert_send_sync::<AnyVerifier>();
+        assert_send_sync::<SharedVerifier>();
+        assert_send_sync::<Error>();
+    }
+
+    #[test]
+    pub fn shared_between_threads() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let signature = signer.sign("header", "claims")?;
+        let verifier = SharedVerifier::from(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let verifier = verifier.clone();
+                let signature = signature.clone();
+                thread::spawn(move || verifier.verify("header", "claims", &signature))
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap()?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+//! Asynchronous counterparts of the signing and verifying algorithms, for keys
+//! that live in a remote service like a KMS or an HSM. Every synchronous
+//! algorithm can also be used as an asynchronous one.
+//!
+//! Only `std::future` is used, so the traits do not depend on any runtime and
+//! work the same with tokio, async-std or any other executor. The returned
+//! futures are `Send`, so they can be spawned onto multithreaded executors.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::{AsyncSigningAlgorithm, Header, Token};
+//! use sha2::Sha256;
+//! use std::collections::BTreeMap;
+//!
+//! async fn issue(key: &impl AsyncSigningAlgorithm) -> Result<String, jwt::Error> {
+//!     let mut claims = BTreeMap::new();
+//!     claims.insert("sub", "someone");
+//!     let header = Header {
+//!         algorithm: key.algorithm_type(),
+//!         ..Default::default()
+//!     };
+//!     let token = Token::new(header, claims).sign_with_async_key(key).await?;
+//!     Ok(token.into())
+//! }
+//! ```
+
+use alloc::string::String;
+use core::future::{ready, Future};
+
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+
+/// An algorithm capable of asynchronously signing base64 encoded header and
+/// claims strings.
+pub trait AsyncSigningAlgorithm {
+    fn algorithm_type(&self) -> AlgorithmType;
+
+    fn sign(
+        &self,
+        header: &str,
+        claims: &str,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+/// An algorithm capable of asynchronously verifying base64 encoded header and
+/// claims strings.
+pub trait AsyncVerifyingAlgorithm {
+    fn algorithm_type(&self) -> AlgorithmType;
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> impl Future<Output = Result<bool, Error>> + Send;
+}
+
+impl<T: SigningAlgorithm> AsyncSigningAlgorithm for T {
+    fn algorithm_type(&self) -> AlgorithmType {
+        SigningAlgorithm::algorithm_type(self)
+    }
+
+    fn sign(
+        &self,
+        header: &str,
+        claims: &str,
+    ) -> impl Future<Output = Result<String, Error>> + Send {
+        ready(SigningAlgorithm::sign(self, header, claims))
+    }
+}
+
+impl<T: VerifyingAlgorithm> AsyncVerifyingAlgorithm for T {
+    fn algorithm_type(&self) -> AlgorithmType {
+        VerifyingAlgorithm::algorithm_type(self)
+    }
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> impl Future<Output = Result<bool, Error>> + Send {
+        ready(VerifyingAlgorithm::verify_bytes(
+            self, header, claims, signature,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use hmac::{Hmac, KeyInit};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::Token;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    pub fn futures_are_send() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims: BTreeMap<String, String> = BTreeMap::new();
+
+        let sign = Token::new(Header::default(), claims).sign_with_async_key(&key);
+        assert_send(&sign);
+        let signed = block_on(sign)?;
+
+        let unverified: Token<Header, BTreeMap<String, String>, _> =
+            Token::parse_unverified(signed.as_str())?;
+        let verify = unverified.verify_with_async_key(&key);
+        assert_send(&verify);
+        block_on(verify)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn sync_algorithms_as_async() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let mut claims = BTreeMap::new();
+        claims.insert("sub".to_owned(), "someone".to_owned());
+
+        let signed =
+            block_on(Token::new(Header::default(), claims.clone()).sign_with_async_key(&key))?;
+        let expected = Token::new(Header::default(), claims).sign_with_key(&key)?;
+        assert_eq!(signed.as_str(), expected.as_str());
+
+        let unverified: Token<Header, BTreeMap<String, String>, _> =
+            Token::parse_unverified(signed.as_str())?;
+        let verified = block_on(unverified.verify_with_async_key(&key))?;
+        assert_eq!(verified.claims()["sub"], "someone");
+
+        let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+        let unverified: Token<Header, BTreeMap<String, String>, _> =
+            Token::parse_unverified(signed.as_str())?;
+        match block_on(unverified.verify_with_async_key(&wrong_key)) {
+            Err(Error::InvalidSignature) => Ok(()),
+            x => panic!("Incorrect result {:?}", x.map(|_| ())),
+        }
+    }
+}
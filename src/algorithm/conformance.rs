@@ -0,0 +1,200 @@
+//! Published test vectors. Verification runs against whichever backend
+//! `AnyVerifier::from_jwk` picks for the key. Signing uses
+//! `AnySigner::from_jwk` for HMAC keys and the OpenSSL backend with the
+//! private key of the vector for RSA and EC keys, so a new backend for those
+//! needs its own case here. RSA and EC vectors are only skipped in builds
+//! without the `openssl` feature.
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+use crate::header::{Header, JoseHeader};
+use crate::jwk::Jwk;
+use crate::FromBase64;
+
+struct Vector {
+    source: &'static str,
+    jwk: &'static str,
+    header: &'static str,
+    claims: &'static str,
+    signature: &'static str,
+    /// The private key as PEM, for keys other than HMAC keys, whose JWK already
+    /// holds the secret.
+    private_key: Option<&'static [u8]>,
+    /// Whether signing again yields the same signature, which does not hold
+    /// for ECDSA.
+    deterministic: bool,
+}
+
+// "It’s a dangerous business, Frodo, going out your door. ..."
+const RFC_7520_PAYLOAD: &str = "SXTigJlzIGEgZGFuZ2Vyb3VzIGJ1c2luZXNzLCBGcm9kbywgZ29pbmcgb3V0IHlvdXIgZG9vci4gWW91IHN0ZXAgb250byB0aGUgcm9hZCwgYW5kIGlmIHlvdSBkb24ndCBrZWVwIHlvdXIgZmVldCwgdGhlcmXigJlzIG5vIGtub3dpbmcgd2hlcmUgeW91IG1pZ2h0IGJlIHN3ZXB0IG9mZiB0by4";
+
+// {"iss":"joe",\r\n "exp":1300819380,\r\n "http://example.com/is_root":true}
+const RFC_7515_CLAIMS: &str = "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ";
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        source: "RFC 7515, Appendix A.1",
+        jwk: r#"{"kty":"oct","alg":"HS256","k":"AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow"}"#,
+        header: "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9",
+        claims: RFC_7515_CLAIMS,
+        signature: "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+        private_key: None,
+        deterministic: true,
+    },
+    Vector {
+        source: "RFC 7515, Appendix A.2",
+        jwk: r#"{"kty":"RSA","alg":"RS256","e":"AQAB","n":"ofgWCuLjybRlzo0tZWJjNiuSfb4p4fAkd_wWJcyQoTbji9k0l8W26mPddxHmfHQp-Vaw-4qPCJrcS2mJPMEzP1Pt0Bm4d4QlL-yRT-SFd2lZS-pCgNMsD1W_YpRPEwOWvG6b32690r2jZ47soMZo9wGzjb_7OMg0LOL-bSf63kpaSHSXndS5z5rexMdbBYUsLA9e-KXBdQOS-UTo7WTBEMa2R2CapHg665xsmtdVMTBQY4uDZlxvb3qCo5ZwKh9kG4LT6_I5IhlJH7aGhyxXFvUK-DWNmoudF8NAco9_h9iaGNj8q2ethFkMLs91kzk2PAcDTW9gb54h4FRWyuXpoQ"}"#,
+        header: "eyJhbGciOiJSUzI1NiJ9",
+        claims: RFC_7515_CLAIMS,
+        signature: "cC4hiUPoj9Eetdgtv3hF80EGrhuB__dzERat0XF9g2VtQgr9PJbu3XOiZj5RZmh7AAuHIm4Bh-0Qc_lF5YKt_O8W2Fp5jujGbds9uJdbF9CUAr7t1dnZcAcQjbKBYNX4BAynRFdiuB--f_nZLgrnbyTyWzO75vRK5h6xBArLIARNPvkSjtQBMHlb1L07Qe7K0GarZRmB_eSN9383LcOLn6_dO--xi12jzDwusC-eOkHWEsqtFZESc6BfI7noOPqvhJ1phCnvWh6IeYI2w9QOYEUipUTI8np6LbgGY9Fs98rqVt5AXLIhWkWywlVmtVrBp0igcN_IoypGlUPQGe77Rw",
+        private_key: Some(include_bytes!("../../test/rfc7515-a2-private.pem")),
+        deterministic: true,
+    },
+    Vector {
+        source: "RFC 7515, Appendix A.3",
+        jwk: r#"{"kty":"EC","crv":"P-256","x":"f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU","y":"x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0"}"#,
+        header: "eyJhbGciOiJFUzI1NiJ9",
+        claims: RFC_7515_CLAIMS,
+        signature: "DtEhU3ljbEg8L38VWAfUAqOyKAM6-Xx-F4GawxaepmXFCgfTjDxw5djxLa8ISlSApmWQxfKTUJqPP3-Kg6NU1Q",
+        private_key: Some(include_bytes!("../../test/rfc7515-a3-private.pem")),
+        deterministic: false,
+    },
+    Vector {
+        source: "RFC 7520, Section 4.3",
+        jwk: r#"{"kty":"EC","kid":"bilbo.baggins@hobbiton.example","use":"sig","crv":"P-521","x":"AHKZLLOsCOzz5cY97ewNUajB957y-C-U88c3v13nmGZx6sYl_oJXu9A5RkTKqjqvjyekWF-7ytDyRXYgCF5cj0Kt","y":"AdymlHvOiLxXkEhayXQnNCvDX4h9htZaCJN34kfmC6pV5OhQHiraVySsUdaQkAgDPrwQrJmbnX9cwlGfP-HqHZR1"}"#,
+        header: "eyJhbGciOiJFUzUxMiIsImtpZCI6ImJpbGJvLmJhZ2dpbnNAaG9iYml0b24uZXhhbXBsZSJ9",
+        claims: RFC_7520_PAYLOAD,
+        signature: "AE_R_YZCChjn4791jSQCrdPZCNYqHXCTZH0-JZGYNlaAjP2kqaluUIIUnC9qvbu9Plon7KRTzoNEuT4Va2cmL1eJAQy3mtPBu_u_sDDyYjnAMDxXPn7XrT0lw-kvAD890jl8e2puQens_IEKBpHABlsbEPX6sFY8OcGDqoRuBomu9xQ2",
+        private_key: Some(include_bytes!("../../test/rfc7520-p521-private.pem")),
+        deterministic: false,
+    },
+    Vector {
+        source: "RFC 7520, Section 4.4",
+        jwk: r#"{"kty":"oct","kid":"018c0ae5-4d9b-471b-bfd6-eef314bc7037","use":"sig","alg":"HS256","k":"hJtXIZ2uSN5kbQfbtTNWbpdmhkV8FJG-Onbc6mxCcYg"}"#,
+        header: "eyJhbGciOiJIUzI1NiIsImtpZCI6IjAxOGMwYWU1LTRkOWItNDcxYi1iZmQ2LWVlZjMxNGJjNzAzNyJ9",
+        claims: RFC_7520_PAYLOAD,
+        signature: "s0h6KThzkfBBBkLspW1h84VsJZFTsPPqMDA7g1Md7p0",
+        private_key: None,
+        deterministic: true,
+    },
+    Vector {
+        source: "jwt.io, HS256",
+        jwk: r#"{"kty":"oct","alg":"HS256","k":"eW91ci0yNTYtYml0LXNlY3JldA"}"#,
+        header: "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9",
+        claims: "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ",
+        signature: "SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c",
+        private_key: None,
+        deterministic: true,
+    },
+];
+
+impl Vector {
+    fn jwk(&self) -> Jwk {
+        serde_json::from_str(self.jwk).unwrap_or_else(|e| panic!("{}: {}", self.source, e))
+    }
+
+    fn algorithm_type(&self) -> AlgorithmType {
+        let header: Header = Header::from_base64(self.header).unwrap();
+        header.algorithm_type()
+    }
+
+    fn is_hmac(&self) -> bool {
+        matches!(
+            self.algorithm_type(),
+            AlgorithmType::Hs256 | AlgorithmType::Hs384 | AlgorithmType::Hs512
+        )
+    }
+}
+
+/// `None` if no backend of this build supports the key.
+fn supported<T>(vector: &Vector, result: Result<T, Error>) -> Option<T> {
+    match result {
+        Ok(algorithm) => Some(algorithm),
+        Err(Error::InvalidJwk("unsupported key type"))
+            if !vector.is_hmac() && cfg!(not(feature = "openssl")) =>
+        {
+            None
+        }
+        Err(e) => panic!("{}: {}", vector.source, e),
+    }
+}
+
+#[cfg(feature = "openssl")]
+fn private_key_signer(vector: &Vector, pem: &[u8]) -> Result<AnySigner, Error> {
+    use crate::algorithm::openssl::PKeyWithDigest;
+    use openssl::pkey::PKey;
+
+    let key = PKey::private_key_from_pem(pem)?;
+    Ok(AnySigner::new(PKeyWithDigest::new(
+        vector.algorithm_type(),
+        key,
+    )?))
+}
+
+#[cfg(not(feature = "openssl"))]
+fn private_key_signer(_: &Vector, _: &[u8]) -> Result<AnySigner, Error> {
+    Err(Error::InvalidJwk("unsupported key type"))
+}
+
+fn signer(vector: &Vector) -> Result<AnySigner, Error> {
+    if vector.is_hmac() {
+        return AnySigner::from_jwk(&vector.jwk());
+    }
+    let pem = vector
+        .private_key
+        .unwrap_or_else(|| panic!("{}: missing private key", vector.source));
+    private_key_signer(vector, pem)
+}
+
+#[test]
+pub fn verify() -> Result<(), Error> {
+    for vector in VECTORS {
+        let verifier = match supported(vector, AnyVerifier::from_jwk(&vector.jwk())) {
+            Some(verifier) => verifier,
+            None => continue,
+        };
+        let (header, claims) = (vector.header, vector.claims);
+        assert_eq!(
+            verifier.algorithm_type(),
+            vector.algorithm_type(),
+            "{}",
+            vector.source
+        );
+        assert!(
+            verifier.verify(header, claims, vector.signature)?,
+            "{}",
+            vector.source
+        );
+
+        assert!(
+            !verifier.verify(header, &claims[1..], vector.signature)?,
+            "{}",
+            vector.source
+        );
+    }
+    Ok(())
+}
+
+#[test]
+pub fn sign() -> Result<(), Error> {
+    for vector in VECTORS {
+        let signer = match supported(vector, signer(vector)) {
+            Some(signer) => signer,
+            None => continue,
+        };
+        let (header, claims) = (vector.header, vector.claims);
+        let signature = signer.sign(header, claims)?;
+        if vector.deterministic {
+            assert_eq!(signature, vector.signature, "{}", vector.source);
+        } else {
+            let verifier = AnyVerifier::from_jwk(&vector.jwk())?;
+            assert!(
+                verifier.verify(header, claims, &signature)?,
+                "{}",
+                vector.source
+            );
+        }
+    }
+    Ok(())
+}
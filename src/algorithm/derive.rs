@@ -0,0 +1,140 @@
+//! Deriving HMAC keys from a master secret with
+//! [HKDF](https://tools.ietf.org/html/rfc5869), so that one secret can back
+//! the tokens of many audiences or tenants. Each key is derived with a label,
+//! and a token signed with the key of one label does not verify with the key
+//! of another.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::derive::MasterSecret;
+//! use jwt::{AlgorithmType, SignWithKey, VerifyWithKey};
+//! use std::collections::BTreeMap;
+//!
+//! let master = MasterSecret::new(b"a long and random master secret".to_vec());
+//! let orders = master.signer(AlgorithmType::Hs256, "orders").unwrap();
+//! let token = BTreeMap::from([("sub", "someone")]).sign_with_key(&orders).unwrap();
+//!
+//! let billing = master.verifier(AlgorithmType::Hs256, "billing").unwrap();
+//! let claims: Result<BTreeMap<String, String>, _> = token.verify_with_key(&billing);
+//! assert!(claims.is_err());
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::rust_crypto::minimum_hmac_key_length;
+use crate::algorithm::secret::SecretBytes;
+use crate::algorithm::AlgorithmType;
+#[cfg(feature = "encryption")]
+use crate::encryption::{ClaimKey, KEY_LENGTH};
+use crate::error::Error;
+
+/// Prefix of the HKDF info of every derived key, followed by the algorithm
+/// and the label.
+const INFO_PREFIX: &[u8] = b"jwt hmac key";
+/// Prefix of the HKDF info of claim encryption keys, followed by the label.
+#[cfg(feature = "encryption")]
+const CLAIM_KEY_INFO_PREFIX: &[u8] = b"jwt claim key";
+
+/// A secret to derive HMAC keys from. The derived keys are as long as the
+/// minimum key length of their algorithm.
+#[derive(Clone, Debug)]
+pub struct MasterSecret {
+    secret: SecretBytes,
+    salt: Option<Vec<u8>>,
+}
+
+impl MasterSecret {
+    pub fn new(secret: impl Into<SecretBytes>) -> Self {
+        MasterSecret {
+            secret: secret.into(),
+            salt: None,
+        }
+    }
+
+    /// Salt the derivation. A salt is not secret, but keys derived with
+    /// different salts are unrelated.
+    pub fn salt(mut self, salt: impl Into<Vec<u8>>) -> Self {
+        self.salt = Some(salt.into());
+        self
+    }
+
+    /// Derive the key for an HMAC algorithm and a label.
+    pub fn derive(&self, algorithm_type: AlgorithmType, label: &str) -> Result<SecretBytes, Error> {
+        let length = minimum_hmac_key_length(algorithm_type)
+            .ok_or(Error::InvalidKeyForAlgorithm(algorithm_type))?;
+        let info = [
+            INFO_PREFIX,
+            b" ",
+            algorithm_type.as_str().as_bytes(),
+            b" ",
+            label.as_bytes(),
+        ];
+
+        let mut key = vec![0; length];
+        Hkdf::<Sha256>::new(self.salt.as_deref(), &self.secret)
+            .expand_multi_info(&info, &mut key)
+            .map_err(|_| Error::InvalidKeyForAlgorithm(algorithm_type))?;
+        Ok(SecretBytes::from(key))
+    }
+
+    /// Derive the key for encrypting claims with a label, see
+    /// [encryption](../../encryption/index.html).
+    #[cfg(feature = "encryption")]
+    pub fn claim_key(&self, label: &str) -> Result<ClaimKey, Error> {
+        let info = [CLAIM_KEY_INFO_PREFIX, b" ", label.as_bytes()];
+        let mut key = zeroize::Zeroizing::new([0; KEY_LENGTH]);
+        Hkdf::<Sha256>::new(self.salt.as_deref(), &self.secret)
+            .expand_multi_info(&info, &mut *key)
+            .map_err(|_| Error::InvalidEncryptionKey(KEY_LENGTH))?;
+        ClaimKey::new(&*key)
+    }
+
+    /// A signer with the key derived for the label.
+    pub fn signer(&self, algorithm_type: AlgorithmType, label: &str) -> Result<AnySigner, Error> {
+        AnySigner::hmac(algorithm_type, &self.derive(algorithm_type, label)?)
+    }
+
+    /// A verifier with the key derived for the label.
+    pub fn verifier(
+        &self,
+        algorithm_type: AlgorithmType,
+        label: &str,
+    ) -> Result<AnyVerifier, Error> {
+        AnyVerifier::hmac(algorithm_type, &self.derive(algorithm_type, label)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::derive::MasterSecret;
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+
+    #[test]
+    pub fn derive() -> Result<(), Error> {
+        let master = MasterSecret::new(b"master".to_vec());
+        let key = master.derive(AlgorithmType::Hs256, "a")?;
+        assert_eq!(key.len(), 32);
+        assert_eq!(key, master.derive(AlgorithmType::Hs256, "a")?);
+        assert_ne!(key, master.derive(AlgorithmType::Hs256, "b")?);
+        assert_ne!(key[..], master.derive(AlgorithmType::Hs512, "a")?[..32]);
+        assert_ne!(
+            key,
+            master
+                .clone()
+                .salt("salt")
+                .derive(AlgorithmType::Hs256, "a")?
+        );
+        assert_eq!(master.derive(AlgorithmType::Hs384, "a")?.len(), 48);
+
+        assert!(matches!(
+            master.derive(AlgorithmType::Es256, "a"),
+            Err(Error::InvalidKeyForAlgorithm(AlgorithmType::Es256))
+        ));
+        Ok(())
+    }
+}
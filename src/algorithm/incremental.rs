@@ -0,0 +1,337 @@
+//! Signing and verifying input that is fed in pieces, for payloads too large
+//! to hold in memory, e.g. detached documents
+//! ([RFC 7515, Appendix F](https://tools.ietf.org/html/rfc7515#appendix-F)).
+//! The payload is base64url encoded while it is fed, so neither the payload
+//! nor its encoding is ever needed in one piece.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::algorithm::incremental::{StreamingSigner, StreamingVerifier};
+//! use jwt::Header;
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let mut signer = StreamingSigner::new(&key, &Header::default()).unwrap();
+//! for chunk in [&b"a large "[..], b"document"] {
+//!     signer.update(chunk).unwrap();
+//! }
+//! let token = signer.finish_detached().unwrap();
+//!
+//! let (header, signature) = token.split_once("..").unwrap();
+//! let mut verifier = StreamingVerifier::new(&key, header).unwrap();
+//! verifier.update(b"a large document").unwrap();
+//! assert!(verifier.finish(signature).unwrap());
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+use crate::header::{reject_critical, Header, JoseHeader};
+use crate::token::raw::{error_offset, Component};
+use crate::{FromBase64, ToBase64, SEPARATOR};
+
+/// Bytes of payload encoded at a time.
+const CHUNK: usize = 768;
+
+/// The state of a signature over input that is fed in pieces.
+pub trait SigningInput {
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error>;
+
+    /// The base64url encoded signature of all of the input.
+    fn finish(self) -> Result<String, Error>;
+}
+
+/// The state of a verification of input that is fed in pieces.
+pub trait VerifyingInput {
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error>;
+
+    /// Check the decoded signature against all of the input.
+    fn finish(self, signature: &[u8]) -> Result<bool, Error>;
+}
+
+/// A signing algorithm that accepts its input in pieces.
+pub trait IncrementalSigningAlgorithm: SigningAlgorithm {
+    type Input<'a>: SigningInput
+    where
+        Self: 'a;
+
+    fn begin(&self) -> Result<Self::Input<'_>, Error>;
+}
+
+/// A verifying algorithm that accepts its input in pieces.
+pub trait IncrementalVerifyingAlgorithm: VerifyingAlgorithm {
+    type Input<'a>: VerifyingInput
+    where
+        Self: 'a;
+
+    fn begin(&self) -> Result<Self::Input<'_>, Error>;
+}
+
+/// Base64url encodes the payload in groups of three bytes, keeping the bytes
+/// of an incomplete group until more arrive.
+#[derive(Default)]
+struct Encoder {
+    pending: [u8; 3],
+    pending_len: usize,
+}
+
+impl Encoder {
+    fn feed(
+        &mut self,
+        mut bytes: &[u8],
+        mut sink: impl FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if self.pending_len > 0 {
+            let take = bytes.len().min(3 - self.pending_len);
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&bytes[..take]);
+            self.pending_len += take;
+            bytes = &bytes[take..];
+            if self.pending_len < 3 {
+                return Ok(());
+            }
+            self.flush(&mut sink)?;
+        }
+
+        let whole = bytes.len() / 3 * 3;
+        let mut encoded = [0; CHUNK / 3 * 4];
+        for chunk in bytes[..whole].chunks(CHUNK) {
+            let length = URL_SAFE_NO_PAD
+                .encode_slice(chunk, &mut encoded)
+                .map_err(|_| Error::BufferTooSmall)?;
+            sink(&encoded[..length])?;
+        }
+
+        let rest = &bytes[whole..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+        Ok(())
+    }
+
+    fn flush(&mut self, mut sink: impl FnMut(&[u8]) -> Result<(), Error>) -> Result<(), Error> {
+        let mut encoded = [0; 4];
+        let length = URL_SAFE_NO_PAD
+            .encode_slice(&self.pending[..self.pending_len], &mut encoded)
+            .map_err(|_| Error::BufferTooSmall)?;
+        self.pending_len = 0;
+        sink(&encoded[..length])
+    }
+}
+
+/// Signs a token whose payload is fed in pieces.
+pub struct StreamingSigner<I> {
+    input: I,
+    header: String,
+    encoder: Encoder,
+}
+
+impl<I: SigningInput> StreamingSigner<I> {
+    /// Start signing a token with the header. The algorithm of the header has
+    /// to match the key.
+    pub fn new<'a, K, H>(key: &'a K, header: &H) -> Result<Self, Error>
+    where
+        K: IncrementalSigningAlgorithm<Input<'a> = I>,
+        H: ToBase64 + JoseHeader,
+    {
+        check_algorithm(header.algorithm_type(), key.algorithm_type())?;
+        let header = header.to_base64()?.into_owned();
+
+        let mut input = key.begin()?;
+        input.update(header.as_bytes())?;
+        input.update(SEPARATOR.as_bytes())?;
+        Ok(StreamingSigner {
+            input,
+            header,
+            encoder: Encoder::default(),
+        })
+    }
+
+    /// The encoded header.
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// Feed the next bytes of the payload, before encoding.
+    pub fn update(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let input = &mut self.input;
+        self.encoder.feed(payload, |encoded| input.update(encoded))
+    }
+
+    /// The encoded signature.
+    pub fn finish(mut self) -> Result<String, Error> {
+        let input = &mut self.input;
+        self.encoder.flush(|encoded| input.update(encoded))?;
+        self.input.finish()
+    }
+
+    /// The token with a detached payload, the header and the signature
+    /// separated by two dots.
+    pub fn finish_detached(self) -> Result<String, Error> {
+        let header = self.header.clone();
+        let signature = self.finish()?;
+        Ok([&*header, "", &signature].join(SEPARATOR))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: SigningInput> std::io::Write for StreamingSigner<I> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Verifies a token whose payload is fed in pieces.
+pub struct StreamingVerifier<I> {
+    input: I,
+    encoder: Encoder,
+}
+
+impl<I: VerifyingInput> StreamingVerifier<I> {
+    /// Start verifying a token with the encoded header. The algorithm of the
+    /// header has to match the key.
+    pub fn new<'a, K>(key: &'a K, header: &str) -> Result<Self, Error>
+    where
+        K: IncrementalVerifyingAlgorithm<Input<'a> = I>,
+    {
+        let decoded: Header = Header::from_base64(header)
+            .map_err(|e| Error::Malformed(Component::Header, 0, Box::new(e)))?;
+        check_algorithm(decoded.algorithm_type(), key.algorithm_type())?;
+        reject_critical(&decoded)?;
+
+        let mut input = key.begin()?;
+        input.update(header.as_bytes())?;
+        input.update(SEPARATOR.as_bytes())?;
+        Ok(StreamingVerifier {
+            input,
+            encoder: Encoder::default(),
+        })
+    }
+
+    /// Feed the next bytes of the payload, before encoding.
+    pub fn update(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let input = &mut self.input;
+        self.encoder.feed(payload, |encoded| input.update(encoded))
+    }
+
+    /// Check the encoded signature against the payload.
+    pub fn finish(mut self, signature: &str) -> Result<bool, Error> {
+        let signature_bytes = URL_SAFE_NO_PAD.decode(signature).map_err(|e| {
+            let error = Error::Base64(e);
+            let offset = error_offset(signature, &error);
+            Error::Malformed(Component::Signature, offset, Box::new(error))
+        })?;
+
+        let input = &mut self.input;
+        self.encoder.flush(|encoded| input.update(encoded))?;
+        self.input.finish(&signature_bytes)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: VerifyingInput> std::io::Write for StreamingVerifier<I> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn check_algorithm(header: AlgorithmType, key: AlgorithmType) -> Result<(), Error> {
+    if header != key {
+        return Err(Error::AlgorithmMismatch(header, key));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use hmac::{Hmac, KeyInit};
+    use sha2::{Sha256, Sha384};
+
+    use crate::algorithm::incremental::{StreamingSigner, StreamingVerifier};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::ToBase64;
+
+    #[test]
+    pub fn matches_signature() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let payload: Vec<u8> = (0..2000u32).map(|n| (n % 251) as u8).collect();
+        let header = Header::default();
+        let expected = key.sign(&header.to_base64()?, &URL_SAFE_NO_PAD.encode(&payload))?;
+
+        for split in [1, 2, 5, 768, 1000] {
+            let mut signer = StreamingSigner::new(&key, &header)?;
+            for chunk in payload.chunks(split) {
+                signer.update(chunk)?;
+            }
+            assert_eq!(signer.finish()?, expected);
+        }
+
+        let mut signer = StreamingSigner::new(&key, &header)?;
+        signer.write_all(&payload).unwrap();
+        let token = signer.finish_detached()?;
+        let (header, signature) = token.split_once("..").unwrap();
+        assert_eq!(signature, expected);
+
+        let mut verifier = StreamingVerifier::new(&key, header)?;
+        verifier.write_all(&payload).unwrap();
+        assert!(verifier.finish(signature)?);
+
+        let mut verifier = StreamingVerifier::new(&key, header)?;
+        verifier.update(&payload[1..])?;
+        assert!(!verifier.finish(signature)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn algorithm_mismatch() -> Result<(), Error> {
+        let key: Hmac<Sha384> = Hmac::new_from_slice(b"secret")?;
+        assert!(matches!(
+            StreamingSigner::new(&key, &Header::default()),
+            Err(Error::AlgorithmMismatch(
+                AlgorithmType::Hs256,
+                AlgorithmType::Hs384
+            ))
+        ));
+        assert!(matches!(
+            StreamingVerifier::new(&key, &Header::default().to_base64()?),
+            Err(Error::AlgorithmMismatch(
+                AlgorithmType::Hs256,
+                AlgorithmType::Hs384
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn critical_parameters() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let header = Header {
+            critical: Some(vec!["exp".into()]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            StreamingVerifier::new(&key, &header.to_base64()?),
+            Err(Error::UnsupportedCriticalParameter(ref name)) if name == "exp"
+        ));
+        Ok(())
+    }
+}
@@ -1,29 +1,61 @@
 //! Algorithms capable of signing and verifying tokens. By default only the
 //! `hmac` crate's `Hmac` type is supported. For more algorithms, enable the
 //! feature `openssl` and see the [openssl](openssl/index.html)
-//! module. The `none` algorithm is explicitly not supported.
+//! module, or enable the feature `signature` to use the keys of the
+//! RustCrypto signature crates through the [signature](signature/index.html)
+//! module. The `none` algorithm is only supported explicitly through the
+//! [unsecured](unsecured/index.html) module.
 //! ## Examples
 //! ```
-//! use hmac::{Hmac, Mac};
+//! use hmac::{Hmac, KeyInit};
 //! use sha2::Sha256;
 //!
 //! let hs256_key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
 //! ```
 
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 
 use crate::error::Error;
+use crate::token::raw::{error_offset, Component};
 
+pub mod allowlist;
+pub mod any;
+pub mod asynchronous;
+#[cfg(test)]
+mod conformance;
+#[cfg(feature = "hmac")]
+pub mod derive;
+pub mod incremental;
 #[cfg(feature = "openssl")]
 pub mod openssl;
+#[cfg(feature = "openssl")]
+pub mod revocation;
 pub mod rust_crypto;
+pub mod secret;
+#[cfg(feature = "signature")]
+pub mod signature;
 pub mod store;
+#[cfg(feature = "thumbprint")]
+pub mod thumbprint;
+pub mod unsecured;
+#[cfg(feature = "openssl")]
+pub mod x5c;
 
 /// The type of an algorithm, corresponding to the
 /// [JWA](https://tools.ietf.org/html/rfc7518) specification.
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum AlgorithmType {
+    #[default]
     Hs256,
     Hs384,
     Hs512,
@@ -40,9 +72,76 @@ pub enum AlgorithmType {
     None,
 }
 
-impl Default for AlgorithmType {
-    fn default() -> Self {
-        AlgorithmType::Hs256
+impl AlgorithmType {
+    /// The name of the algorithm as used in the `alg` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlgorithmType::Hs256 => "HS256",
+            AlgorithmType::Hs384 => "HS384",
+            AlgorithmType::Hs512 => "HS512",
+            AlgorithmType::Rs256 => "RS256",
+            AlgorithmType::Rs384 => "RS384",
+            AlgorithmType::Rs512 => "RS512",
+            AlgorithmType::Es256 => "ES256",
+            AlgorithmType::Es384 => "ES384",
+            AlgorithmType::Es512 => "ES512",
+            AlgorithmType::Ps256 => "PS256",
+            AlgorithmType::Ps384 => "PS384",
+            AlgorithmType::Ps512 => "PS512",
+            AlgorithmType::None => "none",
+        }
+    }
+
+    /// The length in bytes of the signatures of the algorithm, or `None` for
+    /// RSA, whose signatures are as long as the modulus of the key.
+    pub fn signature_length(&self) -> Option<usize> {
+        match self {
+            AlgorithmType::Hs256 => Some(32),
+            AlgorithmType::Hs384 => Some(48),
+            AlgorithmType::Hs512 => Some(64),
+            // R and S, each as long as the order of the curve
+            AlgorithmType::Es256 => Some(2 * 32),
+            AlgorithmType::Es384 => Some(2 * 48),
+            AlgorithmType::Es512 => Some(2 * 66),
+            AlgorithmType::Rs256
+            | AlgorithmType::Rs384
+            | AlgorithmType::Rs512
+            | AlgorithmType::Ps256
+            | AlgorithmType::Ps384
+            | AlgorithmType::Ps512 => None,
+            AlgorithmType::None => Some(0),
+        }
+    }
+}
+
+impl fmt::Display for AlgorithmType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AlgorithmType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let algorithm_type = match s {
+            "HS256" => AlgorithmType::Hs256,
+            "HS384" => AlgorithmType::Hs384,
+            "HS512" => AlgorithmType::Hs512,
+            "RS256" => AlgorithmType::Rs256,
+            "RS384" => AlgorithmType::Rs384,
+            "RS512" => AlgorithmType::Rs512,
+            "ES256" => AlgorithmType::Es256,
+            "ES384" => AlgorithmType::Es384,
+            "ES512" => AlgorithmType::Es512,
+            "PS256" => AlgorithmType::Ps256,
+            "PS384" => AlgorithmType::Ps384,
+            "PS512" => AlgorithmType::Ps512,
+            "none" => AlgorithmType::None,
+            _ => return Err(Error::UnknownAlgorithmType(s.to_owned())),
+        };
+
+        Ok(algorithm_type)
     }
 }
 
@@ -52,17 +151,30 @@ pub trait SigningAlgorithm {
     fn algorithm_type(&self) -> AlgorithmType;
 
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error>;
+
+    /// The length in bytes of the signatures of the key, if it is known
+    /// without signing. Implementations for RSA keys should return the size
+    /// of the modulus.
+    fn signature_length(&self) -> Option<usize> {
+        self.algorithm_type().signature_length()
+    }
 }
 
 /// An algorithm capable of verifying base64 encoded header and claims strings.
+/// Implementations comparing an expected signature against the given one
+/// should use [constant_time_eq](fn.constant_time_eq.html).
 pub trait VerifyingAlgorithm {
     fn algorithm_type(&self) -> AlgorithmType;
 
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error>;
 
     fn verify(&self, header: &str, claims: &str, signature: &str) -> Result<bool, Error> {
-        let signature_bytes = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)?;
-        self.verify_bytes(header, claims, &*signature_bytes)
+        let signature_bytes = URL_SAFE_NO_PAD.decode(signature).map_err(|e| {
+            let error = Error::Base64(e);
+            let offset = error_offset(signature, &error);
+            Error::Malformed(Component::Signature, offset, Box::new(error))
+        })?;
+        self.verify_bytes(header, claims, &signature_bytes)
     }
 }
 
@@ -85,4 +197,65 @@ impl<T: AsRef<dyn SigningAlgorithm>> SigningAlgorithm for T {
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
         self.as_ref().sign(header, claims)
     }
+
+    fn signature_length(&self) -> Option<usize> {
+        self.as_ref().signature_length()
+    }
+}
+
+/// Compare two byte strings in time that only depends on their lengths, not
+/// their contents. Use this instead of `==` for signatures, MACs, hashes of
+/// tokens and other secrets, so that an attacker can not learn how many
+/// leading bytes of a guess are correct.
+/// ## Examples
+/// ```
+/// use jwt::algorithm::constant_time_eq;
+///
+/// assert!(constant_time_eq(b"signature", b"signature"));
+/// assert!(!constant_time_eq(b"signature", b"signaturf"));
+/// ```
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::{constant_time_eq, AlgorithmType};
+    use crate::error::Error;
+
+    #[test]
+    fn names() -> Result<(), Error> {
+        let algorithms = [
+            AlgorithmType::Hs256,
+            AlgorithmType::Hs384,
+            AlgorithmType::Hs512,
+            AlgorithmType::Rs256,
+            AlgorithmType::Rs384,
+            AlgorithmType::Rs512,
+            AlgorithmType::Es256,
+            AlgorithmType::Es384,
+            AlgorithmType::Es512,
+            AlgorithmType::Ps256,
+            AlgorithmType::Ps384,
+            AlgorithmType::Ps512,
+            AlgorithmType::None,
+        ];
+
+        for algorithm in algorithms.iter() {
+            let name = algorithm.to_string();
+            assert_eq!(serde_json::to_string(algorithm)?, format!("\"{}\"", name));
+            assert_eq!(name.parse::<AlgorithmType>()?, *algorithm);
+        }
+
+        assert!("hs256".parse::<AlgorithmType>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
 }
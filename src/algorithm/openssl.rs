@@ -3,25 +3,35 @@
 //! can only be used for verification.
 //! ## Examples
 //! ```
-//! use jwt::PKeyWithDigest;
-//! use openssl::hash::MessageDigest;
+//! use jwt::{AlgorithmType, PKeyWithDigest};
 //! use openssl::pkey::PKey;
 //! let pem = include_bytes!("../../test/rs256-public.pem");
-//! let rs256_public_key = PKeyWithDigest {
-//!     digest: MessageDigest::sha256(),
-//!     key: PKey::public_key_from_pem(pem).unwrap(),
-//! };
+//! let key = PKey::public_key_from_pem(pem).unwrap();
+//!
+//! // RSA keys shorter than 2048 bits are rejected, unless allowed explicitly.
+//! assert!(PKeyWithDigest::new(AlgorithmType::Rs256, key.clone()).is_err());
+//! let rs256_public_key =
+//!     PKeyWithDigest::new_with_minimum_rsa_bits(AlgorithmType::Rs256, key, 1024).unwrap();
 //! ```
 
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::algorithm::incremental::{
+    IncrementalSigningAlgorithm, IncrementalVerifyingAlgorithm, SigningInput, VerifyingInput,
+};
 use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
 use crate::error::Error;
+use crate::jwk::{Jwk, KeyOperation, KeyType, KeyUsePolicy};
 use crate::SEPARATOR;
 
-use openssl::bn::BigNum;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
 use openssl::ecdsa::EcdsaSig;
 use openssl::hash::MessageDigest;
 use openssl::nid::Nid;
-use openssl::pkey::{Id, PKey, Private, Public};
+use openssl::pkey::{HasPublic, Id, PKey, Private, Public};
+use openssl::rsa::Rsa;
 use openssl::sign::{Signer, Verifier};
 
 /// A wrapper class around [PKey](../../../openssl/pkey/struct.PKey.html) that
@@ -32,6 +42,89 @@ pub struct PKeyWithDigest<T> {
     pub key: PKey<T>,
 }
 
+/// The minimum size of RSA keys in bits accepted by
+/// [PKeyWithDigest::new](struct.PKeyWithDigest.html#method.new).
+pub const MINIMUM_RSA_KEY_BITS: u32 = 2048;
+
+impl<T: HasPublic> PKeyWithDigest<T> {
+    /// Create a key that is bound to a single algorithm. Fails if the key can
+    /// not be used for the algorithm, e.g. if the curve of an elliptic curve
+    /// key does not match, or if an RSA key is weak. Prefer this over
+    /// constructing the struct directly, which only checks the combination of
+    /// key type and digest.
+    pub fn new(algorithm_type: AlgorithmType, key: PKey<T>) -> Result<Self, Error> {
+        PKeyWithDigest::new_with_minimum_rsa_bits(algorithm_type, key, MINIMUM_RSA_KEY_BITS)
+    }
+
+    /// Like [new](#method.new), but with a custom minimum size for RSA keys.
+    pub fn new_with_minimum_rsa_bits(
+        algorithm_type: AlgorithmType,
+        key: PKey<T>,
+        minimum_bits: u32,
+    ) -> Result<Self, Error> {
+        let (id, curve, digest) = match algorithm_type {
+            AlgorithmType::Rs256 => (Id::RSA, None, MessageDigest::sha256()),
+            AlgorithmType::Rs384 => (Id::RSA, None, MessageDigest::sha384()),
+            AlgorithmType::Rs512 => (Id::RSA, None, MessageDigest::sha512()),
+            AlgorithmType::Es256 => (Id::EC, Some(Nid::X9_62_PRIME256V1), MessageDigest::sha256()),
+            AlgorithmType::Es384 => (Id::EC, Some(Nid::SECP384R1), MessageDigest::sha384()),
+            AlgorithmType::Es512 => (Id::EC, Some(Nid::SECP521R1), MessageDigest::sha512()),
+            _ => return Err(Error::InvalidKeyForAlgorithm(algorithm_type)),
+        };
+
+        if key.id() != id {
+            return Err(Error::InvalidKeyForAlgorithm(algorithm_type));
+        }
+
+        if let Some(curve) = curve {
+            if key.ec_key()?.group().curve_name() != Some(curve) {
+                return Err(Error::InvalidKeyForAlgorithm(algorithm_type));
+            }
+        }
+
+        if id == Id::RSA {
+            check_rsa_key(&key.rsa()?, minimum_bits)?;
+        }
+
+        Ok(PKeyWithDigest { digest, key })
+    }
+
+    /// The public key as a JSON Web Key, with its algorithm.
+    pub fn to_jwk(&self) -> Result<Jwk, Error> {
+        let encode = |number: Vec<u8>| Some(URL_SAFE_NO_PAD.encode(number));
+        let mut jwk = if self.key.id() == Id::EC {
+            let ec_key = self.key.ec_key()?;
+            let group = ec_key.group();
+            let mut x = BigNum::new()?;
+            let mut y = BigNum::new()?;
+            let mut context = BigNumContext::new()?;
+            ec_key
+                .public_key()
+                .affine_coordinates(group, &mut x, &mut y, &mut context)?;
+            let size = (group.degree() as i32 + 7) / 8;
+
+            let mut jwk = Jwk::new(KeyType::EllipticCurve);
+            jwk.crv = match group.curve_name() {
+                Some(Nid::X9_62_PRIME256V1) => Some("P-256".into()),
+                Some(Nid::SECP384R1) => Some("P-384".into()),
+                Some(Nid::SECP521R1) => Some("P-521".into()),
+                _ => return Err(Error::InvalidJwk("crv")),
+            };
+            jwk.x = encode(x.to_vec_padded(size)?);
+            jwk.y = encode(y.to_vec_padded(size)?);
+            jwk
+        } else {
+            let rsa = self.key.rsa()?;
+            let mut jwk = Jwk::new(KeyType::Rsa);
+            jwk.n = encode(rsa.n().to_vec());
+            jwk.e = encode(rsa.e().to_vec());
+            jwk
+        };
+        jwk.algorithm = Some(self.algorithm_type().as_str().into());
+        Ok(jwk)
+    }
+}
+
 impl<T> PKeyWithDigest<T> {
     fn algorithm_type(&self) -> AlgorithmType {
         match (self.key.id(), self.digest.type_()) {
@@ -46,25 +139,89 @@ impl<T> PKeyWithDigest<T> {
     }
 }
 
+impl PKeyWithDigest<Public> {
+    /// Create a public key from an RSA or elliptic curve JSON Web Key. Keys
+    /// whose `use` or `key_ops` forbid verifying are rejected.
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+        PKeyWithDigest::from_jwk_with_policy(jwk, KeyUsePolicy::Enforce)
+    }
+
+    /// Create a public key from a JSON Web Key, honoring or ignoring its
+    /// `use` and `key_ops`.
+    pub fn from_jwk_with_policy(jwk: &Jwk, policy: KeyUsePolicy) -> Result<Self, Error> {
+        jwk.check_operation(KeyOperation::Verify, policy)?;
+        let algorithm_type = jwk.algorithm_type()?;
+        let key = match (jwk.key_type, algorithm_type) {
+            (KeyType::Rsa, AlgorithmType::Rs256 | AlgorithmType::Rs384 | AlgorithmType::Rs512) => {
+                let n = BigNum::from_slice(&Jwk::decode_parameter(&jwk.n, "n")?)?;
+                let e = BigNum::from_slice(&Jwk::decode_parameter(&jwk.e, "e")?)?;
+                PKey::from_rsa(Rsa::from_public_components(n, e)?)?
+            }
+            (
+                KeyType::EllipticCurve,
+                AlgorithmType::Es256 | AlgorithmType::Es384 | AlgorithmType::Es512,
+            ) => {
+                let curve = match jwk.crv.as_deref() {
+                    Some("P-256") => Nid::X9_62_PRIME256V1,
+                    Some("P-384") => Nid::SECP384R1,
+                    Some("P-521") => Nid::SECP521R1,
+                    _ => return Err(Error::InvalidJwk("crv")),
+                };
+                let group = EcGroup::from_curve_name(curve)?;
+                let x = BigNum::from_slice(&Jwk::decode_parameter(&jwk.x, "x")?)?;
+                let y = BigNum::from_slice(&Jwk::decode_parameter(&jwk.y, "y")?)?;
+                PKey::from_ec_key(EcKey::from_public_key_affine_coordinates(&group, &x, &y)?)?
+            }
+            _ => return Err(Error::InvalidJwk("algorithm does not match key type")),
+        };
+
+        PKeyWithDigest::new(algorithm_type, key)
+    }
+
+    /// Create a public key from PEM, for the algorithm the key implies:
+    /// RS256 for RSA keys and the algorithm of their curve for elliptic curve
+    /// keys.
+    pub fn from_pem(pem: &[u8]) -> Result<Self, Error> {
+        let key = PKey::public_key_from_pem(pem)?;
+        let algorithm_type = if key.id() == Id::EC {
+            match key.ec_key()?.group().curve_name() {
+                Some(Nid::SECP384R1) => AlgorithmType::Es384,
+                Some(Nid::SECP521R1) => AlgorithmType::Es512,
+                _ => AlgorithmType::Es256,
+            }
+        } else {
+            AlgorithmType::Rs256
+        };
+
+        PKeyWithDigest::new(algorithm_type, key)
+    }
+}
+
 impl SigningAlgorithm for PKeyWithDigest<Private> {
     fn algorithm_type(&self) -> AlgorithmType {
         PKeyWithDigest::algorithm_type(self)
     }
 
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
-        let mut signer = Signer::new(self.digest.clone(), &self.key)?;
+        let mut signer = Signer::new(self.digest, &self.key)?;
         signer.update(header.as_bytes())?;
         signer.update(SEPARATOR.as_bytes())?;
         signer.update(claims.as_bytes())?;
         let signer_signature = signer.sign_to_vec()?;
 
-        let signature = if self.key.id() == Id::EC {
-            der_to_jose(&signer_signature)?
-        } else {
-            signer_signature
+        let signature = match coordinate_length(&self.key)? {
+            Some(length) => der_to_jose(&signer_signature, length)?,
+            None => signer_signature,
         };
 
-        Ok(base64::encode_config(&signature, base64::URL_SAFE_NO_PAD))
+        Ok(URL_SAFE_NO_PAD.encode(&signature))
+    }
+
+    fn signature_length(&self) -> Option<usize> {
+        match self.key.id() {
+            Id::EC => PKeyWithDigest::algorithm_type(self).signature_length(),
+            _ => Some(self.key.size()),
+        }
     }
 }
 
@@ -74,7 +231,7 @@ impl VerifyingAlgorithm for PKeyWithDigest<Public> {
     }
 
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
-        let mut verifier = Verifier::new(self.digest.clone(), &self.key)?;
+        let mut verifier = Verifier::new(self.digest, &self.key)?;
         verifier.update(header.as_bytes())?;
         verifier.update(SEPARATOR.as_bytes())?;
         verifier.update(claims.as_bytes())?;
@@ -90,11 +247,103 @@ impl VerifyingAlgorithm for PKeyWithDigest<Public> {
     }
 }
 
-/// OpenSSL by default signs ECDSA in DER, but JOSE expects them in a concatenated (R, S) format
-fn der_to_jose(der: &[u8]) -> Result<Vec<u8>, Error> {
-    let signature = EcdsaSig::from_der(&der)?;
-    let r = signature.r().to_vec();
-    let s = signature.s().to_vec();
+/// The state of an OpenSSL signature over input that is fed in pieces.
+pub struct OpenSslSigningInput<'a> {
+    signer: Signer<'a>,
+    coordinate_length: Option<usize>,
+}
+
+impl SigningInput for OpenSslSigningInput<'_> {
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        Ok(self.signer.update(bytes)?)
+    }
+
+    fn finish(self) -> Result<String, Error> {
+        let signer_signature = self.signer.sign_to_vec()?;
+        let signature = match self.coordinate_length {
+            Some(length) => der_to_jose(&signer_signature, length)?,
+            None => signer_signature,
+        };
+        Ok(URL_SAFE_NO_PAD.encode(&signature))
+    }
+}
+
+impl IncrementalSigningAlgorithm for PKeyWithDigest<Private> {
+    type Input<'a> = OpenSslSigningInput<'a>;
+
+    fn begin(&self) -> Result<OpenSslSigningInput<'_>, Error> {
+        Ok(OpenSslSigningInput {
+            signer: Signer::new(self.digest, &self.key)?,
+            coordinate_length: coordinate_length(&self.key)?,
+        })
+    }
+}
+
+/// The state of an OpenSSL verification of input that is fed in pieces.
+pub struct OpenSslVerifyingInput<'a> {
+    verifier: Verifier<'a>,
+    ec: bool,
+}
+
+impl VerifyingInput for OpenSslVerifyingInput<'_> {
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        Ok(self.verifier.update(bytes)?)
+    }
+
+    fn finish(self, signature: &[u8]) -> Result<bool, Error> {
+        let verified = if self.ec {
+            self.verifier.verify(&jose_to_der(signature)?)?
+        } else {
+            self.verifier.verify(signature)?
+        };
+        Ok(verified)
+    }
+}
+
+impl IncrementalVerifyingAlgorithm for PKeyWithDigest<Public> {
+    type Input<'a> = OpenSslVerifyingInput<'a>;
+
+    fn begin(&self) -> Result<OpenSslVerifyingInput<'_>, Error> {
+        Ok(OpenSslVerifyingInput {
+            verifier: Verifier::new(self.digest, &self.key)?,
+            ec: self.key.id() == Id::EC,
+        })
+    }
+}
+
+/// Reject RSA keys that are too small or degenerate. Degenerate keys have an
+/// even modulus, or an exponent that is even or not larger than one.
+fn check_rsa_key<T: HasPublic>(rsa: &Rsa<T>, minimum_bits: u32) -> Result<(), Error> {
+    let bits = rsa.size() * 8;
+    if bits < minimum_bits {
+        return Err(Error::RsaKeyTooShort(bits, minimum_bits));
+    }
+
+    let (n, e) = (rsa.n(), rsa.e());
+    if !n.is_bit_set(0) || !e.is_bit_set(0) || e.num_bits() <= 1 {
+        return Err(Error::DegenerateKey);
+    }
+
+    Ok(())
+}
+
+/// The length in bytes of a coordinate of the curve of an EC key, `None` for
+/// other keys.
+fn coordinate_length<T: HasPublic>(key: &PKey<T>) -> Result<Option<usize>, Error> {
+    if key.id() != Id::EC {
+        return Ok(None);
+    }
+    let degree = key.ec_key()?.group().degree() as usize;
+    Ok(Some(degree.div_ceil(8)))
+}
+
+/// OpenSSL by default signs ECDSA in DER, but JOSE expects them in a concatenated (R, S) format,
+/// with both padded to the length of a coordinate
+fn der_to_jose(der: &[u8], coordinate_length: usize) -> Result<Vec<u8>, Error> {
+    let signature = EcdsaSig::from_der(der)?;
+    let length = coordinate_length as i32;
+    let r = signature.r().to_vec_padded(length)?;
+    let s = signature.s().to_vec_padded(length)?;
     Ok([r, s].concat())
 }
 
@@ -108,23 +357,36 @@ fn jose_to_der(jose: &[u8]) -> Result<Vec<u8>, Error> {
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithm::incremental::{StreamingSigner, StreamingVerifier};
     use crate::algorithm::openssl::PKeyWithDigest;
     use crate::algorithm::AlgorithmType::*;
     use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
     use crate::error::Error;
     use crate::header::PrecomputedAlgorithmOnlyHeader as AlgOnly;
+    use crate::jwk::{Jwk, KeyType};
     use crate::ToBase64;
 
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use openssl::bn::{BigNum, BigNumContext};
     use openssl::hash::MessageDigest;
     use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
 
     // {"sub":"1234567890","name":"John Doe","admin":true}
-    const CLAIMS: &'static str =
-        "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+    const CLAIMS: &str = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
 
-    const RS256_SIGNATURE: &'static str =
+    const RS256_SIGNATURE: &str =
     "cQsAHF2jHvPGFP5zTD8BgoJrnzEx6JNQCpupebWLFnOc2r_punDDTylI6Ia4JZNkvy2dQP-7W-DEbFQ3oaarHsDndqUgwf9iYlDQxz4Rr2nEZX1FX0-FMEgFPeQpdwveCgjtTYUbVy37ijUySN_rW-xZTrsh_Ug-ica8t-zHRIw";
 
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<PKeyWithDigest<openssl::pkey::Private>>();
+        assert_send_sync::<PKeyWithDigest<openssl::pkey::Public>>();
+    }
+
     #[test]
     fn rs256_sign() -> Result<(), Error> {
         let pem = include_bytes!("../../test/rs256-private.pem");
@@ -172,8 +434,180 @@ mod tests {
         };
 
         let verification_result =
-            public_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &*signature)?;
+            public_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &signature)?;
+        assert!(verification_result);
+        Ok(())
+    }
+
+    #[test]
+    fn rs256_streaming() -> Result<(), Error> {
+        let private_key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../../test/rs256-private.pem"))?,
+        };
+        let public_key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::public_key_from_pem(include_bytes!("../../test/rs256-public.pem"))?,
+        };
+        let payload = URL_SAFE_NO_PAD.decode(CLAIMS)?;
+
+        let mut signer = StreamingSigner::new(&private_key, &AlgOnly(Rs256))?;
+        for chunk in payload.chunks(7) {
+            signer.update(chunk)?;
+        }
+        assert_eq!(signer.finish()?, RS256_SIGNATURE);
+
+        let mut verifier = StreamingVerifier::new(&public_key, &AlgOnly(Rs256).to_base64()?)?;
+        verifier.update(&payload)?;
+        assert!(verifier.finish(RS256_SIGNATURE)?);
+        Ok(())
+    }
+
+    #[test]
+    fn rs256_from_jwk() -> Result<(), Error> {
+        let private_key = PKeyWithDigest::new(Rs256, PKey::from_rsa(Rsa::generate(2048)?)?)?;
+        let signature = private_key.sign(&AlgOnly(Rs256).to_base64()?, CLAIMS)?;
+        let rsa = private_key.key.rsa()?;
+
+        let mut jwk = Jwk::new(KeyType::Rsa);
+        jwk.algorithm = Some("RS256".into());
+        jwk.n = Some(URL_SAFE_NO_PAD.encode(rsa.n().to_vec()));
+        jwk.e = Some(URL_SAFE_NO_PAD.encode(rsa.e().to_vec()));
+
+        let algorithm = PKeyWithDigest::from_jwk(&jwk)?;
+        let verification_result =
+            algorithm.verify(&AlgOnly(Rs256).to_base64()?, CLAIMS, &signature)?;
         assert!(verification_result);
+
+        jwk.algorithm = Some("ES256".into());
+        assert!(PKeyWithDigest::from_jwk(&jwk).is_err());
         Ok(())
     }
+
+    #[test]
+    fn es256_from_jwk() -> Result<(), Error> {
+        let private_pem = include_bytes!("../../test/es256-private.pem");
+        let private_key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(private_pem)?,
+        };
+        let signature = private_key.sign(&AlgOnly(Es256).to_base64()?, CLAIMS)?;
+
+        let public_pem = include_bytes!("../../test/es256-public.pem");
+        let ec_key = PKey::public_key_from_pem(public_pem)?.ec_key()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+        let mut context = BigNumContext::new()?;
+        ec_key
+            .public_key()
+            .affine_coordinates(ec_key.group(), &mut x, &mut y, &mut context)?;
+
+        let mut jwk = Jwk::new(KeyType::EllipticCurve);
+        jwk.crv = Some("P-256".into());
+        jwk.x = Some(URL_SAFE_NO_PAD.encode(x.to_vec()));
+        jwk.y = Some(URL_SAFE_NO_PAD.encode(y.to_vec()));
+
+        let public_key = PKeyWithDigest::from_jwk(&jwk)?;
+        let verification_result =
+            public_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &signature)?;
+        assert!(verification_result);
+        Ok(())
+    }
+
+    #[test]
+    fn bound_to_algorithm() -> Result<(), Error> {
+        let rsa_pem = include_bytes!("../../test/rs256-public.pem");
+        let es_pem = include_bytes!("../../test/es256-public.pem");
+
+        let rs256 = PKeyWithDigest::new_with_minimum_rsa_bits(
+            Rs256,
+            PKey::public_key_from_pem(rsa_pem)?,
+            1024,
+        )?;
+        assert_eq!(VerifyingAlgorithm::algorithm_type(&rs256), Rs256);
+        assert!(PKeyWithDigest::new(Es256, PKey::public_key_from_pem(rsa_pem)?).is_err());
+        assert!(PKeyWithDigest::new(Hs256, PKey::public_key_from_pem(rsa_pem)?).is_err());
+
+        let es256 = PKeyWithDigest::new(Es256, PKey::public_key_from_pem(es_pem)?)?;
+        assert_eq!(VerifyingAlgorithm::algorithm_type(&es256), Es256);
+        assert!(PKeyWithDigest::new(Es512, PKey::public_key_from_pem(es_pem)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn from_pem() -> Result<(), Error> {
+        let es256 = PKeyWithDigest::from_pem(include_bytes!("../../test/es256-public.pem"))?;
+        assert_eq!(VerifyingAlgorithm::algorithm_type(&es256), Es256);
+        // The test RSA key has only 1024 bits
+        assert!(matches!(
+            PKeyWithDigest::from_pem(include_bytes!("../../test/rs256-public.pem")),
+            Err(Error::RsaKeyTooShort(1024, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn to_jwk() -> Result<(), Error> {
+        let es256 = PKeyWithDigest::from_pem(include_bytes!("../../test/es256-public.pem"))?;
+        let jwk = es256.to_jwk()?;
+        assert_eq!(jwk.algorithm.as_deref(), Some("ES256"));
+        assert_eq!(jwk.crv.as_deref(), Some("P-256"));
+        let from_jwk = PKeyWithDigest::from_jwk(&jwk)?;
+        assert!(from_jwk.key.public_eq(&es256.key));
+
+        let rs256 = PKeyWithDigest::new_with_minimum_rsa_bits(
+            Rs256,
+            PKey::public_key_from_pem(include_bytes!("../../test/rs256-public.pem"))?,
+            1024,
+        )?;
+        let jwk = rs256.to_jwk()?;
+        assert_eq!(jwk.e.as_deref(), Some("AQAB"));
+        assert_eq!(jwk.key_type, KeyType::Rsa);
+        Ok(())
+    }
+
+    #[test]
+    fn algorithm_confusion() -> Result<(), Error> {
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::VerifyWithKey;
+        use crate::{Claims, Header, Token};
+        use hmac::{Hmac, KeyInit};
+        use sha2::Sha256;
+
+        // Sign an HS256 token with the public key as the secret
+        let pem = include_bytes!("../../test/rs256-public.pem");
+        let hmac_key: Hmac<Sha256> = Hmac::new_from_slice(pem)?;
+        let forged = Claims::default().sign_with_key(&hmac_key)?;
+
+        let public_key = PKeyWithDigest::new_with_minimum_rsa_bits(
+            Rs256,
+            PKey::public_key_from_pem(pem)?,
+            1024,
+        )?;
+        let result: Result<Token<Header, Claims, _>, _> =
+            forged.as_str().verify_with_key(&public_key);
+        match result {
+            Err(Error::AlgorithmMismatch(Hs256, Rs256)) => Ok(()),
+            x => panic!("Incorrect result {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn rsa_key_strength() -> Result<(), Error> {
+        let pem = include_bytes!("../../test/rs256-public.pem");
+        match PKeyWithDigest::new(Rs256, PKey::public_key_from_pem(pem)?) {
+            Err(Error::RsaKeyTooShort(1024, 2048)) => (),
+            x => panic!("Incorrect result {:?}", x.map(|_| ())),
+        }
+
+        let rsa = Rsa::generate(2048)?;
+        assert!(PKeyWithDigest::new(Rs256, PKey::from_rsa(rsa.clone())?).is_ok());
+
+        let n = rsa.n().to_owned()?;
+        let degenerate = Rsa::from_public_components(n, BigNum::from_u32(1)?)?;
+        match PKeyWithDigest::new(Rs256, PKey::from_rsa(degenerate)?) {
+            Err(Error::DegenerateKey) => Ok(()),
+            x => panic!("Incorrect result {:?}", x.map(|_| ())),
+        }
+    }
 }
@@ -0,0 +1,313 @@
+//! Revocation checking of `x5c` certificates through OCSP and CRLs, for
+//! [TrustAnchors](../x5c/struct.TrustAnchors.html). The responses and lists
+//! are downloaded by a [Fetcher](trait.Fetcher.html), so any HTTP client,
+//! cache or offline mirror can be plugged in.
+//!
+//! For each certificate of a chain, the OCSP responder it names is asked
+//! first, then its CRL distribution points. OCSP responses must be signed by
+//! the issuer of the certificate, as must CRLs. Certificates that name
+//! neither are not checked. If a certificate names them, but its status can
+//! not be determined, it is rejected unless
+//! [Unavailable::Accept](enum.Unavailable.html) is configured.
+//! ## Examples
+//! ```no_run
+//! use jwt::algorithm::revocation::{Fetcher, RevocationChecker};
+//! use jwt::algorithm::x5c::TrustAnchors;
+//!
+//! struct Offline;
+//!
+//! impl Fetcher for Offline {
+//!     fn get(&self, url: &str) -> Result<Vec<u8>, String> {
+//!         std::fs::read(url.rsplit('/').next().unwrap()).map_err(|e| e.to_string())
+//!     }
+//!
+//!     fn post_ocsp(&self, _url: &str, _request: &[u8]) -> Result<Vec<u8>, String> {
+//!         Err("offline".into())
+//!     }
+//! }
+//!
+//! let anchors = TrustAnchors::system()
+//!     .unwrap()
+//!     .with_revocation(RevocationChecker::new(Offline));
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use openssl::asn1::Asn1Time;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{
+    OcspCertId, OcspCertStatus, OcspFlag, OcspRequest, OcspResponse, OcspResponseStatus,
+};
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{CrlStatus, X509Crl, X509Ref};
+
+use crate::error::Error;
+
+/// Tolerated clock skew for the update times of responses, in seconds.
+const SKEW: u32 = 300;
+
+/// Checks whether a certificate of a verified chain has been revoked.
+pub trait RevocationCheck: Send + Sync {
+    fn check(&self, certificate: &X509Ref, issuer: &X509Ref) -> Result<(), Error>;
+}
+
+/// Downloads revocation information. Errors are reported as the reason the
+/// status is unavailable.
+pub trait Fetcher: Send + Sync {
+    /// Get the DER encoded CRL at the URL.
+    fn get(&self, url: &str) -> Result<Vec<u8>, String>;
+
+    /// Post a DER encoded OCSP request to the responder, with the content
+    /// type `application/ocsp-request`, and return the DER encoded response.
+    fn post_ocsp(&self, url: &str, request: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// What to do with a certificate whose revocation status can not be
+/// determined, e.g. because the responder is down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unavailable {
+    #[default]
+    Reject,
+    Accept,
+}
+
+/// Checks certificates through OCSP and CRLs.
+pub struct RevocationChecker<F> {
+    fetcher: F,
+    unavailable: Unavailable,
+}
+
+impl<F: Fetcher> RevocationChecker<F> {
+    pub fn new(fetcher: F) -> Self {
+        RevocationChecker {
+            fetcher,
+            unavailable: Unavailable::default(),
+        }
+    }
+
+    pub fn on_unavailable(mut self, unavailable: Unavailable) -> Self {
+        self.unavailable = unavailable;
+        self
+    }
+
+    /// Whether the responder says the certificate is revoked, or why it did
+    /// not say.
+    fn ocsp(&self, url: &str, certificate: &X509Ref, issuer: &X509Ref) -> Result<bool, String> {
+        let id = || OcspCertId::from_cert(MessageDigest::sha1(), certificate, issuer);
+        let mut request = OcspRequest::new().map_err(unavailable)?;
+        request
+            .add_id(id().map_err(unavailable)?)
+            .map_err(unavailable)?;
+        let request = request.to_der().map_err(unavailable)?;
+
+        let response = self.fetcher.post_ocsp(url, &request)?;
+        let response = OcspResponse::from_der(&response).map_err(unavailable)?;
+        if response.status() != OcspResponseStatus::SUCCESSFUL {
+            let status = response.status().as_raw();
+            return Err(format!("OCSP responder failed with status {}", status));
+        }
+        let basic = response.basic().map_err(unavailable)?;
+        let mut signers = Stack::new().map_err(unavailable)?;
+        signers.push(issuer.to_owned()).map_err(unavailable)?;
+        let store = X509StoreBuilder::new().map_err(unavailable)?.build();
+        basic
+            .verify(&signers, &store, OcspFlag::TRUST_OTHER)
+            .map_err(|_| "OCSP response is not signed by the issuer".to_owned())?;
+
+        let id = id().map_err(unavailable)?;
+        let status = basic
+            .find_status(&id)
+            .ok_or("OCSP response is for another certificate")?;
+        status
+            .check_validity(SKEW, None)
+            .map_err(|_| "OCSP response is outdated".to_owned())?;
+        match status.status {
+            OcspCertStatus::GOOD => Ok(false),
+            OcspCertStatus::REVOKED => Ok(true),
+            _ => Err("OCSP responder does not know the certificate".into()),
+        }
+    }
+
+    /// Whether the CRL lists the certificate, or why it can not be used.
+    fn crl(&self, url: &str, certificate: &X509Ref, issuer: &X509Ref) -> Result<bool, String> {
+        let crl = self.fetcher.get(url)?;
+        let crl = X509Crl::from_der(&crl).map_err(unavailable)?;
+        let issuer_key = issuer.public_key().map_err(unavailable)?;
+        if !crl.verify(&issuer_key).map_err(unavailable)? {
+            return Err("CRL is not signed by the issuer".into());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let outdated =
+            Asn1Time::from_unix(now.as_secs() as i64 - i64::from(SKEW)).map_err(unavailable)?;
+        if crl.next_update().is_some_and(|next| *next < outdated) {
+            return Err("CRL is outdated".into());
+        }
+        Ok(matches!(
+            crl.get_by_cert(&certificate.to_owned()),
+            CrlStatus::Revoked(_)
+        ))
+    }
+}
+
+fn unavailable(error: ErrorStack) -> String {
+    error.to_string()
+}
+
+impl<F: Fetcher> RevocationCheck for RevocationChecker<F> {
+    fn check(&self, certificate: &X509Ref, issuer: &X509Ref) -> Result<(), Error> {
+        let responders = certificate.ocsp_responders()?;
+        let distribution_points = certificate.crl_distribution_points();
+        let crls = distribution_points
+            .iter()
+            .flatten()
+            .filter_map(|point| point.distpoint()?.fullname())
+            .flatten()
+            .filter_map(|name| name.uri());
+
+        let ocsp = responders
+            .iter()
+            .map(|url| self.ocsp(url, certificate, issuer));
+        let crl = crls.map(|url| self.crl(url, certificate, issuer));
+        let mut reason = None;
+        for result in ocsp.chain(crl) {
+            match result {
+                Ok(false) => return Ok(()),
+                Ok(true) => return Err(Error::CertificateRevoked),
+                Err(e) => reason = Some(e),
+            }
+        }
+
+        match reason {
+            Some(reason) if self.unavailable == Unavailable::Reject => {
+                Err(Error::RevocationUnavailable(reason))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<F: Fetcher> Fetcher for &F {
+    fn get(&self, url: &str) -> Result<Vec<u8>, String> {
+        (**self).get(url)
+    }
+
+    fn post_ocsp(&self, url: &str, request: &[u8]) -> Result<Vec<u8>, String> {
+        (**self).post_ocsp(url, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use openssl::x509::X509;
+
+    use crate::algorithm::revocation::{Fetcher, RevocationCheck, RevocationChecker, Unavailable};
+    use crate::algorithm::x5c::TrustAnchors;
+    use crate::error::Error;
+
+    const ROOT: &[u8] = include_bytes!("../../test/revocation-root.pem");
+    const LEAF: &[u8] = include_bytes!("../../test/revocation-leaf.pem");
+
+    /// Serves fixed responses and records the URLs it was asked for.
+    struct Fixed {
+        crl: Option<&'static [u8]>,
+        ocsp: Option<&'static [u8]>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl Fixed {
+        fn new(crl: Option<&'static [u8]>, ocsp: Option<&'static [u8]>) -> Self {
+            Fixed {
+                crl,
+                ocsp,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Fetcher for Fixed {
+        fn get(&self, url: &str) -> Result<Vec<u8>, String> {
+            self.requests.lock().unwrap().push(url.to_owned());
+            self.crl
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| "unreachable".into())
+        }
+
+        fn post_ocsp(&self, url: &str, _request: &[u8]) -> Result<Vec<u8>, String> {
+            self.requests.lock().unwrap().push(url.to_owned());
+            self.ocsp
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| "unreachable".into())
+        }
+    }
+
+    fn check(fetcher: Fixed, unavailable: Unavailable) -> Result<(), Error> {
+        let (root, leaf) = (X509::from_pem(ROOT)?, X509::from_pem(LEAF)?);
+        let checker = RevocationChecker::new(fetcher).on_unavailable(unavailable);
+        checker.check(&leaf, &root)
+    }
+
+    #[test]
+    pub fn ocsp() -> Result<(), Error> {
+        let good = include_bytes!("../../test/revocation-good.ocsp");
+        let revoked = include_bytes!("../../test/revocation-revoked.ocsp");
+        check(Fixed::new(None, Some(good)), Unavailable::Reject)?;
+        assert!(matches!(
+            check(Fixed::new(None, Some(revoked)), Unavailable::Reject),
+            Err(Error::CertificateRevoked)
+        ));
+        // A response signed by someone else
+        let other = include_bytes!("../../test/revocation-empty.crl");
+        assert!(check(Fixed::new(None, Some(other)), Unavailable::Reject).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn crl() -> Result<(), Error> {
+        let empty = include_bytes!("../../test/revocation-empty.crl");
+        let revoked = include_bytes!("../../test/revocation-revoked.crl");
+
+        let fetcher = Fixed::new(Some(empty), None);
+        let (root, leaf) = (X509::from_pem(ROOT)?, X509::from_pem(LEAF)?);
+        RevocationChecker::new(&fetcher).check(&leaf, &root)?;
+        assert_eq!(
+            *fetcher.requests.lock().unwrap(),
+            ["http://ocsp.test", "http://crl.test/root.crl"]
+        );
+
+        assert!(matches!(
+            check(Fixed::new(Some(revoked), None), Unavailable::Accept),
+            Err(Error::CertificateRevoked)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn unavailable() -> Result<(), Error> {
+        assert!(matches!(
+            check(Fixed::new(None, None), Unavailable::Reject),
+            Err(Error::RevocationUnavailable(_))
+        ));
+        check(Fixed::new(None, None), Unavailable::Accept)
+    }
+
+    #[test]
+    pub fn trust_anchors() -> Result<(), Error> {
+        let revoked = include_bytes!("../../test/revocation-revoked.crl");
+        let anchors = TrustAnchors::from_pem(ROOT)?
+            .with_revocation(RevocationChecker::new(Fixed::new(Some(revoked), None)));
+        let chain = [STANDARD.encode(X509::from_pem(LEAF)?.to_der()?)];
+        assert!(matches!(
+            anchors.verify(&chain),
+            Err(Error::CertificateRevoked)
+        ));
+        Ok(())
+    }
+}
@@ -1,19 +1,28 @@
 //! RustCrypto implementations of signing and verifying algorithms.
 //! According to that organization, only hmac is safely implemented at the
-//! moment.
-
-use digest::{
-    block_buffer::Eager,
-    consts::U256,
-    core_api::{BlockSizeUser, BufferKindUser, CoreProxy, FixedOutputCore},
-    generic_array::typenum::{IsLess, Le, NonZero},
-    HashMarker,
-};
-use hmac::{Hmac, Mac};
+//! moment. The implementations for `Hmac` require the default `hmac` feature.
+
+#[cfg(feature = "hmac")]
+use alloc::string::String;
+
+#[cfg(feature = "hmac")]
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+#[cfg(feature = "hmac")]
+use base64::Engine;
+#[cfg(feature = "hmac")]
+use hmac::{EagerHash, Hmac, KeyInit, Mac};
 
-use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+#[cfg(feature = "hmac")]
+use crate::algorithm::incremental::{
+    IncrementalSigningAlgorithm, IncrementalVerifyingAlgorithm, SigningInput, VerifyingInput,
+};
+use crate::algorithm::AlgorithmType;
+#[cfg(feature = "hmac")]
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
 use crate::error::Error;
+#[cfg(feature = "hmac")]
 use crate::SEPARATOR;
+
 /// A trait used to make the implementation of `SigningAlgorithm` and
 /// `VerifyingAlgorithm` easier.
 /// RustCrypto crates tend to have algorithm types defined at the type level,
@@ -22,6 +31,7 @@ pub trait TypeLevelAlgorithmType {
     fn algorithm_type() -> AlgorithmType;
 }
 
+#[cfg(feature = "hmac")]
 macro_rules! type_level_algorithm_type {
     ($rust_crypto_type: ty, $algorithm_type: expr) => {
         impl TypeLevelAlgorithmType for $rust_crypto_type {
@@ -32,21 +42,84 @@ macro_rules! type_level_algorithm_type {
     };
 }
 
+#[cfg(feature = "hmac")]
 type_level_algorithm_type!(sha2::Sha256, AlgorithmType::Hs256);
+#[cfg(feature = "hmac")]
 type_level_algorithm_type!(sha2::Sha384, AlgorithmType::Hs384);
+#[cfg(feature = "hmac")]
 type_level_algorithm_type!(sha2::Sha512, AlgorithmType::Hs512);
 
+/// What to do when an HMAC key is shorter than the minimum length.
+#[derive(Clone, Copy, Debug)]
+pub enum KeyLengthPolicy {
+    /// Short keys are rejected with an error.
+    Reject,
+    /// Short keys are accepted, but the function is called with the algorithm
+    /// and the key length first, e.g. to log a warning.
+    Warn(fn(AlgorithmType, usize)),
+    /// Short keys are accepted silently.
+    Allow,
+}
+
+/// The minimum key length in bytes for an HMAC algorithm. According to
+/// [RFC 7518](https://tools.ietf.org/html/rfc7518#section-3.2) the key must
+/// be at least as long as the output of the hash function.
+pub fn minimum_hmac_key_length(algorithm_type: AlgorithmType) -> Option<usize> {
+    match algorithm_type {
+        AlgorithmType::Hs256 => Some(32),
+        AlgorithmType::Hs384 => Some(48),
+        AlgorithmType::Hs512 => Some(64),
+        _ => None,
+    }
+}
+
+/// Check the length of an HMAC key against the minimum length of the
+/// algorithm.
+pub fn check_hmac_key_length(
+    algorithm_type: AlgorithmType,
+    key: &[u8],
+    policy: KeyLengthPolicy,
+) -> Result<(), Error> {
+    let minimum = match minimum_hmac_key_length(algorithm_type) {
+        Some(minimum) if key.len() < minimum => minimum,
+        _ => return Ok(()),
+    };
+
+    match policy {
+        KeyLengthPolicy::Reject => Err(Error::KeyTooShort(algorithm_type, key.len(), minimum)),
+        KeyLengthPolicy::Warn(warn) => {
+            warn(algorithm_type, key.len());
+            Ok(())
+        }
+        KeyLengthPolicy::Allow => Ok(()),
+    }
+}
+
+/// Constructors for HMAC keys that check the key length.
+pub trait NewCheckedHmac: Sized {
+    /// Create a key, rejecting keys that are shorter than the minimum length.
+    fn new_checked(key: &[u8]) -> Result<Self, Error> {
+        Self::new_with_policy(key, KeyLengthPolicy::Reject)
+    }
+
+    fn new_with_policy(key: &[u8], policy: KeyLengthPolicy) -> Result<Self, Error>;
+}
+
+#[cfg(feature = "hmac")]
+impl<D> NewCheckedHmac for Hmac<D>
+where
+    D: EagerHash + TypeLevelAlgorithmType,
+{
+    fn new_with_policy(key: &[u8], policy: KeyLengthPolicy) -> Result<Self, Error> {
+        check_hmac_key_length(D::algorithm_type(), key, policy)?;
+        Ok(Hmac::new_from_slice(key)?)
+    }
+}
+
+#[cfg(feature = "hmac")]
 impl<D> SigningAlgorithm for Hmac<D>
 where
-    D: CoreProxy + TypeLevelAlgorithmType,
-    D::Core: HashMarker
-        + BufferKindUser<BufferKind = Eager>
-        + FixedOutputCore
-        + digest::Reset
-        + Default
-        + Clone,
-    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
-    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+    D: EagerHash + TypeLevelAlgorithmType,
 {
     fn algorithm_type(&self) -> AlgorithmType {
         D::algorithm_type()
@@ -56,21 +129,14 @@ where
         let hmac = get_hmac_with_data(self, header, claims);
         let mac_result = hmac.finalize();
         let code = mac_result.into_bytes();
-        Ok(base64::encode_config(&code, base64::URL_SAFE_NO_PAD))
+        Ok(URL_SAFE_NO_PAD.encode(&code))
     }
 }
 
+#[cfg(feature = "hmac")]
 impl<D> VerifyingAlgorithm for Hmac<D>
 where
-    D: CoreProxy + TypeLevelAlgorithmType,
-    D::Core: HashMarker
-        + BufferKindUser<BufferKind = Eager>
-        + FixedOutputCore
-        + digest::Reset
-        + Default
-        + Clone,
-    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
-    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+    D: EagerHash + TypeLevelAlgorithmType,
 {
     fn algorithm_type(&self) -> AlgorithmType {
         D::algorithm_type()
@@ -78,25 +144,96 @@ where
 
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
         let hmac = get_hmac_with_data(self, header, claims);
-        hmac.verify_slice(signature)?;
-        Ok(true)
+        // verify_slice compares in constant time
+        Ok(hmac.verify_slice(signature).is_ok())
+    }
+}
+
+/// The state of an HMAC over input that is fed in pieces.
+#[cfg(feature = "hmac")]
+pub struct HmacInput<D>(Hmac<D>)
+where
+    D: EagerHash;
+
+#[cfg(feature = "hmac")]
+impl<D> HmacInput<D>
+where
+    D: EagerHash,
+{
+    fn new(hmac: &Hmac<D>) -> Self {
+        HmacInput(hmac.clone())
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<D> SigningInput for HmacInput<D>
+where
+    D: EagerHash,
+{
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.0.update(bytes);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<String, Error> {
+        Ok(URL_SAFE_NO_PAD.encode(self.0.finalize().into_bytes()))
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<D> VerifyingInput for HmacInput<D>
+where
+    D: EagerHash,
+{
+    fn update(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.0.update(bytes);
+        Ok(())
+    }
+
+    fn finish(self, signature: &[u8]) -> Result<bool, Error> {
+        Ok(self.0.verify_slice(signature).is_ok())
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<D> IncrementalSigningAlgorithm for Hmac<D>
+where
+    D: EagerHash + TypeLevelAlgorithmType,
+{
+    type Input<'a>
+        = HmacInput<D>
+    where
+        D: 'a;
+
+    fn begin(&self) -> Result<HmacInput<D>, Error> {
+        Ok(HmacInput::new(self))
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<D> IncrementalVerifyingAlgorithm for Hmac<D>
+where
+    D: EagerHash + TypeLevelAlgorithmType,
+{
+    type Input<'a>
+        = HmacInput<D>
+    where
+        D: 'a;
+
+    fn begin(&self) -> Result<HmacInput<D>, Error> {
+        Ok(HmacInput::new(self))
     }
 }
 
-fn get_hmac_with_data<D>(hmac: &Hmac<D>, header: &str, claims: &str) -> Hmac<D>
+/// Cloning the keyed `Hmac` copies the hash states of the padded key and does
+/// not allocate. Creating the `Hmac` from the key instead would hash the key
+/// again for every token, which the `hmac` benchmark shows to be slower.
+#[cfg(feature = "hmac")]
+pub(crate) fn get_hmac_with_data<D>(hmac: &Hmac<D>, header: &str, claims: &str) -> Hmac<D>
 where
-    D: CoreProxy,
-    D::Core: HashMarker
-        + BufferKindUser<BufferKind = Eager>
-        + FixedOutputCore
-        + digest::Reset
-        + Default
-        + Clone,
-    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
-    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+    D: EagerHash,
 {
     let mut hmac = hmac.clone();
-    hmac.reset();
     hmac.update(header.as_bytes());
     hmac.update(SEPARATOR.as_bytes());
     hmac.update(claims.as_bytes());
@@ -105,10 +242,13 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::algorithm::rust_crypto::{KeyLengthPolicy, NewCheckedHmac};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
     use crate::error::Error;
-    use hmac::{Hmac, Mac};
-    use sha2::Sha256;
+    use hmac::{Hmac, KeyInit};
+    use sha2::{Sha256, Sha512};
 
     #[test]
     pub fn sign() -> Result<(), Error> {
@@ -135,4 +275,24 @@ mod tests {
         )?);
         Ok(())
     }
+
+    #[test]
+    pub fn key_length() -> Result<(), Error> {
+        assert!(Hmac::<Sha256>::new_checked(&[0; 32]).is_ok());
+        match Hmac::<Sha256>::new_checked(b"secret") {
+            Err(Error::KeyTooShort(AlgorithmType::Hs256, 6, 32)) => (),
+            x => panic!("Incorrect result {:?}", x.map(|_| ())),
+        }
+        assert!(Hmac::<Sha512>::new_checked(&[0; 48]).is_err());
+        assert!(Hmac::<Sha512>::new_with_policy(&[0; 48], KeyLengthPolicy::Allow).is_ok());
+
+        static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+        fn warn(_: AlgorithmType, _: usize) {
+            WARNINGS.fetch_add(1, Ordering::SeqCst);
+        }
+        Hmac::<Sha256>::new_with_policy(b"secret", KeyLengthPolicy::Warn(warn))?;
+        Hmac::<Sha256>::new_with_policy(&[0; 32], KeyLengthPolicy::Warn(warn))?;
+        assert_eq!(WARNINGS.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
 }
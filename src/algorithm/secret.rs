@@ -0,0 +1,287 @@
+//! Secret key material that is wiped from memory when it is dropped.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::algorithm::secret::SecretBytes;
+//! use jwt::AlgorithmType;
+//!
+//! let secret = SecretBytes::from(std::env::var("JWT_SECRET").unwrap_or_default());
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, &secret).unwrap();
+//! ```
+//!
+//! With the `hmac` feature, the `zeroize` features of the hmac and sha2
+//! crates are enabled, so `Hmac` keys wipe their key-derived state when they
+//! are dropped. Only the padded copy of the secret that the hmac crate keeps
+//! on the stack while setting up a key is never wiped, which is out of reach
+//! of this crate. A [SecretHmac](struct.SecretHmac.html) keeps the secret
+//! itself wrapped instead, so it is not printed and can be compared, and
+//! sets up the HMAC for every signature. Private keys of the openssl crate
+//! are cleared by OpenSSL when they are freed.
+//!
+//! With the `secrecy` feature, the `SecretString` and `SecretSlice<u8>`
+//! (formerly `SecretVec<u8>`) of the secrecy crate convert into
+//! `SecretBytes`, so secrets that an application already keeps in them can
+//! be passed to key constructors without exposing them first.
+//! ```
+//! # #[cfg(feature = "secrecy")]
+//! # {
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::AlgorithmType;
+//! use secrecy::SecretString;
+//!
+//! let secret = SecretString::from("from a vault");
+//! let signer = AnySigner::hmac_secret(AlgorithmType::Hs256, &secret).unwrap();
+//! # }
+//! ```
+
+use alloc::string::String;
+#[cfg(feature = "hmac")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+#[cfg(feature = "hmac")]
+use digest::KeyInit;
+#[cfg(feature = "hmac")]
+use hmac::Hmac;
+#[cfg(feature = "secrecy")]
+use secrecy::{ExposeSecret, SecretSlice, SecretString};
+#[cfg(feature = "hmac")]
+use sha2::{Sha256, Sha384, Sha512};
+use zeroize::Zeroize;
+
+use crate::algorithm::constant_time_eq;
+#[cfg(feature = "hmac")]
+use crate::algorithm::rust_crypto::{check_hmac_key_length, KeyLengthPolicy};
+#[cfg(feature = "hmac")]
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+#[cfg(feature = "hmac")]
+use crate::error::Error;
+
+/// Bytes that are zeroed when dropped. Comparison is constant time, and the
+/// bytes are never printed by `Debug`. Copies made before the bytes were
+/// wrapped, e.g. by growing a `Vec`, are not wiped.
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        SecretBytes(bytes.into())
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        SecretBytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for SecretBytes {
+    fn from(string: String) -> Self {
+        SecretBytes(string.into_bytes())
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<&SecretString> for SecretBytes {
+    fn from(secret: &SecretString) -> Self {
+        SecretBytes::from(secret.expose_secret().as_bytes())
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<SecretString> for SecretBytes {
+    fn from(secret: SecretString) -> Self {
+        SecretBytes::from(&secret)
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<&SecretSlice<u8>> for SecretBytes {
+    fn from(secret: &SecretSlice<u8>) -> Self {
+        SecretBytes::from(secret.expose_secret())
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<SecretSlice<u8>> for SecretBytes {
+    fn from(secret: SecretSlice<u8>) -> Self {
+        SecretBytes::from(&secret)
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {}])", self.0.len())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// An HMAC key of one of the `HS*` algorithms that only keeps the secret, so
+/// that it is wiped from memory when the key is dropped. The HMAC that is set
+/// up for every signature is wiped when it is dropped too, except for the
+/// stack copy of the padded secret, see the [module](index.html).
+#[cfg(feature = "hmac")]
+#[derive(Clone, Debug)]
+pub struct SecretHmac {
+    algorithm_type: AlgorithmType,
+    secret: SecretBytes,
+}
+
+#[cfg(feature = "hmac")]
+impl SecretHmac {
+    pub fn new(
+        algorithm_type: AlgorithmType,
+        secret: impl Into<SecretBytes>,
+    ) -> Result<Self, Error> {
+        SecretHmac::new_with_policy(algorithm_type, secret, KeyLengthPolicy::Allow)
+    }
+
+    /// Create a key, checking the length of the secret.
+    pub fn new_with_policy(
+        algorithm_type: AlgorithmType,
+        secret: impl Into<SecretBytes>,
+        policy: KeyLengthPolicy,
+    ) -> Result<Self, Error> {
+        let secret = secret.into();
+        match algorithm_type {
+            AlgorithmType::Hs256 | AlgorithmType::Hs384 | AlgorithmType::Hs512 => {}
+            other => return Err(Error::UnknownAlgorithmType(other.to_string())),
+        }
+        check_hmac_key_length(algorithm_type, &secret, policy)?;
+        Ok(SecretHmac {
+            algorithm_type,
+            secret,
+        })
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl SigningAlgorithm for SecretHmac {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm_type
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        match self.algorithm_type {
+            AlgorithmType::Hs256 => {
+                Hmac::<Sha256>::new_from_slice(&self.secret)?.sign(header, claims)
+            }
+            AlgorithmType::Hs384 => {
+                Hmac::<Sha384>::new_from_slice(&self.secret)?.sign(header, claims)
+            }
+            _ => Hmac::<Sha512>::new_from_slice(&self.secret)?.sign(header, claims),
+        }
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl VerifyingAlgorithm for SecretHmac {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm_type
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        let secret = &self.secret;
+        match self.algorithm_type {
+            AlgorithmType::Hs256 => {
+                Hmac::<Sha256>::new_from_slice(secret)?.verify_bytes(header, claims, signature)
+            }
+            AlgorithmType::Hs384 => {
+                Hmac::<Sha384>::new_from_slice(secret)?.verify_bytes(header, claims, signature)
+            }
+            _ => Hmac::<Sha512>::new_from_slice(secret)?.verify_bytes(header, claims, signature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use sha2::Sha384;
+
+    use crate::algorithm::secret::{SecretBytes, SecretHmac};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+    use crate::error::Error;
+
+    #[test]
+    pub fn redacted() {
+        let secret = SecretBytes::from("secret".to_owned());
+        assert_eq!(&*secret, b"secret");
+        assert_eq!(format!("{:?}", secret), "SecretBytes([REDACTED; 6])");
+        assert_eq!(secret, SecretBytes::new(b"secret".to_vec()));
+        assert_ne!(secret, SecretBytes::default());
+    }
+
+    #[test]
+    pub fn secret_hmac() -> Result<(), Error> {
+        let key = SecretHmac::new(AlgorithmType::Hs384, b"secret".to_vec())?;
+        assert!(!format!("{:?}", key).contains("secret\""));
+        let hmac: Hmac<Sha384> = Hmac::new_from_slice(b"secret")?;
+        let signature = key.sign("header", "claims")?;
+        assert_eq!(signature, hmac.sign("header", "claims")?);
+        assert!(VerifyingAlgorithm::verify(
+            &key, "header", "claims", &signature
+        )?);
+        assert!(!VerifyingAlgorithm::verify(
+            &key, "header", "other", &signature
+        )?);
+
+        assert!(SecretHmac::new(AlgorithmType::Rs256, b"secret".to_vec()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn hmac_zeroize_on_drop() {
+        // The state of an HMAC is the state of its digests, after the padded
+        // secret
+        fn wiped<T: zeroize::ZeroizeOnDrop>() {}
+        wiped::<sha2::block_api::Sha256VarCore>();
+        wiped::<sha2::block_api::Sha512VarCore>();
+    }
+
+    #[cfg(feature = "secrecy")]
+    #[test]
+    pub fn secrecy() {
+        use secrecy::{SecretSlice, SecretString};
+
+        let string = SecretString::from("secret");
+        assert_eq!(SecretBytes::from(&string), SecretBytes::from(string));
+        let slice = SecretSlice::from(b"secret".to_vec());
+        assert_eq!(&*SecretBytes::from(slice), b"secret");
+    }
+}
@@ -0,0 +1,210 @@
+//! Adapters for the `Signer` and `Verifier` traits of the signature crate,
+//! enabled with the `signature` feature. The RustCrypto crates for RSA,
+//! ECDSA and Ed25519 implement these traits for their keys, so wrapping a
+//! key in a [SignatureSigner](struct.SignatureSigner.html) or a
+//! [SignatureVerifier](struct.SignatureVerifier.html) is enough to sign and
+//! verify tokens with it. The signature must be encoded the way JWS expects
+//! it, e.g. `r || s` for ECDSA, which is what `SignatureEncoding` gives for
+//! these crates.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::signature::{SignatureSigner, SignatureVerifier};
+//! use jwt::{AlgorithmType, RegisteredClaims, SignWithKey, VerifyWithKey};
+//! use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
+//!
+//! let signing_key = SigningKey::from_slice(&[1; 32]).unwrap();
+//! let verifying_key = VerifyingKey::from(&signing_key);
+//!
+//! let signer = SignatureSigner::<_, Signature>::new(AlgorithmType::Es256, signing_key);
+//! let claims = RegisteredClaims {
+//!     subject: Some("someone".into()),
+//!     ..Default::default()
+//! };
+//! let token = claims.sign_with_key(&signer).unwrap();
+//!
+//! let verifier = SignatureVerifier::<_, Signature>::new(AlgorithmType::Es256, verifying_key);
+//! let verified: RegisteredClaims = token.verify_with_key(&verifier).unwrap();
+//! assert_eq!(verified.subject.as_deref(), Some("someone"));
+//! ```
+
+use alloc::string::String;
+use core::marker::PhantomData;
+
+use ::signature::{SignatureEncoding, Signer, Verifier};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+use crate::SEPARATOR;
+
+/// Signs tokens with a key that implements `Signer<S>`.
+pub struct SignatureSigner<K, S> {
+    algorithm_type: AlgorithmType,
+    key: K,
+    signature: PhantomData<fn() -> S>,
+}
+
+impl<K: Signer<S>, S: SignatureEncoding> SignatureSigner<K, S> {
+    /// Sign with the key under the `alg` of the algorithm type, which must
+    /// match the key.
+    pub fn new(algorithm_type: AlgorithmType, key: K) -> Self {
+        SignatureSigner {
+            algorithm_type,
+            key,
+            signature: PhantomData,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Signer<S>, S: SignatureEncoding> SigningAlgorithm for SignatureSigner<K, S> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm_type
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        let message = signing_input(header, claims);
+        let signature = self.key.try_sign(message.as_bytes())?;
+        Ok(URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+}
+
+/// Verifies tokens with a key that implements `Verifier<S>`.
+pub struct SignatureVerifier<K, S> {
+    algorithm_type: AlgorithmType,
+    key: K,
+    signature: PhantomData<fn() -> S>,
+}
+
+impl<K: Verifier<S>, S: SignatureEncoding> SignatureVerifier<K, S> {
+    /// Verify with the key under the `alg` of the algorithm type, which must
+    /// match the key.
+    pub fn new(algorithm_type: AlgorithmType, key: K) -> Self {
+        SignatureVerifier {
+            algorithm_type,
+            key,
+            signature: PhantomData,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Verifier<S>, S: SignatureEncoding> VerifyingAlgorithm for SignatureVerifier<K, S> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm_type
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        // A signature that can not be decoded is not valid for any message
+        let signature = match S::try_from(signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+        let message = signing_input(header, claims);
+        Ok(self.key.verify(message.as_bytes(), &signature).is_ok())
+    }
+}
+
+fn signing_input(header: &str, claims: &str) -> String {
+    let mut message = String::with_capacity(header.len() + SEPARATOR.len() + claims.len());
+    message.push_str(header);
+    message.push_str(SEPARATOR);
+    message.push_str(claims);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
+    use p256::pkcs8::DecodePublicKey;
+    use p256::SecretKey;
+
+    use crate::algorithm::signature::{SignatureSigner, SignatureVerifier};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+    use crate::error::Error;
+
+    const HEADER: &str = "eyJhbGciOiJFUzI1NiJ9";
+    const CLAIMS: &str = "eyJzdWIiOiIxMjM0NTY3ODkwIn0";
+
+    fn keys() -> (SigningKey, VerifyingKey) {
+        let pem = include_str!("../../test/es256-private.pem");
+        let signing_key = SigningKey::from(SecretKey::from_sec1_pem(pem).unwrap());
+        let pem = include_str!("../../test/es256-public.pem");
+        (signing_key, VerifyingKey::from_public_key_pem(pem).unwrap())
+    }
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let (signing_key, verifying_key) = keys();
+        let signer = SignatureSigner::<_, Signature>::new(AlgorithmType::Es256, signing_key);
+        let verifier = SignatureVerifier::<_, Signature>::new(AlgorithmType::Es256, verifying_key);
+
+        let signature = signer.sign(HEADER, CLAIMS)?;
+        assert!(verifier.verify(HEADER, CLAIMS, &signature)?);
+        assert!(!verifier.verify(HEADER, "e30", &signature)?);
+        // Not the 64 bytes of an ES256 signature
+        assert!(!verifier.verify(HEADER, CLAIMS, "c2lnbmF0dXJl")?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    pub fn openssl_interop() -> Result<(), Error> {
+        use crate::algorithm::openssl::PKeyWithDigest;
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+
+        let (signing_key, verifying_key) = keys();
+        let signer = SignatureSigner::<_, Signature>::new(AlgorithmType::Es256, signing_key);
+        let openssl_verifier = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::public_key_from_pem(include_bytes!("../../test/es256-public.pem"))?,
+        };
+        assert!(openssl_verifier.verify(HEADER, CLAIMS, &signer.sign(HEADER, CLAIMS)?)?);
+
+        let openssl_signer = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../../test/es256-private.pem"))?,
+        };
+        let verifier = SignatureVerifier::<_, Signature>::new(AlgorithmType::Es256, verifying_key);
+        assert!(verifier.verify(HEADER, CLAIMS, &openssl_signer.sign(HEADER, CLAIMS)?)?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    pub fn openssl_leading_zeros() -> Result<(), Error> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+
+        use crate::algorithm::openssl::PKeyWithDigest;
+
+        let (_, verifying_key) = keys();
+        let openssl_signer = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../../test/es256-private.pem"))?,
+        };
+        let verifier = SignatureVerifier::<_, Signature>::new(AlgorithmType::Es256, verifying_key);
+
+        // About one in 128 signatures has an R or S with a leading zero byte
+        for _ in 0..10_000 {
+            let signature = openssl_signer.sign(HEADER, CLAIMS)?;
+            let bytes = URL_SAFE_NO_PAD.decode(&signature)?;
+            assert_eq!(bytes.len(), 64);
+            if bytes[0] == 0 || bytes[32] == 0 {
+                assert!(verifier.verify(HEADER, CLAIMS, &signature)?);
+                return Ok(());
+            }
+        }
+        panic!("no signature with a leading zero byte");
+    }
+}
@@ -1,6 +1,10 @@
-use std::borrow::Borrow;
-use std::collections::{BTreeMap, HashMap};
-use std::hash::Hash;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 /// A store of keys that can be retrieved by key id.
 pub trait Store {
@@ -20,6 +24,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, A> Store for HashMap<K, A>
 where
     K: Borrow<str> + Ord + Hash,
@@ -30,3 +35,90 @@ where
         HashMap::get(self, key_id)
     }
 }
+
+/// A store of signing keys that selects the current key for a purpose, e.g.
+/// an audience, so that code issuing tokens does not need to know the key
+/// ids.
+pub trait KeyStore {
+    type Algorithm: ?Sized;
+
+    /// The id of the key to sign tokens for the purpose with, and the key.
+    fn signing_key(&self, purpose: &str) -> Option<(&str, &Self::Algorithm)>;
+}
+
+/// Selects signing keys from a [Store](trait.Store.html) by the key id
+/// assigned to each purpose, and falls back to a default key id for other
+/// purposes. Keys can be verified with the same store.
+#[derive(Clone, Debug, Default)]
+pub struct SigningKeys<S> {
+    store: S,
+    key_ids: BTreeMap<String, String>,
+    default_key_id: Option<String>,
+}
+
+impl<S: Store> SigningKeys<S> {
+    pub fn new(store: S) -> Self {
+        SigningKeys {
+            store,
+            key_ids: BTreeMap::new(),
+            default_key_id: None,
+        }
+    }
+
+    /// Sign tokens for the purpose with the key with this id.
+    pub fn purpose(mut self, purpose: impl Into<String>, key_id: impl Into<String>) -> Self {
+        self.key_ids.insert(purpose.into(), key_id.into());
+        self
+    }
+
+    /// Sign tokens for purposes without a key of their own with the key with
+    /// this id.
+    pub fn default_key(mut self, key_id: impl Into<String>) -> Self {
+        self.default_key_id = Some(key_id.into());
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<S: Store> KeyStore for SigningKeys<S> {
+    type Algorithm = S::Algorithm;
+
+    fn signing_key(&self, purpose: &str) -> Option<(&str, &S::Algorithm)> {
+        let key_id = self.key_ids.get(purpose).or(self.default_key_id.as_ref())?;
+        let key = self.store.get(key_id)?;
+        Some((key_id, key))
+    }
+}
+
+impl<S: Store> Store for SigningKeys<S> {
+    type Algorithm = S::Algorithm;
+
+    fn get(&self, key_id: &str) -> Option<&S::Algorithm> {
+        self.store.get(key_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::algorithm::store::{KeyStore, SigningKeys, Store};
+
+    #[test]
+    pub fn signing_keys() {
+        let store = BTreeMap::from([("a", 1), ("b", 2)]);
+        let keys = SigningKeys::new(store)
+            .purpose("orders", "a")
+            .purpose("broken", "c");
+        assert_eq!(keys.signing_key("orders"), Some(("a", &1)));
+        assert_eq!(keys.signing_key("broken"), None);
+        assert_eq!(keys.signing_key("billing"), None);
+        assert_eq!(keys.get("b"), Some(&2));
+
+        let keys = keys.default_key("b");
+        assert_eq!(keys.signing_key("billing"), Some(("b", &2)));
+    }
+}
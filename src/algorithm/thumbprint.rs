@@ -0,0 +1,149 @@
+//! Signing with the [RFC 7638](https://tools.ietf.org/html/rfc7638)
+//! thumbprint of the key as the `kid` header, so tokens name the key by the
+//! same id as a key set that is published with
+//! [KeyIds::Thumbprint](../../keystore/enum.KeyIds.html), without keeping
+//! track of key ids by hand.
+//!
+//! The thumbprint of a symmetric key is a hash of its secret, so it is only
+//! suitable as a key id for keys with enough entropy.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::thumbprint::ThumbprintSigner;
+//! use jwt::jwk::Jwk;
+//! use jwt::{RegisteredClaims, Token};
+//!
+//! let jwk: Jwk = serde_json::from_str(
+//!     r#"{"kty":"oct","alg":"HS256","k":"c2VjcmV0LXdpdGgtZW5vdWdoLWVudHJvcHk"}"#,
+//! )
+//! .unwrap();
+//! let signer = ThumbprintSigner::from_jwk(&jwk).unwrap();
+//! let token = signer.sign(RegisteredClaims::default()).unwrap();
+//!
+//! let unverified: Token<jwt::Header, RegisteredClaims, _> =
+//!     Token::parse_unverified(&token).unwrap();
+//! assert_eq!(unverified.header().key_id.as_deref(), Some(signer.key_id()));
+//! ```
+
+use alloc::string::String;
+
+#[cfg(feature = "openssl")]
+use openssl::pkey::Private;
+
+use crate::algorithm::any::AnySigner;
+#[cfg(feature = "openssl")]
+use crate::algorithm::openssl::PKeyWithDigest;
+use crate::algorithm::{AlgorithmType, SigningAlgorithm};
+use crate::error::Error;
+use crate::header::Header;
+use crate::jwk::Jwk;
+use crate::token::signed::SignWithKey;
+use crate::{ToBase64, Token};
+
+/// Signs with a key and sets the `kid` of the tokens to its thumbprint.
+pub struct ThumbprintSigner<A> {
+    key: A,
+    thumbprint: String,
+}
+
+impl<A: SigningAlgorithm> ThumbprintSigner<A> {
+    /// Sign with the key whose JWK is `jwk`. Only the public members of the
+    /// JWK are used for the thumbprint.
+    pub fn new(key: A, jwk: &Jwk) -> Result<Self, Error> {
+        Ok(ThumbprintSigner {
+            thumbprint: jwk.thumbprint()?,
+            key,
+        })
+    }
+
+    /// The thumbprint, which is the `kid` of the tokens.
+    pub fn key_id(&self) -> &str {
+        &self.thumbprint
+    }
+
+    /// A header with the algorithm and the `kid` of the key, for signing
+    /// tokens with other header fields.
+    pub fn header(&self) -> Header {
+        Header {
+            algorithm: self.key.algorithm_type(),
+            key_id: Some(self.thumbprint.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Sign claims, with the thumbprint as the `kid`.
+    pub fn sign<T: ToBase64>(&self, claims: T) -> Result<String, Error> {
+        let token = Token::new(self.header(), claims).sign_with_key(&self.key)?;
+        Ok(token.into())
+    }
+
+    pub fn into_inner(self) -> A {
+        self.key
+    }
+}
+
+impl ThumbprintSigner<AnySigner> {
+    /// Sign with the key of a JSON Web Key, see
+    /// [AnySigner::from_jwk](../any/struct.AnySigner.html#method.from_jwk).
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+        ThumbprintSigner::new(AnySigner::from_jwk(jwk)?, jwk)
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl ThumbprintSigner<PKeyWithDigest<Private>> {
+    /// Sign with a private key, with the thumbprint of its public key.
+    pub fn from_pkey(key: PKeyWithDigest<Private>) -> Result<Self, Error> {
+        let jwk = key.to_jwk()?;
+        ThumbprintSigner::new(key, &jwk)
+    }
+}
+
+impl<A: SigningAlgorithm> SigningAlgorithm for ThumbprintSigner<A> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.key.algorithm_type()
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        self.key.sign(header, claims)
+    }
+    fn signature_length(&self) -> Option<usize> {
+        self.key.signature_length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::algorithm::any::AnyVerifier;
+    use crate::algorithm::thumbprint::ThumbprintSigner;
+    use crate::error::Error;
+    use crate::header::HeaderType;
+    use crate::jwk::Jwk;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithStore;
+    use crate::{Header, RegisteredClaims, Token};
+
+    #[test]
+    pub fn key_id() -> Result<(), Error> {
+        let jwk: Jwk = serde_json::from_str(r#"{"kty":"oct","alg":"HS256","k":"c2VjcmV0"}"#)?;
+        let signer = ThumbprintSigner::from_jwk(&jwk)?;
+        assert_eq!(signer.key_id(), jwk.thumbprint()?);
+        let keys = BTreeMap::from([(jwk.thumbprint()?, AnyVerifier::from_jwk(&jwk)?)]);
+
+        let token = signer.sign(RegisteredClaims::default())?;
+        let verified: Token<Header, RegisteredClaims, _> =
+            token.as_str().verify_with_store(&keys)?;
+        assert_eq!(verified.header().key_id.as_deref(), Some(signer.key_id()));
+
+        let header = Header {
+            type_: Some(HeaderType::JsonWebToken),
+            ..signer.header()
+        };
+        let token = Token::new(header, RegisteredClaims::default()).sign_with_key(&signer)?;
+        let verified: Token<Header, RegisteredClaims, _> =
+            token.as_str().verify_with_store(&keys)?;
+        assert_eq!(verified.header().type_, Some(HeaderType::JsonWebToken));
+        Ok(())
+    }
+}
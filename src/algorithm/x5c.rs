@@ -0,0 +1,417 @@
+//! Verification of the `x5c` certificate chains of JSON Web Keys against
+//! configured trust anchors, through OpenSSL. The chain must lead from the
+//! certificate of the key to one of the roots; every certificate must be
+//! within its validity period, and every issuing certificate must be a CA.
+//! Only then is the key of the first certificate trusted, and only if it is
+//! the key of the JWK.
+//!
+//! The roots are either given explicitly or are the ones of the system, in
+//! the default locations of OpenSSL. Intermediate certificates that JWKS
+//! leave out of `x5c` can be configured alongside the roots, and so can
+//! [revocation checking](../revocation/index.html).
+//!
+//! The other way around, a [CertifiedSigner](struct.CertifiedSigner.html)
+//! embeds the chain of its key in the `x5c` header of the tokens it signs,
+//! together with the `x5t#S256` thumbprint of the certificate.
+//! ## Examples
+//! ```no_run
+//! use jwt::algorithm::x5c::TrustAnchors;
+//! use jwt::jwk::Jwk;
+//! use jwt::PKeyWithDigest;
+//!
+//! let anchors = TrustAnchors::from_pem(&std::fs::read("roots.pem").unwrap())
+//!     .unwrap()
+//!     .with_intermediates_pem(&std::fs::read("intermediates.pem").unwrap())
+//!     .unwrap();
+//! let jwk: Jwk = serde_json::from_str(r#"{"kty":"EC","x5c":["..."]}"#).unwrap();
+//! let key = PKeyWithDigest::from_x5c(&jwk, &anchors).unwrap();
+//! ```
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{Private, Public};
+use openssl::stack::Stack;
+use openssl::x509::store::{X509Store, X509StoreBuilder};
+use openssl::x509::{X509StoreContext, X509};
+
+use crate::algorithm::openssl::PKeyWithDigest;
+use crate::algorithm::revocation::RevocationCheck;
+use crate::algorithm::{AlgorithmType, SigningAlgorithm};
+use crate::error::Error;
+use crate::header::Header;
+use crate::jwk::Jwk;
+use crate::token::signed::SignWithKey;
+use crate::{ToBase64, Token};
+
+/// The certificates that `x5c` chains must lead to.
+pub struct TrustAnchors {
+    store: X509Store,
+    intermediates: Vec<X509>,
+    revocation: Option<Box<dyn RevocationCheck>>,
+}
+
+impl TrustAnchors {
+    /// Trust the root certificates of one or more concatenated PEM blocks.
+    pub fn from_pem(pem: &[u8]) -> Result<Self, Error> {
+        TrustAnchors::from_roots(X509::stack_from_pem(pem)?)
+    }
+
+    /// Trust DER encoded root certificates.
+    pub fn from_der<'a>(roots: impl IntoIterator<Item = &'a [u8]>) -> Result<Self, Error> {
+        let roots = roots
+            .into_iter()
+            .map(X509::from_der)
+            .collect::<Result<_, _>>()?;
+        TrustAnchors::from_roots(roots)
+    }
+
+    /// Trust the root certificates of the system.
+    pub fn system() -> Result<Self, Error> {
+        let mut store = X509StoreBuilder::new()?;
+        store.set_default_paths()?;
+        Ok(TrustAnchors {
+            store: store.build(),
+            intermediates: Vec::new(),
+            revocation: None,
+        })
+    }
+
+    fn from_roots(roots: Vec<X509>) -> Result<Self, Error> {
+        let mut store = X509StoreBuilder::new()?;
+        for root in roots {
+            store.add_cert(root)?;
+        }
+        Ok(TrustAnchors {
+            store: store.build(),
+            intermediates: Vec::new(),
+            revocation: None,
+        })
+    }
+
+    /// Use these intermediate certificates to complete chains. They are not
+    /// trusted by themselves.
+    pub fn with_intermediates_pem(mut self, pem: &[u8]) -> Result<Self, Error> {
+        self.intermediates.extend(X509::stack_from_pem(pem)?);
+        Ok(self)
+    }
+
+    /// Check every certificate of a chain but the root for revocation.
+    pub fn with_revocation(mut self, check: impl RevocationCheck + 'static) -> Self {
+        self.revocation = Some(Box::new(check));
+        self
+    }
+
+    /// Verify a chain of base64 (not base64url) DER certificates, starting
+    /// with the one of the key, and return that certificate.
+    pub fn verify(&self, chain: &[String]) -> Result<X509, Error> {
+        let mut certificates = chain.iter().map(|encoded| -> Result<X509, Error> {
+            let der = STANDARD.decode(encoded)?;
+            Ok(X509::from_der(&der)?)
+        });
+        let leaf: X509 = certificates
+            .next()
+            .ok_or(Error::InvalidJwk("x5c is empty"))??;
+        let mut untrusted = Stack::new()?;
+        for certificate in certificates {
+            untrusted.push(certificate?)?;
+        }
+        for intermediate in &self.intermediates {
+            untrusted.push(intermediate.clone())?;
+        }
+
+        let mut context = X509StoreContext::new()?;
+        let result = context.init(&self.store, &leaf, &untrusted, |context| {
+            Ok(if context.verify_cert()? {
+                let chain = context.chain().into_iter().flatten();
+                Ok(chain.map(ToOwned::to_owned).collect::<Vec<_>>())
+            } else {
+                Err(context.error().error_string().to_owned())
+            })
+        })?;
+        let chain = result.map_err(Error::UntrustedCertificate)?;
+
+        if let Some(ref revocation) = self.revocation {
+            for pair in chain.windows(2) {
+                revocation.check(&pair[0], &pair[1])?;
+            }
+        }
+        Ok(leaf)
+    }
+}
+
+impl PKeyWithDigest<Public> {
+    /// Create a public key from a JSON Web Key whose `x5c` chain leads to one
+    /// of the trust anchors. The key of the first certificate must be the key
+    /// of the JWK.
+    pub fn from_x5c(jwk: &Jwk, anchors: &TrustAnchors) -> Result<Self, Error> {
+        let chain = jwk
+            .x509_chain
+            .as_deref()
+            .ok_or(Error::InvalidJwk("no x5c"))?;
+        let leaf = anchors.verify(chain)?;
+        let key = PKeyWithDigest::from_jwk(jwk)?;
+        if !leaf.public_key()?.public_eq(&key.key) {
+            return Err(Error::InvalidJwk("x5c is not for the key"));
+        }
+        Ok(key)
+    }
+}
+
+/// Signs with a private key and puts the certificate chain of the key into
+/// the headers of the tokens: `x5c` and `x5t#S256` by default.
+pub struct CertifiedSigner {
+    key: PKeyWithDigest<Private>,
+    chain: Vec<String>,
+    thumbprint: String,
+    x5c: bool,
+    x5t_s256: bool,
+}
+
+impl CertifiedSigner {
+    /// Sign with the key of the first certificate of the chain. The other
+    /// certificates lead to a root, which may be left out.
+    pub fn new(key: PKeyWithDigest<Private>, chain: &[X509]) -> Result<Self, Error> {
+        let leaf = chain.first().ok_or(Error::InvalidJwk("x5c is empty"))?;
+        if !leaf.public_key()?.public_eq(&key.key) {
+            return Err(Error::InvalidJwk("x5c is not for the key"));
+        }
+        let encoded = chain
+            .iter()
+            .map(|certificate| Ok(STANDARD.encode(certificate.to_der()?)))
+            .collect::<Result<_, Error>>()?;
+        Ok(CertifiedSigner {
+            thumbprint: URL_SAFE_NO_PAD.encode(leaf.digest(MessageDigest::sha256())?),
+            key,
+            chain: encoded,
+            x5c: true,
+            x5t_s256: true,
+        })
+    }
+
+    /// Like [new](#method.new), with the chain in concatenated PEM blocks.
+    pub fn from_pem(key: PKeyWithDigest<Private>, chain: &[u8]) -> Result<Self, Error> {
+        CertifiedSigner::new(key, &X509::stack_from_pem(chain)?)
+    }
+
+    /// Whether to embed the chain as `x5c`.
+    pub fn embed_x5c(mut self, embed: bool) -> Self {
+        self.x5c = embed;
+        self
+    }
+
+    /// Whether to embed the SHA-256 thumbprint of the certificate as
+    /// `x5t#S256`.
+    pub fn embed_x5t_s256(mut self, embed: bool) -> Self {
+        self.x5t_s256 = embed;
+        self
+    }
+
+    /// A header with the algorithm of the key and the embedded members, for
+    /// signing tokens with other header fields.
+    pub fn header(&self) -> Header {
+        let mut header = Header {
+            algorithm: SigningAlgorithm::algorithm_type(&self.key),
+            ..Default::default()
+        };
+        if self.x5c {
+            header.extra.insert("x5c".into(), self.chain.clone().into());
+        }
+        if self.x5t_s256 {
+            let thumbprint = self.thumbprint.clone().into();
+            header.extra.insert("x5t#S256".into(), thumbprint);
+        }
+        header
+    }
+
+    /// Sign claims, with the chain in the header.
+    pub fn sign<T: ToBase64>(&self, claims: T) -> Result<String, Error> {
+        let token = Token::new(self.header(), claims).sign_with_key(&self.key)?;
+        Ok(token.into())
+    }
+}
+
+impl SigningAlgorithm for CertifiedSigner {
+    fn algorithm_type(&self) -> AlgorithmType {
+        SigningAlgorithm::algorithm_type(&self.key)
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        self.key.sign(header, claims)
+    }
+    fn signature_length(&self) -> Option<usize> {
+        self.key.signature_length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use base64::Engine;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private, Public};
+    use openssl::x509::extension::BasicConstraints;
+    use openssl::x509::{X509Builder, X509NameBuilder, X509};
+
+    use crate::algorithm::x5c::{CertifiedSigner, TrustAnchors};
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+    use crate::jwk::Jwk;
+    use crate::token::verified::VerifyWithKey;
+    use crate::{Header, PKeyWithDigest, RegisteredClaims, Token};
+
+    const DAY: i64 = 86400;
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    /// A certificate for the key, signed by the issuer or self-signed, valid
+    /// between two offsets from now in days.
+    fn certificate(
+        name: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        ca: bool,
+        (from, to): (i64, i64),
+    ) -> X509 {
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", name).unwrap();
+        let subject = subject.build();
+        let (issuer_name, signing_key) = match issuer {
+            Some((certificate, key)) => (certificate.subject_name(), key),
+            None => (subject.as_ref(), key),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_issuer_name(issuer_name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        let not_before = Asn1Time::from_unix(now + from * DAY).unwrap();
+        let not_after = Asn1Time::from_unix(now + to * DAY).unwrap();
+        builder.set_not_before(&not_before).unwrap();
+        builder.set_not_after(&not_after).unwrap();
+        if ca {
+            let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+            builder.append_extension(constraints).unwrap();
+        }
+        builder.sign(signing_key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn chain(certificates: &[&X509]) -> Vec<String> {
+        certificates
+            .iter()
+            .map(|c| STANDARD.encode(c.to_der().unwrap()))
+            .collect()
+    }
+
+    fn jwk(key: &PKey<Private>, chain: Vec<String>) -> Jwk {
+        let public: PKey<Public> =
+            PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+        let mut jwk = PKeyWithDigest::new(AlgorithmType::Es256, public)
+            .unwrap()
+            .to_jwk()
+            .unwrap();
+        jwk.x509_chain = Some(chain);
+        jwk
+    }
+
+    #[test]
+    pub fn chains() -> Result<(), Error> {
+        let (root_key, intermediate_key, leaf_key) = (key(), key(), key());
+        let valid = (-1, 30);
+        let root = certificate("root", &root_key, None, true, valid);
+        let issuer = Some((&root, &root_key));
+        let intermediate = certificate("intermediate", &intermediate_key, issuer, true, valid);
+        let issuer = Some((&intermediate, &intermediate_key));
+        let leaf = certificate("leaf", &leaf_key, issuer, false, valid);
+        let anchors = TrustAnchors::from_pem(&root.to_pem()?)?;
+
+        let key =
+            PKeyWithDigest::from_x5c(&jwk(&leaf_key, chain(&[&leaf, &intermediate])), &anchors)?;
+        assert!(key.key.public_eq(&*leaf.public_key()?));
+
+        // The intermediate is missing, unless it is configured
+        assert!(matches!(
+            anchors.verify(&chain(&[&leaf])),
+            Err(Error::UntrustedCertificate(_))
+        ));
+        let completed = TrustAnchors::from_der([&*root.to_der()?])?
+            .with_intermediates_pem(&intermediate.to_pem()?)?;
+        assert!(completed.verify(&chain(&[&leaf])).is_ok());
+
+        // The chain is for another key
+        let other = jwk(&self::key(), chain(&[&leaf, &intermediate]));
+        assert!(PKeyWithDigest::from_x5c(&other, &anchors).is_err());
+
+        // The chain leads to another root
+        let stranger = certificate("stranger", &self::key(), None, true, valid);
+        let other_anchors = TrustAnchors::from_pem(&stranger.to_pem()?)?;
+        assert!(other_anchors
+            .verify(&chain(&[&leaf, &intermediate]))
+            .is_err());
+
+        let expired = certificate("expired", &leaf_key, issuer, false, (-30, -1));
+        assert!(anchors.verify(&chain(&[&expired, &intermediate])).is_err());
+
+        // The issuer of the leaf is not a CA
+        let not_ca = certificate(
+            "not-ca",
+            &intermediate_key,
+            Some((&root, &root_key)),
+            false,
+            valid,
+        );
+        let issued = certificate(
+            "issued",
+            &leaf_key,
+            Some((&not_ca, &intermediate_key)),
+            false,
+            valid,
+        );
+        assert!(anchors.verify(&chain(&[&issued, &not_ca])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn certified_signer() -> Result<(), Error> {
+        let (root_key, leaf_key) = (key(), key());
+        let root = certificate("root", &root_key, None, true, (-1, 30));
+        let leaf = certificate("leaf", &leaf_key, Some((&root, &root_key)), false, (-1, 30));
+        let anchors = TrustAnchors::from_pem(&root.to_pem()?)?;
+        let signing_key = PKeyWithDigest::new(AlgorithmType::Es256, leaf_key.clone())?;
+        let signer = CertifiedSigner::from_pem(signing_key, &leaf.to_pem()?)?;
+
+        let token = signer.sign(RegisteredClaims::default())?;
+        let unverified: Token<Header, RegisteredClaims, _> = Token::parse_unverified(&token)?;
+        let chain: Vec<String> = serde_json::from_value(unverified.header().extra["x5c"].clone())?;
+        assert_eq!(chain, self::chain(&[&leaf]));
+        let thumbprint = URL_SAFE_NO_PAD.encode(leaf.digest(MessageDigest::sha256())?);
+        assert_eq!(unverified.header().extra["x5t#S256"], thumbprint.as_str());
+
+        let verifier = PKeyWithDigest::from_x5c(&jwk(&leaf_key, chain), &anchors)?;
+        assert!(unverified.verify_with_key(&verifier).is_ok());
+
+        let signing_key = PKeyWithDigest::new(AlgorithmType::Es256, leaf_key)?;
+        let signer = CertifiedSigner::new(signing_key, &[leaf])?.embed_x5c(false);
+        assert!(!signer.header().extra.contains_key("x5c"));
+        assert!(signer.header().extra.contains_key("x5t#S256"));
+
+        // The certificate is for another key
+        let other = PKeyWithDigest::new(AlgorithmType::Es256, self::key())?;
+        assert!(CertifiedSigner::new(other, &[root]).is_err());
+        Ok(())
+    }
+}
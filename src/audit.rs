@@ -0,0 +1,204 @@
+//! A hook for recording every token decision, e.g. to ship them to a SIEM.
+//! An [AuditHook](trait.AuditHook.html) given to a
+//! [BearerAuth](../bearer/struct.BearerAuth.html) receives a
+//! [Decision](struct.Decision.html) after each verification, with the issuer,
+//! subject and key id of the token and the reason it was rejected.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::audit::Decision;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::AlgorithmType;
+//! use serde_json::Value;
+//!
+//! let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let auth = BearerAuth::new(verifier).audit(|decision: &Decision| {
+//!     eprintln!("{}", decision.to_json());
+//! });
+//!
+//! assert!(auth.authenticate::<Value>(Some("Bearer not.a.token")).is_err());
+//! ```
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{Error, ErrorKind};
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+use crate::token::raw::{Component, RawToken};
+
+/// Receives a decision for every verified token.
+pub trait AuditHook: Send + Sync {
+    fn decision(&self, decision: &Decision<'_>);
+}
+
+impl<F: Fn(&Decision<'_>) + Send + Sync> AuditHook for F {
+    fn decision(&self, decision: &Decision<'_>) {
+        self(decision)
+    }
+}
+
+/// The outcome of verifying a token. The issuer, subject and key id are read
+/// from the token whether or not it was accepted, so for rejected tokens they
+/// are claimed by whoever presented the token and may be forged.
+#[derive(Clone, Copy, Debug)]
+pub struct Decision<'a> {
+    pub issuer: Option<&'a str>,
+    pub subject: Option<&'a str>,
+    pub key_id: Option<&'a str>,
+    /// Why the token was rejected, if it was.
+    pub failure: Option<&'a Error>,
+}
+
+impl Decision<'_> {
+    pub fn accepted(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// The kind of the failure, if the token was rejected.
+    pub fn kind(&self) -> Option<ErrorKind> {
+        self.failure.map(Error::kind)
+    }
+
+    /// The decision as a flat JSON object with the `iss`, `sub` and `kid` of
+    /// the token, `"accepted"` or `"rejected"` as the `result`, and the stable
+    /// code and message of the failure as `reason` and `message`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "iss": self.issuer,
+            "sub": self.subject,
+            "kid": self.key_id,
+            "result": if self.accepted() { "accepted" } else { "rejected" },
+            "reason": self.kind().map(|kind| kind.code()),
+            "message": self.failure.map(ToString::to_string),
+        })
+    }
+}
+
+/// The parts of a token that are recorded, read without verifying it.
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+#[derive(Default)]
+pub(crate) struct Subject {
+    issuer: Option<String>,
+    subject: Option<String>,
+    key_id: Option<String>,
+}
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+#[derive(Deserialize)]
+struct KeyIdHeader {
+    kid: Option<String>,
+}
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+#[derive(Deserialize)]
+struct SubjectClaims {
+    iss: Option<String>,
+    sub: Option<String>,
+}
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+impl Subject {
+    /// Whatever can be read from the token; a malformed component leaves its
+    /// fields empty.
+    pub(crate) fn read(token: &str) -> Self {
+        let raw = match RawToken::parse(token) {
+            Ok(raw) => raw,
+            Err(_) => return Subject::default(),
+        };
+        let header: Option<KeyIdHeader> = raw.deserialize(Component::Header).ok();
+        let claims: Option<SubjectClaims> = raw.deserialize(Component::Claims).ok();
+        let (issuer, subject) = claims.map_or((None, None), |c| (c.iss, c.sub));
+        Subject {
+            issuer,
+            subject,
+            key_id: header.and_then(|h| h.kid),
+        }
+    }
+
+    pub(crate) fn decision<'a>(&'a self, failure: Option<&'a Error>) -> Decision<'a> {
+        Decision {
+            issuer: self.issuer.as_deref(),
+            subject: self.subject.as_deref(),
+            key_id: self.key_id.as_deref(),
+            failure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use hmac::{Hmac, KeyInit};
+    use serde_json::Value;
+    use sha2::Sha256;
+
+    use crate::algorithm::any::AnyVerifier;
+    use crate::algorithm::AlgorithmType;
+    use crate::audit::Decision;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::Token;
+
+    #[test]
+    pub fn decisions() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&log);
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?)
+            .audit(move |decision: &Decision| sink.lock().unwrap().push(decision.to_json()));
+        let sign = |exp: u64| {
+            let header = Header {
+                key_id: Some("first".into()),
+                ..Default::default()
+            };
+            let claims = BTreeMap::from([
+                ("iss", Value::from("issuer")),
+                ("sub", Value::from("alice")),
+                ("exp", Value::from(exp)),
+            ]);
+            Token::new(header, claims).sign_with_key(&key)
+        };
+
+        auth.authenticate_token::<Value>(sign(u64::MAX)?.as_str())
+            .unwrap();
+        assert!(auth.authenticate_token::<Value>(sign(1)?.as_str()).is_err());
+        assert!(auth.authenticate_token::<Value>("garbage").is_err());
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0]["result"], "accepted");
+        assert_eq!(log[0]["iss"], "issuer");
+        assert_eq!(log[0]["sub"], "alice");
+        assert_eq!(log[0]["kid"], "first");
+        assert_eq!(log[0]["reason"], Value::Null);
+        assert_eq!(log[1]["result"], "rejected");
+        assert_eq!(log[1]["reason"], "token_expired");
+        assert_eq!(log[1]["sub"], "alice");
+        assert_eq!(log[2]["reason"], "malformed_token");
+        assert_eq!(log[2]["kid"], Value::Null);
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+//! Integration with [axum](https://docs.rs/axum), enabled with the `axum`
+//! feature. The [Claims](struct.Claims.html) extractor verifies the bearer
+//! token with the [BearerAuth](../bearer/struct.BearerAuth.html) of the
+//! application state and rejects requests with `401 Unauthorized` and a
+//! `WWW-Authenticate` header. Routes are protected by a
+//! [policy](../policy/index.html) with the [authorize](fn.authorize.html)
+//! middleware, which rejects requests whose claims don't satisfy it with
+//! `403 Forbidden` and a JSON body.
+//! ## Examples
+//! ```
+//! use axum::middleware::from_fn_with_state;
+//! use axum::routing::{get, post};
+//! use axum::Router;
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::axum::{authorize, Authorize, Claims};
+//! use jwt::bearer::BearerAuth;
+//! use jwt::policy::{require_role, require_scope};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! async fn whoami(Claims(claims): Claims<RegisteredClaims>) -> String {
+//!     claims.subject.unwrap_or_default()
+//! }
+//!
+//! async fn create_order() {}
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let orders = Authorize::new(
+//!     auth.clone(),
+//!     require_scope("orders:write").or(require_role("admin")),
+//! );
+//! let app: Router = Router::new()
+//!     .route("/whoami", get(whoami))
+//!     .route(
+//!         "/orders",
+//!         post(create_order).route_layer(from_fn_with_state(orders, authorize)),
+//!     )
+//!     .with_state(auth);
+//! ```
+
+use std::sync::Arc;
+
+use axum::extract::{FromRef, FromRequestParts, Request, State};
+use axum::http::header::{CONTENT_TYPE, WWW_AUTHENTICATE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::error::Error;
+use crate::policy::Policy;
+use crate::source::RequestParts;
+
+/// Extracts the verified claims of the bearer token. The state has to
+/// provide a [BearerAuth](../bearer/struct.BearerAuth.html), either
+/// directly or through `FromRef`.
+#[derive(Clone, Debug)]
+pub struct Claims<T = crate::Claims>(pub T);
+
+impl<S, T> FromRequestParts<S> for Claims<T>
+where
+    BearerAuth: FromRef<S>,
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Rejection> {
+        if let Some(VerifiedClaims(claims)) = parts.extensions.get::<VerifiedClaims>() {
+            return serde_json::from_value(claims.clone())
+                .map(Claims)
+                .map_err(|e| Rejection::InvalidToken(Error::Json(e)));
+        }
+        BearerAuth::from_ref(state)
+            .authenticate_request(&HttpParts(&parts.headers, &parts.uri))
+            .map(Claims)
+    }
+}
+
+/// The claims authorized by the middleware, kept in the request extensions.
+#[derive(Clone)]
+struct VerifiedClaims(Value);
+
+/// The state of the [authorize](fn.authorize.html) middleware: how to
+/// authenticate requests and the policy their claims have to satisfy.
+#[derive(Clone)]
+pub struct Authorize {
+    auth: BearerAuth,
+    policy: Arc<Policy>,
+}
+
+impl Authorize {
+    pub fn new(auth: BearerAuth, policy: Policy) -> Self {
+        Authorize {
+            auth,
+            policy: Arc::new(policy),
+        }
+    }
+}
+
+/// A middleware for `from_fn_with_state` that only passes on requests whose
+/// claims satisfy the policy of the [Authorize](struct.Authorize.html)
+/// state. The [Claims](struct.Claims.html) extractor of the handler reuses
+/// the authorized claims.
+pub async fn authorize(
+    State(authorize): State<Authorize>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, Rejection> {
+    let parts = HttpParts(request.headers(), request.uri());
+    let claims = authorize
+        .auth
+        .authorize_request(&parts, &authorize.policy)?;
+    request.extensions_mut().insert(VerifiedClaims(claims));
+    Ok(next.run(request).await)
+}
+
+struct HttpParts<'a>(&'a HeaderMap, &'a Uri);
+
+impl RequestParts for HttpParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.1.query()
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = match self.body() {
+            Some(body) => {
+                let content_type = HeaderValue::from_static("application/json");
+                (status, [(CONTENT_TYPE, content_type)], body).into_response()
+            }
+            None => status.into_response(),
+        };
+        if let Some(challenge) = self.www_authenticate() {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                response.headers_mut().insert(WWW_AUTHENTICATE, value);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use axum::extract::FromRequestParts;
+    use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+    use axum::http::{Request, StatusCode};
+    use axum::response::IntoResponse;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::axum::Claims;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    pub fn extract_claims() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+
+        let request = Request::get("/")
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+        let Claims(claims) = block_on(Claims::<RegisteredClaims>::from_request_parts(
+            &mut parts, &auth,
+        ))
+        .unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+
+        let (mut parts, _) = Request::get("/").body(()).unwrap().into_parts();
+        let rejection = block_on(Claims::<RegisteredClaims>::from_request_parts(
+            &mut parts, &auth,
+        ))
+        .unwrap_err();
+        let response = rejection.into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "tower")]
+    pub fn authorize() -> Result<(), Error> {
+        use axum::body::{to_bytes, Body};
+        use axum::middleware::from_fn_with_state;
+        use axum::routing::post;
+        use axum::Router;
+        use serde_json::{json, Value};
+        use tower_service::Service;
+
+        use crate::axum::Authorize;
+        use crate::policy::{require_role, require_scope};
+
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let policy = require_scope("orders:write").or(require_role("admin"));
+        async fn create(Claims(claims): Claims<Value>) -> String {
+            claims["sub"].as_str().unwrap_or_default().to_owned()
+        }
+        let mut app: Router = Router::new()
+            .route(
+                "/orders",
+                post(create).route_layer(from_fn_with_state(
+                    Authorize::new(auth.clone(), policy),
+                    super::authorize,
+                )),
+            )
+            .with_state(auth);
+
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let mut call = |claims: Value| -> Result<_, Error> {
+            let token = claims.sign_with_key(&signer)?;
+            let request = Request::post("/orders")
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap();
+            let response = block_on(app.call(request)).unwrap();
+            let status = response.status();
+            let body = block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+            Ok((status, String::from_utf8(body.to_vec()).unwrap()))
+        };
+
+        let (status, body) = call(json!({"sub": "someone", "roles": ["admin"]}))?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "someone");
+
+        let (status, body) = call(json!({"sub": "someone", "scope": "orders:read"}))?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let body: Value = serde_json::from_str(&body)?;
+        assert_eq!(body["error"], "insufficient_scope");
+        assert_eq!(
+            body["required"],
+            json!({"any": [{"scope": "orders:write"}, {"role": "admin"}]})
+        );
+        Ok(())
+    }
+}
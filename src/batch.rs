@@ -0,0 +1,88 @@
+//! Verifying many tokens at once, e.g. the signed events of a message queue.
+//! With the `rayon` feature, the tokens are verified in parallel on the
+//! global rayon thread pool, otherwise one after the other.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnySigner, AnyVerifier, SharedVerifier};
+//! use jwt::batch::verify_batch;
+//! use jwt::{AlgorithmType, RegisteredClaims, SignWithKey};
+//!
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let tokens = vec![
+//!     RegisteredClaims::default().sign_with_key(&signer).unwrap(),
+//!     "not.a.token".to_owned(),
+//! ];
+//!
+//! let verifier: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap().into();
+//! let results = verify_batch(&tokens, &verifier);
+//! assert!(results[0].is_ok());
+//! assert!(results[1].is_err());
+//! ```
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::bearer::TokenVerifier;
+use crate::error::Error;
+
+/// Verify every token with the verifier. The results are in the order of the
+/// tokens.
+#[cfg(feature = "rayon")]
+pub fn verify_batch<T, V>(tokens: &[T], verifier: &V) -> Vec<Result<Value, Error>>
+where
+    T: AsRef<str> + Sync,
+    V: TokenVerifier + ?Sized,
+{
+    tokens
+        .par_iter()
+        .map(|token| verifier.verify_token(token.as_ref()))
+        .collect()
+}
+
+/// Verify every token with the verifier. The results are in the order of the
+/// tokens.
+#[cfg(not(feature = "rayon"))]
+pub fn verify_batch<T, V>(tokens: &[T], verifier: &V) -> Vec<Result<Value, Error>>
+where
+    T: AsRef<str> + Sync,
+    V: TokenVerifier + ?Sized,
+{
+    tokens
+        .iter()
+        .map(|token| verifier.verify_token(token.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::any::{AnySigner, AnyVerifier, SharedVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::batch::verify_batch;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+
+    #[test]
+    pub fn batch() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let forger = AnySigner::hmac(AlgorithmType::Hs256, b"forged")?;
+        let tokens: Vec<String> = (0..100)
+            .map(|n| {
+                let key = if n % 10 == 0 { &forger } else { &signer };
+                serde_json::json!({ "n": n }).sign_with_key(key)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let verifier: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?.into();
+        let results = verify_batch(&tokens, &verifier);
+        assert_eq!(results.len(), 100);
+        for (n, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(claims) => assert_eq!(claims["n"], n),
+                Err(Error::InvalidSignature) => assert_eq!(n % 10, 0),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+//! Authentication of requests with
+//! [bearer tokens](https://tools.ietf.org/html/rfc6750), independent of any
+//! web framework. The framework integrations are built on
+//! [BearerAuth](struct.BearerAuth.html) and turn a
+//! [Rejection](enum.Rejection.html) into a response with its status and
+//! `WWW-Authenticate` header. Tokens are read from the `Authorization` header,
+//! or from the [sources](../source/index.html) the `BearerAuth` is configured
+//! with.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::{BearerAuth, Rejection};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//!
+//! let rejection = auth.authenticate::<RegisteredClaims>(None).unwrap_err();
+//! assert_eq!(rejection.status(), 401);
+//! assert_eq!(rejection.www_authenticate().as_deref(), Some("Bearer"));
+//! ```
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::algorithm::any::SharedVerifier;
+use crate::error::Error;
+use crate::header::Header;
+use crate::policy::Denied;
+use crate::token::verified::VerifyWithKey;
+use crate::Token;
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+use {
+    crate::audit::{AuditHook, Subject},
+    crate::claims::{RegisteredClaims, SecondsSinceEpoch},
+    crate::clock::SystemClock,
+    crate::metrics::Metrics,
+    crate::policy::Policy,
+    crate::source::{AuthorizationHeader, RequestParts, TokenSource},
+    serde::de::DeserializeOwned,
+    serde::Deserialize,
+    std::sync::Arc,
+    std::time::Instant,
+};
+
+/// Verifies the bearer token of a request and checks its `exp` and `nbf`
+/// claims. It is cheap to clone, so it can be shared as application state.
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+#[derive(Clone)]
+pub struct BearerAuth {
+    verifier: Arc<dyn TokenVerifier>,
+    sources: Arc<[Box<dyn TokenSource>]>,
+    metrics: Option<Arc<dyn Metrics>>,
+    audit: Option<Arc<dyn AuditHook>>,
+}
+
+/// Verifies the signature of a token, with one key or by picking a key from
+/// a set, e.g. with a [JwksVerifier](../jwks/struct.JwksVerifier.html).
+pub trait TokenVerifier: Send + Sync {
+    /// The claims of the token, if its signature is valid.
+    fn verify_token(&self, token: &str) -> Result<Value, Error>;
+}
+
+impl TokenVerifier for SharedVerifier {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let verified: Token<Header, Value, _> = token.verify_with_key(self)?;
+        let (_, claims) = verified.into();
+        Ok(claims)
+    }
+}
+
+/// The claims that are checked for every token, whatever type the claims
+/// are deserialized to.
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+#[derive(Deserialize)]
+struct TimeClaims {
+    exp: Option<SecondsSinceEpoch>,
+    nbf: Option<SecondsSinceEpoch>,
+}
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+impl BearerAuth {
+    pub fn new(verifier: impl Into<SharedVerifier>) -> Self {
+        BearerAuth::with_verifier(verifier.into())
+    }
+
+    /// Verify tokens with something other than a single key.
+    pub fn with_verifier(verifier: impl TokenVerifier + 'static) -> Self {
+        BearerAuth {
+            verifier: Arc::new(verifier),
+            sources: Arc::new([Box::new(AuthorizationHeader) as Box<dyn TokenSource>]),
+            metrics: None,
+            audit: None,
+        }
+    }
+
+    /// Read tokens from these sources, in order of priority, instead of only
+    /// the `Authorization` header.
+    pub fn with_sources(mut self, sources: Vec<Box<dyn TokenSource>>) -> Self {
+        self.sources = sources.into();
+        self
+    }
+
+    /// Report the outcome of every authenticated token.
+    pub fn metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Give the decision on every authenticated token to a hook.
+    pub fn audit(mut self, hook: impl AuditHook + 'static) -> Self {
+        self.audit = Some(Arc::new(hook));
+        self
+    }
+
+    /// Authenticate a request by the token of the first source that has one.
+    pub fn authenticate_request<C: DeserializeOwned>(
+        &self,
+        request: &dyn RequestParts,
+    ) -> Result<C, Rejection> {
+        for source in self.sources.iter() {
+            if let Some(token) = source.token(request)? {
+                return self.authenticate_token(token);
+            }
+        }
+        Err(Rejection::Missing)
+    }
+
+    /// Authenticate a request like
+    /// [authenticate_request](#method.authenticate_request) and reject it
+    /// with [Forbidden](enum.Rejection.html#variant.Forbidden) if its claims
+    /// don't satisfy the policy.
+    pub fn authorize_request<C: DeserializeOwned>(
+        &self,
+        request: &dyn RequestParts,
+        policy: &Policy,
+    ) -> Result<C, Rejection> {
+        let claims: Value = self.authenticate_request(request)?;
+        policy.check(&claims)?;
+        Ok(serde_json::from_value(claims).map_err(Error::Json)?)
+    }
+
+    /// Authenticate a request by the value of its `Authorization` header.
+    pub fn authenticate<C: DeserializeOwned>(
+        &self,
+        authorization: Option<&str>,
+    ) -> Result<C, Rejection> {
+        let authorization = authorization.ok_or(Rejection::Missing)?;
+        self.authenticate_token(bearer_token(authorization)?)
+    }
+
+    /// Authenticate a request by a token that was presented in some other
+    /// way than the `Authorization` header.
+    pub fn authenticate_token<C: DeserializeOwned>(&self, token: &str) -> Result<C, Rejection> {
+        let result = self.verify(token);
+        if let Some(ref metrics) = self.metrics {
+            match result {
+                Ok(_) => metrics.accepted(),
+                Err(ref e) => metrics.rejected(e.kind()),
+            }
+        }
+        if let Some(ref audit) = self.audit {
+            let subject = Subject::read(token);
+            audit.decision(&subject.decision(result.as_ref().err()));
+        }
+        Ok(result?)
+    }
+
+    fn verify<C: DeserializeOwned>(&self, token: &str) -> Result<C, Error> {
+        let claims = match self.metrics {
+            Some(ref metrics) => {
+                let start = Instant::now();
+                let claims = self.verifier.verify_token(token);
+                metrics.signature_duration(start.elapsed());
+                claims?
+            }
+            None => self.verifier.verify_token(token)?,
+        };
+
+        let time: TimeClaims = serde_json::from_value(claims.clone()).map_err(Error::Json)?;
+        let registered = RegisteredClaims {
+            expiration: time.exp,
+            not_before: time.nbf,
+            ..Default::default()
+        };
+        registered.check_time_with(&SystemClock)?;
+
+        serde_json::from_value(claims).map_err(Error::Json)
+    }
+}
+
+/// Extract the token from the value of an `Authorization` header. The scheme
+/// is case insensitive.
+pub fn bearer_token(authorization: &str) -> Result<&str, Rejection> {
+    BearerToken::from_header(authorization)
+        .map(|token| token.as_str())
+        .map_err(|_| Rejection::InvalidRequest)
+}
+
+/// The token of an `Authorization: Bearer <token>` header, parsed strictly by
+/// the `b64token` syntax of
+/// [RFC 6750](https://tools.ietf.org/html/rfc6750#section-2.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BearerToken<'a>(&'a str);
+
+impl<'a> BearerToken<'a> {
+    /// Parse the value of an `Authorization` header. The scheme is case
+    /// insensitive and separated from the token by spaces. Whitespace around
+    /// the value is ignored, as HTTP parsers may leave it in.
+    pub fn from_header(value: &'a str) -> Result<Self, HeaderError> {
+        let value = value.trim_matches([' ', '\t']);
+        if value.is_empty() {
+            return Err(HeaderError::Empty);
+        }
+
+        let (scheme, token) = match value.find([' ', '\t']) {
+            Some(end) => (&value[..end], &value[end..]),
+            None => (value, ""),
+        };
+        if !scheme.eq_ignore_ascii_case("Bearer") {
+            return Err(HeaderError::UnsupportedScheme);
+        }
+        if token.starts_with(|c| c != ' ') || token.contains('\t') {
+            return Err(HeaderError::InvalidWhitespace);
+        }
+
+        let token = token.trim_start_matches(' ');
+        if token.is_empty() {
+            return Err(HeaderError::MissingToken);
+        }
+        if token.contains(' ') {
+            return Err(HeaderError::MultipleTokens);
+        }
+        let padding = token.len() - token.trim_end_matches('=').len();
+        let valid = token[..token.len() - padding]
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b));
+        if !valid || padding == token.len() {
+            return Err(HeaderError::InvalidCharacter);
+        }
+        Ok(BearerToken(token))
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+/// The reason the value of an `Authorization` header is not a bearer token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The value is empty.
+    Empty,
+    /// The scheme is not `Bearer`, e.g. `Basic` or `Bearerabc`.
+    UnsupportedScheme,
+    /// The scheme is separated from the token by other whitespace than spaces.
+    InvalidWhitespace,
+    /// The scheme is not followed by a token.
+    MissingToken,
+    /// The scheme is followed by more than one token.
+    MultipleTokens,
+    /// The token has a character that is not allowed in bearer tokens.
+    InvalidCharacter,
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeaderError::Empty => write!(f, "Empty Authorization header"),
+            HeaderError::UnsupportedScheme => write!(f, "Authorization scheme is not Bearer"),
+            HeaderError::InvalidWhitespace => {
+                write!(f, "Invalid whitespace after the Bearer scheme")
+            }
+            HeaderError::MissingToken => write!(f, "Missing bearer token"),
+            HeaderError::MultipleTokens => write!(f, "More than one bearer token"),
+            HeaderError::InvalidCharacter => write!(f, "Invalid character in bearer token"),
+        }
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+/// The reason a request was not authenticated.
+#[derive(Debug)]
+pub enum Rejection {
+    /// The request has no token.
+    Missing,
+    /// The `Authorization` header does not hold a bearer token.
+    InvalidRequest,
+    /// The token was rejected.
+    InvalidToken(Error),
+    /// The token is valid, but does not grant access to the resource.
+    InsufficientScope,
+    /// The token is valid, but its claims don't satisfy the
+    /// [policy](../policy/index.html) of the resource.
+    Forbidden(Denied),
+    /// There is no verifier for the request, e.g. because it is missing from
+    /// the application state.
+    Unconfigured,
+}
+
+impl Rejection {
+    /// The HTTP status code of the response. Errors that are not caused by the
+    /// request map to 500 Internal Server Error.
+    pub fn status(&self) -> u16 {
+        match *self {
+            Rejection::Missing => 401,
+            Rejection::InvalidRequest => 400,
+            Rejection::InvalidToken(ref e) => e.http_status(),
+            Rejection::InsufficientScope | Rejection::Forbidden(_) => 403,
+            Rejection::Unconfigured => 500,
+        }
+    }
+
+    /// The value of the `WWW-Authenticate` response header, if the response
+    /// should have one. The error description is the stable
+    /// [code](../error/enum.ErrorKind.html#method.code) of the error, so that
+    /// no details of the verification are leaked.
+    pub fn www_authenticate(&self) -> Option<String> {
+        match *self {
+            Rejection::Missing => Some("Bearer".to_owned()),
+            Rejection::InvalidRequest => Some(r#"Bearer error="invalid_request""#.to_owned()),
+            Rejection::InvalidToken(ref e) => e.www_authenticate_error().map(|error| {
+                format!(
+                    r#"Bearer error="{}", error_description="{}""#,
+                    error,
+                    e.code()
+                )
+            }),
+            Rejection::InsufficientScope | Rejection::Forbidden(_) => {
+                Some(r#"Bearer error="insufficient_scope""#.to_owned())
+            }
+            Rejection::Unconfigured => None,
+        }
+    }
+
+    /// The JSON body of the response, if it should have one. Only denials by
+    /// a policy have a body, so that the requirements of a resource are only
+    /// shown to authenticated clients.
+    pub fn body(&self) -> Option<String> {
+        match *self {
+            Rejection::Forbidden(ref denied) => Some(denied.body().to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl From<Denied> for Rejection {
+    fn from(denied: Denied) -> Self {
+        Rejection::Forbidden(denied)
+    }
+}
+
+impl From<Error> for Rejection {
+    fn from(error: Error) -> Self {
+        Rejection::InvalidToken(error)
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rejection::Missing => write!(f, "Missing bearer token"),
+            Rejection::InvalidRequest => write!(f, "Invalid Authorization header"),
+            Rejection::InvalidToken(ref e) => write!(f, "Invalid bearer token: {}", e),
+            Rejection::InsufficientScope => write!(f, "Insufficient scope"),
+            Rejection::Forbidden(ref denied) => write!(f, "Forbidden: {}", denied),
+            Rejection::Unconfigured => write!(f, "No bearer token verifier configured"),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Rejection::InvalidToken(ref e) => Some(e),
+            Rejection::Forbidden(ref denied) => Some(denied),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{bearer_token, BearerAuth, BearerToken, HeaderError, Rejection};
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    #[test]
+    pub fn header_values() {
+        assert_eq!(bearer_token("Bearer abc").ok(), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc ").ok(), Some("abc"));
+        assert!(matches!(
+            bearer_token("Basic abc"),
+            Err(Rejection::InvalidRequest)
+        ));
+        assert!(matches!(
+            bearer_token("Bearer"),
+            Err(Rejection::InvalidRequest)
+        ));
+        assert!(matches!(
+            bearer_token("Bearer "),
+            Err(Rejection::InvalidRequest)
+        ));
+    }
+
+    #[test]
+    pub fn strict_header_parsing() {
+        let parse = |value| BearerToken::from_header(value).map(|token| token.as_str());
+        assert_eq!(parse("Bearer a.b-c_d~e+f/g=="), Ok("a.b-c_d~e+f/g=="));
+        assert_eq!(parse(" BEARER abc\t"), Ok("abc"));
+        assert_eq!(parse(""), Err(HeaderError::Empty));
+        assert_eq!(parse("Basic abc"), Err(HeaderError::UnsupportedScheme));
+        assert_eq!(parse("Bearerabc"), Err(HeaderError::UnsupportedScheme));
+        assert_eq!(parse("Bearer\tabc"), Err(HeaderError::InvalidWhitespace));
+        assert_eq!(parse("Bearer"), Err(HeaderError::MissingToken));
+        assert_eq!(parse("Bearer abc def"), Err(HeaderError::MultipleTokens));
+        assert_eq!(parse("Bearer abc,def"), Err(HeaderError::InvalidCharacter));
+        assert_eq!(parse("Bearer a=b"), Err(HeaderError::InvalidCharacter));
+        assert_eq!(parse("Bearer =="), Err(HeaderError::InvalidCharacter));
+    }
+
+    #[test]
+    pub fn authenticate() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+
+        let mut claims = BTreeMap::new();
+        claims.insert("sub", "someone");
+        let token = claims.sign_with_key(&signer)?;
+        let authorization = format!("Bearer {}", token);
+        let claims: RegisteredClaims = auth.authenticate(Some(&authorization)).unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+
+        let expired = RegisteredClaims {
+            expiration: Some(1),
+            ..Default::default()
+        };
+        let token = expired.sign_with_key(&signer)?;
+        let rejection = auth
+            .authenticate_token::<RegisteredClaims>(&token)
+            .unwrap_err();
+        assert_eq!(rejection.status(), 401);
+        assert_eq!(
+            rejection.www_authenticate().as_deref(),
+            Some(r#"Bearer error="invalid_token", error_description="token_expired""#)
+        );
+
+        let rejection = auth
+            .authenticate::<RegisteredClaims>(Some("Bearer a.b.c"))
+            .unwrap_err();
+        assert!(matches!(rejection, Rejection::InvalidToken(_)));
+        assert_eq!(rejection.status(), 401);
+        Ok(())
+    }
+}
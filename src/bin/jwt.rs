@@ -0,0 +1,267 @@
+//! Command line tool for inspecting, signing and verifying tokens, built with
+//! the `cli` feature.
+//!
+//! ```text
+//! jwt decode <token>
+//! jwt sign --key secret.bin --claim sub=someone --claim admin=true
+//! jwt verify --jwks https://example.com/.well-known/jwks.json <token>
+//! ```
+//!
+//! Tokens are read from standard input if they are not given as an argument.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use jwt::algorithm::any::{AnySigner, AnyVerifier};
+use jwt::clock::SystemClock;
+use jwt::jwk::JwkSet;
+use jwt::{AlgorithmType, Claims, Error, Header, SignWithKey, Token, VerifyWithKey};
+use serde_json::Value;
+
+#[derive(Parser)]
+#[command(
+    name = "jwt",
+    version,
+    about = "Decode, sign and verify JSON Web Tokens"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the header and claims of a token without verifying it.
+    Decode { token: Option<String> },
+    /// Sign claims and print the token.
+    Sign {
+        /// File with the HMAC secret, or a private PEM key with the `openssl`
+        /// feature.
+        #[arg(long)]
+        key: PathBuf,
+        #[arg(long, default_value = "HS256")]
+        alg: AlgorithmType,
+        /// The key id to put in the header.
+        #[arg(long)]
+        kid: Option<String>,
+        /// A claim as `name=value`. Values are parsed as JSON if possible and
+        /// used as strings otherwise.
+        #[arg(long = "claim", value_parser = parse_claim)]
+        claims: Vec<(String, Value)>,
+    },
+    /// Verify a token and print its claims.
+    Verify {
+        /// File with the HMAC secret, or a public PEM key with the `openssl`
+        /// feature.
+        #[arg(
+            long,
+            conflicts_with = "jwks",
+            required_unless_present = "jwks",
+            requires = "alg"
+        )]
+        key: Option<PathBuf>,
+        /// The algorithm of the key given with `--key`. It is required, so that
+        /// a public key is never used as an HMAC secret.
+        #[arg(long, conflicts_with = "jwks")]
+        alg: Option<AlgorithmType>,
+        /// The URL or path of a JWKS document with the key.
+        #[arg(long)]
+        jwks: Option<String>,
+        token: Option<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Decode { token } => {
+            let token = read_token(token)?;
+            let token: Token<Value, Value, _> = Token::parse_unverified(&token)?;
+            println!("{}", token.explain()?);
+        }
+        Command::Sign {
+            key,
+            alg,
+            kid,
+            claims,
+        } => {
+            let signer = signer(alg, &fs::read(key)?)?;
+            let header = Header {
+                algorithm: alg,
+                key_id: kid,
+                ..Default::default()
+            };
+            let claims: Claims =
+                serde_json::from_value(Value::Object(claims.into_iter().collect()))?;
+
+            println!(
+                "{}",
+                Token::new(header, claims).sign_with_key(&signer)?.as_str()
+            );
+        }
+        Command::Verify {
+            key,
+            alg,
+            jwks,
+            token,
+        } => {
+            let token = read_token(token)?;
+            let verifier = match (key, jwks) {
+                (Some(key), _) => {
+                    let alg = alg.expect("required by the argument parser");
+                    verifier(alg, &fs::read(key)?)?
+                }
+                (None, Some(jwks)) => jwks_verifier(&jwks, &token)?,
+                (None, None) => unreachable!("required by the argument parser"),
+            };
+
+            let token: Token<Header, Claims, _> = token.as_str().verify_with_key(&verifier)?;
+            token.claims().registered.check_time_with(&SystemClock)?;
+            print_json(token.claims())?;
+        }
+    }
+    Ok(())
+}
+
+fn signer(algorithm: AlgorithmType, key: &[u8]) -> Result<AnySigner, Error> {
+    match algorithm {
+        #[cfg(feature = "openssl")]
+        AlgorithmType::Rs256
+        | AlgorithmType::Rs384
+        | AlgorithmType::Rs512
+        | AlgorithmType::Ps256
+        | AlgorithmType::Ps384
+        | AlgorithmType::Ps512
+        | AlgorithmType::Es256
+        | AlgorithmType::Es384
+        | AlgorithmType::Es512 => {
+            let key = openssl::pkey::PKey::private_key_from_pem(key)?;
+            Ok(AnySigner::new(jwt::PKeyWithDigest::new(algorithm, key)?))
+        }
+        _ => AnySigner::hmac(algorithm, key),
+    }
+}
+
+fn verifier(algorithm: AlgorithmType, key: &[u8]) -> Result<AnyVerifier, Error> {
+    match algorithm {
+        #[cfg(feature = "openssl")]
+        AlgorithmType::Rs256
+        | AlgorithmType::Rs384
+        | AlgorithmType::Rs512
+        | AlgorithmType::Ps256
+        | AlgorithmType::Ps384
+        | AlgorithmType::Ps512
+        | AlgorithmType::Es256
+        | AlgorithmType::Es384
+        | AlgorithmType::Es512 => {
+            let key = openssl::pkey::PKey::public_key_from_pem(key)?;
+            Ok(AnyVerifier::new(jwt::PKeyWithDigest::new(algorithm, key)?))
+        }
+        // Anyone can sign with a public key used as a secret
+        _ if key.trim_ascii_start().starts_with(b"-----BEGIN") => {
+            Err(Error::InvalidKeyForAlgorithm(algorithm))
+        }
+        _ => AnyVerifier::hmac(algorithm, key),
+    }
+}
+
+/// Select the key of the token by its key id, or the only key of the set if
+/// the token has none.
+fn jwks_verifier(location: &str, token: &str) -> Result<AnyVerifier, Box<dyn std::error::Error>> {
+    let document = if location.starts_with("https://") || location.starts_with("http://") {
+        ureq::get(location).call()?.into_string()?
+    } else {
+        fs::read_to_string(location)?
+    };
+    let jwks: JwkSet = serde_json::from_str(&document)?;
+
+    let unverified: Token<Header, Value, _> = Token::parse_unverified(token)?;
+    let jwk = match (&unverified.header().key_id, jwks.keys.as_slice()) {
+        (Some(key_id), _) => jwks
+            .find(key_id)
+            .ok_or_else(|| Error::NoKeyWithKeyId(key_id.clone()))?,
+        (None, [jwk]) => jwk,
+        (None, _) => return Err(Error::NoKeyId.into()),
+    };
+
+    Ok(AnyVerifier::from_jwk(jwk)?)
+}
+
+fn read_token(token: Option<String>) -> io::Result<String> {
+    match token {
+        Some(token) => Ok(token),
+        None => {
+            let mut token = String::new();
+            io::stdin().read_to_string(&mut token)?;
+            Ok(token.trim().to_owned())
+        }
+    }
+}
+
+fn parse_claim(claim: &str) -> Result<(String, Value), String> {
+    let (name, value) = claim
+        .split_once('=')
+        .ok_or_else(|| format!("expected name=value, got {:?}", claim))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+    Ok((name.to_owned(), value))
+}
+
+fn print_json(value: &impl serde::Serialize) -> serde_json::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use jwt::{AlgorithmType, Error};
+    use serde_json::{json, Value};
+
+    use crate::{parse_claim, verifier, Cli};
+
+    #[test]
+    pub fn claims() {
+        assert_eq!(
+            parse_claim("sub=someone"),
+            Ok(("sub".into(), json!("someone")))
+        );
+        assert_eq!(
+            parse_claim("exp=1700000000"),
+            Ok(("exp".into(), json!(1700000000)))
+        );
+        assert_eq!(
+            parse_claim("admin=true"),
+            Ok(("admin".into(), Value::Bool(true)))
+        );
+        assert_eq!(parse_claim("a=b=c"), Ok(("a".into(), json!("b=c"))));
+        assert!(parse_claim("sub").is_err());
+    }
+
+    #[test]
+    pub fn verify_needs_the_algorithm_of_a_key() {
+        assert!(Cli::try_parse_from(["jwt", "verify", "--key", "key.pem", "token"]).is_err());
+        assert!(Cli::try_parse_from([
+            "jwt", "verify", "--key", "key.pem", "--alg", "RS256", "token"
+        ])
+        .is_ok());
+
+        let public_key = include_bytes!("../../test/rs256-public.pem");
+        assert!(matches!(
+            verifier(AlgorithmType::Hs256, public_key),
+            Err(Error::InvalidKeyForAlgorithm(AlgorithmType::Hs256))
+        ));
+        assert!(verifier(AlgorithmType::Hs256, b"secret").is_ok());
+    }
+}
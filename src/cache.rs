@@ -0,0 +1,221 @@
+//! Caching the claims of verified tokens, for services that see the same
+//! token on every request of a session. Tokens are looked up by their SHA-256
+//! hash, and cached claims are dropped when the token expires or after a
+//! maximum age, whichever comes first. Failed verifications are not cached.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnyVerifier, SharedVerifier};
+//! use jwt::bearer::BearerAuth;
+//! use jwt::cache::CachedVerifier;
+//! use jwt::AlgorithmType;
+//!
+//! let verifier: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap().into();
+//! let auth = BearerAuth::with_verifier(CachedVerifier::new(verifier, 10_000).max_age(60));
+//! ```
+//!
+//! A cached token stays accepted until its entry is dropped, even if its key
+//! was removed in the meantime. The maximum age bounds how long that is.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::bearer::TokenVerifier;
+use crate::claims::SecondsSinceEpoch;
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::metrics::Metrics;
+
+/// Cached claims are dropped after this many seconds by default.
+pub const DEFAULT_MAX_AGE: SecondsSinceEpoch = 5 * 60;
+
+struct Entry {
+    claims: Value,
+    until: SecondsSinceEpoch,
+}
+
+/// Verifies tokens with another verifier and remembers the claims of the
+/// most recently used tokens.
+pub struct CachedVerifier<V, C = SystemClock> {
+    verifier: V,
+    entries: Mutex<LruCache<[u8; 32], Entry>>,
+    max_age: SecondsSinceEpoch,
+    clock: C,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl<V: TokenVerifier> CachedVerifier<V> {
+    /// Cache the claims of up to `capacity` tokens, at least one.
+    pub fn new(verifier: V, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        CachedVerifier {
+            verifier,
+            entries: Mutex::new(LruCache::new(capacity)),
+            max_age: DEFAULT_MAX_AGE,
+            clock: SystemClock,
+            metrics: None,
+        }
+    }
+}
+
+impl<V: TokenVerifier, C: Clock> CachedVerifier<V, C> {
+    /// Drop cached claims after this many seconds, even if the token has not
+    /// expired yet.
+    pub fn max_age(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.max_age = seconds;
+        self
+    }
+
+    /// Expire entries according to another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> CachedVerifier<V, D> {
+        CachedVerifier {
+            verifier: self.verifier,
+            entries: self.entries,
+            max_age: self.max_age,
+            clock,
+            metrics: self.metrics,
+        }
+    }
+
+    /// Report cache hits and misses.
+    pub fn metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// The number of cached tokens, including expired ones that were not
+    /// looked up since.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget all tokens, e.g. after a key was revoked.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl<V: TokenVerifier, C: Clock + Send + Sync> TokenVerifier for CachedVerifier<V, C> {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let hash: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        let now = self.clock.now();
+
+        {
+            let mut entries = self.entries.lock().unwrap();
+            match entries.get(&hash) {
+                Some(entry) if now < entry.until => {
+                    if let Some(ref metrics) = self.metrics {
+                        metrics.cache_hit();
+                    }
+                    return Ok(entry.claims.clone());
+                }
+                Some(_) => {
+                    entries.pop(&hash);
+                }
+                None => {}
+            }
+        }
+        if let Some(ref metrics) = self.metrics {
+            metrics.cache_miss();
+        }
+
+        let claims = self.verifier.verify_token(token)?;
+        let mut until = now.saturating_add(self.max_age);
+        if let Some(expiration) = claims.get("exp").and_then(Value::as_u64) {
+            until = until.min(expiration);
+        }
+        if now < until {
+            let entry = Entry {
+                claims: claims.clone(),
+                until,
+            };
+            self.entries.lock().unwrap().put(hash, entry);
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+
+    use crate::bearer::TokenVerifier;
+    use crate::cache::CachedVerifier;
+    use crate::error::Error;
+    use crate::metrics::Counters;
+
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    impl TokenVerifier for Counting {
+        fn verify_token(&self, token: &str) -> Result<Value, Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            match token {
+                "short" => Ok(json!({"exp": 1010})),
+                "long" => Ok(json!({"exp": 5000})),
+                _ => Err(Error::InvalidSignature),
+            }
+        }
+    }
+
+    #[test]
+    pub fn cache() -> Result<(), Error> {
+        let now = Arc::new(AtomicU64::new(1000));
+        let clock = Arc::clone(&now);
+        let verifier = CachedVerifier::new(Counting::default(), 2)
+            .max_age(100)
+            .with_clock(move || clock.load(Ordering::SeqCst));
+        let calls = || verifier.verifier.0.load(Ordering::SeqCst);
+
+        assert_eq!(verifier.verify_token("short")?["exp"], 1010);
+        assert_eq!(verifier.verify_token("short")?["exp"], 1010);
+        assert_eq!(calls(), 1);
+        assert!(verifier.verify_token("forged").is_err());
+        assert!(verifier.verify_token("forged").is_err());
+        assert_eq!(calls(), 3);
+        assert_eq!(verifier.len(), 1);
+
+        // Bounded by the expiration of the token
+        now.store(1010, Ordering::SeqCst);
+        verifier.verify_token("short")?;
+        assert_eq!(calls(), 4);
+        assert_eq!(verifier.len(), 0);
+
+        // Bounded by the maximum age
+        verifier.verify_token("long")?;
+        now.store(1109, Ordering::SeqCst);
+        verifier.verify_token("long")?;
+        assert_eq!(calls(), 5);
+        now.store(1110, Ordering::SeqCst);
+        verifier.verify_token("long")?;
+        assert_eq!(calls(), 6);
+
+        verifier.clear();
+        assert!(verifier.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn metrics() -> Result<(), Error> {
+        let counters = Arc::new(Counters::default());
+        let verifier = CachedVerifier::new(Counting::default(), 2)
+            .with_clock(|| 1000)
+            .metrics(Arc::clone(&counters));
+
+        verifier.verify_token("short")?;
+        verifier.verify_token("short")?;
+        assert!(verifier.verify_token("forged").is_err());
+        assert_eq!((counters.cache_hits(), counters.cache_misses()), (1, 2));
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Canonical JSON after [RFC 8785](https://tools.ietf.org/html/rfc8785), for
+//! systems that re-derive or compare protected headers byte for byte.
+//! Members of objects are sorted by the UTF-16 code units of their names and
+//! no whitespace is written, so the encoding does not depend on the order of
+//! struct fields, serde attributes or the map type of `serde_json`.
+//!
+//! Strings and integers are written as RFC 8785 requires. Other numbers are
+//! written as `serde_json` writes them, which differs from RFC 8785 for some
+//! fractional and very large numbers.
+//! ## Examples
+//! ```
+//! use jwt::canonical::Canonical;
+//! use jwt::header::{Header, HeaderType};
+//! use jwt::{AlgorithmType, ToBase64};
+//!
+//! let header = Header {
+//!     algorithm: AlgorithmType::Hs256,
+//!     key_id: Some("first".into()),
+//!     type_: Some(HeaderType::JsonWebToken),
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     jwt::canonical::to_canonical_json(&header).unwrap(),
+//!     r#"{"alg":"HS256","kid":"first","typ":"JWT"}"#
+//! );
+//! assert_eq!(
+//!     Canonical(header).to_base64().unwrap(),
+//!     "eyJhbGciOiJIUzI1NiIsImtpZCI6ImZpcnN0IiwidHlwIjoiSldUIn0"
+//! );
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::header::{HeaderContentType, HeaderType, JoseHeader};
+use crate::ToBase64;
+
+/// Encodes the value as canonical JSON when used as the header or the claims
+/// of a token. The header accessors delegate to the value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Canonical<T>(pub T);
+
+impl<T: Serialize> ToBase64 for Canonical<T> {
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        let json = to_canonical_json(&self.0)?;
+        Ok(Cow::Owned(URL_SAFE_NO_PAD.encode(json)))
+    }
+}
+
+impl<T: JoseHeader> JoseHeader for Canonical<T> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.0.algorithm_type()
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.0.key_id()
+    }
+
+    fn type_(&self) -> Option<HeaderType> {
+        self.0.type_()
+    }
+
+    fn content_type(&self) -> Option<HeaderContentType> {
+        self.0.content_type()
+    }
+
+    fn critical(&self) -> Option<&[String]> {
+        self.0.critical()
+    }
+}
+
+/// Serialize to canonical JSON.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    let value = serde_json::to_value(value)?;
+    let mut out = String::new();
+    write_value(&value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), Error> {
+    match *value {
+        Value::Array(ref items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(ref members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (name, member)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(name)?);
+                out.push(':');
+                write_value(member, out)?;
+            }
+            out.push('}');
+        }
+        ref scalar => out.push_str(&serde_json::to_string(scalar)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use hmac::{Hmac, KeyInit};
+    use serde_json::json;
+    use sha2::Sha256;
+
+    use crate::algorithm::AlgorithmType;
+    use crate::canonical::{to_canonical_json, Canonical};
+    use crate::claims::Claims;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::Token;
+
+    #[test]
+    pub fn member_order() -> Result<(), Error> {
+        let value = json!({
+            "b": [3, {"z": null, "a": true}],
+            "a": "text\n\u{1f600}",
+            "\u{e000}": 1,
+            "\u{1f600}": 2,
+        });
+        // U+1F600 is encoded as surrogates, which sort before U+E000
+        assert_eq!(
+            to_canonical_json(&value)?,
+            "{\"a\":\"text\\n\u{1f600}\",\"b\":[3,{\"a\":true,\"z\":null}],\"\u{1f600}\":2,\"\u{e000}\":1}"
+        );
+
+        let map: HashMap<String, u8> = (0..3).map(|i| (format!("k{}", i), i)).collect();
+        assert_eq!(to_canonical_json(&map)?, r#"{"k0":0,"k1":1,"k2":2}"#);
+        Ok(())
+    }
+
+    #[test]
+    pub fn canonical_header() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let header = Header {
+            algorithm: AlgorithmType::Hs256,
+            key_id: Some("first".into()),
+            ..Default::default()
+        };
+        let signed = Token::new(Canonical(header), Claims::default()).sign_with_key(&key)?;
+        let token_str = signed.as_str();
+
+        let verified: Token<Header, Claims, _> = token_str.verify_with_key(&key)?;
+        assert_eq!(verified.header().key_id.as_deref(), Some("first"));
+        assert!(token_str.starts_with("eyJhbGciOiJIUzI1NiIsImtpZCI6ImZpcnN0In0."));
+        Ok(())
+    }
+}
@@ -1,12 +1,20 @@
 //! Convenience structs for commonly defined fields in claims.
 
-use std::collections::BTreeMap;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::fmt;
+use core::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::clock::Clock;
+use crate::error::Error;
+use crate::redact::{self, RedactedMap};
+
 /// Generic [JWT claims](https://tools.ietf.org/html/rfc7519#page-8) with
-/// defined fields for registered and private claims.
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// defined fields for registered and private claims. `Debug` redacts
+/// [sensitive claims](../redact/index.html).
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Claims {
     #[serde(flatten)]
     pub registered: RegisteredClaims,
@@ -23,11 +31,27 @@ impl Claims {
     }
 }
 
+impl fmt::Debug for Claims {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Claims")
+            .field("registered", &self.registered)
+            .field("private", &RedactedMap(&self.private))
+            .finish()
+    }
+}
+
+impl AsRef<RegisteredClaims> for Claims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+}
+
 pub type SecondsSinceEpoch = u64;
 
 /// Registered claims according to the
-/// [JWT specification](https://tools.ietf.org/html/rfc7519#page-9).
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// [JWT specification](https://tools.ietf.org/html/rfc7519#page-9). `Debug`
+/// redacts [sensitive claims](../redact/index.html).
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct RegisteredClaims {
     #[serde(rename = "iss", skip_serializing_if = "Option::is_none")]
     pub issuer: Option<String>,
@@ -51,9 +75,200 @@ pub struct RegisteredClaims {
     pub json_web_token_id: Option<String>,
 }
 
+impl fmt::Debug for RegisteredClaims {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RegisteredClaims")
+            .field("issuer", redact::claim("iss", &self.issuer))
+            .field("subject", redact::claim("sub", &self.subject))
+            .field("audience", redact::claim("aud", &self.audience))
+            .field("expiration", redact::claim("exp", &self.expiration))
+            .field("not_before", redact::claim("nbf", &self.not_before))
+            .field("issued_at", redact::claim("iat", &self.issued_at))
+            .field(
+                "json_web_token_id",
+                redact::claim("jti", &self.json_web_token_id),
+            )
+            .finish()
+    }
+}
+
+impl RegisteredClaims {
+    /// Check the `exp` and `nbf` claims against the given time. Missing
+    /// claims are not checked.
+    pub fn check_time(&self, now: SecondsSinceEpoch) -> Result<(), Error> {
+        if let Some(expiration) = self.expiration {
+            if now >= expiration {
+                let by = Duration::from_secs(now - expiration);
+                return Err(Error::Expired { by });
+            }
+        }
+
+        if let Some(not_before) = self.not_before {
+            if now < not_before {
+                let by = Duration::from_secs(not_before - now);
+                return Err(Error::NotYetValid { by });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the `exp` and `nbf` claims against the time of a clock.
+    pub fn check_time_with(&self, clock: &impl Clock) -> Result<(), Error> {
+        self.check_time(clock.now())
+    }
+
+    /// The time until the token expires, zero if it has. `None` without an
+    /// `exp` claim.
+    #[cfg(any(
+        all(
+            feature = "std",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ),
+        all(feature = "js", target_arch = "wasm32", target_os = "unknown"),
+    ))]
+    pub fn remaining_ttl(&self) -> Option<Duration> {
+        self.remaining_ttl_with(&crate::clock::SystemClock)
+    }
+
+    /// Like [remaining_ttl](#method.remaining_ttl), by the time of a clock.
+    pub fn remaining_ttl_with(&self, clock: &impl Clock) -> Option<Duration> {
+        let expiration = self.expiration?;
+        Some(Duration::from_secs(expiration.saturating_sub(clock.now())))
+    }
+
+    /// Whether the `exp` claim has passed by the time of a clock.
+    pub fn is_expired(&self, clock: &impl Clock) -> bool {
+        self.remaining_ttl_with(clock) == Some(Duration::ZERO)
+    }
+
+    /// Whether the token expires within the duration, or already has, e.g.
+    /// to refresh it ahead of time. Tokens without an `exp` claim never do.
+    #[cfg(any(
+        all(
+            feature = "std",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ),
+        all(feature = "js", target_arch = "wasm32", target_os = "unknown"),
+    ))]
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.expires_within_with(duration, &crate::clock::SystemClock)
+    }
+
+    /// Like [expires_within](#method.expires_within), by the time of a clock.
+    pub fn expires_within_with(&self, duration: Duration, clock: &impl Clock) -> bool {
+        self.remaining_ttl_with(clock)
+            .is_some_and(|remaining| remaining <= duration)
+    }
+}
+
+/// A trait for claims types that expose their registered claims, so that a
+/// [Validation](../validation/struct.Validation.html) can check custom structs
+/// without converting them to JSON. Claims that a type does not have are
+/// `None` by default.
+/// ## Examples
+/// ```
+/// use jwt::claims::{JwtClaims, SecondsSinceEpoch};
+/// use jwt::validation::Validation;
+///
+/// struct Session {
+///     issuer: String,
+///     expires: SecondsSinceEpoch,
+/// }
+///
+/// impl JwtClaims for Session {
+///     fn issuer(&self) -> Option<&str> {
+///         Some(&self.issuer)
+///     }
+///
+///     fn expiration(&self) -> Option<SecondsSinceEpoch> {
+///         Some(self.expires)
+///     }
+/// }
+///
+/// let session = Session { issuer: "auth".into(), expires: 1000 };
+/// let validation = Validation::new().issuer("auth");
+/// assert!(validation.validate_claims(&session, 900).is_ok());
+/// assert!(validation.validate_claims(&session, 1000).is_err());
+/// ```
+pub trait JwtClaims {
+    fn issuer(&self) -> Option<&str> {
+        None
+    }
+
+    /// The audiences of the token, one for an `aud` claim that is a string.
+    fn audience(&self) -> &[String] {
+        &[]
+    }
+
+    fn expiration(&self) -> Option<SecondsSinceEpoch> {
+        None
+    }
+
+    fn not_before(&self) -> Option<SecondsSinceEpoch> {
+        None
+    }
+
+    fn jwt_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl JwtClaims for RegisteredClaims {
+    fn issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    fn audience(&self) -> &[String] {
+        self.audience.as_slice()
+    }
+
+    fn expiration(&self) -> Option<SecondsSinceEpoch> {
+        self.expiration
+    }
+
+    fn not_before(&self) -> Option<SecondsSinceEpoch> {
+        self.not_before
+    }
+
+    fn jwt_id(&self) -> Option<&str> {
+        self.json_web_token_id.as_deref()
+    }
+}
+
+impl JwtClaims for Claims {
+    fn issuer(&self) -> Option<&str> {
+        self.registered.issuer()
+    }
+
+    fn audience(&self) -> &[String] {
+        self.registered.audience()
+    }
+
+    fn expiration(&self) -> Option<SecondsSinceEpoch> {
+        self.registered.expiration()
+    }
+
+    fn not_before(&self) -> Option<SecondsSinceEpoch> {
+        self.registered.not_before()
+    }
+
+    fn jwt_id(&self) -> Option<&str> {
+        self.registered.jwt_id()
+    }
+}
+
+impl AsRef<RegisteredClaims> for RegisteredClaims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::claims::Claims;
+    use std::time::Duration;
+
+    use crate::claims::{Claims, RegisteredClaims};
     use crate::error::Error;
     use crate::{FromBase64, ToBase64};
     use serde_json::Value;
@@ -89,4 +304,50 @@ mod tests {
         assert_eq!(claims, Claims::from_base64(&*enc)?);
         Ok(())
     }
+
+    #[test]
+    fn check_time() {
+        let claims = RegisteredClaims {
+            not_before: Some(100),
+            expiration: Some(200),
+            ..Default::default()
+        };
+
+        assert!(claims.check_time(100).is_ok());
+        assert!(claims.check_time(199).is_ok());
+        match claims.check_time(90) {
+            Err(Error::NotYetValid { by }) => assert_eq!(by, Duration::from_secs(10)),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        match claims.check_time(230) {
+            Err(Error::Expired { by }) => assert_eq!(by, Duration::from_secs(30)),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        assert!(RegisteredClaims::default().check_time(0).is_ok());
+    }
+
+    #[test]
+    fn expiration() {
+        let claims = RegisteredClaims {
+            expiration: Some(200),
+            ..Default::default()
+        };
+        let at = |now: u64| move || now;
+
+        assert_eq!(
+            claims.remaining_ttl_with(&at(150)),
+            Some(Duration::from_secs(50))
+        );
+        assert_eq!(claims.remaining_ttl_with(&at(250)), Some(Duration::ZERO));
+        assert!(!claims.is_expired(&at(199)));
+        assert!(claims.is_expired(&at(200)));
+        assert!(claims.expires_within_with(Duration::from_secs(60), &at(150)));
+        assert!(!claims.expires_within_with(Duration::from_secs(10), &at(150)));
+        assert!(claims.expires_within(Duration::ZERO));
+
+        let forever = RegisteredClaims::default();
+        assert_eq!(forever.remaining_ttl(), None);
+        assert!(!forever.is_expired(&at(u64::MAX)));
+        assert!(!forever.expires_within(Duration::MAX));
+    }
 }
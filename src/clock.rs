@@ -0,0 +1,72 @@
+//! Sources of the current time, for checking the `exp` and `nbf` claims.
+//! [SystemClock](struct.SystemClock.html) uses `std::time::SystemTime`, except
+//! on `wasm32-unknown-unknown`, where `SystemTime` is not available and the
+//! time is read from JavaScript's `Date.now()` with the `js` feature.
+//! Without the `js` feature, the parts of the crate that need the system
+//! time, like [BearerAuth](../bearer/struct.BearerAuth.html), are not
+//! available there.
+//! ## Examples
+//! ```
+//! use jwt::RegisteredClaims;
+//!
+//! let fixed = || 1_700_000_000;
+//! let claims = RegisteredClaims {
+//!     expiration: Some(1_800_000_000),
+//!     ..Default::default()
+//! };
+//! assert!(claims.check_time_with(&fixed).is_ok());
+//! ```
+
+use crate::claims::SecondsSinceEpoch;
+
+/// A source of the current time.
+pub trait Clock {
+    fn now(&self) -> SecondsSinceEpoch;
+}
+
+impl<F: Fn() -> SecondsSinceEpoch> Clock for F {
+    fn now(&self) -> SecondsSinceEpoch {
+        self()
+    }
+}
+
+/// The system time.
+#[cfg(any(
+    all(
+        feature = "std",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ),
+    all(feature = "js", target_arch = "wasm32", target_os = "unknown"),
+))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+impl Clock for SystemClock {
+    fn now(&self) -> SecondsSinceEpoch {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    }
+}
+
+#[cfg(all(feature = "js", target_arch = "wasm32", target_os = "unknown"))]
+impl Clock for SystemClock {
+    fn now(&self) -> SecondsSinceEpoch {
+        (js_sys::Date::now() / 1000.0) as SecondsSinceEpoch
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::clock::{Clock, SystemClock};
+
+    #[test]
+    pub fn system_clock() {
+        // 2020-01-01
+        assert!(SystemClock.now() > 1_577_836_800);
+    }
+}
@@ -0,0 +1,180 @@
+//! Reading tokens from cookies, for browser sessions where the token is kept
+//! in an `HttpOnly` cookie instead of being sent in the `Authorization`
+//! header.
+//!
+//! Cookies without a prefix can be set by any subdomain, or by a page served
+//! over plain HTTP. With [host_prefix](struct.CookieToken.html#method.host_prefix)
+//! only the `__Host-` variant of the cookie is accepted, which browsers only
+//! store when it was set by the host itself with `Secure` and `Path=/`.
+//! ## Examples
+//! ```
+//! use jwt::cookie::CookieToken;
+//!
+//! let cookie = CookieToken::new("session").host_prefix(true);
+//! let header = "theme=dark; __Host-session=eyJhbGciOiJIUzI1NiJ9.e30.c2lnbmF0dXJl";
+//! assert_eq!(
+//!     cookie.extract(header).unwrap(),
+//!     Some("eyJhbGciOiJIUzI1NiJ9.e30.c2lnbmF0dXJl")
+//! );
+//! assert_eq!(cookie.extract("session=forged").unwrap(), None);
+//! ```
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+use serde::de::DeserializeOwned;
+
+#[cfg(any(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "js"
+))]
+use crate::bearer::BearerAuth;
+use crate::bearer::Rejection;
+use crate::source::{RequestParts, TokenSource};
+
+const HOST_PREFIX: &str = "__Host-";
+
+/// Extracts a token from the `Cookie` header of requests.
+#[derive(Clone, Debug)]
+pub struct CookieToken {
+    name: String,
+    host_prefix: bool,
+}
+
+impl CookieToken {
+    pub fn new(name: impl Into<String>) -> Self {
+        CookieToken {
+            name: name.into(),
+            host_prefix: false,
+        }
+    }
+
+    /// Only accept the cookie with the `__Host-` prefix in front of the name.
+    pub fn host_prefix(mut self, required: bool) -> Self {
+        self.host_prefix = required;
+        self
+    }
+
+    /// The full name of the cookie, with the prefix if it is required.
+    pub fn name(&self) -> String {
+        if self.host_prefix && !self.name.starts_with(HOST_PREFIX) {
+            format!("{}{}", HOST_PREFIX, self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Find the token in the value of a `Cookie` header. A cookie that is
+    /// sent more than once is rejected, because there is no telling which
+    /// one was set by whom.
+    pub fn extract<'a>(&self, cookie_header: &'a str) -> Result<Option<&'a str>, Rejection> {
+        let name = self.name();
+        let mut token = None;
+        for pair in cookie_header.split(';') {
+            let (cookie, value) = match pair.trim().split_once('=') {
+                Some(cookie) => cookie,
+                None => continue,
+            };
+            if cookie != name {
+                continue;
+            }
+            if token.is_some() {
+                return Err(Rejection::InvalidRequest);
+            }
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            token = Some(value);
+        }
+
+        match token {
+            Some("") => Err(Rejection::InvalidRequest),
+            token => Ok(token),
+        }
+    }
+
+    /// Authenticate a request by the value of its `Cookie` header.
+    #[cfg(any(
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "js"
+    ))]
+    pub fn authenticate<C: DeserializeOwned>(
+        &self,
+        auth: &BearerAuth,
+        cookie_header: Option<&str>,
+    ) -> Result<C, Rejection> {
+        let token = cookie_header
+            .map(|header| self.extract(header))
+            .transpose()?
+            .flatten()
+            .ok_or(Rejection::Missing)?;
+        auth.authenticate_token(token)
+    }
+}
+
+impl TokenSource for CookieToken {
+    fn token<'a>(&self, request: &'a dyn RequestParts) -> Result<Option<&'a str>, Rejection> {
+        match request.header("cookie")? {
+            Some(header) => self.extract(header),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{BearerAuth, Rejection};
+    use crate::cookie::CookieToken;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    #[test]
+    pub fn extract() {
+        let cookie = CookieToken::new("token");
+        assert_eq!(cookie.extract("a=1; token=abc").unwrap(), Some("abc"));
+        assert_eq!(cookie.extract("token=\"abc\"").unwrap(), Some("abc"));
+        assert_eq!(cookie.extract("a=1; tokens=abc").unwrap(), None);
+        assert!(matches!(
+            cookie.extract("token=abc; token=def"),
+            Err(Rejection::InvalidRequest)
+        ));
+        assert!(matches!(
+            cookie.extract("token="),
+            Err(Rejection::InvalidRequest)
+        ));
+
+        let cookie = cookie.host_prefix(true);
+        assert_eq!(cookie.name(), "__Host-token");
+        assert_eq!(cookie.extract("token=abc").unwrap(), None);
+        assert_eq!(cookie.extract("__Host-token=abc").unwrap(), Some("abc"));
+        assert_eq!(
+            CookieToken::new("__Host-token").host_prefix(true).name(),
+            "__Host-token"
+        );
+    }
+
+    #[test]
+    pub fn authenticate() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let cookie = CookieToken::new("session").host_prefix(true);
+
+        let header = format!("__Host-session={}", token);
+        let claims: RegisteredClaims = cookie.authenticate(&auth, Some(&header)).unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+        assert!(matches!(
+            cookie.authenticate::<RegisteredClaims>(&auth, None),
+            Err(Rejection::Missing)
+        ));
+        Ok(())
+    }
+}
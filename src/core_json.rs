@@ -0,0 +1,223 @@
+//! Headers and claims that are encoded and decoded with serde-json-core
+//! instead of serde_json, enabled with the `serde-json-core` feature. The
+//! feature does not change the JSON backend of anything else; only the types
+//! wrapped in [CoreJson](struct.CoreJson.html) use serde-json-core, and can
+//! be used wherever the crate takes a header or claims.
+//!
+//! serde-json-core only supports types whose structure is known up front, so
+//! `serde_json::Value`, flattened fields and untagged enums fail to decode,
+//! like this crate's `Header` and `Claims`. Maps must have `&str` keys:
+//! serde-json-core panics on the keys of a `BTreeMap<String, _>` while
+//! parsing, before the signature is checked, so such types must not be
+//! wrapped.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::core_json::CoreJson;
+//! use jwt::{AlgorithmType, JoseHeader, SignWithKey, Token, VerifyWithKey};
+//! use serde::{Deserialize, Serialize};
+//! use sha2::Sha256;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct DeviceHeader {
+//!     alg: AlgorithmType,
+//! }
+//!
+//! impl JoseHeader for DeviceHeader {
+//!     fn algorithm_type(&self) -> AlgorithmType {
+//!         self.alg
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct DeviceClaims {
+//!     sub: String,
+//!     exp: u64,
+//! }
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let header = CoreJson(DeviceHeader { alg: AlgorithmType::Hs256 });
+//! let claims = CoreJson(DeviceClaims { sub: "sensor-7".into(), exp: 1_700_000_000 });
+//! let token = Token::new(header, claims).sign_with_key(&key).unwrap();
+//!
+//! let verified: Token<CoreJson<DeviceHeader>, CoreJson<DeviceClaims>, _> =
+//!     token.as_str().verify_with_key(&key).unwrap();
+//! assert_eq!(verified.claims().sub, "sensor-7");
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::header::{HeaderContentType, HeaderType, JoseHeader};
+use crate::{FromBase64, ToBase64};
+
+/// A header or claims that are encoded and decoded with serde-json-core.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoreJson<T>(pub T);
+
+impl<T> CoreJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for CoreJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CoreJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Serialize> ToBase64 for CoreJson<T> {
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Owned(URL_SAFE_NO_PAD.encode(to_json(&self.0)?)))
+    }
+}
+
+impl<T: DeserializeOwned> FromBase64 for CoreJson<T> {
+    fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error> {
+        CoreJson::from_decoded(URL_SAFE_NO_PAD.decode(raw)?)
+    }
+
+    fn from_decoded(decoded: Vec<u8>) -> Result<Self, Error> {
+        let mut unescaped = vec![0; decoded.len()];
+        let (value, _) = serde_json_core::from_slice_escaped(&decoded, &mut unescaped)?;
+        Ok(CoreJson(value))
+    }
+}
+
+impl<T: JoseHeader> JoseHeader for CoreJson<T> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.0.algorithm_type()
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.0.key_id()
+    }
+
+    fn type_(&self) -> Option<HeaderType> {
+        self.0.type_()
+    }
+
+    fn content_type(&self) -> Option<HeaderContentType> {
+        self.0.content_type()
+    }
+
+    fn critical(&self) -> Option<&[String]> {
+        self.0.critical()
+    }
+}
+
+/// Serialize to JSON with serde-json-core, growing the buffer until the
+/// value fits.
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![0; 256];
+    loop {
+        match serde_json_core::to_slice(value, &mut buffer) {
+            Ok(length) => {
+                buffer.truncate(length);
+                return Ok(buffer);
+            }
+            Err(serde_json_core::ser::Error::BufferFull) => {
+                let length = buffer.len() * 2;
+                buffer.resize(length, 0);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use serde::{Deserialize, Serialize};
+    use sha2::Sha256;
+
+    use crate::core_json::CoreJson;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::{AlgorithmType, Claims, FromBase64, JoseHeader, ToBase64, Token};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct DeviceHeader {
+        alg: AlgorithmType,
+    }
+
+    impl JoseHeader for DeviceHeader {
+        fn algorithm_type(&self) -> AlgorithmType {
+            self.alg
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Device {
+        sub: String,
+        exp: u64,
+        roles: Vec<String>,
+    }
+
+    #[test]
+    pub fn round_trip() -> Result<(), Error> {
+        let device = CoreJson(Device {
+            sub: "sensor \"7\"".to_owned(),
+            exp: 1_700_000_000,
+            roles: vec!["a".repeat(300)],
+        });
+
+        let encoded = device.to_base64()?;
+        assert_eq!(CoreJson::<Device>::from_base64(&*encoded)?, device);
+        Ok(())
+    }
+
+    #[test]
+    pub fn tokens() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let header = CoreJson(DeviceHeader {
+            alg: AlgorithmType::Hs256,
+        });
+        let claims = CoreJson(Device {
+            sub: "sensor".to_owned(),
+            exp: 1_700_000_000,
+            roles: Vec::new(),
+        });
+        let token = Token::new(header, claims).sign_with_key(&key)?;
+
+        // Only the wrapped types use serde-json-core
+        let verified: Token<CoreJson<DeviceHeader>, CoreJson<Device>, _> =
+            token.as_str().verify_with_key(&key)?;
+        assert_eq!(verified.claims().sub, "sensor");
+        let verified: Token<Header, Claims, _> = token.as_str().verify_with_key(&key)?;
+        assert_eq!(
+            verified.claims().registered.subject.as_deref(),
+            Some("sensor")
+        );
+
+        // Flattened private claims need serde_json
+        let unsupported: Result<Token<CoreJson<DeviceHeader>, CoreJson<Claims>, _>, _> =
+            token.as_str().verify_with_key(&key);
+        assert!(matches!(
+            unsupported,
+            Err(Error::Malformed(_, _, e)) if matches!(*e, Error::SerdeJsonCoreDe(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Signing and verifying HMAC tokens without heap allocation, e.g. on
+//! microcontrollers validating device tokens. Tokens are written to and
+//! decoded from caller-provided buffers with serde-json-core, so claims should
+//! use bounded types like [heapless::String](../../heapless/struct.String.html).
+//! Requires the `heapless` feature.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::embedded::{self, BoundedClaims};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let claims = BoundedClaims::<16> {
+//!     subject: Some("sensor-7".parse().unwrap()),
+//!     expiration: Some(1_700_000_000),
+//!     ..Default::default()
+//! };
+//!
+//! let mut scratch = [0; 128];
+//! let mut token = [0; 256];
+//! let token = embedded::sign_into(&claims, &key, &mut scratch, &mut token).unwrap();
+//!
+//! let mut scratch = [0; 128];
+//! let verified: BoundedClaims<16> = embedded::verify(token, &key, &mut scratch).unwrap();
+//! assert_eq!(verified, claims);
+//! ```
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::{DecodeSliceError, EncodeSliceError, Engine};
+use heapless::{String, Vec};
+use hmac::{EagerHash, Hmac, Mac};
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::rust_crypto::{get_hmac_with_data, TypeLevelAlgorithmType};
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::header::reject_critical_names;
+use crate::token::raw::RawToken;
+use crate::SEPARATOR;
+
+/// The registered claims with strings of at most `N` bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundedClaims<const N: usize> {
+    #[serde(rename = "iss", skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String<N>>,
+
+    #[serde(rename = "sub", skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String<N>>,
+
+    #[serde(rename = "aud", skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String<N>>,
+
+    #[serde(rename = "exp", skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+
+    #[serde(rename = "nbf", skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<u64>,
+
+    #[serde(rename = "iat", skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<u64>,
+
+    #[serde(rename = "jti", skip_serializing_if = "Option::is_none")]
+    pub json_web_token_id: Option<String<N>>,
+}
+
+/// The only header parameter that is written. `crit` is read to reject the
+/// parameters it lists.
+#[derive(Serialize, Deserialize)]
+struct AlgorithmOnlyHeader<'a> {
+    alg: AlgorithmType,
+
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    crit: Option<Vec<&'a str, MAX_CRITICAL_PARAMETERS>>,
+}
+
+/// Headers with more critical parameters are rejected while parsing.
+const MAX_CRITICAL_PARAMETERS: usize = 8;
+
+/// The largest HMAC output, of HS512.
+const MAX_SIGNATURE_LENGTH: usize = 64;
+
+/// Sign the claims and write the token to `out`, returning the written part.
+/// `scratch` must be large enough for the JSON of the claims.
+pub fn sign_into<'a, C, D>(
+    claims: &C,
+    key: &Hmac<D>,
+    scratch: &mut [u8],
+    out: &'a mut [u8],
+) -> Result<&'a str, Error>
+where
+    C: Serialize,
+    D: EagerHash + TypeLevelAlgorithmType,
+{
+    let header = AlgorithmOnlyHeader {
+        alg: D::algorithm_type(),
+        crit: None,
+    };
+
+    let header_end = encode_json(&header, scratch, out)?;
+    let claims_start = push_separator(out, header_end)?;
+    let claims_end = claims_start + encode_json(claims, scratch, &mut out[claims_start..])?;
+
+    let (header_str, claims_str) = {
+        let signing_input = as_str(&out[..claims_end])?;
+        (&signing_input[..header_end], &signing_input[claims_start..])
+    };
+    let signature = get_hmac_with_data(key, header_str, claims_str)
+        .finalize()
+        .into_bytes();
+
+    let signature_start = push_separator(out, claims_end)?;
+    let signature_end = signature_start
+        + URL_SAFE_NO_PAD
+            .encode_slice(signature, &mut out[signature_start..])
+            .map_err(|EncodeSliceError::OutputSliceTooSmall| Error::BufferTooSmall)?;
+
+    as_str(&out[..signature_end])
+}
+
+/// Verify a token and deserialize its claims, which may borrow from
+/// `scratch`. `scratch` must be large enough for the decoded header and
+/// claims, plus the unescaped strings of the claims.
+pub fn verify<'a, C, D>(token: &str, key: &Hmac<D>, scratch: &'a mut [u8]) -> Result<C, Error>
+where
+    C: Deserialize<'a>,
+    D: EagerHash + TypeLevelAlgorithmType,
+{
+    let raw = RawToken::parse(token)?;
+
+    let header_length = decode_into(raw.header, scratch)?;
+    {
+        // The header borrows from scratch, which is reused for the claims
+        let (header, _): (AlgorithmOnlyHeader, _) =
+            serde_json_core::from_slice(&scratch[..header_length])?;
+        let key_algorithm = D::algorithm_type();
+        if header.alg != key_algorithm {
+            return Err(Error::AlgorithmMismatch(header.alg, key_algorithm));
+        }
+        reject_critical_names(header.crit.iter().flatten().copied())?;
+    }
+
+    let mut signature = [0; MAX_SIGNATURE_LENGTH];
+    let signature_length = decode_into(raw.signature, &mut signature).map_err(|e| match e {
+        Error::BufferTooSmall => Error::InvalidSignature,
+        e => e,
+    })?;
+    get_hmac_with_data(key, raw.header, raw.claims)
+        .verify_slice(&signature[..signature_length])
+        .map_err(|_| Error::InvalidSignature)?;
+
+    let claims_length = decode_into(raw.claims, scratch)?;
+    let (json, unescaped) = scratch.split_at_mut(claims_length);
+    let (claims, _) = serde_json_core::from_slice_escaped(json, unescaped)?;
+    Ok(claims)
+}
+
+/// Serialize a value to JSON in `scratch` and base64 encode it into `out`,
+/// returning the encoded length.
+fn encode_json<T: Serialize>(
+    value: &T,
+    scratch: &mut [u8],
+    out: &mut [u8],
+) -> Result<usize, Error> {
+    let length = serde_json_core::to_slice(value, scratch).map_err(|e| match e {
+        serde_json_core::ser::Error::BufferFull => Error::BufferTooSmall,
+        e => e.into(),
+    })?;
+
+    URL_SAFE_NO_PAD
+        .encode_slice(&scratch[..length], out)
+        .map_err(|EncodeSliceError::OutputSliceTooSmall| Error::BufferTooSmall)
+}
+
+fn decode_into(encoded: &str, out: &mut [u8]) -> Result<usize, Error> {
+    URL_SAFE_NO_PAD
+        .decode_slice(encoded, out)
+        .map_err(|e| match e {
+            DecodeSliceError::DecodeError(e) => Error::Base64(e),
+            DecodeSliceError::OutputSliceTooSmall => Error::BufferTooSmall,
+        })
+}
+
+fn push_separator(out: &mut [u8], at: usize) -> Result<usize, Error> {
+    let separator = SEPARATOR.as_bytes();
+    let end = at + separator.len();
+    out.get_mut(at..end)
+        .ok_or(Error::BufferTooSmall)?
+        .copy_from_slice(separator);
+    Ok(end)
+}
+
+fn as_str(bytes: &[u8]) -> Result<&str, Error> {
+    core::str::from_utf8(bytes).map_err(|_| Error::Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use serde::Deserialize;
+    use sha2::{Sha256, Sha512};
+
+    use crate::algorithm::AlgorithmType;
+    use crate::embedded::{self, BoundedClaims};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::{Claims, RegisteredClaims, Token};
+
+    #[test]
+    pub fn compatible_with_alloc_api() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = Claims::new(RegisteredClaims {
+            subject: Some("sensor \"7\"".into()),
+            expiration: Some(1_700_000_000),
+            ..Default::default()
+        });
+        let token = claims.sign_with_key(&key)?;
+
+        let mut scratch = [0; 128];
+        let verified: BoundedClaims<16> = embedded::verify(&token, &key, &mut scratch)?;
+        assert_eq!(verified.subject.as_deref(), Some("sensor \"7\""));
+        assert_eq!(verified.expiration, Some(1_700_000_000));
+
+        let mut scratch = [0; 128];
+        let mut out = [0; 256];
+        let signed = embedded::sign_into(&verified, &key, &mut scratch, &mut out)?;
+        assert_eq!(signed, token);
+        Ok(())
+    }
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        sub: &'a str,
+    }
+
+    #[test]
+    pub fn borrowed_claims() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = BoundedClaims::<8> {
+            subject: Some("device".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let mut scratch = [0; 64];
+        let mut out = [0; 128];
+        let token = embedded::sign_into(&claims, &key, &mut scratch, &mut out)?;
+
+        let mut scratch = [0; 64];
+        let borrowed: Borrowed = embedded::verify(token, &key, &mut scratch)?;
+        assert_eq!(borrowed.sub, "device");
+        Ok(())
+    }
+
+    #[test]
+    pub fn rejected() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = BoundedClaims::<8>::default();
+
+        let mut scratch = [0; 64];
+        let mut out = [0; 16];
+        match embedded::sign_into(&claims, &key, &mut scratch, &mut out) {
+            Err(Error::BufferTooSmall) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+
+        let mut out = [0; 128];
+        let token = embedded::sign_into(&claims, &key, &mut scratch, &mut out)?;
+
+        let other: Hmac<Sha256> = Hmac::new_from_slice(b"other")?;
+        match embedded::verify::<BoundedClaims<8>, _>(token, &other, &mut [0; 64]) {
+            Err(Error::InvalidSignature) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+
+        let hs512: Hmac<Sha512> = Hmac::new_from_slice(b"secret")?;
+        match embedded::verify::<BoundedClaims<8>, _>(token, &hs512, &mut [0; 64]) {
+            Err(Error::AlgorithmMismatch(AlgorithmType::Hs256, AlgorithmType::Hs512)) => Ok(()),
+            x => panic!("Incorrect result {:?}", x),
+        }
+    }
+
+    #[test]
+    pub fn critical_parameters() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let header = Header {
+            critical: Some(vec!["exp".into()]),
+            ..Default::default()
+        };
+        let token = Token::new(header, Claims::default()).sign_with_key(&key)?;
+
+        match embedded::verify::<BoundedClaims<8>, _>(token.as_str(), &key, &mut [0; 64]) {
+            Err(Error::UnsupportedCriticalParameter(ref name)) if name == "exp" => Ok(()),
+            x => panic!("Incorrect result {:?}", x),
+        }
+    }
+}
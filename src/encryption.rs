@@ -0,0 +1,372 @@
+//! Encrypting the values of sensitive claims, e.g. a social security number,
+//! enabled with the `encryption` feature. An encrypted claim is a compact
+//! [JWE](https://tools.ietf.org/html/rfc7516) string with the algorithm `dir`
+//! and the encryption `A256GCM`, so that the rest of the token stays readable
+//! and it can be decrypted by any JOSE library with the key.
+//!
+//! A [ClaimCipher](struct.ClaimCipher.html) encrypts with its current
+//! [ClaimKey](struct.ClaimKey.html) and decrypts with the key whose id is the
+//! `kid` of the encrypted claim, so keys can be rotated while tokens
+//! encrypted with the previous key are still in use. Keys are generated
+//! randomly, or derived from a
+//! [MasterSecret](../algorithm/derive/struct.MasterSecret.html).
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::encryption::{ClaimCipher, ClaimKey};
+//! use jwt::{Claims, RegisteredClaims, SignWithKey, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! let cipher = ClaimCipher::new(ClaimKey::generate().unwrap().key_id("2024-01"));
+//! let mut claims = Claims::new(RegisteredClaims::default());
+//! claims.private.insert("ssn".into(), "078-05-1120".into());
+//! cipher.encrypt_claims(&mut claims.private, &["ssn"]).unwrap();
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+//! let token = claims.sign_with_key(&key).unwrap();
+//!
+//! let mut claims: Claims = token.verify_with_key(&key).unwrap();
+//! assert_ne!(claims.private["ssn"], "078-05-1120");
+//! cipher.decrypt_claims(&mut claims.private, &["ssn"]).unwrap();
+//! assert_eq!(claims.private["ssn"], "078-05-1120");
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use aes_gcm::aead::AeadInOut;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce, Tag};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// The length of keys in bytes.
+pub const KEY_LENGTH: usize = 32;
+
+const ALGORITHM: &str = "dir";
+const ENCRYPTION: &str = "A256GCM";
+const IV_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+
+#[derive(Serialize, Deserialize)]
+struct JweHeader {
+    alg: String,
+    enc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+/// A 256 bit AES key and its id. The key is wiped from memory when it is
+/// dropped.
+#[derive(Clone)]
+pub struct ClaimKey {
+    cipher: Aes256Gcm,
+    key_id: Option<String>,
+}
+
+impl ClaimKey {
+    pub fn new(key: &[u8]) -> Result<Self, Error> {
+        let cipher =
+            Aes256Gcm::new_from_slice(key).map_err(|_| Error::InvalidEncryptionKey(key.len()))?;
+        Ok(ClaimKey {
+            cipher,
+            key_id: None,
+        })
+    }
+
+    /// A new random key.
+    pub fn generate() -> Result<Self, Error> {
+        let mut key = zeroize::Zeroizing::new([0; KEY_LENGTH]);
+        getrandom::getrandom(&mut *key).map_err(Error::Random)?;
+        ClaimKey::new(&*key)
+    }
+
+    /// Set the id of the key, which is the `kid` of the claims it encrypts.
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    fn seal(&self, plaintext: Vec<u8>) -> Result<String, Error> {
+        let header = JweHeader {
+            alg: ALGORITHM.into(),
+            enc: ENCRYPTION.into(),
+            kid: self.key_id.clone(),
+        };
+        let header = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?);
+        let mut iv = [0; IV_LENGTH];
+        getrandom::getrandom(&mut iv).map_err(Error::Random)?;
+
+        let mut ciphertext = plaintext;
+        // Only fails for plaintexts longer than GCM allows
+        let tag = self
+            .cipher
+            .encrypt_inout_detached(
+                &iv.into(),
+                header.as_bytes(),
+                ciphertext.as_mut_slice().into(),
+            )
+            .map_err(|_| Error::Format)?;
+        Ok(format!(
+            "{}..{}.{}.{}",
+            header,
+            URL_SAFE_NO_PAD.encode(iv),
+            URL_SAFE_NO_PAD.encode(ciphertext),
+            URL_SAFE_NO_PAD.encode(tag)
+        ))
+    }
+
+    fn open(&self, jwe: Jwe) -> Result<Vec<u8>, Error> {
+        // The lengths were checked when parsing
+        let nonce = Nonce::try_from(&jwe.iv[..]).map_err(|_| Error::DecryptionFailed)?;
+        let tag = Tag::try_from(&jwe.tag[..]).map_err(|_| Error::DecryptionFailed)?;
+        let mut plaintext = jwe.ciphertext;
+        self.cipher
+            .decrypt_inout_detached(
+                &nonce,
+                jwe.protected.as_bytes(),
+                plaintext.as_mut_slice().into(),
+                &tag,
+            )
+            .map_err(|_| Error::DecryptionFailed)?;
+        Ok(plaintext)
+    }
+}
+
+impl fmt::Debug for ClaimKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClaimKey")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The parts of a compact JWE.
+struct Jwe<'a> {
+    protected: &'a str,
+    header: JweHeader,
+    iv: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: Vec<u8>,
+}
+
+impl<'a> Jwe<'a> {
+    fn parse(jwe: &'a str) -> Result<Self, Error> {
+        let mut parts = jwe.split('.');
+        let (protected, key, iv, ciphertext, tag) = match (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) {
+            (Some(protected), Some(key), Some(iv), Some(ciphertext), Some(tag), None) => {
+                (protected, key, iv, ciphertext, tag)
+            }
+            _ => return Err(Error::Format),
+        };
+
+        let header: JweHeader = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(protected)?)?;
+        if header.alg != ALGORITHM || header.enc != ENCRYPTION {
+            let encryption = format!("{} with {}", header.alg, header.enc);
+            return Err(Error::UnsupportedEncryption(encryption));
+        }
+        let (iv, tag) = (URL_SAFE_NO_PAD.decode(iv)?, URL_SAFE_NO_PAD.decode(tag)?);
+        // Direct encryption has no encrypted key
+        if !key.is_empty() || iv.len() != IV_LENGTH || tag.len() != TAG_LENGTH {
+            return Err(Error::Format);
+        }
+        Ok(Jwe {
+            protected,
+            header,
+            iv,
+            ciphertext: URL_SAFE_NO_PAD.decode(ciphertext)?,
+            tag,
+        })
+    }
+}
+
+/// Encrypts claims with the current key and decrypts them with the current or
+/// a previous key.
+#[derive(Clone, Debug)]
+pub struct ClaimCipher {
+    current: ClaimKey,
+    previous: Vec<ClaimKey>,
+}
+
+impl ClaimCipher {
+    pub fn new(current: ClaimKey) -> Self {
+        ClaimCipher {
+            current,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Also decrypt with a previous key. Keys are told apart by their id.
+    pub fn previous(mut self, key: ClaimKey) -> Self {
+        self.previous.push(key);
+        self
+    }
+
+    /// Encrypt a value into a compact JWE.
+    pub fn encrypt<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, Error> {
+        self.current.seal(serde_json::to_vec(value)?)
+    }
+
+    /// Decrypt a compact JWE with the key of its `kid`.
+    pub fn decrypt<T: DeserializeOwned>(&self, jwe: &str) -> Result<T, Error> {
+        let jwe = Jwe::parse(jwe)?;
+        let key = core::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.key_id == jwe.header.kid);
+        let key = match (key, &jwe.header.kid) {
+            (Some(key), _) => key,
+            (None, Some(kid)) => return Err(Error::NoKeyWithKeyId(kid.clone())),
+            (None, None) => return Err(Error::NoKeyId),
+        };
+        Ok(serde_json::from_slice(&key.open(jwe)?)?)
+    }
+
+    /// Replace the values of the named claims with their encryption. Claims
+    /// that are not present are skipped.
+    pub fn encrypt_claims(
+        &self,
+        claims: &mut BTreeMap<String, Value>,
+        names: &[&str],
+    ) -> Result<(), Error> {
+        for name in names {
+            if let Some(value) = claims.get_mut(*name) {
+                *value = Value::String(self.encrypt(value)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt the values of the named claims in place. Claims that are not
+    /// present are skipped.
+    pub fn decrypt_claims(
+        &self,
+        claims: &mut BTreeMap<String, Value>,
+        names: &[&'static str],
+    ) -> Result<(), Error> {
+        for name in names {
+            if let Some(value) = claims.get_mut(*name) {
+                let jwe = value.as_str().ok_or(Error::InvalidClaim(name))?;
+                *value = self.decrypt(jwe)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use serde_json::{json, Value};
+
+    use crate::algorithm::derive::MasterSecret;
+    use crate::encryption::{ClaimCipher, ClaimKey};
+    use crate::error::Error;
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let cipher = ClaimCipher::new(ClaimKey::new(&[7; 32])?.key_id("1"));
+        let value = json!({"number": "078-05-1120", "country": "US"});
+        let jwe = cipher.encrypt(&value)?;
+        assert_ne!(jwe, cipher.encrypt(&value)?);
+        assert_eq!(jwe.split('.').nth(1), Some(""));
+        let header: Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(jwe.split('.').next().unwrap())?)?;
+        assert_eq!(header, json!({"alg": "dir", "enc": "A256GCM", "kid": "1"}));
+        assert_eq!(cipher.decrypt::<Value>(&jwe)?, value);
+
+        let mut claims = BTreeMap::from([
+            ("ssn".to_owned(), json!("078-05-1120")),
+            ("name".to_owned(), json!("someone")),
+        ]);
+        cipher.encrypt_claims(&mut claims, &["ssn", "missing"])?;
+        assert!(claims["ssn"].as_str().unwrap().starts_with("ey"));
+        assert_eq!(claims["name"], "someone");
+        cipher.decrypt_claims(&mut claims, &["ssn", "missing"])?;
+        assert_eq!(claims["ssn"], "078-05-1120");
+        assert!(matches!(
+            cipher.decrypt_claims(&mut claims, &["name"]),
+            Err(Error::Format)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn rotation() -> Result<(), Error> {
+        let master = MasterSecret::new(b"master".to_vec());
+        let old = ClaimCipher::new(master.claim_key("claims")?.key_id("old"));
+        let jwe = old.encrypt("secret")?;
+
+        let new = ClaimCipher::new(ClaimKey::generate()?.key_id("new"))
+            .previous(master.claim_key("claims")?.key_id("old"));
+        assert_eq!(new.decrypt::<String>(&jwe)?, "secret");
+        assert!(matches!(
+            ClaimCipher::new(ClaimKey::generate()?.key_id("new")).decrypt::<String>(&jwe),
+            Err(Error::NoKeyWithKeyId(_))
+        ));
+        assert!(matches!(
+            ClaimCipher::new(ClaimKey::generate()?).decrypt::<String>(&jwe),
+            Err(Error::NoKeyWithKeyId(_))
+        ));
+
+        // Another key with the same id
+        let other = ClaimCipher::new(master.claim_key("other")?.key_id("old"));
+        assert!(matches!(
+            other.decrypt::<String>(&jwe),
+            Err(Error::DecryptionFailed)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn tampering() -> Result<(), Error> {
+        let cipher = ClaimCipher::new(ClaimKey::generate()?);
+        let jwe = cipher.encrypt("secret")?;
+        let parts: Vec<&str> = jwe.split('.').collect();
+
+        let mut ciphertext = URL_SAFE_NO_PAD.decode(parts[3])?;
+        ciphertext[0] ^= 1;
+        let altered = [
+            parts[0],
+            "",
+            parts[2],
+            &URL_SAFE_NO_PAD.encode(ciphertext),
+            parts[4],
+        ];
+        assert!(matches!(
+            cipher.decrypt::<String>(&altered.join(".")),
+            Err(Error::DecryptionFailed)
+        ));
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"dir","enc":"A128GCM"}"#);
+        let altered = [&header, "", parts[2], parts[3], parts[4]];
+        assert!(matches!(
+            cipher.decrypt::<String>(&altered.join(".")),
+            Err(Error::UnsupportedEncryption(_))
+        ));
+        assert!(matches!(
+            cipher.decrypt::<String>(&parts[..4].join(".")),
+            Err(Error::Format)
+        ));
+
+        assert!(matches!(
+            ClaimKey::new(b"short"),
+            Err(Error::InvalidEncryptionKey(5))
+        ));
+        Ok(())
+    }
+}
@@ -1,32 +1,285 @@
-use std::fmt;
-use std::string::FromUtf8Error;
+use alloc::boxed::Box;
+use alloc::string::{FromUtf8Error, String};
+use core::fmt;
+use core::time::Duration;
 
 use base64::DecodeError;
+#[cfg(feature = "hmac")]
 use crypto_common::InvalidLength;
+#[cfg(feature = "hmac")]
 use digest::MacError;
 use serde_json::Error as JsonError;
 
 use self::Error::*;
 use crate::algorithm::AlgorithmType;
+use crate::jwk::KeyOperation;
+use crate::token::raw::Component;
 
+/// New variants may be added without a major release; branch on
+/// [kind](#method.kind) where a match needs to be exhaustive.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     AlgorithmMismatch(AlgorithmType, AlgorithmType),
+    AlgorithmNotAllowed(AlgorithmType),
     Base64(DecodeError),
+    BufferTooSmall,
+    /// An `x5c` certificate has been revoked.
+    CertificateRevoked,
+    /// The claims do not match the JSON Schema of the validation.
+    ClaimsSchemaMismatch(String),
+    ComponentTooLong(Component, usize, usize),
+    /// An encrypted claim was not encrypted with the key, or was altered.
+    DecryptionFailed,
+    DecompressedTooLong(usize),
+    DegenerateKey,
+    DuplicateMember(String),
+    Expired {
+        by: Duration,
+    },
     Format,
+    /// A claim has the wrong type or value.
+    InvalidClaim(&'static str),
+    /// A key for encrypting claims does not have 32 bytes.
+    InvalidEncryptionKey(usize),
+    InvalidJwk(&'static str),
+    InvalidKeyForAlgorithm(AlgorithmType),
+    /// A JSON Schema for claims could not be compiled.
+    InvalidSchema(String),
     InvalidSignature,
+    /// The `typ` header is missing or names another type of token.
+    InvalidTokenType,
     Json(JsonError),
+    JsonTooDeep(usize),
+    JsonTooManyMembers(usize),
+    /// The `use` or `key_ops` parameter of a JWK forbid the operation.
+    KeyOperationNotPermitted(KeyOperation),
+    KeyTooShort(AlgorithmType, usize, usize),
+    /// A component could not be decoded. Contains the byte offset within the
+    /// token string at which decoding failed and the underlying error.
+    Malformed(Component, usize, Box<Error>),
+    MissingClaim(&'static str),
     NoClaimsComponent,
     NoHeaderComponent,
+    NoKeyForAlgorithm(AlgorithmType),
+    /// No signing key is configured for the purpose.
+    NoKeyForPurpose(String),
     NoKeyId,
     NoKeyWithKeyId(String),
     NoSignatureComponent,
+    NotYetValid {
+        by: Duration,
+    },
+    /// The revocation status of an `x5c` certificate is not known.
+    RevocationUnavailable(String),
+    RsaKeyTooShort(u32, u32),
+    #[cfg(feature = "hmac")]
     RustCryptoMac(MacError),
+    #[cfg(feature = "hmac")]
     RustCryptoMacKeyLength(InvalidLength),
+    /// The session of the `sid` claim has ended, or never existed.
+    SessionEnded(String),
+    /// The session of the `sid` claim could not be looked up.
+    SessionUnavailable(String),
+    SignatureCount(usize),
+    TokenTooLong(usize, usize),
     TooManyComponents,
+    UnknownAlgorithmType(String),
+    /// No keys are configured for the issuer.
+    UnknownIssuer(String),
+    /// The `crit` header lists a parameter that is not understood.
+    UnsupportedCriticalParameter(String),
+    /// The `alg` and `enc` of an encrypted claim are not `dir` and `A256GCM`.
+    UnsupportedEncryption(String),
+    UnprotectedHeader,
+    UnsecuredToken,
+    /// An `x5c` certificate chain does not lead to a trust anchor.
+    UntrustedCertificate(String),
     Utf8(FromUtf8Error),
     #[cfg(feature = "openssl")]
     OpenSsl(openssl::error::ErrorStack),
+    /// The system has no randomness to encrypt claims with.
+    #[cfg(feature = "encryption")]
+    Random(getrandom::Error),
+    #[cfg(feature = "signature")]
+    Signature(signature::Error),
+    #[cfg(feature = "simd-json")]
+    SimdJson(simd_json::Error),
+    #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+    SerdeJsonCoreDe(serde_json_core::de::Error),
+    #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+    SerdeJsonCoreSer(serde_json_core::ser::Error),
+}
+
+/// The class of failure of an [Error](enum.Error.html), for callers that need
+/// to branch on why a token was rejected without matching every variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The algorithm of the token does not match the key.
+    AlgorithmMismatch,
+    /// The algorithm is unknown or not supported.
+    UnsupportedAlgorithm,
+    /// The signature does not match the header and claims.
+    InvalidSignature,
+    /// The token has expired.
+    Expired,
+    /// The token is not valid yet.
+    NotYetValid,
+    /// A claim is missing or has the wrong value, e.g. the audience.
+    InvalidClaims,
+    /// The token was issued by an issuer that is not trusted.
+    UnknownIssuer,
+    /// The token string does not have the expected structure.
+    MalformedToken,
+    /// The header could not be decoded.
+    MalformedHeader,
+    /// The claims could not be decoded.
+    MalformedClaims,
+    /// The signature could not be decoded.
+    MalformedSignature,
+    /// The header does not contain a key id, but one is required.
+    MissingKeyId,
+    /// No key was found for the key id.
+    UnknownKeyId,
+    /// The key itself is invalid.
+    InvalidKey,
+    /// The token can not be represented in the requested serialization.
+    Unrepresentable,
+    /// Encoding or decoding failed outside of a specific component.
+    Encoding,
+    /// The underlying cryptography library failed.
+    Crypto,
+}
+
+impl ErrorKind {
+    /// A stable string code for the kind of error, e.g. for API responses.
+    /// Unlike the `Display` output, these will not change between versions.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ErrorKind::AlgorithmMismatch => "algorithm_mismatch",
+            ErrorKind::UnsupportedAlgorithm => "unsupported_algorithm",
+            ErrorKind::InvalidSignature => "invalid_signature",
+            ErrorKind::Expired => "token_expired",
+            ErrorKind::NotYetValid => "token_not_yet_valid",
+            ErrorKind::InvalidClaims => "invalid_claims",
+            ErrorKind::UnknownIssuer => "unknown_issuer",
+            ErrorKind::MalformedToken => "malformed_token",
+            ErrorKind::MalformedHeader => "malformed_header",
+            ErrorKind::MalformedClaims => "malformed_claims",
+            ErrorKind::MalformedSignature => "malformed_signature",
+            ErrorKind::MissingKeyId => "missing_key_id",
+            ErrorKind::UnknownKeyId => "unknown_key_id",
+            ErrorKind::InvalidKey => "invalid_key",
+            ErrorKind::Unrepresentable => "unrepresentable",
+            ErrorKind::Encoding => "encoding_error",
+            ErrorKind::Crypto => "crypto_error",
+        }
+    }
+
+    /// The error code for the `WWW-Authenticate` response header according to
+    /// [RFC 6750](https://tools.ietf.org/html/rfc6750#section-3.1). Errors
+    /// that are not caused by the presented token have no error code.
+    pub fn www_authenticate_error(&self) -> Option<&'static str> {
+        match *self {
+            ErrorKind::InvalidKey | ErrorKind::Unrepresentable | ErrorKind::Crypto => None,
+            _ => Some("invalid_token"),
+        }
+    }
+
+    /// The suggested HTTP status code. Rejected tokens map to 401
+    /// Unauthorized and errors that are not caused by the presented token map
+    /// to 500 Internal Server Error.
+    pub fn http_status(&self) -> u16 {
+        match self.www_authenticate_error() {
+            Some(_) => 401,
+            None => 500,
+        }
+    }
+}
+
+impl Error {
+    /// See [ErrorKind::code](enum.ErrorKind.html#method.code).
+    pub fn code(&self) -> &'static str {
+        self.kind().code()
+    }
+
+    /// See
+    /// [ErrorKind::www_authenticate_error](enum.ErrorKind.html#method.www_authenticate_error).
+    pub fn www_authenticate_error(&self) -> Option<&'static str> {
+        self.kind().www_authenticate_error()
+    }
+
+    /// See [ErrorKind::http_status](enum.ErrorKind.html#method.http_status).
+    pub fn http_status(&self) -> u16 {
+        self.kind().http_status()
+    }
+
+    /// Classify the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            AlgorithmMismatch(_, _) => ErrorKind::AlgorithmMismatch,
+            AlgorithmNotAllowed(_) | UnknownAlgorithmType(_) | UnsupportedEncryption(_) => {
+                ErrorKind::UnsupportedAlgorithm
+            }
+            InvalidSignature | UnsecuredToken => ErrorKind::InvalidSignature,
+            Expired { .. } => ErrorKind::Expired,
+            NotYetValid { .. } => ErrorKind::NotYetValid,
+            ClaimsSchemaMismatch(_)
+            | DecryptionFailed
+            | InvalidClaim(_)
+            | InvalidTokenType
+            | MissingClaim(_)
+            | SessionEnded(_)
+            | SessionUnavailable(_) => ErrorKind::InvalidClaims,
+            UnknownIssuer(_) => ErrorKind::UnknownIssuer,
+            Format
+            | NoHeaderComponent
+            | NoClaimsComponent
+            | NoSignatureComponent
+            | TooManyComponents
+            | TokenTooLong(_, _)
+            | ComponentTooLong(_, _, _)
+            | DecompressedTooLong(_)
+            | DuplicateMember(_)
+            | JsonTooDeep(_)
+            | JsonTooManyMembers(_) => ErrorKind::MalformedToken,
+            Malformed(Component::Header, _, _) | UnsupportedCriticalParameter(_) => {
+                ErrorKind::MalformedHeader
+            }
+            Malformed(Component::Claims, _, _) => ErrorKind::MalformedClaims,
+            Malformed(Component::Signature, _, _) => ErrorKind::MalformedSignature,
+            NoKeyId => ErrorKind::MissingKeyId,
+            NoKeyForAlgorithm(_) | NoKeyForPurpose(_) | NoKeyWithKeyId(_) => {
+                ErrorKind::UnknownKeyId
+            }
+            CertificateRevoked
+            | DegenerateKey
+            | InvalidEncryptionKey(_)
+            | InvalidJwk(_)
+            | InvalidKeyForAlgorithm(_)
+            | KeyOperationNotPermitted(_)
+            | KeyTooShort(_, _, _)
+            | RevocationUnavailable(_)
+            | RsaKeyTooShort(_, _)
+            | UntrustedCertificate(_) => ErrorKind::InvalidKey,
+            BufferTooSmall | SignatureCount(_) | UnprotectedHeader => ErrorKind::Unrepresentable,
+            Base64(_) | InvalidSchema(_) | Json(_) | Utf8(_) => ErrorKind::Encoding,
+            #[cfg(feature = "hmac")]
+            RustCryptoMac(_) => ErrorKind::InvalidSignature,
+            #[cfg(feature = "hmac")]
+            RustCryptoMacKeyLength(_) => ErrorKind::InvalidKey,
+            #[cfg(feature = "openssl")]
+            OpenSsl(_) => ErrorKind::Crypto,
+            #[cfg(feature = "encryption")]
+            Random(_) => ErrorKind::Crypto,
+            #[cfg(feature = "signature")]
+            Signature(_) => ErrorKind::Crypto,
+            #[cfg(feature = "simd-json")]
+            SimdJson(_) => ErrorKind::Encoding,
+            #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+            SerdeJsonCoreDe(_) | SerdeJsonCoreSer(_) => ErrorKind::Encoding,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -35,26 +288,147 @@ impl fmt::Display for Error {
             AlgorithmMismatch(a, b) => {
                 write!(f, "Expected algorithm type {:?} but found {:?}", a, b)
             }
+            Expired { by } => write!(f, "Token expired {} seconds ago", by.as_secs()),
+            NotYetValid { by } => {
+                write!(f, "Token is not valid for another {} seconds", by.as_secs())
+            }
+            AlgorithmNotAllowed(a) => write!(f, "Algorithm type {:?} is not allowed", a),
+            NoKeyForAlgorithm(a) => write!(f, "No key found for algorithm type {:?}", a),
             NoKeyId => write!(f, "No key id found"),
             NoKeyWithKeyId(ref kid) => write!(f, "Key with key id {} not found", kid),
+            NoKeyForPurpose(ref purpose) => write!(f, "No signing key for {} found", purpose),
             NoHeaderComponent => write!(f, "No header component found in token string"),
             NoClaimsComponent => write!(f, "No claims component found in token string"),
             NoSignatureComponent => write!(f, "No signature component found in token string"),
             TooManyComponents => write!(f, "Too many components found in token string"),
+            TokenTooLong(length, max) => write!(
+                f,
+                "Token of {} bytes is longer than the maximum of {} bytes",
+                length, max
+            ),
+            ComponentTooLong(component, length, max) => write!(
+                f,
+                "The {} of {} bytes is longer than the maximum of {} bytes",
+                component, length, max
+            ),
+            DuplicateMember(ref name) => write!(f, "Duplicate JSON member {}", name),
+            JsonTooDeep(max) => write!(f, "JSON is nested deeper than {} levels", max),
+            JsonTooManyMembers(max) => write!(f, "JSON has more than {} members", max),
+            DecompressedTooLong(max) => {
+                write!(f, "Decompressed payload is longer than {} bytes", max)
+            }
+            SignatureCount(n) => write!(f, "Expected exactly one signature but found {}", n),
+            UnknownAlgorithmType(ref alg) => write!(f, "Unknown algorithm type {}", alg),
+            UnknownIssuer(ref issuer) => write!(f, "Unknown issuer {}", issuer),
+            UnsupportedCriticalParameter(ref name) => {
+                write!(f, "Unsupported critical header parameter {}", name)
+            }
+            UnsupportedEncryption(ref encryption) => {
+                write!(f, "Unsupported claim encryption {}", encryption)
+            }
+            InvalidClaim(name) => write!(f, "Invalid {} claim", name),
+            MissingClaim(name) => write!(f, "Missing {} claim", name),
+            UnprotectedHeader => write!(f, "Unprotected header can not be represented"),
+            BufferTooSmall => write!(f, "Buffer too small"),
+            UnsecuredToken => write!(f, "Unsecured token without a signature"),
+            CertificateRevoked => write!(f, "Certificate has been revoked"),
+            RevocationUnavailable(ref reason) => {
+                write!(f, "Revocation status of certificate is unknown: {}", reason)
+            }
+            UntrustedCertificate(ref reason) => {
+                write!(f, "Certificate chain is not trusted: {}", reason)
+            }
+            ClaimsSchemaMismatch(ref reason) => {
+                write!(f, "Claims do not match the schema: {}", reason)
+            }
+            InvalidSchema(ref reason) => write!(f, "Invalid claims schema: {}", reason),
+            SessionEnded(ref sid) => write!(f, "Session {} has ended", sid),
+            SessionUnavailable(ref reason) => {
+                write!(f, "Session could not be looked up: {}", reason)
+            }
             Format => write!(f, "Format"),
             InvalidSignature => write!(f, "Invalid signature"),
+            DecryptionFailed => write!(f, "Encrypted claim could not be decrypted"),
+            InvalidEncryptionKey(length) => {
+                write!(f, "Encryption key of {} bytes is not 32 bytes long", length)
+            }
+            InvalidTokenType => write!(f, "Token is not of the expected type"),
+            InvalidJwk(reason) => write!(f, "Invalid JWK: {}", reason),
+            KeyOperationNotPermitted(operation) => {
+                write!(f, "Key may not be used to {}", operation.as_str())
+            }
+            KeyTooShort(a, length, minimum) => write!(
+                f,
+                "Key of {} bytes is shorter than the minimum of {} bytes for algorithm type {:?}",
+                length, minimum, a
+            ),
+            RsaKeyTooShort(bits, minimum) => write!(
+                f,
+                "RSA key of {} bits is shorter than the minimum of {} bits",
+                bits, minimum
+            ),
+            DegenerateKey => write!(f, "Degenerate key"),
+            InvalidKeyForAlgorithm(a) => {
+                write!(f, "Key can not be used for algorithm type {:?}", a)
+            }
+            Malformed(component, offset, ref x) => {
+                write!(
+                    f,
+                    "Malformed {} component at byte {}: {}",
+                    component, offset, x
+                )
+            }
             Base64(ref x) => write!(f, "{}", x),
             Json(ref x) => write!(f, "{}", x),
             Utf8(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "hmac")]
             RustCryptoMac(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "hmac")]
             RustCryptoMacKeyLength(ref x) => write!(f, "{}", x),
             #[cfg(feature = "openssl")]
             OpenSsl(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "encryption")]
+            Random(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "signature")]
+            Signature(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "simd-json")]
+            SimdJson(ref x) => write!(f, "{}", x),
+            #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+            SerdeJsonCoreDe(ref x) => write!(f, "{}", x),
+            #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+            SerdeJsonCoreSer(ref x) => write!(f, "{}", x),
         }
     }
 }
 
-impl std::error::Error for Error {}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Malformed(_, _, ref x) => Some(&**x),
+            Base64(ref x) => Some(x),
+            Json(ref x) => Some(x),
+            Utf8(ref x) => Some(x),
+            #[cfg(feature = "hmac")]
+            RustCryptoMac(ref x) => Some(x),
+            #[cfg(feature = "hmac")]
+            RustCryptoMacKeyLength(ref x) => Some(x),
+            #[cfg(feature = "openssl")]
+            OpenSsl(ref x) => Some(x),
+            #[cfg(feature = "encryption")]
+            Random(ref x) => Some(x),
+            #[cfg(feature = "signature")]
+            Signature(ref x) => Some(x),
+            #[cfg(feature = "simd-json")]
+            SimdJson(ref x) => Some(x),
+            #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+            SerdeJsonCoreDe(ref x) => Some(x),
+            #[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+            SerdeJsonCoreSer(ref x) => Some(x),
+            _ => None,
+        }
+    }
+}
 
 macro_rules! error_wrap {
     ($f:ty, $e:expr) => {
@@ -69,7 +443,57 @@ macro_rules! error_wrap {
 error_wrap!(DecodeError, Base64);
 error_wrap!(JsonError, Json);
 error_wrap!(FromUtf8Error, Utf8);
+#[cfg(feature = "hmac")]
 error_wrap!(MacError, RustCryptoMac);
+#[cfg(feature = "hmac")]
 error_wrap!(InvalidLength, RustCryptoMacKeyLength);
 #[cfg(feature = "openssl")]
 error_wrap!(openssl::error::ErrorStack, Error::OpenSsl);
+#[cfg(feature = "signature")]
+error_wrap!(signature::Error, Error::Signature);
+#[cfg(feature = "simd-json")]
+error_wrap!(simd_json::Error, Error::SimdJson);
+#[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+error_wrap!(serde_json_core::de::Error, Error::SerdeJsonCoreDe);
+#[cfg(any(feature = "serde-json-core", feature = "heapless"))]
+error_wrap!(serde_json_core::ser::Error, Error::SerdeJsonCoreSer);
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::error::Error as _;
+    use std::time::Duration;
+
+    use crate::error::{Error, ErrorKind};
+    use crate::token::raw::Component;
+    use crate::FromBase64;
+
+    #[test]
+    fn malformed_source() {
+        let json_error = serde_json::Value::from_base64("bm90IGpzb24").unwrap_err();
+        let error = Error::Malformed(Component::Claims, 0, Box::new(json_error));
+
+        assert_eq!(error.kind(), ErrorKind::MalformedClaims);
+        #[cfg(feature = "std")]
+        {
+            let source = error.source().unwrap();
+            assert!(source.downcast_ref::<Error>().is_some());
+            assert!(source.source().is_some());
+        }
+    }
+
+    #[test]
+    fn http_mapping() {
+        let expired = Error::Expired {
+            by: Duration::from_secs(1),
+        };
+        assert_eq!(expired.code(), "token_expired");
+        assert_eq!(expired.http_status(), 401);
+        assert_eq!(expired.www_authenticate_error(), Some("invalid_token"));
+
+        let unrepresentable = Error::UnprotectedHeader;
+        assert_eq!(unrepresentable.code(), "unrepresentable");
+        assert_eq!(unrepresentable.http_status(), 500);
+        assert_eq!(unrepresentable.www_authenticate_error(), None);
+    }
+}
@@ -0,0 +1,309 @@
+//! A C interface for signing and verifying HMAC tokens, enabled with the
+//! `ffi` feature. The declarations are in `include/jwt.h`. To build a shared
+//! library, run
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Every function returns a [JwtStatus](enum.JwtStatus.html) and writes a
+//! string to `out` that has to be freed with `jwt_string_free`. On success the
+//! string is the result, otherwise it is a description of the error.
+//! ## Examples
+//! ```c
+//! char *token = NULL;
+//! if (jwt_sign("HS256", key, key_len, "{\"sub\":\"someone\"}", &token) == JWT_OK) {
+//!     puts(token);
+//! }
+//! jwt_string_free(token);
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::AlgorithmType;
+use crate::claims::Claims;
+use crate::clock::SystemClock;
+use crate::error::{Error, ErrorKind};
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+use crate::Token;
+
+/// The result of a call. The values are part of the C interface and will not
+/// change.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JwtStatus {
+    Ok = 0,
+    NullArgument = 1,
+    InvalidUtf8 = 2,
+    AlgorithmMismatch = 3,
+    UnsupportedAlgorithm = 4,
+    InvalidSignature = 5,
+    Expired = 6,
+    NotYetValid = 7,
+    MalformedToken = 8,
+    InvalidKey = 9,
+    Other = 10,
+}
+
+impl From<ErrorKind> for JwtStatus {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::AlgorithmMismatch => JwtStatus::AlgorithmMismatch,
+            ErrorKind::UnsupportedAlgorithm => JwtStatus::UnsupportedAlgorithm,
+            ErrorKind::InvalidSignature => JwtStatus::InvalidSignature,
+            ErrorKind::Expired => JwtStatus::Expired,
+            ErrorKind::NotYetValid => JwtStatus::NotYetValid,
+            ErrorKind::MalformedToken
+            | ErrorKind::MalformedHeader
+            | ErrorKind::MalformedClaims
+            | ErrorKind::MalformedSignature => JwtStatus::MalformedToken,
+            ErrorKind::InvalidKey => JwtStatus::InvalidKey,
+            _ => JwtStatus::Other,
+        }
+    }
+}
+
+/// An error before the library is called, or one from the library.
+enum FfiError {
+    NullArgument,
+    InvalidUtf8,
+    Jwt(Error),
+}
+
+impl From<Error> for FfiError {
+    fn from(error: Error) -> Self {
+        FfiError::Jwt(error)
+    }
+}
+
+impl From<serde_json::Error> for FfiError {
+    fn from(error: serde_json::Error) -> Self {
+        FfiError::Jwt(Error::Json(error))
+    }
+}
+
+/// Sign the JSON claims with an HMAC key. The algorithm is the name of one of
+/// the `HS*` algorithms, e.g. `"HS256"`.
+///
+/// # Safety
+/// `algorithm` and `claims_json` have to be null terminated strings, `key`
+/// has to point to `key_len` readable bytes and `out` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn jwt_sign(
+    algorithm: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    claims_json: *const c_char,
+    out: *mut *mut c_char,
+) -> JwtStatus {
+    respond(out, || {
+        let algorithm_type: AlgorithmType = read_str(algorithm)?.parse()?;
+        let signer = AnySigner::hmac(algorithm_type, read_bytes(key, key_len)?)?;
+        let claims: Claims = serde_json::from_str(read_str(claims_json)?)?;
+        let header = Header {
+            algorithm: algorithm_type,
+            ..Default::default()
+        };
+
+        let token = Token::new(header, claims).sign_with_key(&signer)?;
+        Ok(token.as_str().to_owned())
+    })
+}
+
+/// Verify a token with an HMAC key and check the `exp` and `nbf` claims
+/// against the system time. On success, the claims are written as JSON.
+///
+/// # Safety
+/// `algorithm` and `token` have to be null terminated strings, `key` has to
+/// point to `key_len` readable bytes and `out` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn jwt_verify(
+    algorithm: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    token: *const c_char,
+    out: *mut *mut c_char,
+) -> JwtStatus {
+    respond(out, || {
+        let algorithm_type: AlgorithmType = read_str(algorithm)?.parse()?;
+        let verifier = AnyVerifier::hmac(algorithm_type, read_bytes(key, key_len)?)?;
+        let token: Token<Header, Claims, _> = read_str(token)?.verify_with_key(&verifier)?;
+        token.claims().registered.check_time_with(&SystemClock)?;
+
+        Ok(serde_json::to_string(token.claims())?)
+    })
+}
+
+/// Decode the claims of a token as JSON **without** verifying the signature.
+///
+/// # Safety
+/// `token` has to be a null terminated string and `out` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn jwt_decode_claims_json(
+    token: *const c_char,
+    out: *mut *mut c_char,
+) -> JwtStatus {
+    respond(out, || {
+        let token: Token<Header, Claims, _> = Token::parse_unverified(read_str(token)?)?;
+        Ok(serde_json::to_string(token.claims())?)
+    })
+}
+
+/// Free a string returned by one of the other functions. Null is ignored.
+///
+/// # Safety
+/// `string` has to be null or a string returned by this library, which has
+/// not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jwt_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+unsafe fn read_str<'a>(string: *const c_char) -> Result<&'a str, FfiError> {
+    if string.is_null() {
+        return Err(FfiError::NullArgument);
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| FfiError::InvalidUtf8)
+}
+
+unsafe fn read_bytes<'a>(bytes: *const u8, len: usize) -> Result<&'a [u8], FfiError> {
+    match (bytes.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(FfiError::NullArgument),
+        (false, _) => Ok(slice::from_raw_parts(bytes, len)),
+    }
+}
+
+/// Run the call and write its result or error message to `out`.
+unsafe fn respond(
+    out: *mut *mut c_char,
+    call: impl FnOnce() -> Result<String, FfiError>,
+) -> JwtStatus {
+    if out.is_null() {
+        return JwtStatus::NullArgument;
+    }
+
+    let (status, message) = match call() {
+        Ok(result) => (JwtStatus::Ok, result),
+        Err(FfiError::NullArgument) => (JwtStatus::NullArgument, "null argument".to_owned()),
+        Err(FfiError::InvalidUtf8) => (JwtStatus::InvalidUtf8, "invalid UTF-8".to_owned()),
+        Err(FfiError::Jwt(e)) => (e.kind().into(), e.to_string()),
+    };
+
+    // Neither tokens nor JSON nor error messages contain null bytes
+    *out = CString::new(message).map_or(ptr::null_mut(), CString::into_raw);
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    use crate::ffi::{jwt_decode_claims_json, jwt_sign, jwt_string_free, jwt_verify, JwtStatus};
+
+    unsafe fn take(out: *mut c_char) -> String {
+        let string = CStr::from_ptr(out).to_str().unwrap().to_owned();
+        jwt_string_free(out);
+        string
+    }
+
+    #[test]
+    pub fn sign_and_verify() {
+        let algorithm = CString::new("HS256").unwrap();
+        let key = b"secret";
+        let claims = CString::new(r#"{"sub":"someone","admin":true}"#).unwrap();
+
+        unsafe {
+            let mut out = ptr::null_mut();
+            let status = jwt_sign(
+                algorithm.as_ptr(),
+                key.as_ptr(),
+                key.len(),
+                claims.as_ptr(),
+                &mut out,
+            );
+            assert_eq!(status, JwtStatus::Ok);
+            let token = CString::new(take(out)).unwrap();
+
+            let status = jwt_verify(
+                algorithm.as_ptr(),
+                key.as_ptr(),
+                key.len(),
+                token.as_ptr(),
+                &mut out,
+            );
+            assert_eq!(status, JwtStatus::Ok);
+            assert_eq!(take(out), r#"{"sub":"someone","admin":true}"#);
+
+            let status = jwt_decode_claims_json(token.as_ptr(), &mut out);
+            assert_eq!(status, JwtStatus::Ok);
+            assert_eq!(take(out), r#"{"sub":"someone","admin":true}"#);
+
+            let wrong = b"wrong";
+            let status = jwt_verify(
+                algorithm.as_ptr(),
+                wrong.as_ptr(),
+                wrong.len(),
+                token.as_ptr(),
+                &mut out,
+            );
+            assert_eq!(status, JwtStatus::InvalidSignature);
+            take(out);
+        }
+    }
+
+    #[test]
+    pub fn errors() {
+        // {"exp":1} signed with "secret"
+        let token = CString::new(
+            "eyJhbGciOiJIUzI1NiJ9.eyJleHAiOjF9.NED7FDrPJCtAvhJ-OxAe5ft0SIqmDbUaeKm7233QG3A",
+        )
+        .unwrap();
+        let algorithm = CString::new("HS256").unwrap();
+
+        unsafe {
+            let mut out = ptr::null_mut();
+            assert_eq!(
+                jwt_decode_claims_json(ptr::null(), &mut out),
+                JwtStatus::NullArgument
+            );
+            take(out);
+            assert_eq!(
+                jwt_decode_claims_json(token.as_ptr(), ptr::null_mut()),
+                JwtStatus::NullArgument
+            );
+
+            let key = b"secret";
+            let status = jwt_verify(
+                algorithm.as_ptr(),
+                key.as_ptr(),
+                key.len(),
+                token.as_ptr(),
+                &mut out,
+            );
+            assert_eq!(status, JwtStatus::Expired);
+            take(out);
+
+            let malformed = CString::new("not a token").unwrap();
+            assert_eq!(
+                jwt_decode_claims_json(malformed.as_ptr(), &mut out),
+                JwtStatus::MalformedToken
+            );
+            take(out);
+
+            let unknown = CString::new("none").unwrap();
+            let status = jwt_verify(unknown.as_ptr(), ptr::null(), 0, token.as_ptr(), &mut out);
+            assert_eq!(status, JwtStatus::UnsupportedAlgorithm);
+            assert!(!take(out).is_empty());
+        }
+    }
+}
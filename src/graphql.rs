@@ -0,0 +1,222 @@
+//! Integration with [async-graphql](https://docs.rs/async-graphql), enabled
+//! with the `async-graphql` feature. [authenticate](fn.authenticate.html)
+//! verifies the bearer token when the GraphQL request is set up and adds the
+//! claims to its data, where resolvers find them with `ctx.data::<T>()`.
+//! Fields are protected with the [Authenticated](struct.Authenticated.html)
+//! and [ScopeGuard](struct.ScopeGuard.html) guards.
+//! ## Examples
+//! ```
+//! use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::graphql::{authenticate, ScopeGuard};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(guard = "ScopeGuard::new(\"profile\")")]
+//!     async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+//!         ctx.data::<RegisteredClaims>().ok()?.subject.clone()
+//!     }
+//! }
+//!
+//! let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! // With the value of the `Authorization` header of the HTTP request
+//! let request = authenticate::<RegisteredClaims>(&auth, "{ me }".into(), None).unwrap();
+//! ```
+
+use async_graphql::{Context, ErrorExtensions, Guard, Request};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::error::Error;
+use crate::source::RequestParts;
+
+/// The scopes granted by the verified token, for the guards.
+struct Scopes(Vec<String>);
+
+impl Scopes {
+    fn from_claims(claims: &Value) -> Self {
+        Scopes(crate::policy::scopes(claims).map(str::to_owned).collect())
+    }
+}
+
+/// Verify the bearer token in the value of the `Authorization` header and add
+/// its claims of type `T` to the data of the request. Requests without the
+/// header are passed on without claims, so that the guards can reject them
+/// field by field, but invalid tokens reject the whole request.
+pub fn authenticate<T>(
+    auth: &BearerAuth,
+    request: Request,
+    authorization: Option<&str>,
+) -> Result<Request, Rejection>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let authorization = match authorization {
+        Some(authorization) => authorization,
+        None => return Ok(request),
+    };
+    let claims: Value = auth.authenticate(Some(authorization))?;
+    with_claims::<T>(request, claims)
+}
+
+/// Like [authenticate](fn.authenticate.html), but with the token from the
+/// [sources](../source/index.html) of the `BearerAuth`.
+pub fn authenticate_request<T>(
+    auth: &BearerAuth,
+    request: Request,
+    parts: &dyn RequestParts,
+) -> Result<Request, Rejection>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    match auth.authenticate_request::<Value>(parts) {
+        Ok(claims) => with_claims::<T>(request, claims),
+        Err(Rejection::Missing) => Ok(request),
+        Err(rejection) => Err(rejection),
+    }
+}
+
+fn with_claims<T>(request: Request, claims: Value) -> Result<Request, Rejection>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let scopes = Scopes::from_claims(&claims);
+    let claims: T = serde_json::from_value(claims).map_err(Error::Json)?;
+    Ok(request.data(claims).data(scopes))
+}
+
+fn rejected(rejection: Rejection) -> async_graphql::Error {
+    let code = match rejection {
+        Rejection::InsufficientScope | Rejection::Forbidden(_) => "FORBIDDEN",
+        _ => "UNAUTHENTICATED",
+    };
+    async_graphql::Error::new(rejection.to_string()).extend_with(|_, e| e.set("code", code))
+}
+
+/// Allows access to authenticated requests.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Authenticated;
+
+impl Guard for Authenticated {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<Scopes>() {
+            Some(_) => Ok(()),
+            None => Err(rejected(Rejection::Missing)),
+        }
+    }
+}
+
+/// Allows access to authenticated requests whose token grants the scope.
+#[derive(Clone, Debug)]
+pub struct ScopeGuard {
+    scope: String,
+}
+
+impl ScopeGuard {
+    pub fn new(scope: impl Into<String>) -> Self {
+        ScopeGuard {
+            scope: scope.into(),
+        }
+    }
+}
+
+impl Guard for ScopeGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<Scopes>() {
+            Some(Scopes(scopes)) if scopes.contains(&self.scope) => Ok(()),
+            Some(_) => Err(rejected(Rejection::InsufficientScope)),
+            None => Err(rejected(Rejection::Missing)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context as TaskContext, Poll, Waker};
+
+    use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, PathSegment, Schema};
+    use serde_json::json;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::graphql::{authenticate, Authenticated, ScopeGuard};
+    use crate::token::signed::SignWithKey;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = TaskContext::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn public(&self) -> &str {
+            "hello"
+        }
+
+        #[graphql(guard = "Authenticated")]
+        async fn me(&self, ctx: &Context<'_>) -> String {
+            ctx.data_unchecked::<crate::Claims>()
+                .registered
+                .subject
+                .clone()
+                .unwrap_or_default()
+        }
+
+        #[graphql(guard = "ScopeGuard::new(\"admin\")")]
+        async fn secret(&self) -> &str {
+            "42"
+        }
+    }
+
+    #[test]
+    pub fn guards() -> Result<(), Error> {
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = json!({ "sub": "someone", "scope": "profile email" });
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let authorization = format!("Bearer {}", token);
+
+        let request = authenticate::<crate::Claims>(&auth, "{ public me }".into(), None).unwrap();
+        let response = block_on(schema.execute(request));
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].path, [PathSegment::Field("me".into())]);
+
+        let request =
+            authenticate::<crate::Claims>(&auth, "{ me }".into(), Some(&authorization)).unwrap();
+        let response = block_on(schema.execute(request));
+        assert!(response.errors.is_empty());
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            json!({ "me": "someone" })
+        );
+
+        let request =
+            authenticate::<crate::Claims>(&auth, "{ secret }".into(), Some(&authorization))
+                .unwrap();
+        let response = block_on(schema.execute(request));
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("code"), Some(&"FORBIDDEN".into()));
+
+        assert!(
+            authenticate::<crate::Claims>(&auth, "{ me }".into(), Some("Bearer a.b.c")).is_err()
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 //! Convenience structs for commonly defined fields in headers.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 use serde::{Deserialize, Serialize};
 
@@ -24,10 +27,42 @@ pub trait JoseHeader {
     fn content_type(&self) -> Option<HeaderContentType> {
         None
     }
+
+    /// The parameters listed in `crit`, which must be understood to verify
+    /// the token.
+    fn critical(&self) -> Option<&[String]> {
+        None
+    }
+}
+
+/// Extension parameters that `crit` may list.
+const UNDERSTOOD_PARAMETERS: &[&str] = &[];
+
+/// Reject a header with a critical parameter that is not understood, as
+/// [RFC 7515](https://tools.ietf.org/html/rfc7515#section-4.1.11) requires.
+pub(crate) fn reject_critical<H: JoseHeader>(header: &H) -> Result<(), Error> {
+    let critical = header.critical().unwrap_or_default();
+    reject_critical_names(critical.iter().map(String::as_str))
+}
+
+/// Like [reject_critical], for the names that `crit` lists.
+pub(crate) fn reject_critical_names<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    match names
+        .into_iter()
+        .find(|name| !UNDERSTOOD_PARAMETERS.contains(name))
+    {
+        Some(name) => Err(Error::UnsupportedCriticalParameter(name.into())),
+        None => Ok(()),
+    }
 }
 
 /// Generic [JWT header](https://tools.ietf.org/html/rfc7519#page-11) with
-/// defined fields for common fields.
+/// defined fields for common fields. The fields are always serialized in the
+/// order `alg`, `kid`, `typ`, `cty`, `crit`, followed by any other members;
+/// wrap the header in [Canonical](../canonical/struct.Canonical.html) for
+/// canonical JSON instead.
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Header {
     #[serde(rename = "alg")]
@@ -41,6 +76,18 @@ pub struct Header {
 
     #[serde(rename = "cty", skip_serializing_if = "Option::is_none")]
     pub content_type: Option<HeaderContentType>,
+
+    /// Tokens are rejected when verified if this lists a parameter that is
+    /// not understood.
+    #[serde(rename = "crit", skip_serializing_if = "Option::is_none")]
+    pub critical: Option<Vec<String>>,
+
+    /// Members without a field of their own, e.g. vendor specific
+    /// parameters, kept so that a parsed header is serialized with them again.
+    /// Being flattened, they keep the header from being wrapped in
+    /// [CoreJson](../core_json/struct.CoreJson.html).
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 impl JoseHeader for Header {
@@ -59,6 +106,10 @@ impl JoseHeader for Header {
     fn content_type(&self) -> Option<HeaderContentType> {
         self.content_type
     }
+
+    fn critical(&self) -> Option<&[String]> {
+        self.critical.as_deref()
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -66,6 +117,16 @@ impl JoseHeader for Header {
 pub enum HeaderType {
     #[serde(rename = "JWT")]
     JsonWebToken,
+    /// A [Security Event Token](../secevent/index.html).
+    #[serde(rename = "secevent+jwt", alias = "application/secevent+jwt")]
+    SecurityEvent,
+    /// An [OAuth 2.0 access token](https://tools.ietf.org/html/rfc9068).
+    #[serde(rename = "at+jwt", alias = "application/at+jwt")]
+    AccessToken,
+    /// A refresh token issued by a
+    /// [PairIssuer](../refresh/struct.PairIssuer.html).
+    #[serde(rename = "refresh+jwt")]
+    RefreshToken,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -132,8 +193,19 @@ impl<'a> JoseHeader for BorrowedKeyHeader<'a> {
 mod tests {
     use crate::algorithm::AlgorithmType;
     use crate::error::Error;
-    use crate::header::{Header, HeaderType, PrecomputedAlgorithmOnlyHeader};
-    use crate::{FromBase64, ToBase64};
+    use std::collections::BTreeMap;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use hmac::{Hmac, KeyInit};
+    use serde_json::Value;
+    use sha2::Sha256;
+
+    use crate::claims::Claims;
+    use crate::header::{Header, HeaderContentType, HeaderType, PrecomputedAlgorithmOnlyHeader};
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::{FromBase64, ToBase64, Token};
 
     #[test]
     fn from_base64() -> Result<(), Error> {
@@ -160,6 +232,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn extra_members() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let json = r#"{"alg":"HS256","x-vendor":{"region":"eu"}}"#;
+        let token_str = Token::new(
+            Header::from_base64(&*URL_SAFE_NO_PAD.encode(json))?,
+            Claims::default(),
+        )
+        .sign_with_key(&key)?;
+
+        let token: Token<Header, Claims, _> = token_str.as_str().verify_with_key(&key)?;
+        assert_eq!(token.header().extra["x-vendor"]["region"], "eu");
+        let (header, mut claims) = token.into();
+        claims.registered.expiration = Some(1);
+        let resigned = Token::new(header, claims).sign_with_key(&key)?;
+
+        let header: Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(resigned.raw_header())?)?;
+        assert_eq!(header, serde_json::from_str::<Value>(json)?);
+        Ok(())
+    }
+
+    #[test]
+    fn critical_parameters() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let json = r#"{"alg":"HS256","x-vendor":{"region":"eu"},"crit":["x-vendor"]}"#;
+        let header = Header::from_base64(&*URL_SAFE_NO_PAD.encode(json))?;
+        assert_eq!(header.critical, Some(vec!["x-vendor".to_owned()]));
+        let token_str = Token::new(header, Claims::default()).sign_with_key(&key)?;
+
+        let token: Result<Token<Header, Claims, _>, _> = token_str.as_str().verify_with_key(&key);
+        assert!(matches!(
+            token,
+            Err(Error::UnsupportedCriticalParameter(ref name)) if name == "x-vendor"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn field_order() -> Result<(), Error> {
+        let header = Header {
+            algorithm: AlgorithmType::Es256,
+            key_id: Some("first".into()),
+            type_: Some(HeaderType::JsonWebToken),
+            content_type: Some(HeaderContentType::JsonWebToken),
+            critical: Some(vec!["b64".into()]),
+            extra: BTreeMap::from([("b64".into(), false.into())]),
+        };
+        assert_eq!(
+            serde_json::to_string(&header)?,
+            r#"{"alg":"ES256","kid":"first","typ":"JWT","cty":"JWT","crit":["b64"],"b64":false}"#
+        );
+        Ok(())
+    }
+
     #[test]
     fn precomputed_headers() -> Result<(), Error> {
         let algorithms = [
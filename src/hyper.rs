@@ -0,0 +1,208 @@
+//! A wrapper for plain [hyper](https://docs.rs/hyper) services, enabled with
+//! the `hyper` feature. [JwtService](struct.JwtService.html) verifies the
+//! bearer token of every request and inserts the claims into the request
+//! extensions. Requests without a valid token are answered with the status
+//! and `WWW-Authenticate` header of the
+//! [Rejection](../bearer/enum.Rejection.html) and an empty body.
+//! ## Examples
+//! ```
+//! use std::convert::Infallible;
+//!
+//! use hyper::service::service_fn;
+//! use hyper::{Request, Response};
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::hyper::JwtService;
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let whoami = service_fn(|request: Request<String>| async move {
+//!     let claims = request.extensions().get::<RegisteredClaims>().unwrap();
+//!     Ok::<_, Infallible>(Response::new(claims.subject.clone().unwrap_or_default()))
+//! });
+//! let service = JwtService::<_, RegisteredClaims>::new(whoami, auth);
+//! // Serve it with `hyper::server::conn::http1::Builder::serve_connection`
+//! ```
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::header::WWW_AUTHENTICATE;
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode, Uri};
+use hyper::service::Service;
+use pin_project_lite::pin_project;
+use serde::de::DeserializeOwned;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::source::RequestParts;
+
+/// Verifies the bearer token of requests before passing them on to the
+/// inner service with the claims of type `T` in their extensions.
+pub struct JwtService<S, T = crate::Claims> {
+    inner: S,
+    auth: BearerAuth,
+    claims: PhantomData<fn() -> T>,
+}
+
+impl<S, T> JwtService<S, T> {
+    pub fn new(inner: S, auth: BearerAuth) -> Self {
+        JwtService {
+            inner,
+            auth,
+            claims: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, T> Clone for JwtService<S, T> {
+    fn clone(&self) -> Self {
+        JwtService::new(self.inner.clone(), self.auth.clone())
+    }
+}
+
+impl<S, T, B, ResBody> Service<Request<B>> for JwtService<S, T>
+where
+    S: Service<Request<B>, Response = Response<ResBody>>,
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let parts = HttpParts(request.headers(), request.uri());
+        let claims = self.auth.authenticate_request::<T>(&parts);
+
+        match claims {
+            Ok(claims) => {
+                request.extensions_mut().insert(claims);
+                ResponseFuture::Inner {
+                    future: self.inner.call(request),
+                }
+            }
+            Err(rejection) => ResponseFuture::Rejected {
+                response: Some(rejection_response(&rejection)),
+            },
+        }
+    }
+}
+
+struct HttpParts<'a>(&'a HeaderMap, &'a Uri);
+
+impl RequestParts for HttpParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.1.query()
+    }
+}
+
+pin_project! {
+    /// The response future of [JwtService](struct.JwtService.html).
+    #[project = ResponseFutureProjection]
+    pub enum ResponseFuture<F, ResBody> {
+        Inner { #[pin] future: F },
+        Rejected { response: Option<Response<ResBody>> },
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F, ResBody>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            ResponseFutureProjection::Inner { future } => future.poll(cx),
+            ResponseFutureProjection::Rejected { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+fn rejection_response<B: Default>(rejection: &Rejection) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() =
+        StatusCode::from_u16(rejection.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(challenge) = rejection.www_authenticate() {
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+    use http::{Request, Response, StatusCode};
+    use hyper::service::{service_fn, Service};
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::hyper::JwtService;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    pub fn service() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let whoami = service_fn(|request: Request<String>| async move {
+            let claims = request.extensions().get::<RegisteredClaims>().unwrap();
+            Ok::<_, Infallible>(Response::new(claims.subject.clone().unwrap_or_default()))
+        });
+        let service = JwtService::<_, RegisteredClaims>::new(whoami, auth);
+
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let request = Request::get("/")
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(String::new())
+            .unwrap();
+        let response = block_on(service.call(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "someone");
+
+        let request = Request::get("/")
+            .header(AUTHORIZATION, "Basic dXNlcjpwYXNz")
+            .body(String::new())
+            .unwrap();
+        let response = block_on(service.call(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers()[WWW_AUTHENTICATE],
+            r#"Bearer error="invalid_request""#
+        );
+        Ok(())
+    }
+}
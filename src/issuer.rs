@@ -0,0 +1,182 @@
+//! Accepting tokens from many issuers, each with its own keys and
+//! [validation](../validation/struct.Validation.html), e.g. for services that
+//! trust the identity providers of all of their tenants. The `iss` claim of a
+//! token selects the keys to verify it with.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnyVerifier, SharedVerifier};
+//! use jwt::bearer::BearerAuth;
+//! use jwt::issuer::MultiIssuerVerifier;
+//! use jwt::keystore::SharedKeySet;
+//! use jwt::validation::Validation;
+//! use jwt::AlgorithmType;
+//!
+//! let acme: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"acme").unwrap().into();
+//! let verifier = MultiIssuerVerifier::new()
+//!     .issuer("https://acme.example.com", acme, Validation::new().audience("api"))
+//!     .issuer("https://idp.example.org", SharedKeySet::default(), Validation::new());
+//! let auth = BearerAuth::with_verifier(verifier);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::bearer::TokenVerifier;
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::Header;
+use crate::token::Unverified;
+use crate::validation::Validation;
+use crate::Token;
+
+/// Only the issuer is read before the token is verified.
+#[derive(Deserialize)]
+struct IssuerClaim {
+    iss: Option<String>,
+}
+
+#[derive(Clone)]
+struct Issuer {
+    keys: Arc<dyn TokenVerifier>,
+    validation: Validation,
+}
+
+/// Verifies tokens with the keys of their issuer and checks their claims
+/// with the validation of that issuer. Tokens of other issuers are rejected
+/// with [Error::UnknownIssuer](../error/enum.Error.html#variant.UnknownIssuer).
+/// It is cheap to clone.
+#[derive(Clone)]
+pub struct MultiIssuerVerifier<C = SystemClock> {
+    issuers: Arc<HashMap<String, Issuer>>,
+    clock: C,
+}
+
+impl MultiIssuerVerifier {
+    pub fn new() -> Self {
+        MultiIssuerVerifier {
+            issuers: Arc::new(HashMap::new()),
+            clock: SystemClock,
+        }
+    }
+}
+
+impl Default for MultiIssuerVerifier {
+    fn default() -> Self {
+        MultiIssuerVerifier::new()
+    }
+}
+
+impl<C: Clock> MultiIssuerVerifier<C> {
+    /// Trust the issuer `iss` with these keys. The `iss` claim is always
+    /// checked, whether the validation checks it or not.
+    pub fn issuer(
+        mut self,
+        iss: impl Into<String>,
+        keys: impl TokenVerifier + 'static,
+        validation: Validation,
+    ) -> Self {
+        let iss = iss.into();
+        let validation = validation.issuer(iss.clone());
+        let issuer = Issuer {
+            keys: Arc::new(keys),
+            validation,
+        };
+        Arc::make_mut(&mut self.issuers).insert(iss, issuer);
+        self
+    }
+
+    /// Validate the time claims against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> MultiIssuerVerifier<D> {
+        MultiIssuerVerifier {
+            issuers: self.issuers,
+            clock,
+        }
+    }
+
+    pub fn issuers(&self) -> impl Iterator<Item = &str> {
+        self.issuers.keys().map(String::as_str)
+    }
+}
+
+impl<C: Clock + Send + Sync> TokenVerifier for MultiIssuerVerifier<C> {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let unverified: Token<Header, IssuerClaim, Unverified> = Token::parse_unverified(token)?;
+        let iss = unverified
+            .claims()
+            .iss
+            .as_deref()
+            .ok_or(Error::MissingClaim("iss"))?;
+        let issuer = self
+            .issuers
+            .get(iss)
+            .ok_or_else(|| Error::UnknownIssuer(iss.to_owned()))?;
+
+        let claims = issuer.keys.verify_token(token)?;
+        issuer.validation.validate_with(&claims, &self.clock)?;
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::any::SharedVerifier;
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::issuer::MultiIssuerVerifier;
+    use crate::token::signed::SignWithKey;
+    use crate::validation::Validation;
+
+    fn sign(claims: serde_json::Value, secret: &[u8]) -> Result<String, Error> {
+        claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, secret)?)
+    }
+
+    fn verifier(secret: &[u8]) -> Result<SharedVerifier, Error> {
+        Ok(AnyVerifier::hmac(AlgorithmType::Hs256, secret)?.into())
+    }
+
+    #[test]
+    pub fn routing() -> Result<(), Error> {
+        let verifier = MultiIssuerVerifier::new()
+            .issuer("a", verifier(b"a")?, Validation::new().audience("api"))
+            .issuer("b", verifier(b"b")?, Validation::new())
+            .with_clock(|| 100);
+
+        let token = sign(serde_json::json!({"iss": "a", "aud": ["api"]}), b"a")?;
+        assert_eq!(verifier.verify_token(&token)?["iss"], "a");
+        let token = sign(serde_json::json!({"iss": "b", "exp": 200}), b"b")?;
+        assert!(verifier.verify_token(&token).is_ok());
+
+        // Signed with the key of another tenant
+        let token = sign(serde_json::json!({"iss": "a", "aud": "api"}), b"b")?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::InvalidSignature)
+        ));
+        let token = sign(serde_json::json!({"iss": "a", "aud": "web"}), b"a")?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        let token = sign(serde_json::json!({"iss": "b", "exp": 50}), b"b")?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::Expired { .. })
+        ));
+        let token = sign(serde_json::json!({"iss": "c"}), b"c")?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::UnknownIssuer(_))
+        ));
+        let token = sign(serde_json::json!({}), b"a")?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::MissingClaim("iss"))
+        ));
+        Ok(())
+    }
+}
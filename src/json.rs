@@ -0,0 +1,97 @@
+//! The JSON backend used for headers and claims. serde_json is used by
+//! default. With the `simd-json` feature, decoding uses simd-json instead.
+//! serde-json-core is never used here, only for the types that opt into it,
+//! see [core_json](../core_json/index.html).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Serialize to JSON.
+pub(crate) fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec(value)?)
+}
+
+/// Serialize to JSON and append its base64url encoding to a buffer. With
+/// serde_json and `std`, the JSON is encoded while it is written, without an
+/// intermediate buffer.
+#[cfg(feature = "std")]
+pub(crate) fn encode_json_into<T: Serialize>(value: &T, out: &mut String) -> Result<(), Error> {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::write::EncoderStringWriter;
+
+    let mut writer = EncoderStringWriter::from_consumer(out, &URL_SAFE_NO_PAD);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.into_inner();
+    Ok(())
+}
+
+/// Serialize to JSON and append its base64url encoding to a buffer.
+#[cfg(not(feature = "std"))]
+pub(crate) fn encode_json_into<T: Serialize>(value: &T, out: &mut String) -> Result<(), Error> {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    URL_SAFE_NO_PAD.encode_string(to_json(value)?, out);
+    Ok(())
+}
+
+/// Deserialize decoded JSON.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn from_json<T: for<'de> Deserialize<'de>>(json_bytes: Vec<u8>) -> Result<T, Error> {
+    Ok(serde_json::from_slice(&json_bytes)?)
+}
+
+/// Deserialize decoded JSON with simd-json, which parses in place.
+#[cfg(feature = "simd-json")]
+pub(crate) fn from_json<T: for<'de> Deserialize<'de>>(mut json_bytes: Vec<u8>) -> Result<T, Error> {
+    Ok(simd_json::serde::from_slice(&mut json_bytes)?)
+}
+
+/// Deserialize decoded JSON that may borrow strings from the buffer. Strings
+/// with escapes can only be borrowed as `Cow`.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn from_json_borrowed<'de, T: Deserialize<'de>>(
+    json_bytes: &'de mut [u8],
+) -> Result<T, Error> {
+    Ok(serde_json::from_slice(json_bytes)?)
+}
+
+/// Deserialize decoded JSON with simd-json, which unescapes in place.
+#[cfg(feature = "simd-json")]
+pub(crate) fn from_json_borrowed<'de, T: Deserialize<'de>>(
+    json_bytes: &'de mut [u8],
+) -> Result<T, Error> {
+    Ok(simd_json::serde::from_slice(json_bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::error::Error;
+    use crate::json::{from_json, to_json};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Device {
+        sub: String,
+        exp: u64,
+        roles: Vec<String>,
+    }
+
+    #[test]
+    pub fn round_trip() -> Result<(), Error> {
+        let device = Device {
+            sub: "sensor \"7\"".to_owned(),
+            exp: 1_700_000_000,
+            roles: vec!["a".repeat(300)],
+        };
+
+        let json = to_json(&device)?;
+        assert_eq!(from_json::<Device>(json)?, device);
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! [JSON Web Keys](https://tools.ietf.org/html/rfc7517), the JSON
+//! representation of keys that identity providers publish. The key
+//! parameters are kept in their base64 encoded form. See
+//! [AnyVerifier::from_jwk](../algorithm/any/struct.AnyVerifier.html#method.from_jwk)
+//! to turn a key into an algorithm.
+
+#[cfg(feature = "thumbprint")]
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "thumbprint")]
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "thumbprint")]
+use sha2::{Digest, Sha256};
+
+use crate::algorithm::secret::SecretBytes;
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::redact::Redaction;
+
+/// The family of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyType {
+    #[serde(rename = "oct")]
+    Octet,
+    #[serde(rename = "RSA")]
+    Rsa,
+    #[serde(rename = "EC")]
+    EllipticCurve,
+    #[serde(rename = "OKP")]
+    OctetKeyPair,
+    /// Any key type not defined above, so that key sets with unknown keys can
+    /// still be read.
+    #[serde(other)]
+    Unknown,
+}
+
+/// An operation a key is used for, as listed in the `key_ops` parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyOperation {
+    Sign,
+    Verify,
+}
+
+impl KeyOperation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyOperation::Sign => "sign",
+            KeyOperation::Verify => "verify",
+        }
+    }
+}
+
+/// Whether to honor the `use` and `key_ops` parameters of a key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyUsePolicy {
+    /// Keys whose `use` is not `sig`, or whose `key_ops` do not contain the
+    /// operation, are rejected.
+    #[default]
+    Enforce,
+    /// The parameters are ignored, e.g. for keys of a provider that publishes
+    /// them wrongly.
+    Ignore,
+}
+
+/// A single JSON Web Key. `Debug` does not print the symmetric key.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Jwk {
+    #[serde(rename = "kty")]
+    pub key_type: KeyType,
+
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    pub public_key_use: Option<String>,
+
+    #[serde(rename = "key_ops", skip_serializing_if = "Option::is_none")]
+    pub key_operations: Option<Vec<String>>,
+
+    /// Kept as a string, since key sets can contain keys for algorithms that
+    /// are not supported for signing, e.g. encryption keys.
+    #[serde(rename = "alg", skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+
+    #[serde(rename = "kid", skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    /// The symmetric key of an octet key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub k: Option<String>,
+
+    /// The modulus of an RSA key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+
+    /// The exponent of an RSA key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+
+    /// The curve of an elliptic curve key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+
+    /// The x coordinate of an elliptic curve key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+
+    /// The y coordinate of an elliptic curve key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+
+    #[serde(rename = "x5c", skip_serializing_if = "Option::is_none")]
+    pub x509_chain: Option<Vec<String>>,
+}
+
+impl fmt::Debug for Jwk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Jwk")
+            .field("key_type", &self.key_type)
+            .field("public_key_use", &self.public_key_use)
+            .field("key_operations", &self.key_operations)
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .field("k", &self.k.as_ref().map(|_| Redaction))
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("crv", &self.crv)
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("x509_chain", &self.x509_chain)
+            .finish()
+    }
+}
+
+impl Jwk {
+    /// Create a key of the given type without any parameters.
+    pub fn new(key_type: KeyType) -> Self {
+        Jwk {
+            key_type,
+            public_key_use: None,
+            key_operations: None,
+            algorithm: None,
+            key_id: None,
+            k: None,
+            n: None,
+            e: None,
+            crv: None,
+            x: None,
+            y: None,
+            x509_chain: None,
+        }
+    }
+
+    /// The algorithm of the key. Elliptic curve keys without an explicit
+    /// algorithm use the algorithm matching their curve.
+    pub fn algorithm_type(&self) -> Result<AlgorithmType, Error> {
+        if let Some(ref algorithm) = self.algorithm {
+            return algorithm.parse();
+        }
+
+        match (self.key_type, self.crv.as_deref()) {
+            (KeyType::EllipticCurve, Some("P-256")) => Ok(AlgorithmType::Es256),
+            (KeyType::EllipticCurve, Some("P-384")) => Ok(AlgorithmType::Es384),
+            (KeyType::EllipticCurve, Some("P-521")) => Ok(AlgorithmType::Es512),
+            _ => Err(Error::InvalidJwk("no algorithm")),
+        }
+    }
+
+    /// Decode the symmetric key of an octet key.
+    pub fn secret(&self) -> Result<SecretBytes, Error> {
+        Jwk::decode_parameter(&self.k, "k").map(SecretBytes::from)
+    }
+
+    /// The [RFC 7638](https://tools.ietf.org/html/rfc7638) thumbprint of the
+    /// key, base64url encoded: the SHA-256 hash of its required members.
+    /// Requires the `thumbprint` feature, which `std` enables.
+    #[cfg(feature = "thumbprint")]
+    pub fn thumbprint(&self) -> Result<String, Error> {
+        // The required members in lexicographic order
+        let (k, e, n) = (self.k.as_deref(), self.e.as_deref(), self.n.as_deref());
+        let (crv, x, y) = (self.crv.as_deref(), self.x.as_deref(), self.y.as_deref());
+        let members = match self.key_type {
+            KeyType::Octet => vec![("k", k), ("kty", Some("oct"))],
+            KeyType::Rsa => vec![("e", e), ("kty", Some("RSA")), ("n", n)],
+            KeyType::EllipticCurve => vec![("crv", crv), ("kty", Some("EC")), ("x", x), ("y", y)],
+            KeyType::OctetKeyPair => vec![("crv", crv), ("kty", Some("OKP")), ("x", x)],
+            KeyType::Unknown => return Err(Error::InvalidJwk("unsupported key type")),
+        };
+
+        let mut canonical = Vec::new();
+        for (name, value) in members {
+            let value = value.ok_or(Error::InvalidJwk(name))?;
+            canonical.push(format!(r#""{}":{}"#, name, serde_json::to_string(value)?));
+        }
+        let canonical = format!("{{{}}}", canonical.join(","));
+
+        Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())))
+    }
+
+    /// Check that the key may be used for the operation. Keys without `use`
+    /// and `key_ops` may be used for anything.
+    pub fn check_operation(
+        &self,
+        operation: KeyOperation,
+        policy: KeyUsePolicy,
+    ) -> Result<(), Error> {
+        if policy == KeyUsePolicy::Ignore {
+            return Ok(());
+        }
+
+        let use_permits = self.public_key_use.as_deref().is_none_or(|u| u == "sig");
+        let operations_permit = self
+            .key_operations
+            .as_ref()
+            .is_none_or(|ops| ops.iter().any(|op| op == operation.as_str()));
+        if use_permits && operations_permit {
+            Ok(())
+        } else {
+            Err(Error::KeyOperationNotPermitted(operation))
+        }
+    }
+
+    /// Decode one of the base64 encoded key parameters.
+    pub(crate) fn decode_parameter(
+        parameter: &Option<String>,
+        name: &'static str,
+    ) -> Result<Vec<u8>, Error> {
+        let encoded = parameter.as_deref().ok_or(Error::InvalidJwk(name))?;
+        Ok(URL_SAFE_NO_PAD.decode(encoded)?)
+    }
+}
+
+/// A set of keys, as published on JWKS endpoints.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Find the key with the given key id.
+    pub fn find(&self, key_id: &str) -> Option<&Jwk> {
+        self.keys
+            .iter()
+            .find(|jwk| jwk.key_id.as_deref() == Some(key_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+    use crate::jwk::{Jwk, JwkSet, KeyOperation, KeyType, KeyUsePolicy};
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use sha2::{Digest, Sha256};
+
+    const JWKS: &str = r#"{"keys":[
+        {"kty":"oct","kid":"hmac","alg":"HS256","k":"c2VjcmV0"},
+        {"kty":"EC","kid":"ec","crv":"P-256","x":"x","y":"y"},
+        {"kty":"RSA","kid":"enc","use":"enc","alg":"RSA-OAEP","n":"n","e":"AQAB"},
+        {"kty":"unknown","kid":"other"}
+    ]}"#;
+
+    #[test]
+    fn parse_set() -> Result<(), Error> {
+        let jwks: JwkSet = serde_json::from_str(JWKS)?;
+
+        assert_eq!(jwks.keys.len(), 4);
+        assert_eq!(
+            jwks.find("hmac").unwrap().algorithm_type()?,
+            AlgorithmType::Hs256
+        );
+        assert_eq!(
+            jwks.find("ec").unwrap().algorithm_type()?,
+            AlgorithmType::Es256
+        );
+        assert!(jwks.find("enc").unwrap().algorithm_type().is_err());
+        assert_eq!(jwks.find("other").unwrap().key_type, KeyType::Unknown);
+        assert!(jwks.find("missing").is_none());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "thumbprint")]
+    fn thumbprint() -> Result<(), Error> {
+        // https://tools.ietf.org/html/rfc7638#section-3.1
+        let jwk: Jwk = serde_json::from_str(
+            r#"{"kty":"RSA","n":"0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw","e":"AQAB","alg":"RS256","kid":"2011-04-29"}"#,
+        )?;
+        assert_eq!(
+            jwk.thumbprint()?,
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+
+        let jwk: Jwk = serde_json::from_str(r#"{"kty":"oct","k":"c2VjcmV0"}"#)?;
+        assert_eq!(
+            jwk.thumbprint()?,
+            URL_SAFE_NO_PAD.encode(Sha256::digest(br#"{"k":"c2VjcmV0","kty":"oct"}"#))
+        );
+        assert!(Jwk::new(KeyType::Rsa).thumbprint().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn key_operations() -> Result<(), Error> {
+        let check = |json: &str, operation| -> Result<(), Error> {
+            let jwk: Jwk = serde_json::from_str(json)?;
+            jwk.check_operation(operation, KeyUsePolicy::Enforce)
+        };
+        check(r#"{"kty":"oct"}"#, KeyOperation::Sign)?;
+        check(r#"{"kty":"oct","use":"sig"}"#, KeyOperation::Verify)?;
+        check(
+            r#"{"kty":"oct","key_ops":["sign","verify"]}"#,
+            KeyOperation::Sign,
+        )?;
+        check(
+            r#"{"kty":"oct","use":"sig","key_ops":["verify"]}"#,
+            KeyOperation::Verify,
+        )?;
+
+        for (json, operation) in [
+            (r#"{"kty":"RSA","use":"enc"}"#, KeyOperation::Verify),
+            (r#"{"kty":"oct","key_ops":["verify"]}"#, KeyOperation::Sign),
+            (
+                r#"{"kty":"oct","key_ops":["encrypt"]}"#,
+                KeyOperation::Verify,
+            ),
+        ] {
+            assert!(matches!(
+                check(json, operation),
+                Err(Error::KeyOperationNotPermitted(op)) if op == operation
+            ));
+        }
+
+        let jwk: Jwk = serde_json::from_str(r#"{"kty":"RSA","use":"enc"}"#)?;
+        jwk.check_operation(KeyOperation::Verify, KeyUsePolicy::Ignore)?;
+        Ok(())
+    }
+}
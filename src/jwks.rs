@@ -0,0 +1,670 @@
+//! Verification with the keys that an identity provider publishes on its JWKS
+//! endpoint, enabled with the `jwks` feature. A
+//! [JwksVerifier](struct.JwksVerifier.html) holds the current
+//! [KeySet](struct.KeySet.html) and a [Refresher](struct.Refresher.html)
+//! fetches the keys in the background and swaps in the new set, so
+//! verification never waits for the network.
+//!
+//! Requests are made with [reqwest](https://docs.rs/reqwest), without its
+//! default features. Enable one of its TLS features in your application to
+//! reach `https` endpoints.
+//!
+//! The refresher follows the HTTP caching headers of the endpoint: it fetches
+//! again when the `Cache-Control: max-age` of the last response has passed,
+//! and revalidates with `If-None-Match` when the response had an `ETag`, so
+//! unchanged keys are not downloaded again. Without `max-age`, the configured
+//! interval is used.
+//!
+//! Pin the keys with [pin](struct.Refresher.html#method.pin) to only trust
+//! known keys, even if the endpoint is compromised and serves others.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::jwks::{JwksClient, JwksVerifier, Refresher};
+//! use std::time::Duration;
+//!
+//! let verifier = JwksVerifier::default();
+//! let auth = BearerAuth::with_verifier(verifier.clone());
+//!
+//! let client = JwksClient::new("https://idp.example.com/.well-known/jwks.json");
+//! let refresher = Refresher::new(client, verifier).interval(Duration::from_secs(600));
+//! // refresher.refresh().await?;
+//! // tokio::spawn(refresher.run());
+//! ```
+//!
+//! To verify tokens before the endpoint answers, start with a local copy of
+//! the keys:
+//! ```no_run
+//! # use jwt::jwks::{JwksClient, JwksVerifier, Refresher};
+//! # let verifier = JwksVerifier::default();
+//! # let client = JwksClient::new("https://idp.example.com/.well-known/jwks.json");
+//! let refresher = Refresher::new(client, verifier);
+//! refresher.bootstrap_from_file("/etc/service/jwks.json").unwrap();
+//! // tokio::spawn(refresher.run());
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::jwk::JwkSet;
+use crate::keystore::{LoadError, Pins, SharedKeySet};
+
+pub use crate::keystore::KeySet;
+
+/// Verifies tokens with the keys of a JWKS endpoint, see
+/// [SharedKeySet](../keystore/struct.SharedKeySet.html).
+pub type JwksVerifier = SharedKeySet;
+
+/// Keys are fetched again after this long by default, if the endpoint does
+/// not say how long they may be cached.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Keys are not fetched more often than this by default, however short the
+/// `max-age` of the endpoint.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+type ErrorHandler = Box<dyn Fn(&JwksError) + Send + Sync>;
+
+/// Fetches the key set of a JWKS endpoint.
+#[derive(Clone, Debug)]
+pub struct JwksClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl JwksClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        JwksClient {
+            url: url.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Make requests with this client, e.g. one with timeouts or a proxy.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub async fn fetch(&self) -> Result<JwkSet, JwksError> {
+        match self.fetch_if_none_match(None).await? {
+            Fetched::Modified { set, .. } => Ok(set),
+            // Only possible with an entity tag
+            Fetched::NotModified { .. } => Err(JwksError::Status(304)),
+        }
+    }
+
+    /// Fetch the key set unless its entity tag is still the given one, with
+    /// the caching headers of the response.
+    pub async fn fetch_if_none_match(&self, etag: Option<&str>) -> Result<Fetched, JwksError> {
+        trace_debug!(url = %self.url, "fetching JWKS");
+        self.fetch_set(etag).await.inspect_err(|_error| {
+            trace_warn!(url = %self.url, error = %_error, "JWKS fetch failed");
+        })
+    }
+
+    async fn fetch_set(&self, etag: Option<&str>) -> Result<Fetched, JwksError> {
+        let mut request = self
+            .http
+            .get(&self.url)
+            .header("accept", "application/json");
+        if let Some(etag) = etag {
+            request = request.header("if-none-match", etag);
+        }
+        let response = request.send().await.map_err(JwksError::Http)?;
+
+        let headers = response.headers();
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let max_age = header("cache-control").and_then(|cache_control| {
+            let age = header("age").and_then(|age| age.trim().parse().ok());
+            max_age(cache_control, age.unwrap_or(0))
+        });
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED && etag.is_some() {
+            return Ok(Fetched::NotModified { max_age });
+        }
+        if !status.is_success() {
+            return Err(JwksError::Status(status.as_u16()));
+        }
+        let etag = header("etag").map(str::to_owned);
+        let body = response.text().await.map_err(JwksError::Http)?;
+        let set = serde_json::from_str(&body).map_err(JwksError::Json)?;
+        Ok(Fetched::Modified { set, etag, max_age })
+    }
+}
+
+/// The response of a JWKS endpoint.
+#[derive(Debug)]
+pub enum Fetched {
+    Modified {
+        set: JwkSet,
+        etag: Option<String>,
+        /// How long the key set may be cached, from `Cache-Control`.
+        max_age: Option<Duration>,
+    },
+    /// The key set still has the entity tag it was fetched with.
+    NotModified { max_age: Option<Duration> },
+}
+
+/// The time a response may still be cached for, from the value of its
+/// `Cache-Control` header and its `Age` in seconds. `no-cache` and
+/// `no-store` allow no caching at all.
+fn max_age(cache_control: &str, age: u64) -> Option<Duration> {
+    let mut max_age = None;
+    for directive in cache_control.split(',') {
+        let directive = directive.trim();
+        let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+        match &*name.trim().to_ascii_lowercase() {
+            "no-cache" | "no-store" => return Some(Duration::ZERO),
+            "max-age" => max_age = value.trim().trim_matches('"').parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+    max_age.map(|max_age| Duration::from_secs(max_age.saturating_sub(age)))
+}
+
+/// Delays between the retries of failed fetches. The delay doubles with
+/// every failure, from `initial` up to `max`, and each is shortened by a
+/// random part of up to `jitter` of it, so that a fleet does not retry in
+/// lockstep.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// Between 0 and 1.
+    pub jitter: f64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_secs(1),
+            max: DEFAULT_INTERVAL,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay after some number of consecutive failures, at least one.
+    pub fn delay(&self, failures: u32) -> Duration {
+        self.delay_with(failures, random())
+    }
+
+    /// The delay with a random number between 0 and 1.
+    fn delay_with(&self, failures: u32, random: f64) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1).min(31));
+        let delay = self.initial.saturating_mul(factor).min(self.max);
+        delay.mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * random)
+    }
+}
+
+/// A random number between 0 and 1, good enough for jitter, from the
+/// randomly keyed hasher of the standard library.
+fn random() -> f64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+/// Keeps the keys of a [JwksVerifier](struct.JwksVerifier.html) up to date.
+/// When fetches fail, it retries with a [Backoff](struct.Backoff.html) and
+/// keeps verifying with the keys it has: by default for as long as it takes,
+/// or until they have been stale for the time set with
+/// [max_stale](#method.max_stale).
+pub struct Refresher {
+    client: JwksClient,
+    verifier: JwksVerifier,
+    interval: Duration,
+    min_interval: Duration,
+    backoff: Backoff,
+    max_stale: Option<Duration>,
+    pins: Option<Pins>,
+    on_error: Option<ErrorHandler>,
+    state: Mutex<State>,
+}
+
+/// What the last responses said.
+#[derive(Default)]
+struct State {
+    etag: Option<String>,
+    max_age: Option<Duration>,
+    /// When the keys expire, if they were ever fetched.
+    fresh_until: Option<Instant>,
+    /// The number of consecutive failed fetches.
+    failures: u32,
+}
+
+impl Refresher {
+    pub fn new(client: JwksClient, verifier: JwksVerifier) -> Self {
+        Refresher {
+            client,
+            verifier,
+            interval: DEFAULT_INTERVAL,
+            min_interval: DEFAULT_MIN_INTERVAL,
+            backoff: Backoff::default(),
+            max_stale: None,
+            pins: None,
+            on_error: None,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// The time between two fetches when the endpoint does not send a
+    /// `max-age`.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The least time between two fetches, however short the `max-age`.
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Retry failed fetches with this backoff.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Stop verifying with keys that have expired this long ago without a
+    /// successful fetch, which rejects every token until the endpoint is
+    /// reachable again.
+    pub fn max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = Some(max_stale);
+        self
+    }
+
+    /// Only use the keys with these thumbprints, from the endpoint and when
+    /// bootstrapping. Other keys are left out; a key set with only other
+    /// keys fails to refresh.
+    pub fn pin(mut self, pins: Pins) -> Self {
+        self.pins = Some(pins);
+        self
+    }
+
+    /// Call this when a fetch in the background fails, e.g. to log it. The
+    /// previous keys stay in use until a fetch succeeds, or until they are
+    /// too stale.
+    pub fn on_error(mut self, on_error: impl Fn(&JwksError) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// Fetch the keys once and swap them in, unless the endpoint says they
+    /// did not change. Returns the number of usable keys. A key set without
+    /// usable keys is not swapped in.
+    pub async fn refresh(&self) -> Result<usize, JwksError> {
+        let result = self.fetch().await;
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(_) => {
+                state.failures = 0;
+                state.fresh_until = Some(now + state.max_age.unwrap_or(self.interval));
+            }
+            Err(_) => {
+                state.failures = state.failures.saturating_add(1);
+                if self.too_stale(&state, now) && !self.verifier.keys().is_empty() {
+                    trace_warn!(url = %self.client.url, "dropping stale JWKS");
+                    self.verifier.replace(KeySet::default());
+                }
+            }
+        }
+        result
+    }
+
+    async fn fetch(&self) -> Result<usize, JwksError> {
+        let etag = self.state.lock().unwrap().etag.clone();
+        let (set, etag) = match self.client.fetch_if_none_match(etag.as_deref()).await? {
+            Fetched::NotModified { max_age } => {
+                self.state.lock().unwrap().max_age = max_age;
+                trace_debug!(url = %self.client.url, "JWKS not modified");
+                return Ok(self.verifier.keys().len());
+            }
+            Fetched::Modified { set, etag, max_age } => {
+                self.state.lock().unwrap().max_age = max_age;
+                (set, etag)
+            }
+        };
+
+        let keys = self.key_set(&set);
+        if keys.is_empty() {
+            trace_warn!(url = %self.client.url, "JWKS has no usable keys");
+            return Err(JwksError::NoKeys);
+        }
+        let len = keys.len();
+        trace_debug!(url = %self.client.url, keys = len, "swapping in JWKS");
+        self.verifier.replace(keys);
+        self.state.lock().unwrap().etag = etag;
+        Ok(len)
+    }
+
+    fn key_set(&self, jwks: &JwkSet) -> KeySet {
+        match self.pins {
+            Some(ref pins) => KeySet::from_jwks_pinned(jwks, pins),
+            None => KeySet::from_jwks(jwks),
+        }
+    }
+
+    fn too_stale(&self, state: &State, now: Instant) -> bool {
+        match (self.max_stale, state.fresh_until) {
+            (Some(max_stale), Some(fresh_until)) => now > fresh_until + max_stale,
+            _ => false,
+        }
+    }
+
+    /// Verify with the keys of a local file or directory, e.g. a key set
+    /// bundled with the service, until they are fetched from the endpoint.
+    /// Returns the number of usable keys; without any, the keys are left as
+    /// they are. The first fetch of [run](#method.run) is not delayed by
+    /// this.
+    pub fn bootstrap_from_file(&self, path: impl AsRef<Path>) -> Result<usize, LoadError> {
+        let keys = match self.pins {
+            Some(ref pins) => KeySet::load_pinned(path, pins)?,
+            None => KeySet::load(path)?,
+        };
+        Ok(self.bootstrap_with(keys))
+    }
+
+    /// Like [bootstrap_from_file](#method.bootstrap_from_file), with a key
+    /// set that is already parsed, e.g. from `include_str!`.
+    pub fn bootstrap(&self, jwks: &JwkSet) -> usize {
+        self.bootstrap_with(self.key_set(jwks))
+    }
+
+    fn bootstrap_with(&self, keys: KeySet) -> usize {
+        let len = keys.len();
+        if len > 0 {
+            trace_debug!(url = %self.client.url, keys = len, "bootstrapping JWKS");
+            self.verifier.replace(keys);
+        }
+        len
+    }
+
+    /// Whether the keys have expired and could not be fetched again since.
+    pub fn is_stale(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.failures > 0
+            && state
+                .fresh_until
+                .is_some_and(|fresh| Instant::now() > fresh)
+    }
+
+    /// The time until the next fetch.
+    fn delay(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        match state.max_age {
+            _ if state.failures > 0 => self.backoff.delay(state.failures),
+            _ if state.fresh_until.is_none() => Duration::ZERO,
+            Some(max_age) => max_age.max(self.min_interval),
+            None => self.interval,
+        }
+    }
+
+    /// Refresh the keys whenever they expire, forever. Spawn it onto a tokio
+    /// runtime, after the first [refresh](#method.refresh) or right after
+    /// [bootstrapping](#method.bootstrap_from_file) to fetch at once.
+    pub async fn run(self) {
+        loop {
+            tokio::time::sleep(self.delay()).await;
+            if let Err(e) = self.refresh().await {
+                if let Some(ref on_error) = self.on_error {
+                    on_error(&e);
+                }
+            }
+        }
+    }
+}
+
+/// The reason keys could not be fetched.
+#[derive(Debug)]
+pub enum JwksError {
+    Http(reqwest::Error),
+    /// The endpoint answered with this HTTP status.
+    Status(u16),
+    Json(serde_json::Error),
+    /// The key set has no keys that can be used for verification.
+    NoKeys,
+}
+
+impl fmt::Display for JwksError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JwksError::Http(ref e) => write!(f, "JWKS request failed: {}", e),
+            JwksError::Status(status) => write!(f, "JWKS request failed with {}", status),
+            JwksError::Json(ref e) => write!(f, "Invalid JWKS: {}", e),
+            JwksError::NoKeys => write!(f, "JWKS has no usable keys"),
+        }
+    }
+}
+
+impl std::error::Error for JwksError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            JwksError::Http(ref e) => Some(e),
+            JwksError::Json(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::algorithm::any::AnySigner;
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{BearerAuth, Rejection};
+    use crate::error::Error;
+    use crate::jwk::JwkSet;
+    use crate::jwks::{max_age, Backoff, JwksClient, JwksVerifier, KeySet, Refresher};
+    use crate::keystore::Pins;
+    use crate::token::signed::SignWithKey;
+    use crate::{Header, RegisteredClaims, Token};
+
+    fn sign(key_id: &str, secret: &[u8]) -> Result<String, Error> {
+        let header = Header {
+            algorithm: AlgorithmType::Hs256,
+            key_id: Some(key_id.into()),
+            ..Default::default()
+        };
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, secret)?;
+        Ok(Token::new(header, claims).sign_with_key(&signer)?.into())
+    }
+
+    #[test]
+    pub fn key_set() -> Result<(), Error> {
+        let jwks: JwkSet = serde_json::from_str(
+            r#"{"keys":[
+                {"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"},
+                {"kty":"oct","alg":"HS256","k":"bm8ga2lk"},
+                {"kty":"RSA","kid":"enc","alg":"RSA-OAEP","n":"n","e":"AQAB"}
+            ]}"#,
+        )?;
+        let keys = KeySet::from_jwks(&jwks);
+        assert_eq!(keys.key_ids().collect::<Vec<_>>(), ["old"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn swap_keys() -> Result<(), Error> {
+        let old: JwkSet = serde_json::from_str(
+            r#"{"keys":[{"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"}]}"#,
+        )?;
+        let new: JwkSet = serde_json::from_str(
+            r#"{"keys":[{"kty":"oct","kid":"new","alg":"HS256","k":"bmV3"}]}"#,
+        )?;
+
+        let verifier = JwksVerifier::new(KeySet::from_jwks(&old));
+        let auth = BearerAuth::with_verifier(verifier.clone());
+        let old_token = sign("old", b"old")?;
+        let new_token = sign("new", b"new")?;
+
+        let claims: RegisteredClaims = auth.authenticate_token(&old_token).unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+        assert!(matches!(
+            auth.authenticate_token::<RegisteredClaims>(&new_token),
+            Err(Rejection::InvalidToken(Error::NoKeyWithKeyId(_)))
+        ));
+
+        let held = verifier.keys();
+        verifier.replace(KeySet::from_jwks(&new));
+        assert_eq!(held.len(), 1);
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&new_token)
+            .is_ok());
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&old_token)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn cache_control() {
+        let max_age = |header, age| max_age(header, age).map(|d: Duration| d.as_secs());
+        assert_eq!(max_age("public, max-age=3600", 0), Some(3600));
+        assert_eq!(max_age("Max-Age=\"60\", must-revalidate", 0), Some(60));
+        assert_eq!(max_age("max-age=600", 100), Some(500));
+        assert_eq!(max_age("max-age=600", 900), Some(0));
+        assert_eq!(max_age("private, no-cache", 0), Some(0));
+        assert_eq!(max_age("max-age=600, no-store", 0), Some(0));
+        assert_eq!(max_age("public", 0), None);
+        assert_eq!(max_age("max-age=soon", 0), None);
+    }
+
+    #[test]
+    pub fn delay() {
+        let refresher = Refresher::new(JwksClient::new("http://idp.test"), JwksVerifier::default())
+            .interval(Duration::from_secs(300))
+            .min_interval(Duration::from_secs(60))
+            .backoff(Backoff {
+                jitter: 0.0,
+                ..Default::default()
+            });
+        assert_eq!(refresher.delay(), Duration::ZERO);
+        refresher.state.lock().unwrap().fresh_until = Some(Instant::now());
+        assert_eq!(refresher.delay(), Duration::from_secs(300));
+
+        refresher.state.lock().unwrap().max_age = Some(Duration::from_secs(3600));
+        assert_eq!(refresher.delay(), Duration::from_secs(3600));
+        refresher.state.lock().unwrap().max_age = Some(Duration::ZERO);
+        assert_eq!(refresher.delay(), Duration::from_secs(60));
+
+        refresher.state.lock().unwrap().failures = 3;
+        assert_eq!(refresher.delay(), Duration::from_secs(4));
+    }
+
+    #[test]
+    pub fn backoff() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(2),
+            max: Duration::from_secs(60),
+            jitter: 0.5,
+        };
+        let delays: Vec<_> = (1..=7)
+            .map(|n| backoff.delay_with(n, 0.0).as_secs())
+            .collect();
+        assert_eq!(delays, [2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff.delay_with(3, 1.0), Duration::from_secs(4));
+        assert_eq!(backoff.delay_with(u32::MAX, 0.0), Duration::from_secs(60));
+        for _ in 0..100 {
+            let delay = backoff.delay(4);
+            assert!(delay > Duration::from_secs(8) && delay <= Duration::from_secs(16));
+        }
+    }
+
+    #[test]
+    pub fn stale_keys() -> Result<(), Error> {
+        let jwks: JwkSet = serde_json::from_str(
+            r#"{"keys":[{"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"}]}"#,
+        )?;
+        let verifier = JwksVerifier::new(KeySet::from_jwks(&jwks));
+        let refresher = Refresher::new(JwksClient::new("http://idp.test"), verifier.clone())
+            .max_stale(Duration::from_secs(60));
+        let now = Instant::now();
+        let mut state = refresher.state.lock().unwrap();
+        state.failures = 1;
+        assert!(!refresher.too_stale(&state, now));
+
+        state.fresh_until = Some(now);
+        assert!(!refresher.too_stale(&state, now + Duration::from_secs(60)));
+        assert!(refresher.too_stale(&state, now + Duration::from_secs(61)));
+        Ok(())
+    }
+
+    #[test]
+    pub fn bootstrap() -> Result<(), Error> {
+        let jwks: JwkSet = serde_json::from_str(
+            r#"{"keys":[{"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"}]}"#,
+        )?;
+        let verifier = JwksVerifier::default();
+        let auth = BearerAuth::with_verifier(verifier.clone());
+        let refresher = Refresher::new(JwksClient::new("http://idp.test"), verifier);
+
+        assert_eq!(refresher.bootstrap(&JwkSet { keys: Vec::new() }), 0);
+        assert_eq!(refresher.bootstrap(&jwks), 1);
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("old", b"old")?)
+            .is_ok());
+        assert_eq!(refresher.delay(), Duration::ZERO);
+
+        let path = std::env::temp_dir().join(format!("jwks-bootstrap-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"keys":[{"kty":"oct","kid":"new","alg":"HS256","k":"bmV3"}]}"#,
+        )
+        .unwrap();
+        let loaded = refresher.bootstrap_from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), 1);
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("new", b"new")?)
+            .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    pub fn pinned() -> Result<(), Error> {
+        let jwks: JwkSet = serde_json::from_str(
+            r#"{"keys":[
+                {"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"},
+                {"kty":"oct","kid":"new","alg":"HS256","k":"bmV3"}
+            ]}"#,
+        )?;
+        let pins = Pins::new([jwks.keys[1].thumbprint()?]);
+        let verifier = JwksVerifier::default();
+        let auth = BearerAuth::with_verifier(verifier.clone());
+        let refresher = Refresher::new(JwksClient::new("http://idp.test"), verifier).pin(pins);
+
+        assert_eq!(refresher.bootstrap(&jwks), 1);
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("new", b"new")?)
+            .is_ok());
+        assert!(matches!(
+            auth.authenticate_token::<RegisteredClaims>(&sign("old", b"old")?),
+            Err(Rejection::InvalidToken(Error::NoKeyWithKeyId(_)))
+        ));
+        assert_eq!(
+            refresher.bootstrap(&JwkSet {
+                keys: vec![jwks.keys[0].clone()]
+            }),
+            0
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,695 @@
+//! Sets of verification keys by key id, and loading them from files. A
+//! [SharedKeySet](struct.SharedKeySet.html) can be replaced while it is in
+//! use, and with the `notify` feature a
+//! [FileKeyStore](struct.FileKeyStore.html) replaces it whenever the key
+//! files change, so keys can be rotated without restarting the process.
+//!
+//! Key files are JWKs (`.json`, `.jwk`) or key sets (`.jwks`, or JSON with a
+//! `keys` member), and with the `openssl` feature public keys in PEM
+//! (`.pem`). Keys without a `kid` and PEM keys get the file name without its
+//! extension or their [thumbprint](enum.KeyIds.html) as their key id. A
+//! [DirKeyStore](struct.DirKeyStore.html) loads a directory once.
+//!
+//! Keys can be [pinned](struct.Pins.html) to a set of thumbprints, so a key
+//! file or JWKS endpoint that serves extra keys does not get them trusted.
+//!
+//! Tokens without a `kid` are rejected, unless a `SharedKeySet` is told to
+//! [try all keys](struct.SharedKeySet.html#method.try_all_keys) of their
+//! algorithm, as some legacy issuers require.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::keystore::{KeySet, SharedKeySet};
+//!
+//! let keys = SharedKeySet::new(KeySet::default());
+//! let auth = BearerAuth::with_verifier(keys.clone());
+//! // Later, e.g. after a new key was deployed
+//! # let path = std::env::temp_dir().join("jwt-keystore-example.jwks");
+//! # std::fs::write(&path, r#"{"keys":[{"kty":"oct","kid":"a","alg":"HS256","k":"c2VjcmV0"}]}"#).unwrap();
+//! keys.replace(KeySet::load(&path).unwrap());
+//! assert_eq!(keys.keys().key_ids().collect::<Vec<_>>(), ["a"]);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::algorithm::any::AnyVerifier;
+use crate::algorithm::store::Store;
+use crate::algorithm::{AlgorithmType, VerifyingAlgorithm};
+use crate::bearer::TokenVerifier;
+use crate::error::Error;
+use crate::header::Header;
+use crate::jwk::{Jwk, JwkSet};
+use crate::token::verified::{VerifyWithKey, VerifyWithStore};
+use crate::token::Unverified;
+use crate::Token;
+
+/// The verifiers of a key set by key id.
+#[derive(Default)]
+pub struct KeySet {
+    verifiers: BTreeMap<String, AnyVerifier>,
+}
+
+impl KeySet {
+    /// The verifiers of the keys in a set. Keys without a key id and keys
+    /// that can't be used for verification, like encryption keys, are left
+    /// out.
+    pub fn from_jwks(jwks: &JwkSet) -> Self {
+        let mut keys = KeySet::default();
+        keys.extend_from_jwks(jwks, KeyIds::FileName, None);
+        keys
+    }
+
+    /// Like [from_jwks](#method.from_jwks), leaving out the keys that are not
+    /// pinned.
+    pub fn from_jwks_pinned(jwks: &JwkSet, pins: &Pins) -> Self {
+        let mut keys = KeySet::default();
+        keys.extend_from_jwks(jwks, KeyIds::FileName, Some(pins));
+        keys
+    }
+
+    /// Load the keys of a file, or of all key files in a directory.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        KeySet::load_with(path, KeyIds::FileName)
+    }
+
+    /// Like [load](#method.load), with key ids for keys without a `kid` from
+    /// somewhere else than their file names.
+    pub fn load_with(path: impl AsRef<Path>, key_ids: KeyIds) -> Result<Self, LoadError> {
+        KeySet::load_from(path.as_ref(), key_ids, None)
+    }
+
+    /// Like [load](#method.load), leaving out the keys that are not pinned.
+    pub fn load_pinned(path: impl AsRef<Path>, pins: &Pins) -> Result<Self, LoadError> {
+        KeySet::load_from(path.as_ref(), KeyIds::FileName, Some(pins))
+    }
+
+    fn load_from(path: &Path, key_ids: KeyIds, pins: Option<&Pins>) -> Result<Self, LoadError> {
+        let mut keys = KeySet::default();
+        let metadata = fs::metadata(path).map_err(|e| LoadError::Io(path.to_owned(), e))?;
+        if !metadata.is_dir() {
+            keys.load_file(path, key_ids, pins)?;
+            return Ok(keys);
+        }
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path).map_err(|e| LoadError::Io(path.to_owned(), e))? {
+            let file = entry.map_err(|e| LoadError::Io(path.to_owned(), e))?.path();
+            if file.is_file() && is_key_file(&file) {
+                files.push(file);
+            }
+        }
+        files.sort();
+        for file in files {
+            keys.load_file(&file, key_ids, pins)?;
+        }
+        Ok(keys)
+    }
+
+    /// Add a key, replacing the key with the same id.
+    pub fn insert(&mut self, key_id: impl Into<String>, verifier: AnyVerifier) {
+        self.verifiers.insert(key_id.into(), verifier);
+    }
+
+    pub fn len(&self) -> usize {
+        self.verifiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verifiers.is_empty()
+    }
+
+    pub fn key_ids(&self) -> impl Iterator<Item = &str> {
+        self.verifiers.keys().map(String::as_str)
+    }
+
+    fn extend_from_jwks(&mut self, jwks: &JwkSet, key_ids: KeyIds, pins: Option<&Pins>) {
+        for jwk in &jwks.keys {
+            if !is_pinned(pins, jwk) {
+                continue;
+            }
+            let key_id = match (&jwk.key_id, key_ids) {
+                (Some(key_id), _) => key_id.clone(),
+                (None, KeyIds::Thumbprint) => match jwk.thumbprint() {
+                    Ok(thumbprint) => thumbprint,
+                    Err(_) => continue,
+                },
+                (None, KeyIds::FileName) => continue,
+            };
+            match AnyVerifier::from_jwk(jwk) {
+                Ok(verifier) => self.insert(key_id, verifier),
+                Err(_error) => {
+                    trace_debug!(kid = %key_id, error = %_error, "skipping unusable key");
+                }
+            }
+        }
+    }
+
+    fn load_file(
+        &mut self,
+        path: &Path,
+        key_ids: KeyIds,
+        pins: Option<&Pins>,
+    ) -> Result<(), LoadError> {
+        let contents = fs::read(path).map_err(|e| LoadError::Io(path.to_owned(), e))?;
+        let key_error = |e: Error| LoadError::Key(path.to_owned(), e);
+        let file_key_id = || {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .ok_or(LoadError::Unsupported(path.to_owned()))
+        };
+
+        if path.extension().and_then(|e| e.to_str()) == Some("pem") {
+            #[cfg(feature = "openssl")]
+            {
+                let key = crate::algorithm::openssl::PKeyWithDigest::from_pem(&contents)
+                    .map_err(key_error)?;
+                if !is_pinned(pins, &key.to_jwk().map_err(key_error)?) {
+                    return Ok(());
+                }
+                let key_id = match key_ids {
+                    KeyIds::FileName => file_key_id()?,
+                    KeyIds::Thumbprint => key
+                        .to_jwk()
+                        .and_then(|jwk| jwk.thumbprint())
+                        .map_err(key_error)?,
+                };
+                self.insert(key_id, AnyVerifier::new(key));
+                return Ok(());
+            }
+            #[cfg(not(feature = "openssl"))]
+            return Err(LoadError::Unsupported(path.to_owned()));
+        }
+
+        let json: Value = serde_json::from_slice(&contents).map_err(|e| key_error(e.into()))?;
+        if json.get("keys").is_some() {
+            let jwks: JwkSet = serde_json::from_value(json).map_err(|e| key_error(e.into()))?;
+            self.extend_from_jwks(&jwks, key_ids, pins);
+        } else {
+            let jwk: Jwk = serde_json::from_value(json).map_err(|e| key_error(e.into()))?;
+            if !is_pinned(pins, &jwk) {
+                return Ok(());
+            }
+            let verifier = AnyVerifier::from_jwk(&jwk).map_err(key_error)?;
+            let key_id = match (jwk.key_id.clone(), key_ids) {
+                (Some(key_id), _) => key_id,
+                (None, KeyIds::FileName) => file_key_id()?,
+                (None, KeyIds::Thumbprint) => jwk.thumbprint().map_err(key_error)?,
+            };
+            self.insert(key_id, verifier);
+        }
+        Ok(())
+    }
+}
+
+impl Store for KeySet {
+    type Algorithm = AnyVerifier;
+
+    fn get(&self, key_id: &str) -> Option<&AnyVerifier> {
+        self.verifiers.get(key_id)
+    }
+}
+
+/// The [thumbprints](../jwk/struct.Jwk.html#method.thumbprint) of the only
+/// keys to trust. Keys whose thumbprint can not be computed are not pinned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pins {
+    thumbprints: BTreeSet<String>,
+}
+
+impl Pins {
+    pub fn new(thumbprints: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Pins {
+            thumbprints: thumbprints.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn contains(&self, jwk: &Jwk) -> bool {
+        jwk.thumbprint()
+            .is_ok_and(|thumbprint| self.thumbprints.contains(&thumbprint))
+    }
+}
+
+/// Whether the key may be used, without pins or with them.
+fn is_pinned(pins: Option<&Pins>, jwk: &Jwk) -> bool {
+    let pinned = pins.is_none_or(|pins| pins.contains(jwk));
+    if !pinned {
+        trace_warn!(kid = ?jwk.key_id, "skipping key that is not pinned");
+    }
+    pinned
+}
+
+/// Where the key ids of keys without a `kid` come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyIds {
+    /// The file name without its extension. Keys without a `kid` in key sets
+    /// are left out.
+    #[default]
+    FileName,
+    /// The [thumbprint](../jwk/struct.Jwk.html#method.thumbprint) of the key.
+    Thumbprint,
+}
+
+/// The keys of all key files in a directory, loaded once.
+pub struct DirKeyStore {
+    keys: KeySet,
+}
+
+impl DirKeyStore {
+    /// Load the keys of the directory, with the file names as key ids for
+    /// keys without a `kid`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, LoadError> {
+        DirKeyStore::load_with(dir, KeyIds::FileName)
+    }
+
+    pub fn load_with(dir: impl AsRef<Path>, key_ids: KeyIds) -> Result<Self, LoadError> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            let error = io::Error::new(io::ErrorKind::InvalidInput, "not a directory");
+            return Err(LoadError::Io(dir.to_owned(), error));
+        }
+        Ok(DirKeyStore {
+            keys: KeySet::load_with(dir, key_ids)?,
+        })
+    }
+
+    pub fn keys(&self) -> &KeySet {
+        &self.keys
+    }
+
+    pub fn into_keys(self) -> KeySet {
+        self.keys
+    }
+}
+
+impl Store for DirKeyStore {
+    type Algorithm = AnyVerifier;
+
+    fn get(&self, key_id: &str) -> Option<&AnyVerifier> {
+        self.keys.get(key_id)
+    }
+}
+
+fn is_key_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("json" | "jwk" | "jwks" | "pem")
+    )
+}
+
+/// Verifies tokens with the key of their `kid` from a key set that can be
+/// replaced at any time. Clones share the key set.
+#[derive(Clone, Default)]
+pub struct SharedKeySet {
+    keys: Arc<RwLock<Arc<KeySet>>>,
+    max_candidates: usize,
+}
+
+impl SharedKeySet {
+    pub fn new(keys: KeySet) -> Self {
+        SharedKeySet {
+            keys: Arc::new(RwLock::new(Arc::new(keys))),
+            max_candidates: 0,
+        }
+    }
+
+    /// Verify tokens without a `kid` with each key of their algorithm, if
+    /// there are at most `max_keys` of them; with more, the tokens are still
+    /// rejected with [NoKeyId](../error/enum.Error.html#variant.NoKeyId).
+    /// Every key is tried before a token is rejected, and it is rejected
+    /// with [InvalidSignature](../error/enum.Error.html#variant.InvalidSignature)
+    /// whatever the keys failed with. Only this clone tries the keys.
+    pub fn try_all_keys(mut self, max_keys: usize) -> Self {
+        self.max_candidates = max_keys;
+        self
+    }
+
+    /// The current key set. Verifications that already hold it are not
+    /// affected when it is replaced.
+    pub fn keys(&self) -> Arc<KeySet> {
+        Arc::clone(&self.keys.read().unwrap())
+    }
+
+    /// Verify all tokens from now on with these keys.
+    pub fn replace(&self, keys: KeySet) {
+        *self.keys.write().unwrap() = Arc::new(keys);
+    }
+}
+
+impl TokenVerifier for SharedKeySet {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let keys = self.keys();
+        if self.max_candidates > 0 {
+            let unverified: Token<Header, IgnoredAny, Unverified> = Token::parse_unverified(token)?;
+            if unverified.header().key_id.is_none() {
+                return self.try_keys(&keys, token, unverified.header().algorithm);
+            }
+        }
+        let verified: Token<Header, Value, _> = token.verify_with_store(&*keys)?;
+        let (_, claims) = verified.into();
+        Ok(claims)
+    }
+}
+
+impl SharedKeySet {
+    fn try_keys(
+        &self,
+        keys: &KeySet,
+        token: &str,
+        algorithm: AlgorithmType,
+    ) -> Result<Value, Error> {
+        let candidates: Vec<&AnyVerifier> = keys
+            .verifiers
+            .values()
+            .filter(|key| key.algorithm_type() == algorithm)
+            .collect();
+        if candidates.is_empty() {
+            return Err(Error::NoKeyForAlgorithm(algorithm));
+        }
+        if candidates.len() > self.max_candidates {
+            return Err(Error::NoKeyId);
+        }
+
+        for key in candidates {
+            let verified: Result<Token<Header, Value, _>, _> = token.verify_with_key(key);
+            if let Ok(verified) = verified {
+                let (_, claims) = verified.into();
+                return Ok(claims);
+            }
+        }
+        Err(Error::InvalidSignature)
+    }
+}
+
+/// Keeps a [SharedKeySet](struct.SharedKeySet.html) in sync with a key file
+/// or directory, for as long as it lives.
+#[cfg(feature = "notify")]
+pub struct FileKeyStore {
+    keys: SharedKeySet,
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "notify")]
+impl FileKeyStore {
+    /// Load the keys and reload them whenever the files change. Files that
+    /// fail to load keep the previous keys in use.
+    pub fn watch(path: impl Into<PathBuf>) -> Result<Self, LoadError> {
+        FileKeyStore::watch_with(path, |_| {})
+    }
+
+    /// Like [watch](#method.watch), but passes the errors of reloads to
+    /// `on_error`, e.g. to log them.
+    pub fn watch_with(
+        path: impl Into<PathBuf>,
+        on_error: impl Fn(&LoadError) + Send + 'static,
+    ) -> Result<Self, LoadError> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let path = path.into();
+        let keys = SharedKeySet::new(KeySet::load(&path)?);
+
+        let shared = keys.clone();
+        let reload_path = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                match event {
+                    // Reading the files while reloading causes access events
+                    Ok(event) if matches!(event.kind, EventKind::Access(_)) => return,
+                    Ok(_) => {}
+                    Err(e) => return on_error(&LoadError::Watch(e)),
+                }
+                match KeySet::load(&reload_path) {
+                    Ok(keys) => shared.replace(keys),
+                    Err(e) => on_error(&e),
+                }
+            })
+            .map_err(LoadError::Watch)?;
+
+        // Files are often replaced instead of written to, so a single file is
+        // watched through its directory
+        let watched = match path.parent() {
+            Some(parent) if !path.is_dir() => parent,
+            _ => &path,
+        };
+        let watched = if watched.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            watched
+        };
+        watcher
+            .watch(watched, RecursiveMode::NonRecursive)
+            .map_err(LoadError::Watch)?;
+
+        Ok(FileKeyStore {
+            keys,
+            _watcher: watcher,
+        })
+    }
+
+    /// The keys, to verify tokens with, e.g. with
+    /// [BearerAuth::with_verifier](../bearer/struct.BearerAuth.html#method.with_verifier).
+    pub fn keys(&self) -> &SharedKeySet {
+        &self.keys
+    }
+}
+
+/// The reason keys could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    /// The file does not contain a valid key.
+    Key(PathBuf, Error),
+    /// The file has no key id and no file name to take it from, or is PEM
+    /// without the `openssl` feature.
+    Unsupported(PathBuf),
+    #[cfg(feature = "notify")]
+    Watch(notify::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref path, ref e) => write!(f, "Could not read {}: {}", path.display(), e),
+            LoadError::Key(ref path, ref e) => {
+                write!(f, "Invalid key in {}: {}", path.display(), e)
+            }
+            LoadError::Unsupported(ref path) => {
+                write!(f, "Unsupported key file {}", path.display())
+            }
+            #[cfg(feature = "notify")]
+            LoadError::Watch(ref e) => write!(f, "Could not watch key files: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            LoadError::Io(_, ref e) => Some(e),
+            LoadError::Key(_, ref e) => Some(e),
+            #[cfg(feature = "notify")]
+            LoadError::Watch(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::store::Store;
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{BearerAuth, TokenVerifier};
+    use crate::error::Error;
+    use crate::jwk::Jwk;
+    use crate::keystore::{DirKeyStore, KeyIds, KeySet, LoadError, Pins, SharedKeySet};
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithStore;
+    use crate::{Header, RegisteredClaims, Token};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jwt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sign(key_id: &str, secret: &[u8]) -> Result<String, Error> {
+        let header = Header {
+            algorithm: AlgorithmType::Hs256,
+            key_id: Some(key_id.into()),
+            ..Default::default()
+        };
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, secret)?;
+        Ok(Token::new(header, RegisteredClaims::default())
+            .sign_with_key(&signer)?
+            .into())
+    }
+
+    #[test]
+    pub fn load() -> Result<(), Error> {
+        let dir = temp_dir("load");
+        fs::write(
+            dir.join("a.jwks"),
+            r#"{"keys":[{"kty":"oct","kid":"a","alg":"HS256","k":"c2VjcmV0"}]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.jwk"),
+            r#"{"kty":"oct","alg":"HS256","k":"c2VjcmV0"}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("readme.txt"), "not a key").unwrap();
+
+        let keys = KeySet::load(&dir).unwrap();
+        assert_eq!(keys.key_ids().collect::<Vec<_>>(), ["a", "b"]);
+
+        let auth = BearerAuth::with_verifier(SharedKeySet::new(keys));
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("b", b"secret")?)
+            .is_ok());
+
+        fs::write(dir.join("c.json"), "{").unwrap();
+        assert!(matches!(KeySet::load(&dir), Err(LoadError::Key(_, _))));
+        assert!(matches!(
+            KeySet::load(dir.join("missing.jwk")),
+            Err(LoadError::Io(_, _))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    pub fn dir_key_store() -> Result<(), Error> {
+        let dir = temp_dir("dir-key-store");
+        let jwk = r#"{"kty":"oct","alg":"HS256","k":"c2VjcmV0"}"#;
+        fs::write(dir.join("signing.jwk"), jwk).unwrap();
+
+        let store = DirKeyStore::load(&dir).unwrap();
+        assert!(store.get("signing").is_some());
+        let token = sign("signing", b"secret")?;
+        let claims: Result<RegisteredClaims, _> = token.as_str().verify_with_store(&store);
+        assert!(claims.is_ok());
+
+        let thumbprint = serde_json::from_str::<Jwk>(jwk)?.thumbprint()?;
+        let store = DirKeyStore::load_with(&dir, KeyIds::Thumbprint).unwrap();
+        assert_eq!(store.keys().key_ids().collect::<Vec<_>>(), [&*thumbprint]);
+
+        assert!(DirKeyStore::load(dir.join("signing.jwk")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    pub fn pinned() -> Result<(), Error> {
+        let dir = temp_dir("pinned");
+        let (a, b) = (
+            r#"{"kty":"oct","kid":"a","alg":"HS256","k":"YQ"}"#,
+            r#"{"kty":"oct","kid":"b","alg":"HS256","k":"Yg"}"#,
+        );
+        fs::write(
+            dir.join("keys.jwks"),
+            format!(r#"{{"keys":[{},{}]}}"#, a, b),
+        )
+        .unwrap();
+        fs::write(dir.join("c.jwk"), r#"{"kty":"oct","alg":"HS256","k":"Yw"}"#).unwrap();
+
+        let pins = Pins::new([serde_json::from_str::<Jwk>(b)?.thumbprint()?]);
+        let keys = KeySet::load_pinned(&dir, &pins).unwrap();
+        assert_eq!(keys.key_ids().collect::<Vec<_>>(), ["b"]);
+        assert!(KeySet::load_pinned(&dir, &Pins::default())
+            .unwrap()
+            .is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    pub fn reload() -> Result<(), Error> {
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        use crate::keystore::FileKeyStore;
+
+        let dir = temp_dir("reload");
+        let file = dir.join("keys.jwks");
+        fs::write(
+            &file,
+            r#"{"keys":[{"kty":"oct","kid":"old","alg":"HS256","k":"b2xk"}]}"#,
+        )
+        .unwrap();
+
+        let store = FileKeyStore::watch(&file).unwrap();
+        let auth = BearerAuth::with_verifier(store.keys().clone());
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("old", b"old")?)
+            .is_ok());
+
+        fs::write(
+            &file,
+            r#"{"keys":[{"kty":"oct","kid":"new","alg":"HS256","k":"bmV3"}]}"#,
+        )
+        .unwrap();
+        let start = Instant::now();
+        while store.keys().keys().key_ids().next() != Some("new") {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(auth
+            .authenticate_token::<RegisteredClaims>(&sign("new", b"new")?)
+            .is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    pub fn try_all_keys() -> Result<(), Error> {
+        let mut keys = KeySet::default();
+        keys.insert("a", AnyVerifier::hmac(AlgorithmType::Hs256, b"a")?);
+        keys.insert("b", AnyVerifier::hmac(AlgorithmType::Hs256, b"b")?);
+        keys.insert("c", AnyVerifier::hmac(AlgorithmType::Hs384, b"c")?);
+        let sign = |algorithm, secret: &[u8]| -> Result<String, Error> {
+            let header = Header {
+                algorithm,
+                ..Default::default()
+            };
+            let signer = AnySigner::hmac(algorithm, secret)?;
+            Ok(Token::new(header, RegisteredClaims::default())
+                .sign_with_key(&signer)?
+                .into())
+        };
+
+        let strict = SharedKeySet::new(keys);
+        let token = sign(AlgorithmType::Hs256, b"b")?;
+        assert!(matches!(strict.verify_token(&token), Err(Error::NoKeyId)));
+
+        let verifier = strict.clone().try_all_keys(2);
+        assert!(verifier.verify_token(&token).is_ok());
+        assert!(verifier
+            .verify_token(&sign(AlgorithmType::Hs384, b"c")?)
+            .is_ok());
+        assert!(matches!(
+            verifier.verify_token(&sign(AlgorithmType::Hs256, b"c")?),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            verifier.verify_token(&sign(AlgorithmType::Hs512, b"a")?),
+            Err(Error::NoKeyForAlgorithm(AlgorithmType::Hs512))
+        ));
+        // More keys of the algorithm than the bound
+        let bounded = strict.try_all_keys(1);
+        assert!(matches!(bounded.verify_token(&token), Err(Error::NoKeyId)));
+        Ok(())
+    }
+}
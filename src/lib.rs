@@ -5,7 +5,7 @@
 //! Claims can be any `serde::Serialize` type, usually derived with
 //! `serde_derive`.
 //! ```rust
-//! use hmac::{Hmac, Mac};
+//! use hmac::{Hmac, KeyInit};
 //! use jwt::SignWithKey;
 //! use sha2::Sha256;
 //! use std::collections::BTreeMap;
@@ -25,7 +25,7 @@
 //! Claims can be any `serde::Deserialize` type, usually derived with
 //! `serde_derive`.
 //! ```rust
-//! use hmac::{Hmac, Mac};
+//! use hmac::{Hmac, KeyInit};
 //! use jwt::VerifyWithKey;
 //! use sha2::Sha256;
 //! use std::collections::BTreeMap;
@@ -47,7 +47,7 @@
 //! #### Signing
 //! Both header and claims have to implement `serde::Serialize`.
 //! ```rust
-//! use hmac::{Hmac, Mac};
+//! use hmac::{Hmac, KeyInit};
 //! use jwt::{AlgorithmType, Header, SignWithKey, Token};
 //! use sha2::Sha384;
 //! use std::collections::BTreeMap;
@@ -70,7 +70,7 @@
 //! #### Verification
 //! Both header and claims have to implement `serde::Deserialize`.
 //! ```rust
-//! use hmac::{Hmac, Mac};
+//! use hmac::{Hmac, KeyInit};
 //! use jwt::{AlgorithmType, Header, Token, VerifyWithKey};
 //! use sha2::Sha384;
 //! use std::collections::BTreeMap;
@@ -88,33 +88,164 @@
 //! # }
 //! # try_main().unwrap()
 //! ```
+//!
+//! Without the default `std` feature, the crate only needs `alloc`. RSA and
+//! elliptic curve keys through the `openssl` feature require `std`. The HMAC
+//! algorithms are part of the default `hmac` feature, so that a core of the
+//! encoding, the header and claims types, the traits and validation can be
+//! built without any cryptography and combined with another backend. Key
+//! thumbprints hash with SHA-256 and need the `thumbprint` feature, which
+//! `std` enables.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 #[cfg(doctest)]
 doctest!("../README.md");
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
 
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 #[cfg(doctest)]
 use doc_comment::doctest;
 use serde::{Deserialize, Serialize};
 
+use crate::json::{encode_json_into, from_json, to_json};
+
+#[macro_use]
+mod trace;
+
+pub use crate::algorithm::asynchronous::{AsyncSigningAlgorithm, AsyncVerifyingAlgorithm};
 #[cfg(feature = "openssl")]
 pub use crate::algorithm::openssl::PKeyWithDigest;
-pub use crate::algorithm::store::Store;
-pub use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+pub use crate::algorithm::secret::SecretBytes;
+pub use crate::algorithm::store::{KeyStore, Store};
+pub use crate::algorithm::unsecured::Unsecured;
+pub use crate::algorithm::{constant_time_eq, AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
 pub use crate::claims::Claims;
+pub use crate::claims::JwtClaims;
 pub use crate::claims::RegisteredClaims;
 pub use crate::error::Error;
 pub use crate::header::{Header, JoseHeader};
-pub use crate::token::signed::{SignWithKey, SignWithStore};
+pub use crate::token::raw::RawToken;
+pub use crate::token::signed::{SignForPurpose, SignWithKey, SignWithStore};
 pub use crate::token::verified::{VerifyWithKey, VerifyWithStore};
-pub use crate::token::{Unsigned, Unverified, Verified};
+pub use crate::token::{OwnedUnverified, Unsigned, Unverified, Verified};
 
+#[cfg(feature = "actix-web")]
+pub mod actix;
 pub mod algorithm;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bearer;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod canonical;
 pub mod claims;
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod cookie;
+#[cfg(feature = "serde-json-core")]
+pub mod core_json;
+#[cfg(feature = "heapless")]
+pub mod embedded;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(all(feature = "std", feature = "hmac"))]
+pub mod env;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "async-graphql")]
+pub mod graphql;
 pub mod header;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(all(
+    feature = "std",
+    any(
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "js"
+    )
+))]
+pub mod issuer;
+mod json;
+pub mod jwk;
+#[cfg(feature = "jwks")]
+pub mod jwks;
+#[cfg(feature = "std")]
+pub mod keystore;
+#[cfg(feature = "managed")]
+pub mod managed;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+pub mod namespace;
+#[cfg(feature = "oauth2")]
+pub mod oauth2;
+pub mod payload;
+#[cfg(feature = "poem")]
+pub mod poem;
+#[cfg(feature = "std")]
+pub mod policy;
+#[cfg(feature = "jwks")]
+pub mod preset;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod redact;
+#[cfg(feature = "refresh")]
+pub mod refresh;
+#[cfg(feature = "reqwest")]
+pub mod reqwest;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(all(
+    feature = "std",
+    any(
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "js"
+    )
+))]
+pub mod rotation;
+pub mod secevent;
+#[cfg(feature = "session")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod token;
+#[cfg(feature = "tonic")]
+pub mod tonic;
+#[cfg(feature = "tower")]
+pub mod tower;
+pub mod validation;
+#[cfg(feature = "warp")]
+pub mod warp;
+#[cfg(all(
+    feature = "std",
+    any(
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "js"
+    )
+))]
+pub mod websocket;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
 const SEPARATOR: &str = ".";
 
@@ -144,6 +275,16 @@ impl<H, C, S> Token<H, C, S> {
     }
 }
 
+/// Prints the header and claims, but not the signature.
+impl<H: fmt::Debug, C: fmt::Debug, S> fmt::Debug for Token<H, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("header", &self.header)
+            .field("claims", &self.claims)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<H, C, S> From<Token<H, C, S>> for (H, C) {
     fn from(token: Token<H, C, S>) -> Self {
         (token.header, token.claims)
@@ -157,15 +298,26 @@ impl<H, C, S> From<Token<H, C, S>> for (H, C) {
 /// [Serialize](../../serde/trait.Serialize.html). as a base64 encoding of
 /// the object's JSON representation.
 pub trait ToBase64 {
-    fn to_base64(&self) -> Result<Cow<str>, Error>;
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error>;
+
+    /// Append the base64 encoding to a buffer, reusing its capacity. The
+    /// default implementation appends the result of `to_base64`.
+    fn encode_into(&self, out: &mut String) -> Result<(), Error> {
+        out.push_str(&self.to_base64()?);
+        Ok(())
+    }
 }
 
 impl<T: Serialize> ToBase64 for T {
-    fn to_base64(&self) -> Result<Cow<str>, Error> {
-        let json_bytes = serde_json::to_vec(&self)?;
-        let encoded_json_bytes = base64::encode_config(&json_bytes, base64::URL_SAFE_NO_PAD);
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        let json_bytes = to_json(&self)?;
+        let encoded_json_bytes = URL_SAFE_NO_PAD.encode(&json_bytes);
         Ok(Cow::Owned(encoded_json_bytes))
     }
+
+    fn encode_into(&self, out: &mut String) -> Result<(), Error> {
+        encode_json_into(self, out)
+    }
 }
 
 /// A trait used to parse objects from base64 encoding. The return type can
@@ -176,12 +328,23 @@ impl<T: Serialize> ToBase64 for T {
 /// the base64 encoded JSON representation.
 pub trait FromBase64: Sized {
     fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error>;
+
+    /// Parse the object from bytes that were already base64 decoded, e.g. to
+    /// check their size first. The default implementation encodes them again
+    /// for `from_base64`.
+    fn from_decoded(decoded: Vec<u8>) -> Result<Self, Error> {
+        Self::from_base64(&URL_SAFE_NO_PAD.encode(decoded))
+    }
 }
 
 impl<T: for<'de> Deserialize<'de> + Sized> FromBase64 for T {
     fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error> {
-        let json_bytes = base64::decode_config(raw, base64::URL_SAFE_NO_PAD)?;
-        Ok(serde_json::from_slice(&json_bytes)?)
+        let json_bytes = URL_SAFE_NO_PAD.decode(raw)?;
+        from_json(json_bytes)
+    }
+
+    fn from_decoded(decoded: Vec<u8>) -> Result<Self, Error> {
+        from_json(decoded)
     }
 }
 
@@ -195,7 +358,7 @@ mod tests {
     use crate::Claims;
     use crate::Token;
     use hmac::Hmac;
-    use hmac::Mac;
+    use hmac::KeyInit;
     use sha2::Sha256;
 
     #[test]
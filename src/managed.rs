@@ -0,0 +1,270 @@
+//! A token for outgoing requests that renews itself, enabled with the
+//! `managed` feature. A [ManagedToken](struct.ManagedToken.html) asks its
+//! provider, e.g. a closure that signs a token or fetches one from an
+//! identity provider, for a new token shortly before the current one
+//! expires. Callers that need a token while it is renewed wait for that one
+//! renewal instead of starting their own, so the provider is never asked
+//! twice at once.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::clock::{Clock, SystemClock};
+//! use jwt::managed::ManagedToken;
+//! use jwt::{AlgorithmType, RegisteredClaims, SignWithKey};
+//!
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let token = ManagedToken::new(move || {
+//!     let claims = RegisteredClaims {
+//!         subject: Some("service".into()),
+//!         expiration: Some(SystemClock.now() + 300),
+//!         ..Default::default()
+//!     };
+//!     let token = claims.sign_with_key(&signer);
+//!     async move { token }
+//! });
+//! // let bearer = token.get().await?;
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::token::Unverified;
+use crate::Token;
+
+/// Tokens are renewed this many seconds before they expire by default.
+pub const DEFAULT_LEEWAY: SecondsSinceEpoch = 30;
+
+struct CachedToken {
+    token: String,
+    expiration: Option<SecondsSinceEpoch>,
+}
+
+/// Holds the token of a provider and renews it before it expires. Tokens
+/// without an `exp` claim are kept until they are
+/// [invalidated](#method.invalidate). Share it in an `Arc` between clients.
+pub struct ManagedToken<P, C = SystemClock> {
+    provider: P,
+    clock: C,
+    leeway: SecondsSinceEpoch,
+    cached: Mutex<Option<CachedToken>>,
+    renewal: tokio::sync::Mutex<()>,
+}
+
+impl<P> ManagedToken<P> {
+    pub fn new(provider: P) -> Self {
+        ManagedToken {
+            provider,
+            clock: SystemClock,
+            leeway: DEFAULT_LEEWAY,
+            cached: Mutex::new(None),
+            renewal: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+impl<P, C: Clock> ManagedToken<P, C> {
+    /// Renew tokens this many seconds before they expire, instead of
+    /// [DEFAULT_LEEWAY](constant.DEFAULT_LEEWAY.html).
+    pub fn leeway(mut self, leeway: SecondsSinceEpoch) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Check the expiration of tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> ManagedToken<P, D> {
+        ManagedToken {
+            provider: self.provider,
+            clock,
+            leeway: self.leeway,
+            cached: self.cached,
+            renewal: self.renewal,
+        }
+    }
+
+    /// Forget the current token, e.g. after it was rejected, so that the
+    /// next call gets a new one.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+
+    /// The current token, or a new one from the provider if it is about to
+    /// expire.
+    pub async fn get<F, E>(&self) -> Result<String, ManagedError<E>>
+    where
+        P: Fn() -> F,
+        F: Future<Output = Result<String, E>>,
+    {
+        if let Some(token) = self.cached_token() {
+            return Ok(token);
+        }
+
+        let _renewal = self.renewal.lock().await;
+        // Renewed while waiting for the lock
+        if let Some(token) = self.cached_token() {
+            return Ok(token);
+        }
+
+        trace_debug!("renewing managed token");
+        let token = (self.provider)().await.map_err(ManagedError::Provider)?;
+        let parsed: Token<Value, RegisteredClaims, Unverified> =
+            Token::parse_unverified(&token).map_err(ManagedError::InvalidToken)?;
+        *self.cached.lock().unwrap() = Some(CachedToken {
+            expiration: parsed.claims().expiration,
+            token: token.clone(),
+        });
+        Ok(token)
+    }
+
+    fn cached_token(&self) -> Option<String> {
+        let deadline = self.clock.now().saturating_add(self.leeway);
+        let cached = self.cached.lock().unwrap();
+        cached
+            .as_ref()
+            .filter(|cached| cached.expiration.is_none_or(|exp| deadline < exp))
+            .map(|cached| cached.token.clone())
+    }
+}
+
+/// The reason a token could not be renewed.
+#[derive(Debug)]
+pub enum ManagedError<E> {
+    /// The provider failed.
+    Provider(E),
+    /// The provider returned something that is not a token.
+    InvalidToken(Error),
+}
+
+impl<E: fmt::Display> fmt::Display for ManagedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManagedError::Provider(ref e) => write!(f, "Token provider failed: {}", e),
+            ManagedError::InvalidToken(ref e) => write!(f, "Token provider returned {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ManagedError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            ManagedError::Provider(ref e) => Some(e),
+            ManagedError::InvalidToken(ref e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{poll_fn, Future};
+    use std::pin::{pin, Pin};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    use crate::algorithm::any::AnySigner;
+    use crate::algorithm::AlgorithmType;
+    use crate::claims::RegisteredClaims;
+    use crate::error::Error;
+    use crate::managed::{ManagedError, ManagedToken};
+    use crate::token::signed::SignWithKey;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    type Minted = Pin<Box<dyn Future<Output = Result<String, Error>>>>;
+
+    /// A provider that signs tokens expiring at 1100 and is pending once per
+    /// token, counting the tokens.
+    fn provider(minted: &Arc<AtomicU64>) -> Result<impl Fn() -> Minted, Error> {
+        let signer = Arc::new(AnySigner::hmac(AlgorithmType::Hs256, b"secret")?);
+        let minted = Arc::clone(minted);
+        Ok(move || {
+            let serial = minted.fetch_add(1, Ordering::SeqCst);
+            let signer = Arc::clone(&signer);
+            Box::pin(async move {
+                let mut pending = true;
+                poll_fn(|_| match std::mem::replace(&mut pending, false) {
+                    true => Poll::Pending,
+                    false => Poll::Ready(()),
+                })
+                .await;
+                let claims = RegisteredClaims {
+                    json_web_token_id: Some(serial.to_string()),
+                    expiration: Some(1100),
+                    ..Default::default()
+                };
+                claims.sign_with_key(&*signer)
+            }) as Minted
+        })
+    }
+
+    #[test]
+    pub fn renewal() -> Result<(), Error> {
+        let minted = Arc::new(AtomicU64::new(0));
+        let now = Arc::new(AtomicU64::new(1000));
+        let clock = Arc::clone(&now);
+        let token = ManagedToken::new(provider(&minted)?)
+            .leeway(10)
+            .with_clock(move || clock.load(Ordering::SeqCst));
+
+        let first = block_on(token.get()).unwrap();
+        now.store(1089, Ordering::SeqCst);
+        assert_eq!(block_on(token.get()).unwrap(), first);
+        assert_eq!(minted.load(Ordering::SeqCst), 1);
+
+        now.store(1090, Ordering::SeqCst);
+        assert_ne!(block_on(token.get()).unwrap(), first);
+        assert_eq!(minted.load(Ordering::SeqCst), 2);
+
+        token.invalidate();
+        block_on(token.get()).unwrap();
+        assert_eq!(minted.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[test]
+    pub fn single_flight() -> Result<(), Error> {
+        let minted = Arc::new(AtomicU64::new(0));
+        let token = ManagedToken::new(provider(&minted)?).with_clock(|| 1000);
+
+        let (mut a, mut b) = (pin!(token.get()), pin!(token.get()));
+        let mut context = Context::from_waker(Waker::noop());
+        let (mut first, mut second) = (None, None);
+        while first.is_none() || second.is_none() {
+            if first.is_none() {
+                if let Poll::Ready(result) = a.as_mut().poll(&mut context) {
+                    first = Some(result.unwrap());
+                }
+            }
+            if second.is_none() {
+                if let Poll::Ready(result) = b.as_mut().poll(&mut context) {
+                    second = Some(result.unwrap());
+                }
+            }
+        }
+        assert_eq!(first, second);
+        assert_eq!(minted.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn invalid_token() {
+        let token = ManagedToken::new(|| async { Ok::<_, Error>("not a token".to_owned()) });
+        assert!(matches!(
+            block_on(token.get()),
+            Err(ManagedError::InvalidToken(_))
+        ));
+    }
+}
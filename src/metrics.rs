@@ -0,0 +1,190 @@
+//! Hooks for counting the outcomes of verifications, e.g. to alert on a spike
+//! of expired or malformed tokens. A [Metrics](trait.Metrics.html)
+//! implementation is given to a [BearerAuth](../bearer/struct.BearerAuth.html)
+//! for accepted and rejected tokens and the time spent verifying signatures,
+//! and to a [CachedVerifier](../cache/struct.CachedVerifier.html) for cache
+//! hits and misses. [Counters](struct.Counters.html) keeps the numbers in
+//! memory, for exporting them on a schedule or in tests.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::error::ErrorKind;
+//! use jwt::metrics::Counters;
+//! use jwt::AlgorithmType;
+//! use serde_json::Value;
+//! use std::sync::Arc;
+//!
+//! let counters = Arc::new(Counters::default());
+//! let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let auth = BearerAuth::new(verifier).metrics(Arc::clone(&counters));
+//!
+//! assert!(auth.authenticate::<Value>(Some("Bearer not.a.token")).is_err());
+//! assert_eq!(counters.rejected(ErrorKind::MalformedHeader), 1);
+//! assert_eq!(counters.accepted(), 0);
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::ErrorKind;
+
+/// Receives the outcomes of verifications. All methods do nothing by
+/// default, so implementations only override what they record.
+pub trait Metrics: Send + Sync {
+    /// A token was verified and its claims are valid.
+    fn accepted(&self) {}
+
+    /// A token was rejected, for the reason of the kind of error.
+    fn rejected(&self, _kind: ErrorKind) {}
+
+    /// The time it took to verify the signature of a token, whether it was
+    /// valid or not. With a cache in front of the verifier, this includes
+    /// the time of cache hits.
+    fn signature_duration(&self, _duration: Duration) {}
+
+    /// The claims of a token were found in a cache.
+    fn cache_hit(&self) {}
+
+    /// A token was not found in a cache and had to be verified.
+    fn cache_miss(&self) {}
+}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn accepted(&self) {
+        (**self).accepted()
+    }
+
+    fn rejected(&self, kind: ErrorKind) {
+        (**self).rejected(kind)
+    }
+
+    fn signature_duration(&self, duration: Duration) {
+        (**self).signature_duration(duration)
+    }
+
+    fn cache_hit(&self) {
+        (**self).cache_hit()
+    }
+
+    fn cache_miss(&self) {
+        (**self).cache_miss()
+    }
+}
+
+/// Counts outcomes in memory.
+#[derive(Debug, Default)]
+pub struct Counters {
+    accepted: AtomicU64,
+    rejected: Mutex<HashMap<ErrorKind, u64>>,
+    signatures: AtomicU64,
+    signature_nanos: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl Counters {
+    pub fn accepted(&self) -> u64 {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// The number of tokens rejected for one reason.
+    pub fn rejected(&self, kind: ErrorKind) -> u64 {
+        self.rejected
+            .lock()
+            .unwrap()
+            .get(&kind)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The number of rejected tokens by reason.
+    pub fn rejections(&self) -> HashMap<ErrorKind, u64> {
+        self.rejected.lock().unwrap().clone()
+    }
+
+    /// The number of signatures verified and the total time it took.
+    pub fn signatures(&self) -> (u64, Duration) {
+        let nanos = self.signature_nanos.load(Ordering::Relaxed);
+        (
+            self.signatures.load(Ordering::Relaxed),
+            Duration::from_nanos(nanos),
+        )
+    }
+
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+}
+
+impl Metrics for Counters {
+    fn accepted(&self) {
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn rejected(&self, kind: ErrorKind) {
+        *self.rejected.lock().unwrap().entry(kind).or_insert(0) += 1;
+    }
+
+    fn signature_duration(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.signatures.fetch_add(1, Ordering::Relaxed);
+        self.signature_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use hmac::{Hmac, KeyInit};
+    use serde_json::Value;
+    use sha2::Sha256;
+
+    use crate::algorithm::any::AnyVerifier;
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::{Error, ErrorKind};
+    use crate::metrics::Counters;
+    use crate::token::signed::SignWithKey;
+
+    #[test]
+    pub fn counters() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let counters = Arc::new(Counters::default());
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?)
+            .metrics(Arc::clone(&counters));
+        let sign = |exp: u64| BTreeMap::from([("exp", exp)]).sign_with_key(&key);
+
+        let token = sign(u64::MAX)?;
+        auth.authenticate_token::<Value>(&token).unwrap();
+        auth.authenticate_token::<Value>(&token).unwrap();
+        assert!(auth.authenticate_token::<Value>(&sign(1)?).is_err());
+        let other: Hmac<Sha256> = Hmac::new_from_slice(b"other")?;
+        let forged = BTreeMap::from([("exp", u64::MAX)]).sign_with_key(&other)?;
+        assert!(auth.authenticate_token::<Value>(&forged).is_err());
+
+        assert_eq!(counters.accepted(), 2);
+        assert_eq!(counters.rejected(ErrorKind::Expired), 1);
+        assert_eq!(counters.rejected(ErrorKind::InvalidSignature), 1);
+        assert_eq!(counters.rejections().len(), 2);
+        assert_eq!(counters.signatures().0, 4);
+        Ok(())
+    }
+}
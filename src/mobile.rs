@@ -0,0 +1,139 @@
+//! Bindings for Kotlin and Swift through [UniFFI](https://mozilla.github.io/uniffi-rs/),
+//! enabled with the `uniffi` feature. Build the crate as a `cdylib` or
+//! `staticlib` and generate the bindings from it with `uniffi-bindgen`, e.g.
+//! `uniffi-bindgen generate --library libjwt.so --language kotlin`.
+//!
+//! Only HMAC keys are supported. Claims are passed as JSON strings.
+//! ## Examples
+//! ```kotlin
+//! val token = sign("HS256", secret, """{"sub":"someone"}""")
+//! val claims = verify("HS256", secret, token)
+//! ```
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::AlgorithmType;
+use crate::claims::Claims;
+use crate::clock::SystemClock;
+use crate::error::{Error, ErrorKind};
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+use crate::Token;
+
+/// The error type of the bindings, with the message of the underlying
+/// [Error](../error/enum.Error.html).
+#[derive(Debug, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum JwtError {
+    AlgorithmMismatch(String),
+    UnsupportedAlgorithm(String),
+    InvalidSignature(String),
+    Expired(String),
+    NotYetValid(String),
+    MalformedToken(String),
+    InvalidKey(String),
+    Other(String),
+}
+
+impl From<Error> for JwtError {
+    fn from(error: Error) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            ErrorKind::AlgorithmMismatch => JwtError::AlgorithmMismatch(message),
+            ErrorKind::UnsupportedAlgorithm => JwtError::UnsupportedAlgorithm(message),
+            ErrorKind::InvalidSignature => JwtError::InvalidSignature(message),
+            ErrorKind::Expired => JwtError::Expired(message),
+            ErrorKind::NotYetValid => JwtError::NotYetValid(message),
+            ErrorKind::MalformedToken
+            | ErrorKind::MalformedHeader
+            | ErrorKind::MalformedClaims
+            | ErrorKind::MalformedSignature => JwtError::MalformedToken(message),
+            ErrorKind::InvalidKey => JwtError::InvalidKey(message),
+            _ => JwtError::Other(message),
+        }
+    }
+}
+
+impl From<serde_json::Error> for JwtError {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error).into()
+    }
+}
+
+impl std::fmt::Display for JwtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JwtError::AlgorithmMismatch(message)
+            | JwtError::UnsupportedAlgorithm(message)
+            | JwtError::InvalidSignature(message)
+            | JwtError::Expired(message)
+            | JwtError::NotYetValid(message)
+            | JwtError::MalformedToken(message)
+            | JwtError::InvalidKey(message)
+            | JwtError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
+
+/// Sign the JSON claims with an HMAC key, e.g. with algorithm `"HS256"`.
+#[uniffi::export]
+pub fn sign(algorithm: String, key: Vec<u8>, claims_json: String) -> Result<String, JwtError> {
+    let algorithm_type: AlgorithmType = algorithm.parse()?;
+    let signer = AnySigner::hmac(algorithm_type, &key)?;
+    let claims: Claims = serde_json::from_str(&claims_json)?;
+    let header = Header {
+        algorithm: algorithm_type,
+        ..Default::default()
+    };
+
+    let token = Token::new(header, claims).sign_with_key(&signer)?;
+    Ok(token.as_str().to_owned())
+}
+
+/// Verify a token with an HMAC key and check the `exp` and `nbf` claims
+/// against the system time. Returns the claims as JSON.
+#[uniffi::export]
+pub fn verify(algorithm: String, key: Vec<u8>, token: String) -> Result<String, JwtError> {
+    let algorithm_type: AlgorithmType = algorithm.parse()?;
+    let verifier = AnyVerifier::hmac(algorithm_type, &key)?;
+    let token: Token<Header, Claims, _> = token.as_str().verify_with_key(&verifier)?;
+    token.claims().registered.check_time_with(&SystemClock)?;
+
+    Ok(serde_json::to_string(token.claims())?)
+}
+
+/// Decode the claims of a token as JSON **without** verifying the signature.
+#[uniffi::export]
+pub fn decode_claims_json(token: String) -> Result<String, JwtError> {
+    let token: Token<Header, Claims, _> = Token::parse_unverified(&token)?;
+    Ok(serde_json::to_string(token.claims())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mobile::{decode_claims_json, sign, verify, JwtError};
+
+    #[test]
+    pub fn sign_and_verify() -> Result<(), JwtError> {
+        let claims = r#"{"sub":"someone","admin":true}"#;
+        let token = sign("HS384".into(), b"secret".to_vec(), claims.into())?;
+
+        assert_eq!(
+            verify("HS384".into(), b"secret".to_vec(), token.clone())?,
+            claims
+        );
+        assert_eq!(decode_claims_json(token.clone())?, claims);
+
+        match verify("HS384".into(), b"wrong".to_vec(), token) {
+            Err(JwtError::InvalidSignature(_)) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        match verify("RS256".into(), b"secret".to_vec(), "a.b.c".into()) {
+            Err(JwtError::UnsupportedAlgorithm(_)) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        Ok(())
+    }
+}
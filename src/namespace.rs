@@ -0,0 +1,180 @@
+//! Custom claims under a namespace, as identity providers like Auth0 require
+//! for claims they don't define, e.g. `https://example.com/roles`. A
+//! [Namespace](struct.Namespace.html) is the prefix of the names, and a
+//! [ClaimKey](struct.ClaimKey.html) is the name of a claim without the prefix
+//! and the type of its value, so that the claims are read and written
+//! without spelling out their full names.
+//! ## Examples
+//! ```
+//! use jwt::namespace::{ClaimKey, Namespace};
+//! use jwt::Claims;
+//!
+//! const ROLES: ClaimKey<Vec<String>> = ClaimKey::new("roles");
+//!
+//! let namespace = Namespace::new("https://example.com");
+//! let mut claims = Claims::default();
+//! namespace.set(&mut claims, &ROLES, vec!["admin".into()]).unwrap();
+//! assert!(claims.private.contains_key("https://example.com/roles"));
+//!
+//! let roles = namespace.get(&claims, &ROLES).unwrap();
+//! assert_eq!(roles, Some(vec!["admin".to_owned()]));
+//! ```
+
+use alloc::string::String;
+use core::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::claims::Claims;
+use crate::error::Error;
+
+/// The name of a custom claim within a namespace, and the type of its value.
+pub struct ClaimKey<T> {
+    name: &'static str,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> ClaimKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        ClaimKey {
+            name,
+            value: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// The prefix of the names of custom claims.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Namespace {
+    prefix: String,
+}
+
+impl Namespace {
+    /// A namespace of names that start with the prefix. Names are separated
+    /// from a prefix that doesn't end with `/` or `:` by a `/`, so
+    /// `https://example.com` and `https://example.com/` are the same
+    /// namespace.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        let mut prefix = prefix.into();
+        if !prefix.ends_with(['/', ':']) {
+            prefix.push('/');
+        }
+        Namespace { prefix }
+    }
+
+    /// The prefix, with its separator.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The full name of a claim in the namespace.
+    pub fn claim_name(&self, name: &str) -> String {
+        let mut claim_name = String::with_capacity(self.prefix.len() + name.len());
+        claim_name.push_str(&self.prefix);
+        claim_name.push_str(name);
+        claim_name
+    }
+
+    /// The value of a claim, if the claims have it.
+    pub fn get<T: DeserializeOwned>(
+        &self,
+        claims: &Claims,
+        key: &ClaimKey<T>,
+    ) -> Result<Option<T>, Error> {
+        match claims.private.get(&self.claim_name(key.name)) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the value of a claim.
+    pub fn set<T: Serialize>(
+        &self,
+        claims: &mut Claims,
+        key: &ClaimKey<T>,
+        value: T,
+    ) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        claims.private.insert(self.claim_name(key.name), value);
+        Ok(())
+    }
+
+    /// Remove a claim, with its value if the claims had it.
+    pub fn remove<T>(&self, claims: &mut Claims, key: &ClaimKey<T>) -> Option<Value> {
+        claims.private.remove(&self.claim_name(key.name))
+    }
+
+    /// The claims in the namespace, by their names without the prefix.
+    pub fn claims<'a>(&'a self, claims: &'a Claims) -> impl Iterator<Item = (&'a str, &'a Value)> {
+        claims
+            .private
+            .iter()
+            .filter_map(move |(name, value)| Some((name.strip_prefix(&*self.prefix)?, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::claims::Claims;
+    use crate::error::Error;
+    use crate::namespace::{ClaimKey, Namespace};
+    use crate::{FromBase64, ToBase64};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Tenant {
+        id: u32,
+    }
+
+    const ROLES: ClaimKey<Vec<String>> = ClaimKey::new("roles");
+    const TENANT: ClaimKey<Tenant> = ClaimKey::new("tenant");
+
+    #[test]
+    pub fn prefixes() {
+        assert_eq!(
+            Namespace::new("https://example.com").prefix(),
+            "https://example.com/"
+        );
+        assert_eq!(
+            Namespace::new("https://example.com/").prefix(),
+            "https://example.com/"
+        );
+        assert_eq!(
+            Namespace::new("urn:example:").claim_name("roles"),
+            "urn:example:roles"
+        );
+    }
+
+    #[test]
+    pub fn typed_claims() -> Result<(), Error> {
+        let namespace = Namespace::new("https://example.com/");
+        let other = Namespace::new("https://other.example.com/");
+        let mut claims = Claims::default();
+        namespace.set(&mut claims, &ROLES, vec!["admin".into()])?;
+        namespace.set(&mut claims, &TENANT, Tenant { id: 7 })?;
+        claims.private.insert("roles".into(), "user".into());
+
+        let mut claims = Claims::from_base64(claims.to_base64()?.as_bytes())?;
+        assert_eq!(namespace.get(&claims, &ROLES)?, Some(vec!["admin".into()]));
+        assert_eq!(namespace.get(&claims, &TENANT)?, Some(Tenant { id: 7 }));
+        assert_eq!(other.get(&claims, &ROLES)?, None);
+        assert!(matches!(
+            namespace.get(&claims, &ClaimKey::<u32>::new("roles")),
+            Err(Error::Json(_))
+        ));
+
+        let names: Vec<_> = namespace.claims(&claims).map(|(name, _)| name).collect();
+        assert_eq!(names, ["roles", "tenant"]);
+
+        assert!(namespace.remove(&mut claims, &TENANT).is_some());
+        assert_eq!(namespace.get(&claims, &TENANT)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+//! An OAuth 2.0 client for the
+//! [client credentials grant](https://tools.ietf.org/html/rfc6749#section-4.4),
+//! enabled with the `oauth2` feature, for services that call other services
+//! with their own tokens. The client authenticates with a secret, or with a
+//! JWT assertion as in [RFC 7523](https://tools.ietf.org/html/rfc7523) that is
+//! signed by this crate. Tokens are cached until they are about to expire.
+//!
+//! Requests are made with [reqwest](https://docs.rs/reqwest), without its
+//! default features. Enable one of its TLS features in your application to
+//! reach `https` endpoints.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::oauth2::ClientCredentials;
+//! use jwt::AlgorithmType;
+//!
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"client-secret").unwrap();
+//! let client = ClientCredentials::new("https://idp.example.com/oauth2/token", "my-service")
+//!     .client_assertion(signer, None)
+//!     .scope("orders:read");
+//! // let token = client.get_token().await?;
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::algorithm::any::AnySigner;
+use crate::algorithm::SigningAlgorithm;
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::Unverified;
+use crate::Token;
+
+/// Tokens are fetched again this many seconds before they expire by default.
+pub const DEFAULT_LEEWAY: SecondsSinceEpoch = 30;
+/// Client assertions are valid for this many seconds.
+pub const ASSERTION_LIFETIME: SecondsSinceEpoch = 60;
+
+const ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+enum ClientAuthentication {
+    None,
+    Secret(String),
+    Assertion(AnySigner, Option<String>),
+}
+
+struct CachedToken {
+    access_token: String,
+    expiration: Option<SecondsSinceEpoch>,
+}
+
+/// Obtains tokens with the client credentials grant and caches them.
+/// Concurrent calls that find the token expired may each fetch a new one.
+pub struct ClientCredentials<C = SystemClock> {
+    http: reqwest::Client,
+    token_endpoint: String,
+    client_id: String,
+    authentication: ClientAuthentication,
+    scope: Option<String>,
+    leeway: SecondsSinceEpoch,
+    clock: C,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl ClientCredentials {
+    pub fn new(token_endpoint: impl Into<String>, client_id: impl Into<String>) -> Self {
+        ClientCredentials {
+            http: reqwest::Client::new(),
+            token_endpoint: token_endpoint.into(),
+            client_id: client_id.into(),
+            authentication: ClientAuthentication::None,
+            scope: None,
+            leeway: DEFAULT_LEEWAY,
+            clock: SystemClock,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl<C: Clock> ClientCredentials<C> {
+    /// Authenticate with a client secret, sent with HTTP basic
+    /// authentication.
+    pub fn client_secret(mut self, secret: impl Into<String>) -> Self {
+        self.authentication = ClientAuthentication::Secret(secret.into());
+        self
+    }
+
+    /// Authenticate with a JWT assertion signed by the key, with its key ID
+    /// in the header.
+    pub fn client_assertion(mut self, signer: AnySigner, key_id: Option<String>) -> Self {
+        self.authentication = ClientAuthentication::Assertion(signer, key_id);
+        self
+    }
+
+    /// Request tokens for the space separated scopes.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Make requests with this client, e.g. one with timeouts or TLS
+    /// settings.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Fetch tokens again this many seconds before they expire, instead of
+    /// [DEFAULT_LEEWAY](constant.DEFAULT_LEEWAY.html).
+    pub fn leeway(mut self, leeway: SecondsSinceEpoch) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Check the expiration of tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> ClientCredentials<D> {
+        ClientCredentials {
+            http: self.http,
+            token_endpoint: self.token_endpoint,
+            client_id: self.client_id,
+            authentication: self.authentication,
+            scope: self.scope,
+            leeway: self.leeway,
+            clock,
+            cached: self.cached,
+        }
+    }
+
+    /// The cached access token, or a new one if it is about to expire.
+    pub async fn get_token(&self) -> Result<String, OAuthError> {
+        if let Some(token) = self.cached_token() {
+            return Ok(token);
+        }
+
+        let mut request = self.http.post(&self.token_endpoint);
+        if let ClientAuthentication::Secret(ref secret) = self.authentication {
+            request = request.basic_auth(&self.client_id, Some(secret));
+        }
+        let response = request
+            .form(&self.form()?)
+            .send()
+            .await
+            .map_err(OAuthError::Http)?;
+        let status = response.status().as_u16();
+        let body = response.bytes().await.map_err(OAuthError::Http)?;
+
+        let cached = self.parse_response(status, &body)?;
+        let access_token = cached.access_token.clone();
+        *self.cached.lock().unwrap() = Some(cached);
+        Ok(access_token)
+    }
+
+    /// Forget the cached token, e.g. after it was rejected.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+
+    fn cached_token(&self) -> Option<String> {
+        let deadline = self.clock.now().saturating_add(self.leeway);
+        let cached = self.cached.lock().unwrap();
+        cached
+            .as_ref()
+            .filter(|cached| cached.expiration.is_some_and(|exp| deadline < exp))
+            .map(|cached| cached.access_token.clone())
+    }
+
+    fn form(&self) -> Result<Vec<(&'static str, String)>, OAuthError> {
+        let mut form = vec![("grant_type", "client_credentials".to_owned())];
+        if let Some(ref scope) = self.scope {
+            form.push(("scope", scope.clone()));
+        }
+        match self.authentication {
+            ClientAuthentication::None => form.push(("client_id", self.client_id.clone())),
+            ClientAuthentication::Secret(_) => {}
+            ClientAuthentication::Assertion(ref signer, ref key_id) => {
+                form.push(("client_assertion_type", ASSERTION_TYPE.to_owned()));
+                form.push(("client_assertion", self.assertion(signer, key_id)?));
+            }
+        }
+        Ok(form)
+    }
+
+    fn assertion(&self, signer: &AnySigner, key_id: &Option<String>) -> Result<String, OAuthError> {
+        let now = self.clock.now();
+        let header = Header {
+            algorithm: signer.algorithm_type(),
+            key_id: key_id.clone(),
+            ..Default::default()
+        };
+        let claims = RegisteredClaims {
+            issuer: Some(self.client_id.clone()),
+            subject: Some(self.client_id.clone()),
+            audience: Some(self.token_endpoint.clone()),
+            expiration: Some(now + ASSERTION_LIFETIME),
+            issued_at: Some(now),
+            json_web_token_id: Some(unique_id()),
+            ..Default::default()
+        };
+        let token = Token::new(header, claims)
+            .sign_with_key(signer)
+            .map_err(OAuthError::Jwt)?;
+        Ok(token.as_str().to_owned())
+    }
+
+    fn parse_response(&self, status: u16, body: &[u8]) -> Result<CachedToken, OAuthError> {
+        if !(200..300).contains(&status) {
+            let error: ErrorResponse = serde_json::from_slice(body).unwrap_or_default();
+            return Err(OAuthError::Response(
+                status,
+                error.error,
+                error.error_description,
+            ));
+        }
+
+        let response: TokenResponse = serde_json::from_slice(body).map_err(OAuthError::Json)?;
+        if !response.token_type.eq_ignore_ascii_case("Bearer") {
+            return Err(OAuthError::TokenType(response.token_type));
+        }
+        let expiration = match response.expires_in {
+            Some(expires_in) => Some(self.clock.now().saturating_add(expires_in)),
+            // Without `expires_in`, a JWT access token is cached until its
+            // own expiration, and other tokens are not cached at all.
+            None => Token::<Value, RegisteredClaims, Unverified>::parse_unverified(
+                &response.access_token,
+            )
+            .ok()
+            .and_then(|token| token.claims().expiration),
+        };
+        Ok(CachedToken {
+            access_token: response.access_token,
+            expiration,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: Option<SecondsSinceEpoch>,
+}
+
+#[derive(Default, Deserialize)]
+struct ErrorResponse {
+    #[serde(default)]
+    error: String,
+    error_description: Option<String>,
+}
+
+/// An ID for the `jti` claim of assertions, which has to be unique but not
+/// secret.
+fn unique_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let random = RandomState::new().build_hasher().finish();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:016x}{:016x}", random, count)
+}
+
+/// The reason a token could not be obtained.
+#[derive(Debug)]
+pub enum OAuthError {
+    Http(reqwest::Error),
+    /// The authorization server refused the request, with the HTTP status,
+    /// the `error` code and the `error_description`.
+    Response(u16, String, Option<String>),
+    Json(serde_json::Error),
+    /// The token is not a bearer token.
+    TokenType(String),
+    Jwt(crate::error::Error),
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OAuthError::Http(ref e) => write!(f, "Token request failed: {}", e),
+            OAuthError::Response(status, ref error, Some(ref description)) => write!(
+                f,
+                "Token request refused with {}: {} ({})",
+                status, error, description
+            ),
+            OAuthError::Response(status, ref error, None) => {
+                write!(f, "Token request refused with {}: {}", status, error)
+            }
+            OAuthError::Json(ref e) => write!(f, "Invalid token response: {}", e),
+            OAuthError::TokenType(ref token_type) => {
+                write!(f, "Unsupported token type {}", token_type)
+            }
+            OAuthError::Jwt(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for OAuthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            OAuthError::Http(ref e) => Some(e),
+            OAuthError::Json(ref e) => Some(e),
+            OAuthError::Jwt(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::oauth2::{ClientCredentials, OAuthError, ASSERTION_TYPE};
+    use crate::token::verified::VerifyWithKey;
+    use crate::{RegisteredClaims, Token};
+
+    const ENDPOINT: &str = "https://idp.example.com/token";
+
+    #[test]
+    pub fn assertion_form() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let client = ClientCredentials::new(ENDPOINT, "service")
+            .client_assertion(signer, Some("key-1".into()))
+            .scope("read write")
+            .with_clock(|| 1000);
+
+        let form: BTreeMap<_, _> = client.form().unwrap().into_iter().collect();
+        assert_eq!(form["grant_type"], "client_credentials");
+        assert_eq!(form["scope"], "read write");
+        assert_eq!(form["client_assertion_type"], ASSERTION_TYPE);
+        assert!(!form.contains_key("client_id"));
+
+        let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?;
+        let assertion: Token<Header, RegisteredClaims, _> = form["client_assertion"]
+            .as_str()
+            .verify_with_key(&verifier)?;
+        assert_eq!(assertion.header().key_id.as_deref(), Some("key-1"));
+        let claims = assertion.claims();
+        assert_eq!(claims.issuer.as_deref(), Some("service"));
+        assert_eq!(claims.subject.as_deref(), Some("service"));
+        assert_eq!(claims.audience.as_deref(), Some(ENDPOINT));
+        assert_eq!(claims.expiration, Some(1060));
+        assert!(claims.json_web_token_id.is_some());
+
+        let form: BTreeMap<_, _> = client.form().unwrap().into_iter().collect();
+        let other: Token<Header, RegisteredClaims, _> = form["client_assertion"]
+            .as_str()
+            .verify_with_key(&verifier)?;
+        assert_ne!(other.claims().json_web_token_id, claims.json_web_token_id);
+        Ok(())
+    }
+
+    #[test]
+    pub fn caching() {
+        let client = ClientCredentials::new(ENDPOINT, "service")
+            .client_secret("secret")
+            .with_clock(|| 1000);
+
+        let body = br#"{"access_token":"abc","token_type":"bearer","expires_in":60}"#;
+        let cached = client.parse_response(200, body).unwrap();
+        assert_eq!(cached.access_token, "abc");
+        assert_eq!(cached.expiration, Some(1060));
+        *client.cached.lock().unwrap() = Some(cached);
+        assert_eq!(client.cached_token().as_deref(), Some("abc"));
+
+        let client = client.leeway(60);
+        assert_eq!(client.cached_token(), None);
+
+        let body = br#"{"access_token":"opaque","token_type":"Bearer"}"#;
+        assert_eq!(client.parse_response(200, body).unwrap().expiration, None);
+
+        let body = br#"{"access_token":"abc","token_type":"mac"}"#;
+        assert!(matches!(
+            client.parse_response(200, body),
+            Err(OAuthError::TokenType(_))
+        ));
+
+        let body = br#"{"error":"invalid_client"}"#;
+        match client.parse_response(401, body) {
+            Err(OAuthError::Response(401, error, None)) => assert_eq!(error, "invalid_client"),
+            _ => panic!("the error response should be parsed"),
+        }
+    }
+}
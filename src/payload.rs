@@ -0,0 +1,175 @@
+//! Tokens whose payload is arbitrary octets instead of JSON claims, which
+//! [JWS](https://tools.ietf.org/html/rfc7515) permits. The signing input is
+//! always the base64url encoding of the header and payload, so the signing
+//! and verifying algorithms work unchanged; only the payload is encoded and
+//! decoded as bytes instead of JSON. The media type of the payload goes into
+//! the `cty` header parameter.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::payload::{Payload, PayloadHeader};
+//! use jwt::{AlgorithmType, SignWithKey, Token, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let header = PayloadHeader::new(AlgorithmType::Hs256).content_type("application/cbor");
+//! let token = Token::new(header, Payload::new(vec![0xa1, 0x61, 0x61, 0x01]))
+//!     .sign_with_key(&key)
+//!     .unwrap();
+//!
+//! let token: Token<PayloadHeader, Payload, _> = token.as_str().verify_with_key(&key).unwrap();
+//! assert_eq!(token.header().content_type.as_deref(), Some("application/cbor"));
+//! assert_eq!(token.claims().as_bytes(), [0xa1, 0x61, 0x61, 0x01]);
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::{FromBase64, ToBase64};
+
+/// A payload of octets. It is base64url encoded as is, without JSON.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Payload(Vec<u8>);
+
+impl Payload {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Payload(bytes.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for Payload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload(bytes)
+    }
+}
+
+impl From<&[u8]> for Payload {
+    fn from(bytes: &[u8]) -> Self {
+        Payload(bytes.to_vec())
+    }
+}
+
+impl ToBase64 for Payload {
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Owned(URL_SAFE_NO_PAD.encode(&self.0)))
+    }
+
+    fn encode_into(&self, out: &mut String) -> Result<(), Error> {
+        URL_SAFE_NO_PAD.encode_string(&self.0, out);
+        Ok(())
+    }
+}
+
+impl FromBase64 for Payload {
+    fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error> {
+        Ok(Payload(URL_SAFE_NO_PAD.decode(raw)?))
+    }
+
+    fn from_decoded(decoded: Vec<u8>) -> Result<Self, Error> {
+        Ok(Payload(decoded))
+    }
+}
+
+/// A header for octet payloads, with the media type of the payload as a
+/// string.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PayloadHeader {
+    #[serde(rename = "alg")]
+    pub algorithm: AlgorithmType,
+
+    #[serde(rename = "kid", skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    #[serde(rename = "cty", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl PayloadHeader {
+    pub fn new(algorithm: AlgorithmType) -> Self {
+        PayloadHeader {
+            algorithm,
+            ..Default::default()
+        }
+    }
+
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Set the media type of the payload. The `application/` prefix may be
+    /// left out, as RFC 7515 recommends.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+impl JoseHeader for PayloadHeader {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use sha2::Sha256;
+
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+    use crate::payload::{Payload, PayloadHeader};
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::Token;
+
+    #[test]
+    pub fn octets() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let bytes = vec![0xff, 0x00, 0xfe, b'.'];
+        let header = PayloadHeader::new(AlgorithmType::Hs256).content_type("octet-stream");
+        let token = Token::new(header, Payload::new(bytes.clone())).sign_with_key(&key)?;
+        assert_eq!(token.as_str().split('.').nth(1), Some("_wD-Lg"));
+
+        let verified: Token<PayloadHeader, Payload, _> = token.as_str().verify_with_key(&key)?;
+        assert_eq!(verified.claims().as_bytes(), bytes);
+        assert_eq!(
+            verified.header().content_type.as_deref(),
+            Some("octet-stream")
+        );
+
+        let token_str = Payload::from(&b"plain"[..]).sign_with_key(&key)?;
+        let claims: Payload = token_str.as_str().verify_with_key(&key)?;
+        assert_eq!(&*claims, b"plain");
+        Ok(())
+    }
+}
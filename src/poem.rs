@@ -0,0 +1,195 @@
+//! Integration with [poem](https://docs.rs/poem), enabled with the `poem`
+//! feature. [JwtAuth](struct.JwtAuth.html) is a middleware that rejects
+//! requests without a valid bearer token, and [Claims](struct.Claims.html)
+//! extracts the verified claims in handlers. The
+//! [BearerAuth](../bearer/struct.BearerAuth.html) is taken from the
+//! middleware, or from the endpoint data for routes without it.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::poem::{Claims, JwtAuth};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//! use poem::{get, handler, EndpointExt, Route};
+//!
+//! #[handler]
+//! fn whoami(Claims(claims): Claims<RegisteredClaims>) -> String {
+//!     claims.subject.unwrap_or_default()
+//! }
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let app = Route::new().at("/whoami", get(whoami)).with(JwtAuth::new(auth));
+//! ```
+
+use poem::http::header::{CONTENT_TYPE, WWW_AUTHENTICATE};
+use poem::http::{HeaderValue, StatusCode};
+use poem::{Endpoint, FromRequest, Middleware, Request, RequestBody, Response};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::error::Error;
+use crate::source::RequestParts;
+
+/// Extracts the verified claims of the bearer token.
+#[derive(Clone, Debug)]
+pub struct Claims<T = crate::Claims>(pub T);
+
+/// The claims verified by the middleware, kept in the request extensions.
+#[derive(Clone)]
+struct VerifiedClaims(Value);
+
+impl<'a, T: DeserializeOwned> FromRequest<'a> for Claims<T> {
+    async fn from_request(request: &'a Request, _: &mut RequestBody) -> poem::Result<Self> {
+        let claims = match request.extensions().get::<VerifiedClaims>() {
+            Some(VerifiedClaims(claims)) => serde_json::from_value(claims.clone())
+                .map_err(|e| Rejection::InvalidToken(Error::Json(e))),
+            None => match request.data::<BearerAuth>() {
+                Some(auth) => auth.authenticate_request(&PoemParts(request)),
+                None => Err(Rejection::Unconfigured),
+            },
+        };
+        Ok(Claims(claims?))
+    }
+}
+
+impl poem::error::ResponseError for Rejection {
+    fn status(&self) -> StatusCode {
+        StatusCode::from_u16(Rejection::status(self)).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn as_response(&self) -> Response {
+        let builder = Response::builder().status(poem::error::ResponseError::status(self));
+        let mut response = match self.body() {
+            Some(body) => builder.header(CONTENT_TYPE, "application/json").body(body),
+            None => builder.finish(),
+        };
+        if let Some(challenge) = self.www_authenticate() {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                response.headers_mut().insert(WWW_AUTHENTICATE, value);
+            }
+        }
+        response
+    }
+}
+
+/// A middleware that verifies the bearer token of every request.
+#[derive(Clone)]
+pub struct JwtAuth {
+    auth: BearerAuth,
+}
+
+impl JwtAuth {
+    pub fn new(auth: BearerAuth) -> Self {
+        JwtAuth { auth }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for JwtAuth {
+    type Output = JwtAuthEndpoint<E>;
+
+    fn transform(&self, endpoint: E) -> Self::Output {
+        JwtAuthEndpoint {
+            endpoint,
+            auth: self.auth.clone(),
+        }
+    }
+}
+
+/// The endpoint created by [JwtAuth](struct.JwtAuth.html).
+pub struct JwtAuthEndpoint<E> {
+    endpoint: E,
+    auth: BearerAuth,
+}
+
+impl<E: Endpoint> Endpoint for JwtAuthEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut request: Request) -> poem::Result<E::Output> {
+        let claims: Value = self.auth.authenticate_request(&PoemParts(&request))?;
+        request.extensions_mut().insert(VerifiedClaims(claims));
+        self.endpoint.call(request).await
+    }
+}
+
+struct PoemParts<'a>(&'a Request);
+
+impl RequestParts for PoemParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.0.uri().query()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use poem::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+    use poem::http::StatusCode;
+    use poem::{get, handler, Endpoint, EndpointExt, Request, Route};
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::poem::{Claims, JwtAuth};
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[handler]
+    fn whoami(Claims(claims): Claims<RegisteredClaims>) -> String {
+        claims.subject.unwrap_or_default()
+    }
+
+    #[test]
+    pub fn middleware_and_extractor() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let authorization = format!("Bearer {}", token);
+
+        let with_middleware = Route::new()
+            .at("/", get(whoami))
+            .with(JwtAuth::new(auth.clone()));
+        let with_data = Route::new().at("/", get(whoami)).data(auth);
+
+        block_on(async {
+            for app in [with_middleware.boxed(), with_data.boxed()] {
+                let request = Request::builder()
+                    .header(AUTHORIZATION, authorization.as_str())
+                    .finish();
+                let response = app.get_response(request).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.into_body().into_string().await.unwrap(), "someone");
+
+                let response = app.get_response(Request::builder().finish()).await;
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+                assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+            }
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Authorization of authenticated requests by their claims, so that the rules
+//! of a route can live next to its definition. A [Policy](enum.Policy.html)
+//! is built from [require_scope](fn.require_scope.html),
+//! [require_role](fn.require_role.html) and
+//! [require_claim](fn.require_claim.html) and combined with
+//! [or](enum.Policy.html#method.or) and [and](enum.Policy.html#method.and).
+//! A request whose claims don't satisfy the policy is rejected with
+//! [Rejection::Forbidden](../bearer/enum.Rejection.html#variant.Forbidden),
+//! which the framework integrations answer with `403 Forbidden` and a JSON
+//! body that names the requirement.
+//! ## Examples
+//! ```
+//! use jwt::policy::{require_role, require_scope};
+//! use serde_json::json;
+//!
+//! let policy = require_scope("orders:write").or(require_role("admin"));
+//! assert!(policy.check(&json!({"scope": "orders:read orders:write"})).is_ok());
+//! assert!(policy.check(&json!({"roles": ["admin"]})).is_ok());
+//!
+//! let denied = policy.check(&json!({"scope": "orders:read"})).unwrap_err();
+//! assert_eq!(denied.to_string(), r#"Requires scope "orders:write" or role "admin""#);
+//! assert_eq!(
+//!     denied.body(),
+//!     json!({
+//!         "error": "insufficient_scope",
+//!         "error_description": r#"Requires scope "orders:write" or role "admin""#,
+//!         "required": {"any": [{"scope": "orders:write"}, {"role": "admin"}]},
+//!     })
+//! );
+//! ```
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// A rule over the claims of a token.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Policy {
+    /// The space separated `scope` claim, or the `scp` array, contains the
+    /// scope.
+    Scope(String),
+    /// The `roles` claim, an array or a single string, contains the role.
+    Role(String),
+    /// The claim is the value, or an array that contains it.
+    Claim(String, Value),
+    /// At least one of the policies is satisfied.
+    Any(Vec<Policy>),
+    /// All of the policies are satisfied.
+    All(Vec<Policy>),
+}
+
+/// Require a scope.
+pub fn require_scope(scope: impl Into<String>) -> Policy {
+    Policy::Scope(scope.into())
+}
+
+/// Require a role.
+pub fn require_role(role: impl Into<String>) -> Policy {
+    Policy::Role(role.into())
+}
+
+/// Require a claim to have a value, e.g. `require_claim("tenant", "acme")`.
+pub fn require_claim(name: impl Into<String>, value: impl Into<Value>) -> Policy {
+    Policy::Claim(name.into(), value.into())
+}
+
+impl Policy {
+    /// Satisfied by claims that satisfy this policy or the other.
+    pub fn or(self, other: Policy) -> Policy {
+        match self {
+            Policy::Any(mut policies) => {
+                policies.push(other);
+                Policy::Any(policies)
+            }
+            policy => Policy::Any(vec![policy, other]),
+        }
+    }
+
+    /// Satisfied by claims that satisfy both this policy and the other.
+    pub fn and(self, other: Policy) -> Policy {
+        match self {
+            Policy::All(mut policies) => {
+                policies.push(other);
+                Policy::All(policies)
+            }
+            policy => Policy::All(vec![policy, other]),
+        }
+    }
+
+    /// Whether the claims satisfy the policy.
+    pub fn allows(&self, claims: &Value) -> bool {
+        match *self {
+            Policy::Scope(ref scope) => scopes(claims).any(|granted| granted == scope),
+            Policy::Role(ref role) => strings(claims.get("roles")).any(|granted| granted == role),
+            Policy::Claim(ref name, ref value) => match claims.get(name) {
+                Some(Value::Array(values)) => values.contains(value),
+                Some(claim) => claim == value,
+                None => false,
+            },
+            Policy::Any(ref policies) => policies.iter().any(|policy| policy.allows(claims)),
+            Policy::All(ref policies) => policies.iter().all(|policy| policy.allows(claims)),
+        }
+    }
+
+    /// Check the claims, with the requirement that was not met if they don't
+    /// satisfy the policy.
+    pub fn check(&self, claims: &Value) -> Result<(), Denied> {
+        match self.allows(claims) {
+            true => Ok(()),
+            false => Err(Denied(self.clone())),
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Policy::Scope(ref scope) => write!(f, "scope {:?}", scope),
+            Policy::Role(ref role) => write!(f, "role {:?}", role),
+            Policy::Claim(ref name, ref value) => write!(f, "claim {:?} = {}", name, value),
+            Policy::Any(ref policies) => join(f, policies, " or "),
+            Policy::All(ref policies) => join(f, policies, " and "),
+        }
+    }
+}
+
+fn join(f: &mut fmt::Formatter, policies: &[Policy], separator: &str) -> fmt::Result {
+    for (i, policy) in policies.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        match *policy {
+            Policy::Any(_) | Policy::All(_) => write!(f, "({})", policy)?,
+            _ => write!(f, "{}", policy)?,
+        }
+    }
+    Ok(())
+}
+
+/// The claims of a token don't satisfy a policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Denied(pub Policy);
+
+impl Denied {
+    /// The body of a `403 Forbidden` response, with the `error` code of
+    /// [RFC 6750](https://tools.ietf.org/html/rfc6750#section-3.1) and the
+    /// policy the claims don't satisfy.
+    pub fn body(&self) -> Value {
+        json!({
+            "error": "insufficient_scope",
+            "error_description": self.to_string(),
+            "required": self.0,
+        })
+    }
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Requires {}", self.0)
+    }
+}
+
+impl std::error::Error for Denied {}
+
+/// The scopes granted by the space separated `scope` claim of RFC 8693, or
+/// the `scp` array.
+pub(crate) fn scopes(claims: &Value) -> impl Iterator<Item = &str> {
+    strings(claims.get("scope").or_else(|| claims.get("scp")))
+}
+
+/// The strings of a space separated string or an array of strings.
+fn strings(claim: Option<&Value>) -> Box<dyn Iterator<Item = &str> + '_> {
+    match claim {
+        Some(Value::String(values)) => Box::new(values.split_whitespace()),
+        Some(Value::Array(values)) => Box::new(values.iter().filter_map(Value::as_str)),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::policy::{require_claim, require_role, require_scope, Policy};
+
+    #[test]
+    pub fn combinators() {
+        let policy = require_scope("orders:write")
+            .or(require_role("admin"))
+            .or(require_role("owner").and(require_claim("tenant", "acme")));
+        assert!(matches!(policy, Policy::Any(ref policies) if policies.len() == 3));
+
+        assert!(policy.allows(&json!({"scp": ["orders:read", "orders:write"]})));
+        assert!(policy.allows(&json!({"roles": "admin"})));
+        assert!(policy.allows(&json!({"roles": ["owner"], "tenant": "acme"})));
+        assert!(policy.allows(&json!({"roles": ["owner"], "tenant": ["other", "acme"]})));
+        assert!(!policy.allows(&json!({"roles": ["owner"], "tenant": "other"})));
+        assert!(!policy.allows(&json!({"scope": "orders:writer", "role": "admin"})));
+
+        let denied = policy.check(&json!({})).unwrap_err();
+        assert_eq!(
+            denied.to_string(),
+            r#"Requires scope "orders:write" or role "admin" or (role "owner" and claim "tenant" = "acme")"#
+        );
+        assert_eq!(
+            denied.body()["required"]["any"][2],
+            json!({"all": [{"role": "owner"}, {"claim": ["tenant", "acme"]}]})
+        );
+    }
+}
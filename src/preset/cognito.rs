@@ -0,0 +1,226 @@
+//! A preset for the tokens of [Amazon Cognito](https://docs.aws.amazon.com/cognito/latest/developerguide/amazon-cognito-user-pools-using-tokens-verifying-a-jwt.html)
+//! user pools.
+//!
+//! The tokens of a user pool are issued by
+//! `https://cognito-idp.<region>.amazonaws.com/<user pool ID>`, which also
+//! serves the keys at `/.well-known/jwks.json`, and are signed with RS256. A
+//! pool issues ID and access tokens with the same keys, told apart by their
+//! `token_use` claim, so a verifier only accepts one of them. ID tokens have
+//! the app client ID as their `aud`, but access tokens have no `aud` and name
+//! the app client in their `client_id` claim instead.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::preset::cognito::{Cognito, TokenUse};
+//!
+//! let cognito = Cognito::new("eu-west-1", "eu-west-1_AbCdEf123", "1example23456789")
+//!     .token_use(TokenUse::Access);
+//! let auth = BearerAuth::with_verifier(cognito.verifier());
+//! let refresher = cognito.refresher();
+//! // tokio::spawn(refresher.run());
+//! ```
+
+use serde_json::Value;
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::SecondsSinceEpoch;
+use crate::error::Error;
+use crate::jwks::{JwksClient, JwksVerifier, Refresher};
+use crate::preset::ProviderVerifier;
+use crate::validation::Validation;
+
+const ALGORITHMS: &[AlgorithmType] = &[AlgorithmType::Rs256];
+
+/// The kind of token, by its `token_use` claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenUse {
+    /// ID tokens, for the app itself.
+    Id,
+    /// Access tokens, for APIs.
+    Access,
+}
+
+impl TokenUse {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TokenUse::Id => "id",
+            TokenUse::Access => "access",
+        }
+    }
+}
+
+/// Verifies the tokens of a Cognito user pool.
+#[derive(Clone)]
+pub struct Cognito {
+    region: String,
+    user_pool_id: String,
+    token_use: TokenUse,
+    client_ids: Vec<String>,
+    keys: JwksVerifier,
+    leeway: SecondsSinceEpoch,
+}
+
+impl Cognito {
+    /// Verify the access tokens of the user pool for one of its app clients:
+    /// the `aud` of ID tokens or the `client_id` of access tokens must be
+    /// the client ID.
+    pub fn new(
+        region: impl Into<String>,
+        user_pool_id: impl Into<String>,
+        client_id: impl Into<String>,
+    ) -> Self {
+        Cognito {
+            region: region.into(),
+            user_pool_id: user_pool_id.into(),
+            token_use: TokenUse::Access,
+            client_ids: vec![client_id.into()],
+            keys: JwksVerifier::default(),
+            leeway: 0,
+        }
+    }
+
+    /// Accept this kind of token instead.
+    pub fn token_use(mut self, token_use: TokenUse) -> Self {
+        self.token_use = token_use;
+        self
+    }
+
+    /// Also accept tokens for another app client.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_ids.push(client_id.into());
+        self
+    }
+
+    /// Accept tokens this many seconds after they expired.
+    pub fn leeway(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// The `iss` of the tokens of the user pool.
+    pub fn issuer(&self) -> String {
+        format!(
+            "https://cognito-idp.{}.amazonaws.com/{}",
+            self.region, self.user_pool_id
+        )
+    }
+
+    /// The JWKS endpoint of the user pool.
+    pub fn jwks_url(&self) -> String {
+        format!("{}/.well-known/jwks.json", self.issuer())
+    }
+
+    /// The verifier of the tokens. All verifiers of a preset share its keys.
+    pub fn verifier(&self) -> ProviderVerifier {
+        let mut validation = Validation::new()
+            .issuer(self.issuer())
+            .leeway(self.leeway)
+            .require_expiration(true);
+        if self.token_use == TokenUse::Id {
+            for client_id in &self.client_ids {
+                validation = validation.audience(client_id.clone());
+            }
+        }
+
+        let token_use = self.token_use;
+        let client_ids = self.client_ids.clone();
+        ProviderVerifier::new(
+            self.keys.clone(),
+            ALGORITHMS,
+            validation,
+            move |claims, _| {
+                match claims.get("token_use").and_then(Value::as_str) {
+                    Some(used) if used == token_use.as_str() => {}
+                    Some(_) => return Err(Error::InvalidClaim("token_use")),
+                    None => return Err(Error::MissingClaim("token_use")),
+                }
+                // The validation checks the aud of ID tokens
+                if token_use == TokenUse::Id {
+                    return Ok(());
+                }
+                match claims.get("client_id").and_then(Value::as_str) {
+                    Some(client_id) if client_ids.iter().any(|id| id == client_id) => Ok(()),
+                    Some(_) => Err(Error::InvalidClaim("client_id")),
+                    None => Err(Error::MissingClaim("client_id")),
+                }
+            },
+        )
+    }
+
+    /// Keeps the keys of the verifiers up to date.
+    pub fn refresher(&self) -> Refresher {
+        Refresher::new(JwksClient::new(self.jwks_url()), self.keys.clone())
+    }
+}
+
+#[cfg(all(test, feature = "openssl"))]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::preset::cognito::{Cognito, TokenUse};
+    use crate::preset::tests::{sign, with_keys};
+
+    const ISSUER: &str = "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_AbCdEf123";
+
+    fn claims(token_use: &str) -> Value {
+        json!({"iss": ISSUER, "sub": "user", "token_use": token_use, "exp": 2000})
+    }
+
+    #[test]
+    pub fn access_tokens() -> Result<(), Error> {
+        let cognito = Cognito::new("eu-west-1", "eu-west-1_AbCdEf123", "app");
+        assert_eq!(
+            cognito.jwks_url(),
+            format!("{}/.well-known/jwks.json", ISSUER)
+        );
+        let verifier = cognito.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        let mut access = claims("access");
+        access["client_id"] = json!("app");
+        assert!(verifier.verify_token(&sign(access.clone())?).is_ok());
+
+        access["client_id"] = json!("other");
+        assert!(matches!(
+            verifier.verify_token(&sign(access.clone())?),
+            Err(Error::InvalidClaim("client_id"))
+        ));
+        let verifier = cognito.client_id("other").verifier().with_clock(|| 1000);
+        assert!(verifier.verify_token(&sign(access)?).is_ok());
+        let mut id = claims("id");
+        id["aud"] = json!("app");
+        assert!(matches!(
+            verifier.verify_token(&sign(id)?),
+            Err(Error::InvalidClaim("token_use"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn id_tokens() -> Result<(), Error> {
+        let cognito =
+            Cognito::new("eu-west-1", "eu-west-1_AbCdEf123", "app").token_use(TokenUse::Id);
+        let verifier = cognito.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        let mut id = claims("id");
+        id["aud"] = json!("app");
+        assert!(verifier.verify_token(&sign(id.clone())?).is_ok());
+
+        id["aud"] = json!("other");
+        assert!(matches!(
+            verifier.verify_token(&sign(id)?),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        let mut other_pool = claims("id");
+        other_pool["aud"] = json!("app");
+        other_pool["iss"] = json!("https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_Other");
+        assert!(matches!(
+            verifier.verify_token(&sign(other_pool)?),
+            Err(Error::InvalidClaim("iss"))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+//! A preset for the tokens of [Microsoft Entra ID](https://learn.microsoft.com/en-us/entra/identity-platform/access-tokens),
+//! formerly Azure AD.
+//!
+//! Entra issues tokens of two versions, by the `ver` claim: `1.0` tokens are
+//! issued by `https://sts.windows.net/<tenant>/` and name the client
+//! application in `appid`, `2.0` tokens are issued by
+//! `https://login.microsoftonline.com/<tenant>/v2.0` and name it in `azp`.
+//! The tenant is the `tid` claim of the token, so for multi-tenant apps the
+//! issuer is checked against the template with the tenant of the token, and
+//! the tenants can be restricted with [tenant](struct.Entra.html#method.tenant).
+//! All tenants and both versions are signed with the same RS256 keys.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::preset::entra::Entra;
+//!
+//! let entra = Entra::multi_tenant("api://orders")
+//!     .tenant("72f988bf-86f1-41af-91ab-2d7cd011db47")
+//!     .authorized_party("6e74172b-be56-4843-9ff4-e66a39bb12e3");
+//! let auth = BearerAuth::with_verifier(entra.verifier());
+//! let refresher = entra.refresher();
+//! // Or with the endpoint of the OpenID configuration:
+//! // let refresher = entra.discover().await?;
+//! // tokio::spawn(refresher.run());
+//! ```
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::SecondsSinceEpoch;
+use crate::error::Error;
+use crate::jwks::{JwksClient, JwksError, JwksVerifier, Refresher};
+use crate::preset::ProviderVerifier;
+use crate::validation::Validation;
+
+const ALGORITHMS: &[AlgorithmType] = &[AlgorithmType::Rs256];
+
+/// The authority of multi-tenant apps.
+const COMMON: &str = "common";
+
+/// The version of a token, by its `ver` claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenVersion {
+    V1,
+    V2,
+}
+
+impl TokenVersion {
+    /// The `iss` of the tokens of a tenant.
+    pub fn issuer(&self, tenant_id: &str) -> String {
+        match *self {
+            TokenVersion::V1 => format!("https://sts.windows.net/{}/", tenant_id),
+            TokenVersion::V2 => format!("https://login.microsoftonline.com/{}/v2.0", tenant_id),
+        }
+    }
+
+    /// The claim that names the client application.
+    pub fn authorized_party_claim(&self) -> &'static str {
+        match *self {
+            TokenVersion::V1 => "appid",
+            TokenVersion::V2 => "azp",
+        }
+    }
+
+    fn from_claims(claims: &Value) -> Result<Self, Error> {
+        match claims.get("ver").map(Value::as_str) {
+            Some(Some("1.0")) => Ok(TokenVersion::V1),
+            Some(Some("2.0")) => Ok(TokenVersion::V2),
+            Some(_) => Err(Error::InvalidClaim("ver")),
+            None => Err(Error::MissingClaim("ver")),
+        }
+    }
+}
+
+/// The part of the OpenID configuration that is needed.
+#[derive(Deserialize)]
+struct Configuration {
+    jwks_uri: String,
+}
+
+/// Verifies the tokens of one or more Entra tenants for an app.
+#[derive(Clone)]
+pub struct Entra {
+    authority: String,
+    tenants: Vec<String>,
+    audiences: Vec<String>,
+    authorized_parties: Vec<String>,
+    keys: JwksVerifier,
+    leeway: SecondsSinceEpoch,
+}
+
+impl Entra {
+    /// Verify the tokens of a single tenant for the audience, the client ID
+    /// or the application ID URI of the app.
+    pub fn new(tenant_id: impl Into<String>, audience: impl Into<String>) -> Self {
+        let tenant_id = tenant_id.into();
+        Entra {
+            authority: tenant_id.clone(),
+            tenants: vec![tenant_id],
+            ..Entra::multi_tenant(audience)
+        }
+    }
+
+    /// Verify the tokens of any tenant for the audience, until
+    /// [tenant](#method.tenant) restricts them.
+    pub fn multi_tenant(audience: impl Into<String>) -> Self {
+        Entra {
+            authority: COMMON.to_owned(),
+            tenants: Vec::new(),
+            audiences: vec![audience.into()],
+            authorized_parties: Vec::new(),
+            keys: JwksVerifier::default(),
+            leeway: 0,
+        }
+    }
+
+    /// Accept the tokens of this tenant. Once a tenant is set, the `tid` of
+    /// the tokens must be one of them.
+    pub fn tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenants.push(tenant_id.into());
+        self
+    }
+
+    /// Also accept tokens for this audience, e.g. the client ID besides the
+    /// application ID URI.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Accept tokens that were requested by this client application. Once a
+    /// client is set, the `appid` of 1.0 tokens or the `azp` of 2.0 tokens
+    /// must be one of them.
+    pub fn authorized_party(mut self, client_id: impl Into<String>) -> Self {
+        self.authorized_parties.push(client_id.into());
+        self
+    }
+
+    /// Accept tokens this many seconds outside of the times of their claims.
+    pub fn leeway(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// The JWKS endpoint of the tenant, or the common one of all tenants.
+    pub fn jwks_url(&self) -> String {
+        format!(
+            "https://login.microsoftonline.com/{}/discovery/v2.0/keys",
+            self.authority
+        )
+    }
+
+    /// The OpenID configuration of the tenant, or the common one of all
+    /// tenants.
+    pub fn configuration_url(&self) -> String {
+        format!(
+            "https://login.microsoftonline.com/{}/v2.0/.well-known/openid-configuration",
+            self.authority
+        )
+    }
+
+    /// The verifier of the tokens. All verifiers of a preset share its keys.
+    pub fn verifier(&self) -> ProviderVerifier {
+        let validation = self
+            .audiences
+            .iter()
+            .fold(Validation::new(), |validation, audience| {
+                validation.audience(audience.clone())
+            })
+            .leeway(self.leeway)
+            .require_expiration(true);
+
+        let tenants = self.tenants.clone();
+        let authorized_parties = self.authorized_parties.clone();
+        ProviderVerifier::new(
+            self.keys.clone(),
+            ALGORITHMS,
+            validation,
+            move |claims, _| {
+                let version = TokenVersion::from_claims(claims)?;
+                let tid = match claims.get("tid").and_then(Value::as_str) {
+                    Some(tid) if tenants.is_empty() || tenants.iter().any(|t| t == tid) => tid,
+                    Some(_) => return Err(Error::InvalidClaim("tid")),
+                    None => return Err(Error::MissingClaim("tid")),
+                };
+                match claims.get("iss").and_then(Value::as_str) {
+                    Some(iss) if iss == version.issuer(tid) => {}
+                    Some(_) => return Err(Error::InvalidClaim("iss")),
+                    None => return Err(Error::MissingClaim("iss")),
+                }
+
+                if authorized_parties.is_empty() {
+                    return Ok(());
+                }
+                let name = version.authorized_party_claim();
+                match claims.get(name).and_then(Value::as_str) {
+                    Some(party) if authorized_parties.iter().any(|p| p == party) => Ok(()),
+                    Some(_) => Err(Error::InvalidClaim(name)),
+                    None => Err(Error::MissingClaim(name)),
+                }
+            },
+        )
+    }
+
+    /// Keeps the keys of the verifiers up to date, from the JWKS endpoint that
+    /// Entra documents.
+    pub fn refresher(&self) -> Refresher {
+        Refresher::new(JwksClient::new(self.jwks_url()), self.keys.clone())
+    }
+
+    /// Like [refresher](#method.refresher), with the JWKS endpoint of the
+    /// OpenID configuration, e.g. for national clouds.
+    pub async fn discover(&self) -> Result<Refresher, JwksError> {
+        let response = reqwest::Client::new()
+            .get(self.configuration_url())
+            .header("accept", "application/json")
+            .send()
+            .await
+            .map_err(JwksError::Http)?;
+        if !response.status().is_success() {
+            return Err(JwksError::Status(response.status().as_u16()));
+        }
+        let body = response.text().await.map_err(JwksError::Http)?;
+        let configuration: Configuration = serde_json::from_str(&body).map_err(JwksError::Json)?;
+        let client = JwksClient::new(configuration.jwks_uri);
+        Ok(Refresher::new(client, self.keys.clone()))
+    }
+}
+
+#[cfg(all(test, feature = "openssl"))]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::preset::entra::Entra;
+    use crate::preset::tests::{sign, with_keys};
+
+    const TENANT: &str = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+    const OTHER_TENANT: &str = "9188040d-6c67-4c5b-b112-36a304b66dad";
+
+    fn v1(tid: &str) -> Value {
+        json!({
+            "iss": format!("https://sts.windows.net/{}/", tid),
+            "aud": "api://orders",
+            "tid": tid,
+            "ver": "1.0",
+            "appid": "client",
+            "exp": 2000,
+        })
+    }
+
+    fn v2(tid: &str) -> Value {
+        json!({
+            "iss": format!("https://login.microsoftonline.com/{}/v2.0", tid),
+            "aud": "api://orders",
+            "tid": tid,
+            "ver": "2.0",
+            "azp": "client",
+            "exp": 2000,
+        })
+    }
+
+    #[test]
+    pub fn single_tenant() -> Result<(), Error> {
+        let entra = Entra::new(TENANT, "api://orders");
+        assert_eq!(
+            entra.jwks_url(),
+            format!(
+                "https://login.microsoftonline.com/{}/discovery/v2.0/keys",
+                TENANT
+            )
+        );
+        let verifier = entra.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        assert!(verifier.verify_token(&sign(v1(TENANT))?).is_ok());
+        assert!(verifier.verify_token(&sign(v2(TENANT))?).is_ok());
+        assert!(matches!(
+            verifier.verify_token(&sign(v2(OTHER_TENANT))?),
+            Err(Error::InvalidClaim("tid"))
+        ));
+
+        // The issuer of the other version
+        let mut claims = v2(TENANT);
+        claims["ver"] = json!("1.0");
+        assert!(matches!(
+            verifier.verify_token(&sign(claims)?),
+            Err(Error::InvalidClaim("iss"))
+        ));
+        let mut claims = v1(TENANT);
+        claims["aud"] = json!("api://billing");
+        assert!(matches!(
+            verifier.verify_token(&sign(claims)?),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn multi_tenant() -> Result<(), Error> {
+        let entra = Entra::multi_tenant("api://orders").authorized_party("client");
+        assert!(entra.jwks_url().contains("/common/"));
+        let verifier = entra.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        assert!(verifier.verify_token(&sign(v1(TENANT))?).is_ok());
+        assert!(verifier.verify_token(&sign(v2(OTHER_TENANT))?).is_ok());
+
+        // Signed for one tenant, but claiming another
+        let mut claims = v2(TENANT);
+        claims["tid"] = json!(OTHER_TENANT);
+        assert!(matches!(
+            verifier.verify_token(&sign(claims)?),
+            Err(Error::InvalidClaim("iss"))
+        ));
+        let mut claims = v1(TENANT);
+        claims["appid"] = json!("other");
+        assert!(matches!(
+            verifier.verify_token(&sign(claims)?),
+            Err(Error::InvalidClaim("appid"))
+        ));
+        let mut claims = v2(TENANT);
+        claims.as_object_mut().unwrap().remove("azp");
+        assert!(matches!(
+            verifier.verify_token(&sign(claims)?),
+            Err(Error::MissingClaim("azp"))
+        ));
+
+        let restricted = Entra::multi_tenant("api://orders")
+            .tenant(TENANT)
+            .verifier();
+        let restricted = restricted.with_clock(|| 1000);
+        with_keys(restricted.keys())?;
+        assert!(matches!(
+            restricted.verify_token(&sign(v2(OTHER_TENANT))?),
+            Err(Error::InvalidClaim("tid"))
+        ));
+        Ok(())
+    }
+}
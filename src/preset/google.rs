@@ -0,0 +1,259 @@
+//! Presets for the ID tokens of [Firebase Authentication](https://firebase.google.com/docs/auth/admin/verify-id-tokens)
+//! and [Sign in with Google](https://developers.google.com/identity/gsi/web/guides/verify-google-id-token).
+//!
+//! Firebase ID tokens are issued by `https://securetoken.google.com/<project>`
+//! for the project ID as their audience, and must have a non-empty `sub` and
+//! an `iat` and `auth_time` in the past. Google ID tokens are issued by
+//! `https://accounts.google.com`, or `accounts.google.com` without the
+//! scheme, for the OAuth client ID. Both are signed with RS256 by keys that
+//! Google rotates every few hours: new keys are published before they are
+//! used, and the `Cache-Control` of the endpoints says until when the keys
+//! may be cached, which the [Refresher](../../jwks/struct.Refresher.html)
+//! follows.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::preset::google::Google;
+//!
+//! let google = Google::new("1234.apps.googleusercontent.com").leeway(30);
+//! let auth = BearerAuth::with_verifier(google.verifier());
+//! let refresher = google.refresher();
+//! // tokio::spawn(refresher.run());
+//! ```
+
+use serde_json::Value;
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::SecondsSinceEpoch;
+use crate::error::Error;
+use crate::jwks::{JwksClient, JwksVerifier, Refresher};
+use crate::preset::{not_after, ProviderVerifier};
+use crate::validation::Validation;
+
+/// The keys of Firebase ID tokens.
+pub const FIREBASE_JWKS_URL: &str =
+    "https://www.googleapis.com/service_accounts/v1/jwk/securetoken@system.gserviceaccount.com";
+
+/// The keys of Google ID tokens.
+pub const GOOGLE_JWKS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+/// The issuers of Google ID tokens.
+pub const GOOGLE_ISSUERS: [&str; 2] = ["https://accounts.google.com", "accounts.google.com"];
+
+const ALGORITHMS: &[AlgorithmType] = &[AlgorithmType::Rs256];
+
+/// The `sub` of Firebase ID tokens is the user ID, of at most 128 characters.
+const MAX_SUBJECT_LENGTH: usize = 128;
+
+/// Verifies the ID tokens of a Firebase project.
+#[derive(Clone)]
+pub struct Firebase {
+    project_id: String,
+    keys: JwksVerifier,
+    leeway: SecondsSinceEpoch,
+}
+
+impl Firebase {
+    pub fn new(project_id: impl Into<String>) -> Self {
+        Firebase {
+            project_id: project_id.into(),
+            keys: JwksVerifier::default(),
+            leeway: 0,
+        }
+    }
+
+    /// Accept tokens this many seconds outside of the times of their claims.
+    pub fn leeway(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    /// The `iss` of the tokens of the project.
+    pub fn issuer(&self) -> String {
+        format!("https://securetoken.google.com/{}", self.project_id)
+    }
+
+    /// The verifier of the tokens. All verifiers of a preset share its keys.
+    pub fn verifier(&self) -> ProviderVerifier {
+        let validation = Validation::new()
+            .issuer(self.issuer())
+            .audience(self.project_id.clone())
+            .leeway(self.leeway)
+            .require_expiration(true);
+        let leeway = self.leeway;
+        ProviderVerifier::new(
+            self.keys.clone(),
+            ALGORITHMS,
+            validation,
+            move |claims, now| {
+                match claims.get("sub").and_then(Value::as_str) {
+                    Some(sub) if !sub.is_empty() && sub.chars().count() <= MAX_SUBJECT_LENGTH => {}
+                    Some(_) => return Err(Error::InvalidClaim("sub")),
+                    None => return Err(Error::MissingClaim("sub")),
+                }
+                not_after(claims, "iat", now.saturating_add(leeway))?;
+                not_after(claims, "auth_time", now.saturating_add(leeway))
+            },
+        )
+    }
+
+    /// Keeps the keys of the verifiers up to date.
+    pub fn refresher(&self) -> Refresher {
+        Refresher::new(JwksClient::new(FIREBASE_JWKS_URL), self.keys.clone())
+    }
+}
+
+/// Verifies Google ID tokens for one or more OAuth client IDs.
+#[derive(Clone)]
+pub struct Google {
+    client_ids: Vec<String>,
+    keys: JwksVerifier,
+    leeway: SecondsSinceEpoch,
+}
+
+impl Google {
+    pub fn new(client_id: impl Into<String>) -> Self {
+        Google {
+            client_ids: vec![client_id.into()],
+            keys: JwksVerifier::default(),
+            leeway: 0,
+        }
+    }
+
+    /// Also accept tokens for another client ID, e.g. of a mobile app.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_ids.push(client_id.into());
+        self
+    }
+
+    /// Accept tokens this many seconds outside of the times of their claims.
+    pub fn leeway(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// The verifier of the tokens. All verifiers of a preset share its keys.
+    pub fn verifier(&self) -> ProviderVerifier {
+        let validation = self
+            .client_ids
+            .iter()
+            .fold(Validation::new(), |validation, client_id| {
+                validation.audience(client_id.clone())
+            })
+            .leeway(self.leeway)
+            .require_expiration(true);
+        // The issuer has two spellings, which a validation can't accept
+        ProviderVerifier::new(
+            self.keys.clone(),
+            ALGORITHMS,
+            validation,
+            |claims, _| match claims.get("iss").and_then(Value::as_str) {
+                Some(iss) if GOOGLE_ISSUERS.contains(&iss) => Ok(()),
+                Some(_) => Err(Error::InvalidClaim("iss")),
+                None => Err(Error::MissingClaim("iss")),
+            },
+        )
+    }
+
+    /// Keeps the keys of the verifiers up to date.
+    pub fn refresher(&self) -> Refresher {
+        Refresher::new(JwksClient::new(GOOGLE_JWKS_URL), self.keys.clone())
+    }
+}
+
+#[cfg(all(test, feature = "openssl"))]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::algorithm::any::AnySigner;
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::preset::google::{Firebase, Google};
+    use crate::preset::tests::{sign, with_keys};
+    use crate::token::signed::SignWithKey;
+
+    #[test]
+    pub fn firebase() -> Result<(), Error> {
+        let firebase = Firebase::new("my-project").leeway(10);
+        let verifier = firebase.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        let claims = json!({
+            "iss": "https://securetoken.google.com/my-project",
+            "aud": "my-project",
+            "sub": "user",
+            "iat": 900,
+            "auth_time": 1005,
+            "exp": 2000,
+        });
+        assert_eq!(
+            verifier.verify_token(&sign(claims.clone())?)?["sub"],
+            "user"
+        );
+
+        let with = |name: &str, value: Value| {
+            let mut claims = claims.clone();
+            claims[name] = value;
+            sign(claims)
+        };
+        let rejected = |token: String| verifier.verify_token(&token).unwrap_err();
+        assert!(matches!(
+            rejected(with("aud", json!("other-project"))?),
+            Error::InvalidClaim("aud")
+        ));
+        assert!(matches!(
+            rejected(with("iss", json!("https://accounts.google.com"))?),
+            Error::InvalidClaim("iss")
+        ));
+        assert!(matches!(
+            rejected(with("sub", json!(""))?),
+            Error::InvalidClaim("sub")
+        ));
+        assert!(matches!(
+            rejected(with("auth_time", json!(1020))?),
+            Error::InvalidClaim("auth_time")
+        ));
+        let mut without_exp = claims.clone();
+        without_exp.as_object_mut().unwrap().remove("exp");
+        assert!(matches!(
+            rejected(sign(without_exp)?),
+            Error::MissingClaim("exp")
+        ));
+
+        // The right key, but not the algorithm of the provider
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        assert!(matches!(
+            rejected(claims.sign_with_key(&signer)?),
+            Error::AlgorithmNotAllowed(AlgorithmType::Hs256)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn google() -> Result<(), Error> {
+        let google = Google::new("web").client_id("android");
+        let verifier = google.verifier().with_clock(|| 1000);
+        with_keys(verifier.keys())?;
+
+        for iss in ["https://accounts.google.com", "accounts.google.com"] {
+            let token = sign(json!({"iss": iss, "aud": "android", "sub": "1", "exp": 2000}))?;
+            assert!(verifier.verify_token(&token).is_ok());
+        }
+        let token = sign(json!({"iss": "https://evil.example.com", "aud": "web", "exp": 2000}))?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::InvalidClaim("iss"))
+        ));
+        let token = sign(json!({"iss": "accounts.google.com", "aud": "ios", "exp": 2000}))?;
+        assert!(matches!(
+            verifier.verify_token(&token),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+//! Verification of the tokens of well known identity providers, enabled with
+//! the `jwks` feature. Each preset knows the JWKS endpoint of its provider,
+//! the algorithms it signs with and the claims it documents, beyond what a
+//! [Validation](../validation/struct.Validation.html) checks. It gives a
+//! [ProviderVerifier](struct.ProviderVerifier.html) for
+//! [BearerAuth](../bearer/struct.BearerAuth.html) and a
+//! [Refresher](../jwks/struct.Refresher.html) that keeps its keys up to date.
+//!
+//! The presets are in the modules of their providers, e.g.
+//! [google](google/index.html) for Firebase and Sign in with Google,
+//! [cognito](cognito/index.html) for Amazon Cognito and
+//! [entra](entra/index.html) for Microsoft Entra ID.
+//! ## Examples
+//! ```
+//! use jwt::bearer::BearerAuth;
+//! use jwt::preset::google::Firebase;
+//!
+//! let firebase = Firebase::new("my-project");
+//! let auth = BearerAuth::with_verifier(firebase.verifier());
+//! let refresher = firebase.refresher();
+//! // tokio::spawn(refresher.run());
+//! ```
+
+use std::sync::Arc;
+
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::algorithm::AlgorithmType;
+use crate::bearer::TokenVerifier;
+use crate::claims::SecondsSinceEpoch;
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::Header;
+use crate::jwks::JwksVerifier;
+use crate::token::Unverified;
+use crate::validation::Validation;
+use crate::Token;
+
+pub mod cognito;
+pub mod entra;
+pub mod google;
+
+type Check = Arc<dyn Fn(&Value, SecondsSinceEpoch) -> Result<(), Error> + Send + Sync>;
+
+/// Verifies the tokens of a provider with the keys of its JWKS endpoint, and
+/// checks their claims with its validation and the checks of the preset. It
+/// is cheap to clone, and clones share the keys.
+#[derive(Clone)]
+pub struct ProviderVerifier<C = SystemClock> {
+    keys: JwksVerifier,
+    algorithms: &'static [AlgorithmType],
+    validation: Validation,
+    check: Check,
+    clock: C,
+}
+
+impl ProviderVerifier {
+    pub(crate) fn new(
+        keys: JwksVerifier,
+        algorithms: &'static [AlgorithmType],
+        validation: Validation,
+        check: impl Fn(&Value, SecondsSinceEpoch) -> Result<(), Error> + Send + Sync + 'static,
+    ) -> Self {
+        ProviderVerifier {
+            keys,
+            algorithms,
+            validation,
+            check: Arc::new(check),
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<C: Clock> ProviderVerifier<C> {
+    /// Validate the time claims against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> ProviderVerifier<D> {
+        ProviderVerifier {
+            keys: self.keys,
+            algorithms: self.algorithms,
+            validation: self.validation,
+            check: self.check,
+            clock,
+        }
+    }
+
+    /// The keys, e.g. to bootstrap them before they are fetched.
+    pub fn keys(&self) -> &JwksVerifier {
+        &self.keys
+    }
+
+    pub fn validation(&self) -> &Validation {
+        &self.validation
+    }
+}
+
+impl<C: Clock + Send + Sync> TokenVerifier for ProviderVerifier<C> {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let unverified: Token<Header, IgnoredAny, Unverified> = Token::parse_unverified(token)?;
+        let algorithm = unverified.header().algorithm;
+        if !self.algorithms.contains(&algorithm) {
+            return Err(Error::AlgorithmNotAllowed(algorithm));
+        }
+
+        let claims = self.keys.verify_token(token)?;
+        let now = self.clock.now();
+        self.validation.validate(&claims, now)?;
+        (self.check)(&claims, now)?;
+        Ok(claims)
+    }
+}
+
+/// Check that a time claim is not later than `now`, e.g. `iat`.
+pub(crate) fn not_after(
+    claims: &Value,
+    name: &'static str,
+    now: SecondsSinceEpoch,
+) -> Result<(), Error> {
+    match claims.get(name).map(Value::as_u64) {
+        Some(Some(time)) if time <= now => Ok(()),
+        Some(_) => Err(Error::InvalidClaim(name)),
+        None => Err(Error::MissingClaim(name)),
+    }
+}
+
+#[cfg(all(test, feature = "openssl"))]
+mod tests {
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use serde_json::Value;
+
+    use crate::algorithm::any::AnyVerifier;
+    use crate::algorithm::openssl::PKeyWithDigest;
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::jwks::JwksVerifier;
+    use crate::keystore::KeySet;
+    use crate::token::signed::SignWithKey;
+    use crate::Token;
+
+    /// Sign like a provider, with RS256 and the key id `1`.
+    pub(crate) fn sign(claims: Value) -> Result<String, Error> {
+        let key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../../test/rs256-private.pem"))?,
+        };
+        let header = Header {
+            algorithm: AlgorithmType::Rs256,
+            key_id: Some("1".into()),
+            ..Default::default()
+        };
+        Ok(Token::new(header, claims).sign_with_key(&key)?.into())
+    }
+
+    /// Verify with the key of [sign](fn.sign.html).
+    pub(crate) fn with_keys(keys: &JwksVerifier) -> Result<(), Error> {
+        let key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::public_key_from_pem(include_bytes!("../../test/rs256-public.pem"))?,
+        };
+        let mut set = KeySet::default();
+        set.insert("1", AnyVerifier::new(key));
+        keys.replace(set);
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+//! Bindings for Python through [PyO3](https://pyo3.rs), enabled with the
+//! `python` feature. Build an extension module with
+//! [maturin](https://www.maturin.rs), e.g.
+//! `maturin build --features python,pyo3/extension-module`.
+//!
+//! Only HMAC keys are supported. Claims are passed as dictionaries and
+//! errors are raised as `jwt.JwtError`, with the message and the
+//! [code](../error/enum.ErrorKind.html#method.code) of the error as arguments.
+//! ## Examples
+//! ```python
+//! import jwt
+//!
+//! token = jwt.sign("HS256", b"secret", {"sub": "someone"})
+//! claims = jwt.verify("HS256", b"secret", token)
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::AlgorithmType;
+use crate::claims::{Claims, RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+use crate::Token;
+
+create_exception!(jwt, JwtError, PyException);
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> Self {
+        JwtError::new_err((error.to_string(), error.kind().code()))
+    }
+}
+
+/// Sign the claims with an HMAC key, e.g. with algorithm `"HS256"`.
+#[pyfunction]
+fn sign(algorithm: &str, key: &[u8], claims: &Bound<'_, PyDict>) -> PyResult<String> {
+    let algorithm_type: AlgorithmType = algorithm.parse()?;
+    let signer = AnySigner::hmac(algorithm_type, key)?;
+    let claims: Claims = from_dict(claims)?;
+    let header = Header {
+        algorithm: algorithm_type,
+        ..Default::default()
+    };
+
+    let token = Token::new(header, claims).sign_with_key(&signer)?;
+    Ok(token.as_str().to_owned())
+}
+
+/// Verify a token with an HMAC key and check the `exp` and `nbf` claims
+/// against the system time, or `now` if given.
+#[pyfunction]
+#[pyo3(signature = (algorithm, key, token, now=None))]
+fn verify<'py>(
+    py: Python<'py>,
+    algorithm: &str,
+    key: &[u8],
+    token: &str,
+    now: Option<SecondsSinceEpoch>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let algorithm_type: AlgorithmType = algorithm.parse()?;
+    let verifier = AnyVerifier::hmac(algorithm_type, key)?;
+    let token: Token<Header, Claims, _> = token.verify_with_key(&verifier)?;
+    let claims = token.claims();
+    claims
+        .registered
+        .check_time(now.unwrap_or_else(|| SystemClock.now()))?;
+
+    to_dict(py, claims)
+}
+
+/// Check the `exp` and `nbf` claims against the system time, or `now` if
+/// given.
+#[pyfunction]
+#[pyo3(signature = (claims, now=None))]
+fn check_time(claims: &Bound<'_, PyDict>, now: Option<SecondsSinceEpoch>) -> PyResult<()> {
+    let registered: RegisteredClaims = from_dict(claims)?;
+    registered.check_time(now.unwrap_or_else(|| SystemClock.now()))?;
+    Ok(())
+}
+
+/// Decode the claims of a token **without** verifying the signature.
+#[pyfunction]
+fn decode_claims<'py>(py: Python<'py>, token: &str) -> PyResult<Bound<'py, PyAny>> {
+    let token: Token<Header, Claims, _> = Token::parse_unverified(token)?;
+    to_dict(py, token.claims())
+}
+
+/// Convert through the `json` module, so that any JSON serializable object
+/// is accepted.
+fn from_dict<T: serde::de::DeserializeOwned>(dict: &Bound<'_, PyDict>) -> PyResult<T> {
+    let json: String = dict
+        .py()
+        .import("json")?
+        .call_method1("dumps", (dict,))?
+        .extract()?;
+    Ok(serde_json::from_str(&json).map_err(Error::Json)?)
+}
+
+fn to_dict<'py>(py: Python<'py>, claims: &Claims) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(claims).map_err(Error::Json)?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+#[pymodule]
+fn jwt(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("JwtError", m.py().get_type::<JwtError>())?;
+    m.add_function(wrap_pyfunction!(sign, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(check_time, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_claims, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    use crate::python::{check_time, decode_claims, sign, verify, JwtError};
+
+    #[test]
+    pub fn sign_and_verify() -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let claims = PyDict::new(py);
+            claims.set_item("sub", "someone")?;
+            claims.set_item("exp", 2_000_000_000u64)?;
+
+            let token = sign("HS256", b"secret", &claims)?;
+            let verified = verify(py, "HS256", b"secret", &token, None)?;
+            assert!(verified.eq(&claims)?);
+            assert!(decode_claims(py, &token)?.eq(&claims)?);
+
+            let error = verify(py, "HS256", b"secret", &token, Some(2_000_000_000)).unwrap_err();
+            assert!(error.is_instance_of::<JwtError>(py));
+            let (_, code): (String, String) = error.value(py).getattr("args")?.extract()?;
+            assert_eq!(code, "token_expired");
+
+            assert!(check_time(&claims, Some(1_000_000_000)).is_ok());
+            assert!(verify(py, "HS256", b"wrong", &token, None).is_err());
+            Ok(())
+        })
+    }
+}
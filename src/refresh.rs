@@ -0,0 +1,579 @@
+//! Issuing access tokens together with refresh tokens that are rotated on
+//! every use, enabled with the `refresh` feature. Refresh tokens are opaque
+//! random strings. Every refresh token belongs to the family of the login it
+//! descends from, and a refresh token that is used a second time means that
+//! it was stolen, so its whole family is revoked.
+//!
+//! The state lives in a [RefreshStore](trait.RefreshStore.html), which
+//! applications implement for their database.
+//! [MemoryStore](struct.MemoryStore.html) keeps it in memory, for tests and
+//! single instance deployments.
+//!
+//! A [PairIssuer](struct.PairIssuer.html) instead signs both tokens of a
+//! pair as JWTs, with the `typ` headers `at+jwt` and `refresh+jwt` so that
+//! one can not be used as the other, for deployments without a store. The
+//! `ati` claim of the refresh token is the `jti` of its access token. Such
+//! refresh tokens can not be rotated or revoked before they expire.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::refresh::{MemoryStore, RefreshError, RefreshManager};
+//! use jwt::AlgorithmType;
+//!
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let manager = RefreshManager::new(signer, MemoryStore::default());
+//!
+//! let login = manager.issue("someone").unwrap();
+//! let refreshed = manager.refresh(&login.refresh_token).unwrap();
+//! assert!(matches!(
+//!     manager.refresh(&login.refresh_token),
+//!     Err(RefreshError::Reused)
+//! ));
+//! // The replay revoked the tokens issued after it, too
+//! assert!(manager.refresh(&refreshed.refresh_token).is_err());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::algorithm::any::AnySigner;
+use crate::algorithm::SigningAlgorithm;
+use crate::claims::{Claims, RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::{Header, HeaderType};
+use crate::redact::Redaction;
+use crate::token::signed::SignWithKey;
+use crate::Token;
+
+/// Access tokens are valid for this many seconds by default.
+pub const DEFAULT_ACCESS_LIFETIME: SecondsSinceEpoch = 5 * 60;
+/// Refresh tokens are valid for this many seconds by default.
+pub const DEFAULT_REFRESH_LIFETIME: SecondsSinceEpoch = 30 * 24 * 60 * 60;
+
+/// The errors of a [RefreshStore](trait.RefreshStore.html).
+pub type StoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The state of a refresh token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefreshRecord {
+    /// The family of the login the token descends from.
+    pub family: String,
+    pub subject: String,
+    pub expiration: SecondsSinceEpoch,
+    pub used: bool,
+}
+
+/// Persists the state of refresh tokens. Tokens are identified by the
+/// SHA-256 hash of the token, so that the store never holds a usable token.
+pub trait RefreshStore: Send + Sync {
+    /// Store a new refresh token.
+    fn insert(&self, id: &str, record: RefreshRecord) -> Result<(), StoreError>;
+
+    /// Mark a refresh token as used and return its record from before. This
+    /// has to be atomic, so that concurrent requests can't both use a token.
+    fn mark_used(&self, id: &str) -> Result<Option<RefreshRecord>, StoreError>;
+
+    /// Remove all refresh tokens of a family.
+    fn revoke_family(&self, family: &str) -> Result<(), StoreError>;
+}
+
+/// A [RefreshStore](trait.RefreshStore.html) in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, RefreshRecord>>,
+}
+
+impl MemoryStore {
+    /// Forget the tokens that have expired by now.
+    pub fn remove_expired(&self, now: SecondsSinceEpoch) {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, record| record.expiration > now);
+    }
+}
+
+impl RefreshStore for MemoryStore {
+    fn insert(&self, id: &str, record: RefreshRecord) -> Result<(), StoreError> {
+        self.records.lock().unwrap().insert(id.to_owned(), record);
+        Ok(())
+    }
+
+    fn mark_used(&self, id: &str) -> Result<Option<RefreshRecord>, StoreError> {
+        let mut records = self.records.lock().unwrap();
+        Ok(records.get_mut(id).map(|record| {
+            let before = record.clone();
+            record.used = true;
+            before
+        }))
+    }
+
+    fn revoke_family(&self, family: &str) -> Result<(), StoreError> {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, record| record.family != family);
+        Ok(())
+    }
+}
+
+/// An access token and the refresh token to get the next one. `Debug` does
+/// not print the tokens.
+#[derive(Clone)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// The seconds until the access token expires.
+    pub expires_in: SecondsSinceEpoch,
+}
+
+impl fmt::Debug for TokenPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TokenPair")
+            .field("access_token", &Redaction)
+            .field("refresh_token", &Redaction)
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+/// Issues token pairs and rotates refresh tokens.
+pub struct RefreshManager<S, C = SystemClock> {
+    signer: AnySigner,
+    store: S,
+    clock: C,
+    issuer: Option<String>,
+    access_lifetime: SecondsSinceEpoch,
+    refresh_lifetime: SecondsSinceEpoch,
+}
+
+impl<S: RefreshStore> RefreshManager<S> {
+    pub fn new(signer: AnySigner, store: S) -> Self {
+        RefreshManager {
+            signer,
+            store,
+            clock: SystemClock,
+            issuer: None,
+            access_lifetime: DEFAULT_ACCESS_LIFETIME,
+            refresh_lifetime: DEFAULT_REFRESH_LIFETIME,
+        }
+    }
+}
+
+impl<S: RefreshStore, C: Clock> RefreshManager<S, C> {
+    /// Set the `iss` claim of access tokens.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    pub fn access_lifetime(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.access_lifetime = seconds;
+        self
+    }
+
+    pub fn refresh_lifetime(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.refresh_lifetime = seconds;
+        self
+    }
+
+    /// Issue tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> RefreshManager<S, D> {
+        RefreshManager {
+            signer: self.signer,
+            store: self.store,
+            clock,
+            issuer: self.issuer,
+            access_lifetime: self.access_lifetime,
+            refresh_lifetime: self.refresh_lifetime,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Issue the first token pair of a login.
+    pub fn issue(&self, subject: &str) -> Result<TokenPair, RefreshError> {
+        let family = random_token()?;
+        self.issue_in_family(subject, family)
+    }
+
+    /// Exchange a refresh token for a new token pair. A token that was used
+    /// before revokes its family.
+    pub fn refresh(&self, refresh_token: &str) -> Result<TokenPair, RefreshError> {
+        let record = self
+            .store
+            .mark_used(&token_id(refresh_token))
+            .map_err(RefreshError::Store)?
+            .ok_or(RefreshError::Unknown)?;
+        if record.used {
+            self.store
+                .revoke_family(&record.family)
+                .map_err(RefreshError::Store)?;
+            return Err(RefreshError::Reused);
+        }
+        if self.clock.now() >= record.expiration {
+            return Err(RefreshError::Expired);
+        }
+        self.issue_in_family(&record.subject, record.family)
+    }
+
+    /// Revoke the refresh token and all tokens of its family, e.g. on logout.
+    pub fn revoke(&self, refresh_token: &str) -> Result<(), RefreshError> {
+        let record = self
+            .store
+            .mark_used(&token_id(refresh_token))
+            .map_err(RefreshError::Store)?;
+        match record {
+            Some(record) => self
+                .store
+                .revoke_family(&record.family)
+                .map_err(RefreshError::Store),
+            None => Ok(()),
+        }
+    }
+
+    fn issue_in_family(&self, subject: &str, family: String) -> Result<TokenPair, RefreshError> {
+        let now = self.clock.now();
+        let claims = RegisteredClaims {
+            issuer: self.issuer.clone(),
+            subject: Some(subject.to_owned()),
+            expiration: Some(now.saturating_add(self.access_lifetime)),
+            issued_at: Some(now),
+            json_web_token_id: Some(random_token()?),
+            ..Default::default()
+        };
+        let access_token = claims
+            .sign_with_key(&self.signer)
+            .map_err(RefreshError::Jwt)?;
+
+        let refresh_token = random_token()?;
+        let record = RefreshRecord {
+            family,
+            subject: subject.to_owned(),
+            expiration: now.saturating_add(self.refresh_lifetime),
+            used: false,
+        };
+        self.store
+            .insert(&token_id(&refresh_token), record)
+            .map_err(RefreshError::Store)?;
+
+        Ok(TokenPair {
+            access_token,
+            refresh_token,
+            expires_in: self.access_lifetime,
+        })
+    }
+}
+
+/// The configuration of a [PairIssuer](struct.PairIssuer.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairConfig {
+    /// The `iss` claim of both tokens.
+    pub issuer: Option<String>,
+    /// The `aud` claim of access tokens. Refresh tokens are only for the
+    /// issuer and have none.
+    pub audience: Option<String>,
+    pub access_lifetime: SecondsSinceEpoch,
+    pub refresh_lifetime: SecondsSinceEpoch,
+}
+
+impl Default for PairConfig {
+    fn default() -> Self {
+        PairConfig {
+            issuer: None,
+            audience: None,
+            access_lifetime: DEFAULT_ACCESS_LIFETIME,
+            refresh_lifetime: DEFAULT_REFRESH_LIFETIME,
+        }
+    }
+}
+
+/// An access token and a refresh token signed by a
+/// [PairIssuer](struct.PairIssuer.html). `Debug` does not print the tokens.
+#[derive(Clone)]
+pub struct SignedTokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// The `jti` of the access token, and the `ati` of the refresh token.
+    pub access_token_id: String,
+    /// The `jti` of the refresh token.
+    pub refresh_token_id: String,
+    /// The seconds until the access token expires.
+    pub expires_in: SecondsSinceEpoch,
+}
+
+impl fmt::Debug for SignedTokenPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignedTokenPair")
+            .field("access_token", &Redaction)
+            .field("refresh_token", &Redaction)
+            .field("access_token_id", &self.access_token_id)
+            .field("refresh_token_id", &self.refresh_token_id)
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+/// Signs access tokens together with refresh tokens that are JWTs as well.
+pub struct PairIssuer<C = SystemClock> {
+    signer: AnySigner,
+    config: PairConfig,
+    clock: C,
+}
+
+impl PairIssuer {
+    pub fn new(signer: AnySigner, config: PairConfig) -> Self {
+        PairIssuer {
+            signer,
+            config,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<C: Clock> PairIssuer<C> {
+    /// Issue tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> PairIssuer<D> {
+        PairIssuer {
+            signer: self.signer,
+            config: self.config,
+            clock,
+        }
+    }
+
+    pub fn config(&self) -> &PairConfig {
+        &self.config
+    }
+
+    /// Sign a token pair for the subject.
+    pub fn issue(&self, subject: &str) -> Result<SignedTokenPair, RefreshError> {
+        self.issue_with(subject, BTreeMap::new())
+    }
+
+    /// Sign a token pair for the subject, with additional claims in the
+    /// access token, e.g. its `scope`.
+    pub fn issue_with(
+        &self,
+        subject: &str,
+        private: BTreeMap<String, Value>,
+    ) -> Result<SignedTokenPair, RefreshError> {
+        let now = self.clock.now();
+        let (access_token_id, refresh_token_id) = (random_token()?, random_token()?);
+        let registered = |id: &str, lifetime: SecondsSinceEpoch| RegisteredClaims {
+            issuer: self.config.issuer.clone(),
+            subject: Some(subject.to_owned()),
+            expiration: Some(now.saturating_add(lifetime)),
+            issued_at: Some(now),
+            json_web_token_id: Some(id.to_owned()),
+            ..Default::default()
+        };
+
+        let access = Claims {
+            registered: RegisteredClaims {
+                audience: self.config.audience.clone(),
+                ..registered(&access_token_id, self.config.access_lifetime)
+            },
+            private,
+        };
+        let mut refresh = Claims::new(registered(&refresh_token_id, self.config.refresh_lifetime));
+        refresh
+            .private
+            .insert("ati".into(), access_token_id.clone().into());
+
+        Ok(SignedTokenPair {
+            access_token: self.sign(HeaderType::AccessToken, access)?,
+            refresh_token: self.sign(HeaderType::RefreshToken, refresh)?,
+            access_token_id,
+            refresh_token_id,
+            expires_in: self.config.access_lifetime,
+        })
+    }
+
+    fn sign(&self, type_: HeaderType, claims: Claims) -> Result<String, RefreshError> {
+        let header = Header {
+            algorithm: self.signer.algorithm_type(),
+            type_: Some(type_),
+            ..Default::default()
+        };
+        let token = Token::new(header, claims)
+            .sign_with_key(&self.signer)
+            .map_err(RefreshError::Jwt)?;
+        Ok(token.into())
+    }
+}
+
+/// 256 random bits, base64url encoded.
+fn random_token() -> Result<String, RefreshError> {
+    let mut bytes = [0; 32];
+    getrandom::getrandom(&mut bytes).map_err(RefreshError::Random)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn token_id(refresh_token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(refresh_token.as_bytes()))
+}
+
+/// The reason a refresh token was not exchanged.
+#[derive(Debug)]
+pub enum RefreshError {
+    /// The token was never issued, or its family was revoked.
+    Unknown,
+    /// The token was used before, and its family is now revoked.
+    Reused,
+    Expired,
+    Store(StoreError),
+    /// The system has no randomness to create tokens from.
+    Random(getrandom::Error),
+    Jwt(Error),
+}
+
+impl fmt::Display for RefreshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RefreshError::Unknown => write!(f, "Unknown refresh token"),
+            RefreshError::Reused => write!(f, "Refresh token was used before"),
+            RefreshError::Expired => write!(f, "Refresh token expired"),
+            RefreshError::Store(ref e) => write!(f, "Refresh token store failed: {}", e),
+            RefreshError::Random(ref e) => write!(f, "Could not create a random token: {}", e),
+            RefreshError::Jwt(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RefreshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            RefreshError::Store(ref e) => Some(e.as_ref()),
+            RefreshError::Random(ref e) => Some(e),
+            RefreshError::Jwt(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::claims::Claims;
+    use crate::error::Error;
+    use crate::header::{Header, HeaderType};
+    use crate::refresh::{MemoryStore, PairConfig, PairIssuer, RefreshError, RefreshManager};
+    use crate::token::verified::VerifyWithKey;
+    use crate::{RegisteredClaims, Token};
+
+    #[test]
+    pub fn rotation() -> Result<(), Error> {
+        let now = Arc::new(AtomicU64::new(1000));
+        let clock = Arc::clone(&now);
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let manager = RefreshManager::new(signer, MemoryStore::default())
+            .issuer("auth")
+            .access_lifetime(60)
+            .refresh_lifetime(600)
+            .with_clock(move || clock.load(Ordering::SeqCst));
+
+        let login = manager.issue("someone").unwrap();
+        assert_eq!(login.expires_in, 60);
+        let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?;
+        let claims: RegisteredClaims = login.access_token.as_str().verify_with_key(&verifier)?;
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+        assert_eq!(claims.issuer.as_deref(), Some("auth"));
+        assert_eq!(claims.expiration, Some(1060));
+
+        let first = manager.refresh(&login.refresh_token).unwrap();
+        assert_ne!(first.refresh_token, login.refresh_token);
+        let second = manager.refresh(&first.refresh_token).unwrap();
+
+        assert!(matches!(
+            manager.refresh(&first.refresh_token),
+            Err(RefreshError::Reused)
+        ));
+        assert!(matches!(
+            manager.refresh(&second.refresh_token),
+            Err(RefreshError::Unknown)
+        ));
+
+        let other = manager.issue("someone").unwrap();
+        now.store(1600, Ordering::SeqCst);
+        assert!(matches!(
+            manager.refresh(&other.refresh_token),
+            Err(RefreshError::Expired)
+        ));
+        manager.store().remove_expired(1600);
+        assert!(matches!(
+            manager.refresh(&other.refresh_token),
+            Err(RefreshError::Unknown)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn revoke() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let manager = RefreshManager::new(signer, MemoryStore::default());
+        let login = manager.issue("someone").unwrap();
+        let refreshed = manager.refresh(&login.refresh_token).unwrap();
+
+        manager.revoke(&refreshed.refresh_token).unwrap();
+        assert!(matches!(
+            manager.refresh(&refreshed.refresh_token),
+            Err(RefreshError::Unknown)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn signed_pair() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let config = PairConfig {
+            issuer: Some("auth".into()),
+            audience: Some("api".into()),
+            access_lifetime: 60,
+            refresh_lifetime: 600,
+        };
+        let issuer = PairIssuer::new(signer, config).with_clock(|| 1000);
+        let mut scope = std::collections::BTreeMap::new();
+        scope.insert("scope".into(), "read".into());
+        let pair = issuer.issue_with("someone", scope).unwrap();
+        assert_eq!(pair.expires_in, 60);
+        assert_ne!(pair.access_token_id, pair.refresh_token_id);
+
+        let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?;
+        let access: Token<Header, Claims, _> =
+            pair.access_token.as_str().verify_with_key(&verifier)?;
+        assert_eq!(access.header().type_, Some(HeaderType::AccessToken));
+        let claims = &access.claims().registered;
+        assert_eq!(
+            claims.json_web_token_id.as_ref(),
+            Some(&pair.access_token_id)
+        );
+        assert_eq!(claims.audience.as_deref(), Some("api"));
+        assert_eq!(claims.expiration, Some(1060));
+        assert_eq!(access.claims().private["scope"], "read");
+
+        let refresh: Token<Header, Claims, _> =
+            pair.refresh_token.as_str().verify_with_key(&verifier)?;
+        assert_eq!(refresh.header().type_, Some(HeaderType::RefreshToken));
+        let claims = &refresh.claims().registered;
+        assert_eq!(
+            claims.json_web_token_id.as_ref(),
+            Some(&pair.refresh_token_id)
+        );
+        assert_eq!(claims.issuer.as_deref(), Some("auth"));
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+        assert_eq!(claims.audience, None);
+        assert_eq!(claims.expiration, Some(1600));
+        assert_eq!(refresh.claims().private["ati"], *pair.access_token_id);
+        assert!(!refresh.claims().private.contains_key("scope"));
+        Ok(())
+    }
+}
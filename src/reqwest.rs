@@ -0,0 +1,221 @@
+//! A [reqwest-middleware](https://docs.rs/reqwest-middleware) component,
+//! enabled with the `reqwest` feature, that attaches a bearer token to
+//! outgoing requests. Tokens come from a [TokenProvider](trait.TokenProvider.html)
+//! and are reused until they are about to expire, or until a request is
+//! answered with `401 Unauthorized`.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnySigner;
+//! use jwt::reqwest::BearerMiddleware;
+//! use jwt::{AlgorithmType, RegisteredClaims, SignWithKey};
+//!
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let mint = move || {
+//!     let claims = RegisteredClaims {
+//!         subject: Some("service".into()),
+//!         expiration: Some(jwt::clock::Clock::now(&jwt::clock::SystemClock) + 300),
+//!         ..Default::default()
+//!     };
+//!     let token = claims.sign_with_key(&signer).map_err(reqwest_middleware::Error::middleware);
+//!     async move { token }
+//! };
+//! let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+//!     .with(BearerMiddleware::new(mint))
+//!     .build();
+//! ```
+
+use std::future::Future;
+use std::sync::Mutex;
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use serde_json::Value;
+
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::token::Unverified;
+use crate::Token;
+
+/// Tokens are refreshed this many seconds before they expire by default.
+pub const DEFAULT_LEEWAY: SecondsSinceEpoch = 30;
+
+/// Mints or fetches a new token. It is implemented for closures returning a
+/// future.
+pub trait TokenProvider: Send + Sync + 'static {
+    fn token(&self) -> impl Future<Output = Result<String>> + Send;
+}
+
+impl<F, Fut> TokenProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String>> + Send,
+{
+    fn token(&self) -> impl Future<Output = Result<String>> + Send {
+        self()
+    }
+}
+
+struct CachedToken {
+    authorization: HeaderValue,
+    expiration: Option<SecondsSinceEpoch>,
+}
+
+/// Attaches the token of the provider to the `Authorization` header of
+/// requests. Concurrent requests that find the token expired may each ask
+/// the provider for a new one.
+pub struct BearerMiddleware<P, C = SystemClock> {
+    provider: P,
+    clock: C,
+    leeway: SecondsSinceEpoch,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl<P: TokenProvider> BearerMiddleware<P> {
+    pub fn new(provider: P) -> Self {
+        BearerMiddleware {
+            provider,
+            clock: SystemClock,
+            leeway: DEFAULT_LEEWAY,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl<P: TokenProvider, C: Clock> BearerMiddleware<P, C> {
+    /// Refresh tokens this many seconds before they expire, instead of
+    /// [DEFAULT_LEEWAY](constant.DEFAULT_LEEWAY.html).
+    pub fn with_leeway(mut self, leeway: SecondsSinceEpoch) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Check the expiration of tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> BearerMiddleware<P, D> {
+        BearerMiddleware {
+            provider: self.provider,
+            clock,
+            leeway: self.leeway,
+            cached: self.cached,
+        }
+    }
+
+    /// Forget the current token, so that the next request gets a new one.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+
+    async fn authorization(&self) -> Result<HeaderValue> {
+        let deadline = self.clock.now().saturating_add(self.leeway);
+        if let Some(ref cached) = *self.cached.lock().unwrap() {
+            if cached
+                .expiration
+                .is_none_or(|expiration| deadline < expiration)
+            {
+                return Ok(cached.authorization.clone());
+            }
+        }
+
+        let token = self.provider.token().await?;
+        let parsed: Token<Value, RegisteredClaims, Unverified> =
+            Token::parse_unverified(&token).map_err(Error::middleware)?;
+        let mut authorization =
+            HeaderValue::from_str(&format!("Bearer {}", token)).map_err(Error::middleware)?;
+        authorization.set_sensitive(true);
+
+        *self.cached.lock().unwrap() = Some(CachedToken {
+            authorization: authorization.clone(),
+            expiration: parsed.claims().expiration,
+        });
+        Ok(authorization)
+    }
+}
+
+#[async_trait::async_trait]
+impl<P, C> Middleware for BearerMiddleware<P, C>
+where
+    P: TokenProvider,
+    C: Clock + Send + Sync + 'static,
+{
+    async fn handle(
+        &self,
+        mut request: Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let authorization = self.authorization().await?;
+        request.headers_mut().insert(AUTHORIZATION, authorization);
+        let response = next.run(request, extensions).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.invalidate();
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    use crate::algorithm::any::AnySigner;
+    use crate::algorithm::AlgorithmType;
+    use crate::claims::RegisteredClaims;
+    use crate::error::Error;
+    use crate::reqwest::BearerMiddleware;
+    use crate::token::signed::SignWithKey;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    pub fn refresh_near_expiry() -> Result<(), Error> {
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let minted = Arc::new(AtomicU64::new(0));
+        let now = Arc::new(AtomicU64::new(1000));
+
+        let counter = Arc::clone(&minted);
+        let provider = move || {
+            let serial = counter.fetch_add(1, Ordering::SeqCst);
+            let claims = RegisteredClaims {
+                json_web_token_id: Some(serial.to_string()),
+                expiration: Some(1100),
+                ..Default::default()
+            };
+            let token = claims
+                .sign_with_key(&signer)
+                .map_err(reqwest_middleware::Error::middleware);
+            async move { token }
+        };
+        let clock = Arc::clone(&now);
+        let middleware = BearerMiddleware::new(provider)
+            .with_leeway(10)
+            .with_clock(move || clock.load(Ordering::SeqCst));
+
+        let first = block_on(middleware.authorization()).unwrap();
+        now.store(1089, Ordering::SeqCst);
+        assert_eq!(block_on(middleware.authorization()).unwrap(), first);
+        assert_eq!(minted.load(Ordering::SeqCst), 1);
+
+        now.store(1090, Ordering::SeqCst);
+        let second = block_on(middleware.authorization()).unwrap();
+        assert_ne!(second, first);
+        assert!(second.is_sensitive());
+        assert_eq!(minted.load(Ordering::SeqCst), 2);
+
+        middleware.invalidate();
+        block_on(middleware.authorization()).unwrap();
+        assert_eq!(minted.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Integration with [Rocket](https://rocket.rs), enabled with the `rocket`
+//! feature. The [Claims](struct.Claims.html) request guard verifies the
+//! bearer token with the [BearerAuth](../bearer/struct.BearerAuth.html)
+//! managed by Rocket. Register the [catchers](fn.catchers.html) to send the
+//! `WWW-Authenticate` header with rejections.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::rocket::{catchers, Claims};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! #[rocket::get("/whoami")]
+//! fn whoami(claims: Claims<RegisteredClaims>) -> String {
+//!     claims.0.subject.unwrap_or_default()
+//! }
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let rocket = rocket::build()
+//!     .manage(auth)
+//!     .mount("/", rocket::routes![whoami])
+//!     .register("/", catchers());
+//! ```
+
+use rocket::catcher::{BoxFuture, Catcher};
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::Response;
+use serde::de::DeserializeOwned;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::source::RequestParts;
+
+/// A request guard for the verified claims of the bearer token.
+#[derive(Clone, Debug)]
+pub struct Claims<T = crate::Claims>(pub T);
+
+/// The `WWW-Authenticate` header of a rejected request, for the catchers.
+struct Challenge(Option<String>);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromRequest<'r> for Claims<T> {
+    type Error = Rejection;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Rejection> {
+        let claims = match request.rocket().state::<BearerAuth>() {
+            Some(auth) => auth.authenticate_request(&RocketParts(request)),
+            None => Err(Rejection::Unconfigured),
+        };
+
+        match claims {
+            Ok(claims) => Outcome::Success(Claims(claims)),
+            Err(rejection) => {
+                request.local_cache(|| Challenge(rejection.www_authenticate()));
+                let status =
+                    Status::from_code(rejection.status()).unwrap_or(Status::InternalServerError);
+                Outcome::Error((status, rejection))
+            }
+        }
+    }
+}
+
+struct RocketParts<'a, 'r>(&'a Request<'r>);
+
+impl RequestParts for RocketParts<'_, '_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        Ok(self.0.headers().get_one(name))
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.0.uri().query().map(|query| query.as_str())
+    }
+}
+
+/// Catchers for `400 Bad Request`, `401 Unauthorized` and `403 Forbidden`
+/// that add the `WWW-Authenticate` header of rejections by the
+/// [Claims](struct.Claims.html) guard.
+pub fn catchers() -> Vec<Catcher> {
+    [400, 401, 403]
+        .iter()
+        .map(|&code| Catcher::new(code, challenge))
+        .collect()
+}
+
+fn challenge<'r>(status: Status, request: &'r Request<'_>) -> BoxFuture<'r> {
+    let mut response = Response::build().status(status).finalize();
+    if let Challenge(Some(challenge)) = request.local_cache(|| Challenge(None)) {
+        response.set_header(Header::new("WWW-Authenticate", challenge.clone()));
+    }
+    Box::pin(async move { Ok(response) })
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::{Header, Status};
+    use rocket::local::blocking::Client;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::rocket::{catchers, Claims};
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    #[rocket::get("/whoami")]
+    fn whoami(claims: Claims<RegisteredClaims>) -> String {
+        claims.0.subject.unwrap_or_default()
+    }
+
+    #[test]
+    pub fn request_guard() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let rocket = rocket::build()
+            .manage(auth)
+            .mount("/", rocket::routes![whoami])
+            .register("/", catchers());
+        let client = Client::tracked(rocket).unwrap();
+
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let response = client
+            .get("/whoami")
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().as_deref(), Some("someone"));
+
+        let response = client
+            .get("/whoami")
+            .header(Header::new("Authorization", "Bearer a.b.c"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(
+            response.headers().get_one("WWW-Authenticate"),
+            Some(r#"Bearer error="invalid_token", error_description="malformed_header""#)
+        );
+        Ok(())
+    }
+}
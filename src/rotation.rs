@@ -0,0 +1,234 @@
+//! Rotating signing keys without rejecting the tokens that are still in
+//! flight. A [RotatingSigner](struct.RotatingSigner.html) signs with its
+//! current key and puts the key id into the `kid` header. When a new key is
+//! promoted, the previous key keeps verifying tokens for a grace period,
+//! which should be at least the lifetime of the tokens.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnySigner, AnyVerifier};
+//! use jwt::bearer::TokenVerifier;
+//! use jwt::rotation::RotatingSigner;
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! let key = |secret: &[u8]| {
+//!     let signer = AnySigner::hmac(AlgorithmType::Hs256, secret).unwrap();
+//!     let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, secret).unwrap();
+//!     (signer, verifier)
+//! };
+//! let (signer, verifier) = key(b"first");
+//! let keys = RotatingSigner::new("2024-01", signer, verifier).grace_period(3600);
+//! let token = keys.sign(RegisteredClaims::default()).unwrap();
+//!
+//! let (signer, verifier) = key(b"second");
+//! keys.promote("2024-02", signer, verifier);
+//! assert!(keys.verify_token(&token).is_ok());
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use serde_json::Value;
+
+use crate::algorithm::any::{AnySigner, AnyVerifier};
+use crate::algorithm::SigningAlgorithm;
+use crate::bearer::TokenVerifier;
+use crate::claims::SecondsSinceEpoch;
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+use crate::token::Unverified;
+use crate::{JoseHeader, ToBase64, Token};
+
+/// Previous keys verify tokens for this many seconds by default.
+pub const DEFAULT_GRACE_PERIOD: SecondsSinceEpoch = 24 * 60 * 60;
+
+struct Current {
+    key_id: String,
+    signer: AnySigner,
+    verifier: AnyVerifier,
+}
+
+struct Retired {
+    key_id: String,
+    verifier: AnyVerifier,
+    until: SecondsSinceEpoch,
+}
+
+struct Keys {
+    current: Current,
+    retired: Vec<Retired>,
+}
+
+/// Signs with the current key and verifies with the current and the recently
+/// retired keys. Clones share the keys.
+#[derive(Clone)]
+pub struct RotatingSigner<C = SystemClock> {
+    keys: Arc<RwLock<Keys>>,
+    grace_period: SecondsSinceEpoch,
+    clock: C,
+}
+
+impl RotatingSigner {
+    /// Start with a key and its id. The signer and the verifier must belong
+    /// to the same key.
+    pub fn new(key_id: impl Into<String>, signer: AnySigner, verifier: AnyVerifier) -> Self {
+        let current = Current {
+            key_id: key_id.into(),
+            signer,
+            verifier,
+        };
+        RotatingSigner {
+            keys: Arc::new(RwLock::new(Keys {
+                current,
+                retired: Vec::new(),
+            })),
+            grace_period: DEFAULT_GRACE_PERIOD,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<C: Clock> RotatingSigner<C> {
+    /// Keep verifying with a key for this many seconds after it was replaced.
+    pub fn grace_period(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.grace_period = seconds;
+        self
+    }
+
+    /// Measure the grace period with another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> RotatingSigner<D> {
+        RotatingSigner {
+            keys: self.keys,
+            grace_period: self.grace_period,
+            clock,
+        }
+    }
+
+    /// Sign with a new key from now on. The key that was current until now
+    /// is retired for the grace period. Promoting a key id that is retired
+    /// replaces the retired key.
+    pub fn promote(&self, key_id: impl Into<String>, signer: AnySigner, verifier: AnyVerifier) {
+        let now = self.clock.now();
+        let key_id = key_id.into();
+        let mut keys = self.keys.write().unwrap();
+
+        let current = Current {
+            key_id,
+            signer,
+            verifier,
+        };
+        let previous = std::mem::replace(&mut keys.current, current);
+        let current_id = keys.current.key_id.clone();
+        keys.retired
+            .retain(|retired| retired.until > now && retired.key_id != current_id);
+        if previous.key_id != current_id {
+            keys.retired.push(Retired {
+                key_id: previous.key_id,
+                verifier: previous.verifier,
+                until: now.saturating_add(self.grace_period),
+            });
+        }
+    }
+
+    /// The id of the key that tokens are signed with.
+    pub fn current_key_id(&self) -> String {
+        self.keys.read().unwrap().current.key_id.clone()
+    }
+
+    /// The ids of the keys that tokens are verified with, the current one
+    /// first.
+    pub fn key_ids(&self) -> Vec<String> {
+        let now = self.clock.now();
+        let keys = self.keys.read().unwrap();
+        let retired = keys.retired.iter().filter(|retired| retired.until > now);
+        std::iter::once(keys.current.key_id.clone())
+            .chain(retired.map(|retired| retired.key_id.clone()))
+            .collect()
+    }
+
+    /// Sign claims with the current key.
+    pub fn sign<T: ToBase64>(&self, claims: T) -> Result<String, Error> {
+        let keys = self.keys.read().unwrap();
+        let header = Header {
+            algorithm: keys.current.signer.algorithm_type(),
+            key_id: Some(keys.current.key_id.clone()),
+            ..Default::default()
+        };
+        let token = Token::new(header, claims).sign_with_key(&keys.current.signer)?;
+        Ok(token.into())
+    }
+}
+
+impl<C: Clock + Send + Sync> TokenVerifier for RotatingSigner<C> {
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let unverified: Token<Header, Value, Unverified> = Token::parse_unverified(token)?;
+        let key_id = unverified.header().key_id().ok_or(Error::NoKeyId)?;
+
+        let now = self.clock.now();
+        let keys = self.keys.read().unwrap();
+        let verifier = if key_id == keys.current.key_id {
+            &keys.current.verifier
+        } else {
+            keys.retired
+                .iter()
+                .find(|retired| retired.key_id == key_id && retired.until > now)
+                .map(|retired| &retired.verifier)
+                .ok_or_else(|| Error::NoKeyWithKeyId(key_id.to_owned()))?
+        };
+
+        let (_, claims) = unverified.verify_with_key(verifier)?.into();
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::rotation::RotatingSigner;
+    use crate::{Header, RegisteredClaims, Token};
+
+    fn key(secret: &[u8]) -> Result<(AnySigner, AnyVerifier), Error> {
+        Ok((
+            AnySigner::hmac(AlgorithmType::Hs256, secret)?,
+            AnyVerifier::hmac(AlgorithmType::Hs256, secret)?,
+        ))
+    }
+
+    #[test]
+    pub fn rotation() -> Result<(), Error> {
+        let now = Arc::new(AtomicU64::new(1000));
+        let clock = Arc::clone(&now);
+        let (signer, verifier) = key(b"first")?;
+        let keys = RotatingSigner::new("first", signer, verifier)
+            .grace_period(100)
+            .with_clock(move || clock.load(Ordering::SeqCst));
+
+        let first = keys.sign(RegisteredClaims::default())?;
+        let parsed: Token<Header, RegisteredClaims, _> = Token::parse_unverified(&first)?;
+        assert_eq!(parsed.header().key_id.as_deref(), Some("first"));
+
+        let (signer, verifier) = key(b"second")?;
+        keys.promote("second", signer, verifier);
+        assert_eq!(keys.current_key_id(), "second");
+        assert_eq!(keys.key_ids(), ["second", "first"]);
+        let second = keys.sign(RegisteredClaims::default())?;
+        assert!(keys.verify_token(&first).is_ok());
+        assert!(keys.verify_token(&second).is_ok());
+
+        now.store(1100, Ordering::SeqCst);
+        assert!(matches!(
+            keys.verify_token(&first),
+            Err(Error::NoKeyWithKeyId(_))
+        ));
+        assert!(keys.verify_token(&second).is_ok());
+        assert_eq!(keys.key_ids(), ["second"]);
+        Ok(())
+    }
+}
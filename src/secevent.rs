@@ -0,0 +1,313 @@
+//! [Security Event Tokens](https://tools.ietf.org/html/rfc8417), e.g. the
+//! CAEP and RISC events that identity providers and relying parties exchange
+//! about sessions and accounts.
+//!
+//! A SET is signed with the `typ` header `secevent+jwt`, so that it can not
+//! be mistaken for an access or ID token, and verifying one rejects tokens
+//! without it. Its claims must contain `iss`, `iat`, `jti` and `events`, an
+//! object of at least one event, each an object of its own keyed by the URI
+//! of its type. `exp` is optional and checked by the
+//! [Validation](../validation/struct.Validation.html) if present.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, KeyInit};
+//! use jwt::clock::SystemClock;
+//! use jwt::secevent::SecurityEventClaims;
+//! use jwt::validation::Validation;
+//! use serde_json::json;
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+//! let claims = SecurityEventClaims::new("https://idp.example.com", "756E69717565", 1_700_000_000)
+//!     .event(
+//!         "https://schemas.openid.net/secevent/caep/event-type/session-revoked",
+//!         json!({"subject": {"format": "opaque", "id": "dMTlD|1600802906337.16|16008.16"}}),
+//!     );
+//! let token = claims.sign(&key).unwrap();
+//!
+//! let validation = Validation::new().issuer("https://idp.example.com");
+//! let received = SecurityEventClaims::verify_with(&token, &key, &validation, &SystemClock).unwrap();
+//! assert_eq!(received, claims);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+use crate::claims::{JwtClaims, SecondsSinceEpoch};
+use crate::clock::Clock;
+use crate::error::Error;
+use crate::header::{Header, HeaderType};
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+use crate::validation::Validation;
+use crate::Token;
+
+/// The claims of a Security Event Token.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecurityEventClaims {
+    #[serde(rename = "iss")]
+    pub issuer: String,
+
+    #[serde(rename = "iat")]
+    pub issued_at: SecondsSinceEpoch,
+
+    #[serde(rename = "jti")]
+    pub json_web_token_id: String,
+
+    /// One or more audiences, serialized as a string if there is one.
+    #[serde(
+        rename = "aud",
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "audience"
+    )]
+    pub audience: Vec<String>,
+
+    #[serde(rename = "sub", skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    #[serde(rename = "exp", skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<SecondsSinceEpoch>,
+
+    /// When the events happened, if not when the token was issued.
+    #[serde(rename = "toe", skip_serializing_if = "Option::is_none")]
+    pub time_of_event: Option<SecondsSinceEpoch>,
+
+    /// Relates the tokens of one transaction at the issuer.
+    #[serde(rename = "txn", skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+
+    /// The payloads of the events by the URIs of their types.
+    pub events: BTreeMap<String, Value>,
+}
+
+impl SecurityEventClaims {
+    /// Claims without events yet. Add at least one with
+    /// [event](#method.event).
+    pub fn new(
+        issuer: impl Into<String>,
+        json_web_token_id: impl Into<String>,
+        issued_at: SecondsSinceEpoch,
+    ) -> Self {
+        SecurityEventClaims {
+            issuer: issuer.into(),
+            json_web_token_id: json_web_token_id.into(),
+            issued_at,
+            ..Default::default()
+        }
+    }
+
+    /// Add an event. The payload must be a JSON object, `{}` for events
+    /// without one.
+    pub fn event(mut self, event_type: impl Into<String>, payload: Value) -> Self {
+        self.events.insert(event_type.into(), payload);
+        self
+    }
+
+    /// Sign the claims with the `typ` header of SETs.
+    pub fn sign(&self, key: &impl SigningAlgorithm) -> Result<String, Error> {
+        validate(&serde_json::to_value(self)?)?;
+        let header = Header {
+            algorithm: key.algorithm_type(),
+            type_: Some(HeaderType::SecurityEvent),
+            ..Default::default()
+        };
+        let token = Token::new(header, self).sign_with_key(key)?;
+        Ok(token.into())
+    }
+
+    /// Verify a SET and check its claims with the validation, at the time of
+    /// a clock.
+    pub fn verify_with(
+        token: &str,
+        key: &impl VerifyingAlgorithm,
+        validation: &Validation,
+        clock: &impl Clock,
+    ) -> Result<Self, Error> {
+        let verified: Token<Header, Value, _> = token.verify_with_key(key)?;
+        if verified.header().type_ != Some(HeaderType::SecurityEvent) {
+            return Err(Error::InvalidTokenType);
+        }
+        let (_, claims) = verified.into();
+        validate(&claims)?;
+        validation.validate_with(&claims, clock)?;
+        Ok(serde_json::from_value(claims)?)
+    }
+}
+
+impl JwtClaims for SecurityEventClaims {
+    fn issuer(&self) -> Option<&str> {
+        Some(&self.issuer)
+    }
+
+    fn audience(&self) -> &[String] {
+        &self.audience
+    }
+
+    fn expiration(&self) -> Option<SecondsSinceEpoch> {
+        self.expiration
+    }
+
+    fn jwt_id(&self) -> Option<&str> {
+        Some(&self.json_web_token_id)
+    }
+}
+
+/// Check the claims that RFC 8417 requires.
+fn validate(claims: &Value) -> Result<(), Error> {
+    for name in ["iss", "jti"] {
+        match claims.get(name) {
+            Some(Value::String(_)) => {}
+            Some(_) => return Err(Error::InvalidClaim(name)),
+            None => return Err(Error::MissingClaim(name)),
+        }
+    }
+    match claims.get("iat") {
+        Some(iat) if iat.is_u64() => {}
+        Some(_) => return Err(Error::InvalidClaim("iat")),
+        None => return Err(Error::MissingClaim("iat")),
+    }
+    match claims.get("events") {
+        Some(Value::Object(events))
+            if !events.is_empty() && events.values().all(Value::is_object) => {}
+        Some(_) => return Err(Error::InvalidClaim("events")),
+        None => return Err(Error::MissingClaim("events")),
+    }
+    Ok(())
+}
+
+mod audience {
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub fn serialize<S: Serializer>(audience: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+        match audience {
+            [one] => serializer.serialize_str(one),
+            many => many.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<String>, D::Error> {
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(one) => vec![one],
+            OneOrMany::Many(many) => many,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use serde_json::{json, Value};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::secevent::SecurityEventClaims;
+    use crate::token::signed::SignWithKey;
+    use crate::validation::Validation;
+    use crate::Token;
+
+    const RISC: &str = "https://schemas.openid.net/secevent/risc/event-type/account-disabled";
+
+    fn key() -> Result<Hmac<Sha256>, Error> {
+        Ok(Hmac::new_from_slice(b"secret")?)
+    }
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let mut claims = SecurityEventClaims::new("issuer", "1", 1000)
+            .event(RISC, json!({"reason": "hijacking"}));
+        claims.audience = vec!["receiver".into()];
+        let token = claims.sign(&key()?)?;
+
+        let unverified: Token<Header, Value, _> = Token::parse_unverified(&token)?;
+        assert_eq!(
+            unverified.raw_header(),
+            "eyJhbGciOiJIUzI1NiIsInR5cCI6InNlY2V2ZW50K2p3dCJ9"
+        );
+        assert_eq!(unverified.claims()["aud"], "receiver");
+        assert!(unverified.claims().get("exp").is_none());
+
+        let validation = Validation::new().audience("receiver");
+        let received = SecurityEventClaims::verify_with(&token, &key()?, &validation, &|| 2000)?;
+        assert_eq!(received, claims);
+
+        claims.expiration = Some(1500);
+        let token = claims.sign(&key()?)?;
+        assert!(matches!(
+            SecurityEventClaims::verify_with(&token, &key()?, &validation, &|| 2000),
+            Err(Error::Expired { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn required_claims() -> Result<(), Error> {
+        let verify = |token: &str| {
+            SecurityEventClaims::verify_with(token, &key()?, &Validation::new(), &|| 2000)
+        };
+
+        // An ordinary token with the same claims
+        let claims = json!({"iss": "issuer", "iat": 1000, "jti": "1", "events": {RISC: {}}});
+        let token = (&claims).sign_with_key(&key()?)?;
+        assert!(matches!(verify(&token), Err(Error::InvalidTokenType)));
+
+        let set = |claims: Value| -> Result<String, Error> {
+            let header = Header {
+                type_: Some(crate::header::HeaderType::SecurityEvent),
+                ..Default::default()
+            };
+            Ok(Token::new(header, claims).sign_with_key(&key()?)?.into())
+        };
+        assert!(verify(&set(claims.clone())?).is_ok());
+        let without_iat = json!({"iss": "issuer", "jti": "1", "events": {RISC: {}}});
+        assert!(matches!(
+            verify(&set(without_iat)?),
+            Err(Error::MissingClaim("iat"))
+        ));
+        let no_events = json!({"iss": "issuer", "iat": 1000, "jti": "1", "events": {}});
+        assert!(matches!(
+            verify(&set(no_events)?),
+            Err(Error::InvalidClaim("events"))
+        ));
+        let not_object = json!({"iss": "issuer", "iat": 1000, "jti": "1", "events": {RISC: true}});
+        assert!(matches!(
+            verify(&set(not_object)?),
+            Err(Error::InvalidClaim("events"))
+        ));
+
+        assert!(matches!(
+            SecurityEventClaims::new("issuer", "1", 1000).sign(&key()?),
+            Err(Error::InvalidClaim("events"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn audiences() -> Result<(), Error> {
+        let claims: SecurityEventClaims = serde_json::from_value(json!({
+            "iss": "issuer", "iat": 1, "jti": "1", "aud": ["a", "b"], "events": {RISC: {}}
+        }))?;
+        assert_eq!(claims.audience, ["a", "b"]);
+        assert_eq!(serde_json::to_value(&claims)?["aud"], json!(["a", "b"]));
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+//! Sessions that tokens belong to through their `sid` claim, enabled with the
+//! `session` feature, so that tokens can be invalidated before they expire:
+//! ending a session rejects all of its tokens, and all sessions of a subject
+//! can be ended at once, e.g. after a password change.
+//!
+//! A [SessionManager](struct.SessionManager.html) starts sessions and issues
+//! their tokens, and a [SessionVerifier](struct.SessionVerifier.html) checks
+//! that the session of a verified token is still active. Both use a
+//! [SessionStore](trait.SessionStore.html), which applications implement for
+//! their database. [MemoryStore](struct.MemoryStore.html) keeps sessions in
+//! memory, for tests and single instance deployments.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnySigner, AnyVerifier, SharedVerifier};
+//! use jwt::bearer::TokenVerifier;
+//! use jwt::session::{MemoryStore, SessionManager, SessionVerifier};
+//! use jwt::AlgorithmType;
+//! use std::sync::Arc;
+//!
+//! let store = Arc::new(MemoryStore::default());
+//! let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let manager = SessionManager::new(signer, Arc::clone(&store));
+//! let keys: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap().into();
+//! let verifier = SessionVerifier::new(keys, store);
+//!
+//! let session = manager.start("someone").unwrap();
+//! assert!(verifier.verify_token(&session.token).is_ok());
+//! manager.end_all("someone").unwrap();
+//! assert!(verifier.verify_token(&session.token).is_err());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::algorithm::any::AnySigner;
+use crate::bearer::TokenVerifier;
+use crate::claims::{Claims, RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::redact::Redaction;
+use crate::token::signed::SignWithKey;
+
+/// Tokens are valid for this many seconds by default.
+pub const DEFAULT_TOKEN_LIFETIME: SecondsSinceEpoch = 5 * 60;
+/// Sessions last this many seconds by default.
+pub const DEFAULT_SESSION_LIFETIME: SecondsSinceEpoch = 24 * 60 * 60;
+
+/// The errors of a [SessionStore](trait.SessionStore.html).
+pub type StoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The state of a session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub subject: String,
+    pub expiration: SecondsSinceEpoch,
+}
+
+/// Persists sessions by their id.
+pub trait SessionStore: Send + Sync {
+    fn insert(&self, sid: &str, record: SessionRecord) -> Result<(), StoreError>;
+
+    fn get(&self, sid: &str) -> Result<Option<SessionRecord>, StoreError>;
+
+    fn remove(&self, sid: &str) -> Result<(), StoreError>;
+
+    /// Remove all sessions of a subject.
+    fn remove_subject(&self, subject: &str) -> Result<(), StoreError>;
+}
+
+impl<S: SessionStore + ?Sized> SessionStore for Arc<S> {
+    fn insert(&self, sid: &str, record: SessionRecord) -> Result<(), StoreError> {
+        (**self).insert(sid, record)
+    }
+
+    fn get(&self, sid: &str) -> Result<Option<SessionRecord>, StoreError> {
+        (**self).get(sid)
+    }
+
+    fn remove(&self, sid: &str) -> Result<(), StoreError> {
+        (**self).remove(sid)
+    }
+
+    fn remove_subject(&self, subject: &str) -> Result<(), StoreError> {
+        (**self).remove_subject(subject)
+    }
+}
+
+/// A [SessionStore](trait.SessionStore.html) in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, SessionRecord>>,
+}
+
+impl MemoryStore {
+    /// Forget the sessions that have expired by now.
+    pub fn remove_expired(&self, now: SecondsSinceEpoch) {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, record| record.expiration > now);
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn insert(&self, sid: &str, record: SessionRecord) -> Result<(), StoreError> {
+        self.records.lock().unwrap().insert(sid.to_owned(), record);
+        Ok(())
+    }
+
+    fn get(&self, sid: &str) -> Result<Option<SessionRecord>, StoreError> {
+        Ok(self.records.lock().unwrap().get(sid).cloned())
+    }
+
+    fn remove(&self, sid: &str) -> Result<(), StoreError> {
+        self.records.lock().unwrap().remove(sid);
+        Ok(())
+    }
+
+    fn remove_subject(&self, subject: &str) -> Result<(), StoreError> {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, record| record.subject != subject);
+        Ok(())
+    }
+}
+
+/// A new session and its first token. `Debug` does not print the token.
+#[derive(Clone)]
+pub struct Session {
+    pub id: String,
+    pub token: String,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("id", &self.id)
+            .field("token", &Redaction)
+            .finish()
+    }
+}
+
+/// Starts and ends sessions and issues their tokens.
+pub struct SessionManager<S, C = SystemClock> {
+    signer: AnySigner,
+    store: S,
+    clock: C,
+    issuer: Option<String>,
+    token_lifetime: SecondsSinceEpoch,
+    session_lifetime: SecondsSinceEpoch,
+}
+
+impl<S: SessionStore> SessionManager<S> {
+    pub fn new(signer: AnySigner, store: S) -> Self {
+        SessionManager {
+            signer,
+            store,
+            clock: SystemClock,
+            issuer: None,
+            token_lifetime: DEFAULT_TOKEN_LIFETIME,
+            session_lifetime: DEFAULT_SESSION_LIFETIME,
+        }
+    }
+}
+
+impl<S: SessionStore, C: Clock> SessionManager<S, C> {
+    /// Set the `iss` claim of tokens.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    pub fn token_lifetime(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.token_lifetime = seconds;
+        self
+    }
+
+    pub fn session_lifetime(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.session_lifetime = seconds;
+        self
+    }
+
+    /// Issue tokens against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> SessionManager<S, D> {
+        SessionManager {
+            signer: self.signer,
+            store: self.store,
+            clock,
+            issuer: self.issuer,
+            token_lifetime: self.token_lifetime,
+            session_lifetime: self.session_lifetime,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Start a session for the subject and issue its first token.
+    pub fn start(&self, subject: &str) -> Result<Session, SessionError> {
+        let id = random_id()?;
+        let record = SessionRecord {
+            subject: subject.to_owned(),
+            expiration: self.clock.now().saturating_add(self.session_lifetime),
+        };
+        self.store
+            .insert(&id, record.clone())
+            .map_err(SessionError::Store)?;
+        let token = self.sign(&id, &record)?;
+        Ok(Session { id, token })
+    }
+
+    /// Issue another token for an active session. Tokens do not outlive
+    /// their session.
+    pub fn issue(&self, sid: &str) -> Result<String, SessionError> {
+        let record = self
+            .store
+            .get(sid)
+            .map_err(SessionError::Store)?
+            .ok_or(SessionError::Unknown)?;
+        if self.clock.now() >= record.expiration {
+            return Err(SessionError::Expired);
+        }
+        self.sign(sid, &record)
+    }
+
+    /// End a session, e.g. on logout. Its tokens are rejected from now on.
+    pub fn end(&self, sid: &str) -> Result<(), SessionError> {
+        self.store.remove(sid).map_err(SessionError::Store)
+    }
+
+    /// End all sessions of a subject.
+    pub fn end_all(&self, subject: &str) -> Result<(), SessionError> {
+        self.store
+            .remove_subject(subject)
+            .map_err(SessionError::Store)
+    }
+
+    fn sign(&self, sid: &str, record: &SessionRecord) -> Result<String, SessionError> {
+        let now = self.clock.now();
+        let expiration = now
+            .saturating_add(self.token_lifetime)
+            .min(record.expiration);
+        let mut claims = Claims::new(RegisteredClaims {
+            issuer: self.issuer.clone(),
+            subject: Some(record.subject.clone()),
+            expiration: Some(expiration),
+            issued_at: Some(now),
+            ..Default::default()
+        });
+        claims.private.insert("sid".into(), sid.into());
+        claims
+            .sign_with_key(&self.signer)
+            .map_err(SessionError::Jwt)
+    }
+}
+
+/// 128 random bits, base64url encoded.
+fn random_id() -> Result<String, SessionError> {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).map_err(SessionError::Random)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Verifies tokens with another verifier and rejects them unless the session
+/// of their `sid` claim is active and belongs to their subject. Tokens
+/// without a `sid` are rejected with
+/// [Error::MissingClaim](../error/enum.Error.html#variant.MissingClaim).
+pub struct SessionVerifier<V, S, C = SystemClock> {
+    inner: V,
+    store: S,
+    clock: C,
+}
+
+impl<V: TokenVerifier, S: SessionStore> SessionVerifier<V, S> {
+    pub fn new(inner: V, store: S) -> Self {
+        SessionVerifier {
+            inner,
+            store,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<V, S, C> SessionVerifier<V, S, C> {
+    /// Check the expiration of sessions against another clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> SessionVerifier<V, S, D> {
+        SessionVerifier {
+            inner: self.inner,
+            store: self.store,
+            clock,
+        }
+    }
+}
+
+impl<V, S, C> TokenVerifier for SessionVerifier<V, S, C>
+where
+    V: TokenVerifier,
+    S: SessionStore,
+    C: Clock + Send + Sync,
+{
+    fn verify_token(&self, token: &str) -> Result<Value, Error> {
+        let claims = self.inner.verify_token(token)?;
+        let sid = match claims.get("sid") {
+            Some(Value::String(sid)) => sid,
+            Some(_) => return Err(Error::InvalidClaim("sid")),
+            None => return Err(Error::MissingClaim("sid")),
+        };
+        let record = self
+            .store
+            .get(sid)
+            .map_err(|e| Error::SessionUnavailable(e.to_string()))?
+            .filter(|record| self.clock.now() < record.expiration)
+            .ok_or_else(|| {
+                trace_debug!(sid = %sid, "session has ended");
+                Error::SessionEnded(sid.clone())
+            })?;
+        if claims.get("sub").and_then(Value::as_str) != Some(&record.subject) {
+            return Err(Error::InvalidClaim("sub"));
+        }
+        Ok(claims)
+    }
+}
+
+/// The reason a session was not started or a token not issued.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The session never existed or has ended.
+    Unknown,
+    Expired,
+    Store(StoreError),
+    /// The system has no randomness to create session ids from.
+    Random(getrandom::Error),
+    Jwt(Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SessionError::Unknown => write!(f, "Unknown session"),
+            SessionError::Expired => write!(f, "Session expired"),
+            SessionError::Store(ref e) => write!(f, "Session store failed: {}", e),
+            SessionError::Random(ref e) => write!(f, "Could not create a session id: {}", e),
+            SessionError::Jwt(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            SessionError::Store(ref e) => Some(e.as_ref()),
+            SessionError::Random(ref e) => Some(e),
+            SessionError::Jwt(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier, SharedVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::TokenVerifier;
+    use crate::error::Error;
+    use crate::session::{MemoryStore, SessionError, SessionManager, SessionVerifier};
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    #[test]
+    pub fn sessions() -> Result<(), Error> {
+        let now = Arc::new(AtomicU64::new(1000));
+        let (issue_clock, verify_clock) = (Arc::clone(&now), Arc::clone(&now));
+        let store = Arc::new(MemoryStore::default());
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let manager = SessionManager::new(signer, Arc::clone(&store))
+            .token_lifetime(60)
+            .session_lifetime(100)
+            .with_clock(move || issue_clock.load(Ordering::SeqCst));
+        let keys: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?.into();
+        let verifier = SessionVerifier::new(keys, Arc::clone(&store))
+            .with_clock(move || verify_clock.load(Ordering::SeqCst));
+
+        let first = manager.start("alice").unwrap();
+        let claims = verifier.verify_token(&first.token)?;
+        assert_eq!(claims["sid"], first.id.as_str());
+        assert_eq!(claims["exp"], 1060);
+        let second = manager.start("alice").unwrap();
+        let other = manager.start("bob").unwrap();
+        assert_ne!(first.id, second.id);
+
+        // Tokens do not outlive their session
+        now.store(1050, Ordering::SeqCst);
+        let reissued = manager.issue(&first.id).unwrap();
+        assert_eq!(verifier.verify_token(&reissued)?["exp"], 1100);
+
+        manager.end(&first.id).unwrap();
+        assert!(matches!(
+            verifier.verify_token(&first.token),
+            Err(Error::SessionEnded(_))
+        ));
+        assert!(matches!(
+            manager.issue(&first.id),
+            Err(SessionError::Unknown)
+        ));
+        assert!(verifier.verify_token(&second.token).is_ok());
+
+        manager.end_all("alice").unwrap();
+        assert!(verifier.verify_token(&second.token).is_err());
+        assert!(verifier.verify_token(&other.token).is_ok());
+
+        now.store(1100, Ordering::SeqCst);
+        assert!(matches!(
+            manager.issue(&other.id),
+            Err(SessionError::Expired)
+        ));
+        store.remove_expired(1100);
+        assert!(matches!(
+            manager.issue(&other.id),
+            Err(SessionError::Unknown)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn claims() -> Result<(), Error> {
+        let store = Arc::new(MemoryStore::default());
+        let signer = AnySigner::hmac(AlgorithmType::Hs256, b"secret")?;
+        let manager = SessionManager::new(signer, Arc::clone(&store)).with_clock(|| 1000);
+        let keys: SharedVerifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?.into();
+        let verifier = SessionVerifier::new(keys, store).with_clock(|| 1000);
+        let session = manager.start("alice").unwrap();
+
+        let no_sid = RegisteredClaims::default()
+            .sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        assert!(matches!(
+            verifier.verify_token(&no_sid),
+            Err(Error::MissingClaim("sid"))
+        ));
+
+        // A token naming the session of someone else
+        let forged = serde_json::json!({"sub": "mallory", "sid": session.id})
+            .sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        assert!(matches!(
+            verifier.verify_token(&forged),
+            Err(Error::InvalidClaim("sub"))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Where the token of a request is read from. A
+//! [BearerAuth](../bearer/struct.BearerAuth.html) tries its sources in order
+//! and uses the first token it finds, so an application that accepts tokens
+//! in the `Authorization` header, a cookie and a query parameter configures
+//! that once for all of the framework integrations.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::cookie::CookieToken;
+//! use jwt::source::{AuthorizationHeader, QueryParameter};
+//! use jwt::AlgorithmType;
+//!
+//! let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap();
+//! let auth = BearerAuth::new(verifier).with_sources(vec![
+//!     Box::new(AuthorizationHeader),
+//!     Box::new(CookieToken::new("session").host_prefix(true)),
+//!     Box::new(QueryParameter::new("access_token")),
+//! ]);
+//! ```
+
+use crate::bearer::{bearer_token, Rejection};
+
+/// The parts of a request that tokens are read from. The framework
+/// integrations implement it for their request types.
+pub trait RequestParts {
+    /// The value of a header by its lowercase name. Values that are not valid
+    /// UTF-8 are rejected.
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection>;
+
+    /// The query string, without the `?`.
+    fn query(&self) -> Option<&str>;
+}
+
+/// Finds the token in a request.
+pub trait TokenSource: Send + Sync {
+    /// The token, if the request has one in this place.
+    fn token<'a>(&self, request: &'a dyn RequestParts) -> Result<Option<&'a str>, Rejection>;
+}
+
+/// The bearer token of the `Authorization` header, the default source.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AuthorizationHeader;
+
+impl TokenSource for AuthorizationHeader {
+    fn token<'a>(&self, request: &'a dyn RequestParts) -> Result<Option<&'a str>, Rejection> {
+        request
+            .header("authorization")?
+            .map(bearer_token)
+            .transpose()
+    }
+}
+
+/// A query parameter, e.g. `access_token` as in
+/// [RFC 6750](https://tools.ietf.org/html/rfc6750#section-2.3). Tokens in URLs
+/// end up in logs and browser histories, so use it only where nothing else
+/// works.
+#[derive(Clone, Debug)]
+pub struct QueryParameter {
+    name: String,
+}
+
+impl QueryParameter {
+    pub fn new(name: impl Into<String>) -> Self {
+        QueryParameter { name: name.into() }
+    }
+}
+
+impl TokenSource for QueryParameter {
+    fn token<'a>(&self, request: &'a dyn RequestParts) -> Result<Option<&'a str>, Rejection> {
+        query_value(request.query(), &self.name)
+    }
+}
+
+/// The value of a query parameter. Parameters that are given more than once
+/// are rejected.
+pub(crate) fn query_value<'a>(
+    query: Option<&'a str>,
+    name: &str,
+) -> Result<Option<&'a str>, Rejection> {
+    let mut value = None;
+    for pair in query.into_iter().flat_map(|q| q.split('&')) {
+        let (key, current) = pair.split_once('=').unwrap_or((pair, ""));
+        if key == name {
+            if value.is_some() {
+                return Err(Rejection::InvalidRequest);
+            }
+            value = Some(current);
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{BearerAuth, Rejection};
+    use crate::cookie::CookieToken;
+    use crate::error::Error;
+    use crate::source::{AuthorizationHeader, QueryParameter, RequestParts, TokenSource};
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    struct TestRequest {
+        headers: BTreeMap<&'static str, String>,
+        query: Option<&'static str>,
+    }
+
+    impl RequestParts for TestRequest {
+        fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+            Ok(self.headers.get(name).map(String::as_str))
+        }
+
+        fn query(&self) -> Option<&str> {
+            self.query
+        }
+    }
+
+    #[test]
+    pub fn sources() {
+        let mut headers = BTreeMap::new();
+        headers.insert("authorization", "Bearer header".to_owned());
+        headers.insert("cookie", "session=cookie".to_owned());
+        let request = TestRequest {
+            headers,
+            query: Some("a=1&access_token=query"),
+        };
+
+        let token = |source: &dyn TokenSource| source.token(&request).unwrap();
+        assert_eq!(token(&AuthorizationHeader), Some("header"));
+        assert_eq!(token(&CookieToken::new("session")), Some("cookie"));
+        assert_eq!(token(&QueryParameter::new("access_token")), Some("query"));
+        assert_eq!(token(&QueryParameter::new("token")), None);
+
+        let request = TestRequest {
+            headers: BTreeMap::new(),
+            query: Some("token=a&token=b"),
+        };
+        assert!(matches!(
+            QueryParameter::new("token").token(&request),
+            Err(Rejection::InvalidRequest)
+        ));
+    }
+
+    #[test]
+    pub fn priority() -> Result<(), Error> {
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let mut headers = BTreeMap::new();
+        headers.insert("cookie", format!("session={}", token));
+        let request = TestRequest {
+            headers,
+            query: Some("access_token=forged"),
+        };
+
+        let verifier = AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?;
+        let auth = BearerAuth::new(verifier);
+        assert!(matches!(
+            auth.authenticate_request::<RegisteredClaims>(&request),
+            Err(Rejection::Missing)
+        ));
+
+        let auth = auth.with_sources(vec![
+            Box::new(AuthorizationHeader),
+            Box::new(CookieToken::new("session")),
+            Box::new(QueryParameter::new("access_token")),
+        ]);
+        let claims: RegisteredClaims = auth.authenticate_request(&request).unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+//! [proptest](https://docs.rs/proptest) strategies for tokens, headers and
+//! claims, to property test code that handles tokens, e.g. middleware,
+//! against valid and malformed input. The header and claims types also
+//! implement `Arbitrary`.
+//! ## Examples
+//! ```
+//! use jwt::strategy::{malformed_token, signed_token};
+//! use jwt::{Claims, Header, Token, VerifyWithKey};
+//! use hmac::{Hmac, KeyInit};
+//! use proptest::prelude::*;
+//! use sha2::Sha256;
+//!
+//! proptest! {
+//!     fn accepts_signed_tokens(token in signed_token(b"secret")) {
+//!         let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+//!         let verified: Result<Token<Header, Claims, _>, _> = token.verify_with_key(&key);
+//!         prop_assert!(verified.is_ok());
+//!     }
+//!
+//!     fn rejects_malformed_tokens(token in malformed_token(b"secret")) {
+//!         let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+//!         let verified: Result<Token<Header, Claims, _>, _> = token.verify_with_key(&key);
+//!         prop_assert!(verified.is_err());
+//!     }
+//! }
+//! # accepts_signed_tokens();
+//! # rejects_malformed_tokens();
+//! ```
+
+use std::collections::BTreeMap;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, KeyInit};
+use proptest::arbitrary::Arbitrary;
+use proptest::prelude::*;
+use proptest::strategy::BoxedStrategy;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::{Claims, RegisteredClaims};
+use crate::header::{Header, HeaderContentType, HeaderType};
+use crate::token::signed::SignWithKey;
+use crate::{ToBase64, Token};
+
+const REGISTERED: [&str; 7] = ["iss", "sub", "aud", "exp", "nbf", "iat", "jti"];
+
+/// Every algorithm type, including `none`.
+pub fn algorithm_type() -> impl Strategy<Value = AlgorithmType> {
+    prop_oneof![
+        Just(AlgorithmType::Hs256),
+        Just(AlgorithmType::Hs384),
+        Just(AlgorithmType::Hs512),
+        Just(AlgorithmType::Rs256),
+        Just(AlgorithmType::Rs384),
+        Just(AlgorithmType::Rs512),
+        Just(AlgorithmType::Es256),
+        Just(AlgorithmType::Es384),
+        Just(AlgorithmType::Es512),
+        Just(AlgorithmType::Ps256),
+        Just(AlgorithmType::Ps384),
+        Just(AlgorithmType::Ps512),
+        Just(AlgorithmType::None),
+    ]
+}
+
+/// Headers with the algorithm and optional key id, type and content type.
+pub fn header_with(
+    algorithm: impl Strategy<Value = AlgorithmType>,
+) -> impl Strategy<Value = Header> {
+    (
+        algorithm,
+        proptest::option::of("[ -~]{0,16}"),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(|(algorithm, key_id, typ, cty)| Header {
+            algorithm,
+            key_id,
+            type_: typ.then_some(HeaderType::JsonWebToken),
+            content_type: cty.then_some(HeaderContentType::JsonWebToken),
+            critical: None,
+            extra: BTreeMap::new(),
+        })
+}
+
+/// Headers with any algorithm.
+pub fn header() -> impl Strategy<Value = Header> {
+    header_with(algorithm_type())
+}
+
+/// Registered claims, each of them optional.
+pub fn registered_claims() -> impl Strategy<Value = RegisteredClaims> {
+    let text = || proptest::option::of("\\PC{0,24}");
+    let time = || proptest::option::of(0..=4_000_000_000u64);
+    (text(), text(), text(), time(), time(), time(), text()).prop_map(
+        |(issuer, subject, audience, expiration, not_before, issued_at, json_web_token_id)| {
+            RegisteredClaims {
+                issuer,
+                subject,
+                audience,
+                expiration,
+                not_before,
+                issued_at,
+                json_web_token_id,
+            }
+        },
+    )
+}
+
+/// JSON values of private claims, nested up to a few levels.
+pub fn claim_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        "\\PC{0,16}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            proptest::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            proptest::collection::btree_map("[a-z]{1,8}", inner, 0..4)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// Registered claims with a few private claims, whose names do not clash
+/// with the registered ones.
+pub fn claims() -> impl Strategy<Value = Claims> {
+    let name = "[a-z_]{1,12}".prop_filter("registered claim", |name| {
+        !REGISTERED.contains(&name.as_str())
+    });
+    (
+        registered_claims(),
+        proptest::collection::btree_map(name, claim_value(), 0..4),
+    )
+        .prop_map(|(registered, private)| Claims {
+            registered,
+            private: private.into_iter().collect::<BTreeMap<_, _>>(),
+        })
+}
+
+/// Tokens with arbitrary headers and claims, signed with HS256 and the
+/// secret.
+pub fn signed_token(secret: &[u8]) -> impl Strategy<Value = String> {
+    let key: Hmac<Sha256> = Hmac::new_from_slice(secret).expect("HMAC accepts any key length");
+    (header_with(Just(AlgorithmType::Hs256)), claims()).prop_map(move |(header, claims)| {
+        Token::new(header, claims)
+            .sign_with_key(&key)
+            .expect("claims serialize")
+            .into()
+    })
+}
+
+/// Strings that are not valid HS256 tokens of the secret: tokens with a
+/// changed signature, header or claims, with the wrong number of segments,
+/// with segments that are not base64 or not JSON, unsecured tokens and
+/// arbitrary text.
+pub fn malformed_token(secret: &[u8]) -> impl Strategy<Value = String> {
+    let segment = "[A-Za-z0-9_-]{0,40}";
+    let not_json = "\\PC{1,24}".prop_map(|text| URL_SAFE_NO_PAD.encode(format!("{}{{", text)));
+    let unsecured =
+        (header_with(Just(AlgorithmType::None)), claims()).prop_map(|(header, claims)| {
+            let header = header.to_base64().expect("header serializes");
+            let claims = claims.to_base64().expect("claims serialize");
+            format!("{}.{}.", header, claims)
+        });
+
+    prop_oneof![
+        (
+            signed_token(secret),
+            any::<prop::sample::Index>(),
+            0..3usize
+        )
+            .prop_map(|(token, index, segment)| tamper(&token, segment, index)),
+        (signed_token(secret), "[A-Za-z0-9_-]{1,8}")
+            .prop_map(|(token, extra)| format!("{}.{}", token, extra)),
+        (segment, segment).prop_map(|(a, b)| format!("{}.{}", a, b)),
+        (not_json.clone(), not_json, segment).prop_map(|(a, b, c)| format!("{}.{}.{}", a, b, c)),
+        (segment, "[!@#$%^&*()+=/?]{1,4}", segment)
+            .prop_map(|(a, b, c)| format!("{}.{}.{}", a, b, c)),
+        unsecured,
+        "\\PC{0,64}",
+    ]
+}
+
+/// Change one character of a segment of the token, or an empty signature
+/// segment to a non-empty one.
+fn tamper(token: &str, segment: usize, index: prop::sample::Index) -> String {
+    let mut segments: Vec<String> = token.split('.').map(str::to_owned).collect();
+    let target = &mut segments[segment];
+    if target.is_empty() {
+        target.push('A');
+    } else {
+        let position = index.index(target.len());
+        let replaced = target.as_bytes()[position];
+        let replacement = if replaced == b'A' { 'B' } else { 'A' };
+        target.replace_range(position..position + 1, &replacement.to_string());
+    }
+    segments.join(".")
+}
+
+impl Arbitrary for AlgorithmType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        algorithm_type().boxed()
+    }
+}
+
+impl Arbitrary for Header {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        header().boxed()
+    }
+}
+
+impl Arbitrary for RegisteredClaims {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        registered_claims().boxed()
+    }
+}
+
+impl Arbitrary for Claims {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        claims().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use proptest::prelude::*;
+    use sha2::Sha256;
+
+    use crate::claims::Claims;
+    use crate::header::Header;
+    use crate::strategy::{malformed_token, signed_token};
+    use crate::token::verified::VerifyWithKey;
+    use crate::{FromBase64, ToBase64, Token};
+
+    proptest! {
+        #[test]
+        fn round_trip(header in any::<Header>(), claims in any::<Claims>()) {
+            prop_assert_eq!(Header::from_base64(&*header.to_base64().unwrap()).unwrap(), header);
+            prop_assert_eq!(Claims::from_base64(&*claims.to_base64().unwrap()).unwrap(), claims);
+        }
+
+        #[test]
+        fn signed(token in signed_token(b"secret")) {
+            let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+            let verified: Result<Token<Header, Claims, _>, _> = token.verify_with_key(&key);
+            prop_assert!(verified.is_ok());
+        }
+
+        #[test]
+        fn malformed(token in malformed_token(b"secret")) {
+            let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+            let verified: Result<Token<Header, Claims, _>, _> = token.verify_with_key(&key);
+            prop_assert!(verified.is_err(), "accepted {}", token);
+        }
+    }
+}
@@ -0,0 +1,415 @@
+//! Tokens for the test suites of applications, without real keys.
+//! [MockSigner](struct.MockSigner.html) signs with HS256 and a fixed, public
+//! secret, so its signatures are deterministic, and
+//! [TestToken](struct.TestToken.html) builds tokens with sensible claims that
+//! can be made expired, not yet valid, for the wrong audience or with a
+//! tampered signature. [MockClock](struct.MockClock.html) is a clock that
+//! only moves when told to, for testing expiration without sleeping.
+//! ## Examples
+//! ```
+//! use jwt::testing::{MockSigner, TestToken};
+//! use jwt::validation::Validation;
+//! use jwt::VerifyWithKey;
+//! use serde_json::Value;
+//!
+//! let signer = MockSigner::new();
+//! let validation = Validation::new().audience(jwt::testing::AUDIENCE);
+//! let now = 1_700_000_000;
+//!
+//! let token = TestToken::new().issued_at(now).claim("role", "admin").sign(&signer).unwrap();
+//! let claims: Value = token.as_str().verify_with_key(&signer).unwrap();
+//! assert!(validation.validate(&claims, now).is_ok());
+//! assert_eq!(claims["role"], "admin");
+//!
+//! let expired = TestToken::new().issued_at(now).expired().sign(&signer).unwrap();
+//! let claims: Value = expired.as_str().verify_with_key(&signer).unwrap();
+//! assert!(validation.validate(&claims, now).is_err());
+//!
+//! let tampered = TestToken::new().tampered().sign(&signer).unwrap();
+//! let result: Result<Value, _> = tampered.as_str().verify_with_key(&signer);
+//! assert!(result.is_err());
+//! ```
+//!
+//! A mock clock is shared by its clones, so a clone can be handed to the code
+//! under test and moved from the test:
+//! ```
+//! use jwt::testing::{MockClock, TestToken};
+//! use jwt::validation::Validation;
+//!
+//! let clock = MockClock::new(1_700_000_000);
+//! let claims = TestToken::new().issued_at_clock(&clock).expires_in(60).claims();
+//! let validation = Validation::new();
+//!
+//! assert!(validation.validate_with(&claims, &clock).is_ok());
+//! clock.advance(60);
+//! assert!(validation.validate_with(&claims, &clock).is_err());
+//! ```
+//!
+//! The secret is public; never accept tokens of the mock signer outside of
+//! tests.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use hmac::{Hmac, KeyInit};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+use crate::algorithm::any::AnyVerifier;
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::claims::SecondsSinceEpoch;
+use crate::clock::{Clock, SystemClock};
+use crate::error::Error;
+use crate::header::{Header, HeaderType};
+use crate::token::signed::SignWithKey;
+use crate::Token;
+
+/// The secret of the mock signer.
+pub const TEST_SECRET: &[u8] = b"jwt-testing-secret-do-not-use-in-production";
+
+/// The `iss` claim of test tokens.
+pub const ISSUER: &str = "https://issuer.test";
+
+/// The `sub` claim of test tokens.
+pub const SUBJECT: &str = "test-subject";
+
+/// The `aud` claim of test tokens.
+pub const AUDIENCE: &str = "test-audience";
+
+/// Test tokens expire this many seconds after they were issued by default.
+pub const LIFETIME: SecondsSinceEpoch = 60 * 60;
+
+/// Signs and verifies with HS256 and [TEST_SECRET](constant.TEST_SECRET.html).
+#[derive(Clone, Debug)]
+pub struct MockSigner(Hmac<Sha256>);
+
+impl MockSigner {
+    pub fn new() -> Self {
+        MockSigner(Hmac::new_from_slice(TEST_SECRET).expect("HMAC accepts any key length"))
+    }
+
+    /// A verifier of the tokens of the mock signer, e.g. for the
+    /// configuration of the application under test.
+    pub fn verifier(&self) -> AnyVerifier {
+        AnyVerifier::new(self.0.clone())
+    }
+}
+
+impl Default for MockSigner {
+    fn default() -> Self {
+        MockSigner::new()
+    }
+}
+
+impl SigningAlgorithm for MockSigner {
+    fn algorithm_type(&self) -> AlgorithmType {
+        AlgorithmType::Hs256
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        self.0.sign(header, claims)
+    }
+}
+
+impl VerifyingAlgorithm for MockSigner {
+    fn algorithm_type(&self) -> AlgorithmType {
+        AlgorithmType::Hs256
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        self.0.verify_bytes(header, claims, signature)
+    }
+}
+
+/// A clock that is set and advanced by hand. Clones share the time.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock(Arc<AtomicU64>);
+
+impl MockClock {
+    pub fn new(now: SecondsSinceEpoch) -> Self {
+        MockClock(Arc::new(AtomicU64::new(now)))
+    }
+
+    pub fn set(&self, now: SecondsSinceEpoch) {
+        self.0.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward by this many seconds.
+    pub fn advance(&self, seconds: SecondsSinceEpoch) {
+        self.0.fetch_add(seconds, Ordering::SeqCst);
+    }
+
+    /// Move the clock back by this many seconds, e.g. to test tokens that
+    /// are not yet valid.
+    pub fn rewind(&self, seconds: SecondsSinceEpoch) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                Some(now.saturating_sub(seconds))
+            });
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SecondsSinceEpoch {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Builds tokens for tests. By default a token is issued now by
+/// [ISSUER](constant.ISSUER.html) to [SUBJECT](constant.SUBJECT.html) for
+/// [AUDIENCE](constant.AUDIENCE.html), and expires after
+/// [LIFETIME](constant.LIFETIME.html).
+#[derive(Clone, Debug)]
+pub struct TestToken {
+    key_id: Option<String>,
+    issued_at: SecondsSinceEpoch,
+    lifetime: Option<SecondsSinceEpoch>,
+    expired: bool,
+    not_yet_valid: bool,
+    claims: Map<String, Value>,
+    removed: Vec<String>,
+    tampered: bool,
+}
+
+impl TestToken {
+    pub fn new() -> Self {
+        let mut claims = Map::new();
+        claims.insert("iss".into(), ISSUER.into());
+        claims.insert("sub".into(), SUBJECT.into());
+        claims.insert("aud".into(), AUDIENCE.into());
+        TestToken {
+            key_id: None,
+            issued_at: SystemClock.now(),
+            lifetime: Some(LIFETIME),
+            expired: false,
+            not_yet_valid: false,
+            claims,
+            removed: Vec::new(),
+            tampered: false,
+        }
+    }
+
+    /// Issue the token at a fixed time instead of now. The expiration and
+    /// the time it becomes valid are relative to it.
+    pub fn issued_at(mut self, time: SecondsSinceEpoch) -> Self {
+        self.issued_at = time;
+        self
+    }
+
+    /// Issue the token at the time of a clock.
+    pub fn issued_at_clock(self, clock: &impl Clock) -> Self {
+        let now = clock.now();
+        self.issued_at(now)
+    }
+
+    /// Expire the token this many seconds after it was issued.
+    pub fn expires_in(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.lifetime = Some(seconds);
+        self
+    }
+
+    /// Leave out the `exp` claim.
+    pub fn without_expiration(mut self) -> Self {
+        self.lifetime = None;
+        self
+    }
+
+    /// Make the token expire a minute before it was issued, so it is expired
+    /// as soon as it exists.
+    pub fn expired(mut self) -> Self {
+        self.expired = true;
+        self
+    }
+
+    /// Make the token valid only an hour after it was issued.
+    pub fn not_yet_valid(mut self) -> Self {
+        self.not_yet_valid = true;
+        self
+    }
+
+    pub fn issuer(self, issuer: impl Into<String>) -> Self {
+        self.claim("iss", issuer.into())
+    }
+
+    pub fn subject(self, subject: impl Into<String>) -> Self {
+        self.claim("sub", subject.into())
+    }
+
+    pub fn audience(self, audience: impl Into<String>) -> Self {
+        self.claim("aud", audience.into())
+    }
+
+    /// Issue the token for an audience that no application expects.
+    pub fn wrong_audience(self) -> Self {
+        self.audience("wrong-audience")
+    }
+
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Set any claim, replacing the default of a registered one.
+    pub fn claim(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        let name = name.into();
+        self.removed.retain(|removed| *removed != name);
+        self.claims.insert(name, value.into());
+        self
+    }
+
+    /// Leave out a claim, including the registered ones that are set by
+    /// default, e.g. to test that a required claim is checked.
+    pub fn without(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.claims.remove(&name);
+        self.removed.push(name);
+        self
+    }
+
+    /// Change the signature after signing, so the token fails verification.
+    pub fn tampered(mut self) -> Self {
+        self.tampered = true;
+        self
+    }
+
+    /// The claims of the token.
+    pub fn claims(&self) -> Value {
+        let mut claims = self.claims.clone();
+        claims.entry("iat").or_insert_with(|| self.issued_at.into());
+        let expiration = match self.lifetime {
+            _ if self.expired => Some(self.issued_at.saturating_sub(60)),
+            Some(lifetime) => Some(self.issued_at.saturating_add(lifetime)),
+            None => None,
+        };
+        if let Some(expiration) = expiration {
+            claims.entry("exp").or_insert_with(|| expiration.into());
+        }
+        if self.not_yet_valid {
+            let not_before = self.issued_at.saturating_add(60 * 60);
+            claims.insert("nbf".into(), not_before.into());
+        }
+        for name in &self.removed {
+            claims.remove(name);
+        }
+        Value::Object(claims)
+    }
+
+    /// Sign the token, e.g. with a [MockSigner](struct.MockSigner.html).
+    pub fn sign(&self, key: &impl SigningAlgorithm) -> Result<String, Error> {
+        let header = Header {
+            algorithm: key.algorithm_type(),
+            key_id: self.key_id.clone(),
+            type_: Some(HeaderType::JsonWebToken),
+            ..Default::default()
+        };
+        let token: String = Token::new(header, self.claims()).sign_with_key(key)?.into();
+        if !self.tampered {
+            return Ok(token);
+        }
+
+        // Changing the first character of the signature keeps it valid
+        // base64, so verification fails on the signature itself
+        let position = token.rfind('.').map_or(0, |dot| dot + 1);
+        let replacement = if token[position..].starts_with('A') {
+            "B"
+        } else {
+            "A"
+        };
+        let mut tampered = token;
+        tampered.replace_range(position..position + 1, replacement);
+        Ok(tampered)
+    }
+}
+
+impl Default for TestToken {
+    fn default() -> Self {
+        TestToken::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::clock::Clock;
+    use crate::error::Error;
+    use crate::testing::{MockClock, MockSigner, TestToken, AUDIENCE, ISSUER};
+    use crate::token::verified::VerifyWithKey;
+    use crate::validation::Validation;
+
+    #[test]
+    pub fn test_tokens() -> Result<(), Error> {
+        let signer = MockSigner::new();
+        let validation = Validation::new().issuer(ISSUER).audience(AUDIENCE);
+        let now = 1_700_000_000;
+        let verify = |token: String| -> Result<Value, Error> {
+            let claims: Value = token.as_str().verify_with_key(&signer.verifier())?;
+            validation.validate(&claims, now)?;
+            Ok(claims)
+        };
+
+        let token = TestToken::new().issued_at(now).key_id("test");
+        assert_eq!(token.sign(&signer)?, token.sign(&signer)?);
+        let claims = verify(token.sign(&signer)?)?;
+        assert_eq!(claims["exp"], now + 3600);
+        assert_eq!(claims["iat"], now);
+
+        let token = TestToken::new().issued_at(now);
+        assert!(matches!(
+            verify(token.clone().expired().sign(&signer)?),
+            Err(Error::Expired { .. })
+        ));
+        assert!(matches!(
+            verify(token.clone().not_yet_valid().sign(&signer)?),
+            Err(Error::NotYetValid { .. })
+        ));
+        assert!(matches!(
+            verify(token.clone().wrong_audience().sign(&signer)?),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        assert!(matches!(
+            verify(token.clone().without("iss").sign(&signer)?),
+            Err(Error::MissingClaim("iss"))
+        ));
+        assert!(token.clone().without("exp").claims().get("exp").is_none());
+        assert!(matches!(
+            verify(token.tampered().sign(&signer)?),
+            Err(Error::InvalidSignature)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn mock_clock() -> Result<(), Error> {
+        let clock = MockClock::new(1000);
+        let shared = clock.clone();
+        let claims = TestToken::new()
+            .issued_at_clock(&clock)
+            .expires_in(7200)
+            .not_yet_valid()
+            .claims();
+        let validation = Validation::new().leeway(5);
+
+        shared.advance(3594);
+        assert!(matches!(
+            validation.validate_with(&claims, &clock),
+            Err(Error::NotYetValid { .. })
+        ));
+        shared.advance(1);
+        validation.validate_with(&claims, &clock)?;
+
+        clock.set(8204);
+        validation.validate_with(&claims, &clock)?;
+        shared.advance(1);
+        assert!(matches!(
+            validation.validate_with(&claims, &clock),
+            Err(Error::Expired { .. })
+        ));
+        clock.rewind(8000);
+        assert_eq!(shared.now(), 205);
+        clock.rewind(2000);
+        assert_eq!(shared.now(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! Serde support for tokens as their compact string representation, for use
+//! with `#[serde(with = "jwt::token::compact")]`. This allows tokens to be
+//! embedded in larger structs, e.g. API responses. Deserialized tokens are
+//! [OwnedUnverified](../struct.OwnedUnverified.html), since their signature
+//! is not checked; tokens signed here can be serialized on their own with
+//! `#[serde(serialize_with = "jwt::token::compact::serialize")]`.
+//! ## Examples
+//! ```
+//! use jwt::{Header, Claims, OwnedUnverified, Token};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Response {
+//!     #[serde(with = "jwt::token::compact")]
+//!     access_token: Token<Header, Claims, OwnedUnverified>,
+//! }
+//! ```
+
+use alloc::string::String;
+use core::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::token::OwnedUnverified;
+use crate::{FromBase64, Token};
+
+pub fn serialize<T: fmt::Display, S: Serializer>(
+    token: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(token)
+}
+
+/// Like `FromStr`, the signature is not verified.
+pub fn deserialize<'de, H, C, D>(deserializer: D) -> Result<Token<H, C, OwnedUnverified>, D::Error>
+where
+    H: FromBase64,
+    C: FromBase64,
+    D: Deserializer<'de>,
+{
+    let token_str = String::deserialize(deserializer)?;
+    token_str.parse().map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, KeyInit};
+    use serde::{Deserialize, Serialize};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::token::{OwnedUnverified, Signed};
+    use crate::Token;
+
+    #[derive(Serialize, Deserialize)]
+    struct Claims {
+        name: String,
+    }
+
+    #[derive(Serialize)]
+    struct Issued {
+        #[serde(serialize_with = "crate::token::compact::serialize")]
+        access_token: Token<Header, Claims, Signed>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Response {
+        #[serde(with = "crate::token::compact")]
+        access_token: Token<Header, Claims, OwnedUnverified>,
+    }
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = Claims {
+            name: "John Doe".into(),
+        };
+        let access_token = Token::new(Header::default(), claims).sign_with_key(&key)?;
+        let token_str = access_token.to_string();
+
+        let json = serde_json::to_value(Issued { access_token })?;
+        assert_eq!(json["access_token"], token_str);
+
+        let response: Response = serde_json::from_value(json.clone())?;
+        assert_eq!(response.access_token.as_str(), token_str);
+        assert_eq!(serde_json::to_value(&response)?, json);
+
+        let verified: Token<Header, Claims, _> = response.access_token.verify_with_key(&key)?;
+        assert_eq!(verified.claims().name, "John Doe");
+
+        let invalid = serde_json::from_str::<Response>(r#"{"access_token":"header.claims"}"#);
+        assert!(invalid.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! A readable rendering of the header and claims of a token, for logs and
+//! error reports. Time claims are shown as UTC dates next to their value.
+//! ## Examples
+//! ```
+//! use jwt::{Header, Token};
+//! use serde_json::Value;
+//!
+//! let token_str = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiJzb21lb25lIiwiZXhwIjoxNzAwMDAwMDAwfQ.sig";
+//! let token: Token<Header, Value, _> = Token::parse_unverified(token_str).unwrap();
+//! println!("{}", token.explain().unwrap());
+//! // {
+//! //   "claims": {
+//! //     "exp": "1700000000 (2023-11-14T22:13:20Z)",
+//! //     "sub": "someone"
+//! //   },
+//! //   "header": {
+//! //     "alg": "HS256"
+//! //   }
+//! // }
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::Token;
+
+/// The claims holding seconds since the epoch.
+const TIME_CLAIMS: [&str; 3] = ["exp", "nbf", "iat"];
+
+impl<H: Serialize, C: Serialize, S> Token<H, C, S> {
+    /// Render the header and claims as indented JSON, with humanized time
+    /// claims. The output is not meant to be parsed.
+    pub fn explain(&self) -> Result<String, Error> {
+        let mut claims = serde_json::to_value(&self.claims)?;
+        if let Value::Object(ref mut claims) = claims {
+            for name in TIME_CLAIMS {
+                if let Some(value) = claims.get_mut(name) {
+                    if let Some(seconds) = value.as_u64() {
+                        *value = Value::String(format!("{} ({})", seconds, utc_date(seconds)));
+                    }
+                }
+            }
+        }
+
+        let mut explained = Map::new();
+        explained.insert("header".into(), serde_json::to_value(&self.header)?);
+        explained.insert("claims".into(), claims);
+        Ok(serde_json::to_string_pretty(&explained)?)
+    }
+}
+
+/// Format seconds since the epoch as an RFC 3339 date in UTC.
+fn utc_date(seconds: u64) -> String {
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let (hour, minute, second) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    // Days to the civil calendar, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::explain::utc_date;
+    use crate::Token;
+
+    #[test]
+    pub fn dates() {
+        assert_eq!(utc_date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_date(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_date(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(utc_date(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    pub fn explain() -> Result<(), Error> {
+        let claims = Claims::new(RegisteredClaims {
+            subject: Some("someone".into()),
+            issued_at: Some(1_700_000_000),
+            ..Default::default()
+        });
+        let token = Token::new(Header::default(), claims);
+
+        let explained = token.explain()?;
+        assert!(explained.contains(r#""alg": "HS256""#));
+        assert!(explained.contains(r#""sub": "someone""#));
+        assert!(explained.contains(r#""iat": "1700000000 (2023-11-14T22:13:20Z)""#));
+        Ok(())
+    }
+}
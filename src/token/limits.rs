@@ -0,0 +1,294 @@
+//! Size limits for token strings, checked before anything is decoded, so that
+//! a hostile token can not make the verifier allocate or hash large amounts of
+//! data. The structure of the decoded JSON is limited as well, before it is
+//! deserialized.
+//! ## Examples
+//! ```
+//! use jwt::token::limits::Limits;
+//! use jwt::{Claims, Header, Token};
+//!
+//! let limits = Limits {
+//!     max_token_length: 1024,
+//!     ..Default::default()
+//! };
+//!
+//! let huge = "a".repeat(2048);
+//! assert!(Token::<Header, Claims, _>::parse_unverified_with_limits(&huge, &limits).is_err());
+//! ```
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::token::raw::{Base64Mode, Component, RawToken};
+
+/// Maximum lengths in bytes and other restrictions on the structure of
+/// tokens. The defaults are generous for tokens used as bearer credentials.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The whole compact token string.
+    pub max_token_length: usize,
+    /// The base64 encoded header.
+    pub max_header_length: usize,
+    /// The base64 encoded claims.
+    pub max_claims_length: usize,
+    /// The base64 encoded signature.
+    pub max_signature_length: usize,
+    /// The output of decompressing a compressed payload.
+    pub max_decompressed_length: usize,
+    /// The nesting depth of objects and arrays in the decoded header or
+    /// claims.
+    pub max_json_depth: usize,
+    /// The total number of object members and array elements in the decoded
+    /// header or claims.
+    pub max_json_members: usize,
+    /// Reject objects with the same member more than once, instead of
+    /// keeping the last value. See
+    /// [RFC 7515, section 5.2](https://tools.ietf.org/html/rfc7515#section-5.2).
+    pub reject_duplicate_members: bool,
+    /// How strictly the header and claims are decoded. Signatures are decoded
+    /// leniently only by verifiers that support it, like
+    /// [AllowlistVerifier](../../algorithm/allowlist/struct.AllowlistVerifier.html).
+    pub base64_mode: Base64Mode,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_token_length: 64 * 1024,
+            max_header_length: 8 * 1024,
+            max_claims_length: 64 * 1024,
+            max_signature_length: 2 * 1024,
+            max_decompressed_length: 256 * 1024,
+            max_json_depth: 32,
+            max_json_members: 1024,
+            reject_duplicate_members: false,
+            base64_mode: Base64Mode::Strict,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, e.g. for tokens that were created locally.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_token_length: usize::MAX,
+            max_header_length: usize::MAX,
+            max_claims_length: usize::MAX,
+            max_signature_length: usize::MAX,
+            max_decompressed_length: usize::MAX,
+            max_json_depth: usize::MAX,
+            max_json_members: usize::MAX,
+            reject_duplicate_members: false,
+            base64_mode: Base64Mode::Strict,
+        }
+    }
+
+    /// The default limits, additionally rejecting duplicate members.
+    pub fn strict() -> Self {
+        Limits {
+            reject_duplicate_members: true,
+            ..Default::default()
+        }
+    }
+
+    /// The limit for a single component.
+    pub fn max_component_length(&self, component: Component) -> usize {
+        match component {
+            Component::Header => self.max_header_length,
+            Component::Claims => self.max_claims_length,
+            Component::Signature => self.max_signature_length,
+        }
+    }
+
+    /// Check the length of a whole token string.
+    pub fn check_token(&self, token: &str) -> Result<(), Error> {
+        if token.len() > self.max_token_length {
+            return Err(Error::TokenTooLong(token.len(), self.max_token_length));
+        }
+        Ok(())
+    }
+
+    /// Check the length of each component of a split token.
+    pub fn check_components(&self, raw: &RawToken<'_>) -> Result<(), Error> {
+        for component in [Component::Header, Component::Claims, Component::Signature] {
+            let length = raw.component(component).len();
+            let max = self.max_component_length(component);
+            if length > max {
+                return Err(Error::ComponentTooLong(component, length, max));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the amount of data produced so far while decompressing.
+    pub fn check_decompressed(&self, length: usize) -> Result<(), Error> {
+        if length > self.max_decompressed_length {
+            return Err(Error::DecompressedTooLong(self.max_decompressed_length));
+        }
+        Ok(())
+    }
+
+    /// Check the nesting depth and number of members of a JSON document
+    /// without deserializing it, and for duplicate object members if they
+    /// are rejected. Syntax errors are left to the deserializer.
+    pub fn check_json(&self, json: &[u8]) -> Result<(), Error> {
+        // The open containers, with the member names seen so far for objects
+        let mut containers: Vec<(u8, BTreeSet<String>)> = Vec::new();
+        let mut members = 0;
+        let mut string_start = None;
+        let mut escaped = false;
+        let mut expect_key = false;
+        let mut array_opened = false;
+
+        for (i, &byte) in json.iter().enumerate() {
+            if let Some(start) = string_start {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => {
+                        string_start = None;
+                        if let (true, Some((_, keys))) = (expect_key, containers.last_mut()) {
+                            expect_key = false;
+                            let key: String = serde_json::from_slice(&json[start..=i])?;
+                            if keys.contains(&key) {
+                                return Err(Error::DuplicateMember(key));
+                            }
+                            keys.insert(key);
+                        }
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+
+            if array_opened && !byte.is_ascii_whitespace() {
+                array_opened = false;
+                if byte != b']' {
+                    members += 1;
+                }
+            }
+
+            match byte {
+                b'"' => string_start = Some(i),
+                b'{' | b'[' => {
+                    containers.push((byte, BTreeSet::new()));
+                    if containers.len() > self.max_json_depth {
+                        return Err(Error::JsonTooDeep(self.max_json_depth));
+                    }
+                    expect_key = byte == b'{' && self.reject_duplicate_members;
+                    array_opened = byte == b'[';
+                }
+                b'}' | b']' => {
+                    containers.pop();
+                }
+                b':' => members += 1,
+                b',' => match containers.last() {
+                    Some((b'[', _)) => members += 1,
+                    _ => expect_key = self.reject_duplicate_members,
+                },
+                _ => (),
+            }
+
+            if members > self.max_json_members {
+                return Err(Error::JsonTooManyMembers(self.max_json_members));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::limits::Limits;
+    use crate::token::raw::{Component, RawToken};
+    use crate::Token;
+
+    #[test]
+    pub fn component_limits() -> Result<(), Error> {
+        let limits = Limits {
+            max_claims_length: 4,
+            ..Default::default()
+        };
+
+        assert!(RawToken::parse_with_limits("a.b.c", &limits).is_ok());
+        match RawToken::parse_with_limits("a.bbbbb.c", &limits) {
+            Err(Error::ComponentTooLong(Component::Claims, 5, 4)) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn token_limit() -> Result<(), Error> {
+        let token = format!("a.{}.c", "b".repeat(100 * 1024));
+        match RawToken::parse(&token) {
+            Err(Error::TokenTooLong(_, max)) => assert_eq!(max, Limits::default().max_token_length),
+            x => panic!("Incorrect result {:?}", x),
+        }
+
+        assert!(RawToken::parse_with_limits(&token, &Limits::unlimited()).is_ok());
+        assert!(Limits::default().check_decompressed(1024 * 1024).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn json_limits() -> Result<(), Error> {
+        let limits = Limits {
+            max_json_depth: 2,
+            max_json_members: 4,
+            ..Default::default()
+        };
+
+        limits.check_json(br#"{"a":[1,2],"b":"{[[:,,"}"#)?;
+        limits.check_json(br#"{"a":[ ],"b":{}}"#)?;
+        match limits.check_json(br#"{"a":[[1]]}"#) {
+            Err(Error::JsonTooDeep(2)) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        match limits.check_json(br#"{"a":[1,2,3],"b":4}"#) {
+            Err(Error::JsonTooManyMembers(4)) => (),
+            x => panic!("Incorrect result {:?}", x),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn nested_claims() {
+        let claims = format!("{}{}", "[".repeat(64), "]".repeat(64));
+        let token = format!(
+            "eyJhbGciOiJIUzI1NiJ9.{}.sig",
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let result = Token::<Header, serde_json::Value, _>::parse_unverified(&token);
+        match result.map(|_| ()) {
+            Err(Error::Malformed(Component::Claims, _, e)) => {
+                assert!(matches!(*e, Error::JsonTooDeep(32)))
+            }
+            x => panic!("Incorrect result {:?}", x),
+        }
+    }
+
+    #[test]
+    pub fn duplicate_members() -> Result<(), Error> {
+        let strict = Limits::strict();
+        let json = br#"{"alg":"HS256","alg":"none"}"#;
+        Limits::default().check_json(json)?;
+        match strict.check_json(json) {
+            Err(Error::DuplicateMember(key)) => assert_eq!(key, "alg"),
+            x => panic!("Incorrect result {:?}", x),
+        }
+
+        // Escaped names are compared after unescaping
+        assert!(strict.check_json(br#"{"alg":1,"\u0061lg":2}"#).is_err());
+        strict.check_json(br#"{"a":{"a":1},"b":[{"a":1},{"a":2}],"c":"a"}"#)?;
+        Ok(())
+    }
+}
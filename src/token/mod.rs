@@ -1,5 +1,12 @@
 //! A structured representation of a JWT.
 
+use alloc::string::String;
+
+pub mod compact;
+pub mod explain;
+pub mod json;
+pub mod limits;
+pub mod raw;
 pub mod signed;
 pub mod verified;
 
@@ -16,3 +23,9 @@ pub struct Unverified<'a> {
     pub claims_str: &'a str,
     pub signature_str: &'a str,
 }
+
+/// Like [Unverified](struct.Unverified.html), but the token string is owned,
+/// e.g. when the token is parsed with `FromStr`.
+pub struct OwnedUnverified {
+    pub token_string: String,
+}
@@ -0,0 +1,161 @@
+//! gRPC interceptors for [tonic](https://docs.rs/tonic), enabled with the
+//! `tonic` feature. On the client, [BearerInterceptor](struct.BearerInterceptor.html)
+//! attaches a token to every request. On the server,
+//! [JwtInterceptor](struct.JwtInterceptor.html) verifies the `authorization`
+//! metadata and inserts the claims into the request extensions.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::tonic::{BearerInterceptor, JwtInterceptor};
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! // Client side, for `SomeClient::with_interceptor(channel, interceptor)`
+//! let interceptor = BearerInterceptor::new("eyJhbGciOiJIUzI1NiJ9.e30.signature").unwrap();
+//!
+//! // Server side, for `SomeServer::with_interceptor(service, interceptor)`
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let interceptor = JwtInterceptor::<RegisteredClaims>::new(auth);
+//! // Handlers read the claims with `request.extensions().get::<RegisteredClaims>()`
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use tonic::metadata::errors::InvalidMetadataValue;
+use tonic::metadata::{Ascii, MetadataMap, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::source::RequestParts;
+
+const AUTHORIZATION: &str = "authorization";
+
+impl From<Rejection> for Status {
+    fn from(rejection: Rejection) -> Self {
+        let message = rejection.to_string();
+        match rejection {
+            Rejection::Missing | Rejection::InvalidToken(_) => Status::unauthenticated(message),
+            Rejection::InvalidRequest => Status::invalid_argument(message),
+            Rejection::InsufficientScope | Rejection::Forbidden(_) => {
+                Status::permission_denied(message)
+            }
+            Rejection::Unconfigured => Status::internal(message),
+        }
+    }
+}
+
+/// Attaches a bearer token to the `authorization` metadata of client
+/// requests.
+#[derive(Clone, Debug)]
+pub struct BearerInterceptor {
+    authorization: MetadataValue<Ascii>,
+}
+
+impl BearerInterceptor {
+    pub fn new(token: &str) -> Result<Self, InvalidMetadataValue> {
+        let mut authorization: MetadataValue<Ascii> = format!("Bearer {}", token).parse()?;
+        authorization.set_sensitive(true);
+        Ok(BearerInterceptor { authorization })
+    }
+}
+
+impl Interceptor for BearerInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION, self.authorization.clone());
+        Ok(request)
+    }
+}
+
+/// Verifies the bearer token of server requests and inserts the claims of
+/// type `T` into their extensions.
+pub struct JwtInterceptor<T = crate::Claims> {
+    auth: BearerAuth,
+    claims: PhantomData<fn() -> T>,
+}
+
+impl<T> JwtInterceptor<T> {
+    pub fn new(auth: BearerAuth) -> Self {
+        JwtInterceptor {
+            auth,
+            claims: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for JwtInterceptor<T> {
+    fn clone(&self) -> Self {
+        JwtInterceptor::new(self.auth.clone())
+    }
+}
+
+impl<T> Interceptor for JwtInterceptor<T>
+where
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let claims: T = self
+            .auth
+            .authenticate_request(&Metadata(request.metadata()))?;
+        request.extensions_mut().insert(claims);
+        Ok(request)
+    }
+}
+
+struct Metadata<'a>(&'a MetadataMap);
+
+impl RequestParts for Metadata<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::service::Interceptor;
+    use tonic::{Code, Request};
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::tonic::{BearerInterceptor, JwtInterceptor};
+    use crate::RegisteredClaims;
+
+    #[test]
+    pub fn interceptors() -> Result<(), Error> {
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let mut server = JwtInterceptor::<RegisteredClaims>::new(auth);
+
+        let mut client = BearerInterceptor::new(&token).unwrap();
+        let request = client.call(Request::new(())).unwrap();
+        let request = server.call(request).unwrap();
+        let claims = request.extensions().get::<RegisteredClaims>().unwrap();
+        assert_eq!(claims.subject.as_deref(), Some("someone"));
+
+        let status = server.call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let mut client = BearerInterceptor::new("a.b.c").unwrap();
+        let request = client.call(Request::new(())).unwrap();
+        let status = server.call(request).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! A [tower](https://docs.rs/tower) middleware for `http` requests, enabled
+//! with the `tower` feature, for hyper, tonic, axum and other tower based
+//! servers. [JwtLayer](struct.JwtLayer.html) verifies the bearer token of
+//! every request and inserts the claims into the request extensions. Requests
+//! without a valid token are answered with the status and `WWW-Authenticate`
+//! header of the [Rejection](../bearer/enum.Rejection.html) and an empty body.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::tower::JwtLayer;
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let layer = JwtLayer::<RegisteredClaims>::new(auth);
+//! // Handlers read the claims with `request.extensions().get::<RegisteredClaims>()`
+//! ```
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::header::WWW_AUTHENTICATE;
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode, Uri};
+use pin_project_lite::pin_project;
+use serde::de::DeserializeOwned;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::source::RequestParts;
+
+/// Wraps services in a [JwtService](struct.JwtService.html).
+pub struct JwtLayer<T = crate::Claims> {
+    auth: BearerAuth,
+    claims: PhantomData<fn() -> T>,
+}
+
+impl<T> JwtLayer<T> {
+    pub fn new(auth: BearerAuth) -> Self {
+        JwtLayer {
+            auth,
+            claims: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for JwtLayer<T> {
+    fn clone(&self) -> Self {
+        JwtLayer::new(self.auth.clone())
+    }
+}
+
+impl<S, T> Layer<S> for JwtLayer<T> {
+    type Service = JwtService<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JwtService {
+            inner,
+            auth: self.auth.clone(),
+            claims: PhantomData,
+        }
+    }
+}
+
+/// Verifies the bearer token of requests before passing them on with the
+/// claims of type `T` in their extensions.
+pub struct JwtService<S, T = crate::Claims> {
+    inner: S,
+    auth: BearerAuth,
+    claims: PhantomData<fn() -> T>,
+}
+
+impl<S: Clone, T> Clone for JwtService<S, T> {
+    fn clone(&self) -> Self {
+        JwtService {
+            inner: self.inner.clone(),
+            auth: self.auth.clone(),
+            claims: PhantomData,
+        }
+    }
+}
+
+impl<S, T, B, ResBody> Service<Request<B>> for JwtService<S, T>
+where
+    S: Service<Request<B>, Response = Response<ResBody>>,
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let parts = HttpParts(request.headers(), request.uri());
+        let claims = self.auth.authenticate_request::<T>(&parts);
+
+        match claims {
+            Ok(claims) => {
+                request.extensions_mut().insert(claims);
+                ResponseFuture::Inner {
+                    future: self.inner.call(request),
+                }
+            }
+            Err(rejection) => ResponseFuture::Rejected {
+                response: Some(rejection_response(&rejection)),
+            },
+        }
+    }
+}
+
+struct HttpParts<'a>(&'a HeaderMap, &'a Uri);
+
+impl RequestParts for HttpParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.1.query()
+    }
+}
+
+pin_project! {
+    /// The response future of [JwtService](struct.JwtService.html).
+    #[project = ResponseFutureProjection]
+    pub enum ResponseFuture<F, ResBody> {
+        Inner { #[pin] future: F },
+        Rejected { response: Option<Response<ResBody>> },
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F, ResBody>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<ResBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            ResponseFutureProjection::Inner { future } => future.poll(cx),
+            ResponseFutureProjection::Rejected { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+/// An empty response with the status and `WWW-Authenticate` header of the
+/// rejection.
+pub fn rejection_response<B: Default>(rejection: &Rejection) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() =
+        StatusCode::from_u16(rejection.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(challenge) = rejection.www_authenticate() {
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::future::{ready, Future, Ready};
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+    use http::{Request, Response, StatusCode};
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::token::signed::SignWithKey;
+    use crate::tower::JwtLayer;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    struct Whoami;
+
+    impl Service<Request<()>> for Whoami {
+        type Response = Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let claims = request.extensions().get::<RegisteredClaims>().unwrap();
+            ready(Ok(Response::new(
+                claims.subject.clone().unwrap_or_default(),
+            )))
+        }
+    }
+
+    #[test]
+    pub fn layer() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let mut service = JwtLayer::<RegisteredClaims>::new(auth).layer(Whoami);
+
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let request = Request::get("/")
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(())
+            .unwrap();
+        let response = block_on(service.call(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "someone");
+
+        let request = Request::get("/").body(()).unwrap();
+        let response = block_on(service.call(request)).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        assert!(response.body().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+//! Spans and events through [tracing](https://docs.rs/tracing) with the
+//! `tracing` feature. Without it, the macros expand to nothing, so their
+//! arguments are never evaluated. Fields are limited to key ids, algorithms
+//! and errors; key material is never recorded.
+
+/// Enter a debug span until the end of the enclosing block.
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+macro_rules! trace_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+// Only used by modules behind other features
+#[allow(unused_macros)]
+macro_rules! trace_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    };
+}
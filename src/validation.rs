@@ -0,0 +1,413 @@
+//! Checks of the registered claims of verified tokens: the issuer, the
+//! audience and the time the token is valid for, with some leeway for clocks
+//! that are not in sync.
+//!
+//! With the `json-schema` feature, a [ClaimsSchema](struct.ClaimsSchema.html)
+//! also checks the structure of the claims, e.g. the types of private claims,
+//! their allowed values and the members of nested objects.
+//! ## Examples
+//! ```
+//! use jwt::validation::Validation;
+//! use serde_json::json;
+//!
+//! let validation = Validation::new()
+//!     .issuer("https://idp.example.com")
+//!     .audience("orders")
+//!     .leeway(30);
+//!
+//! let claims = json!({"iss": "https://idp.example.com", "aud": ["orders", "billing"], "exp": 1000});
+//! assert!(validation.validate(&claims, 1020).is_ok());
+//! assert!(validation.validate(&claims, 1030).is_err());
+//! ```
+
+use alloc::string::String;
+#[cfg(feature = "json-schema")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "json-schema")]
+use core::fmt;
+use core::time::Duration;
+
+use serde_json::Value;
+
+use crate::claims::{JwtClaims, SecondsSinceEpoch};
+use crate::clock::Clock;
+use crate::error::Error;
+
+/// The expected values of the registered claims. By default only `exp` and
+/// `nbf` are checked, if present.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Validation {
+    issuer: Option<String>,
+    audiences: Vec<String>,
+    leeway: SecondsSinceEpoch,
+    require_expiration: bool,
+    #[cfg(feature = "json-schema")]
+    schema: Option<ClaimsSchema>,
+}
+
+impl Validation {
+    pub fn new() -> Self {
+        Validation::default()
+    }
+
+    /// Require the `iss` claim to be this issuer.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Accept tokens for this audience. Once an audience is set, the `aud`
+    /// claim must contain one of the accepted audiences.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Accept tokens this many seconds after they expired or before they
+    /// became valid.
+    pub fn leeway(mut self, seconds: SecondsSinceEpoch) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Reject tokens without an `exp` claim.
+    pub fn require_expiration(mut self, required: bool) -> Self {
+        self.require_expiration = required;
+        self
+    }
+
+    /// Require the claims to match a JSON Schema.
+    #[cfg(feature = "json-schema")]
+    pub fn schema(mut self, schema: ClaimsSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn expected_issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    /// Check the claims of a token at the given time.
+    pub fn validate(&self, claims: &Value, now: SecondsSinceEpoch) -> Result<(), Error> {
+        if let Some(ref issuer) = self.issuer {
+            match claims.get("iss") {
+                Some(Value::String(iss)) if iss == issuer => {}
+                Some(_) => return Err(Error::InvalidClaim("iss")),
+                None => return Err(Error::MissingClaim("iss")),
+            }
+        }
+
+        if !self.audiences.is_empty() {
+            let accepted = |aud: &Value| aud.as_str().is_some_and(|aud| self.accepts(aud));
+            match claims.get("aud") {
+                Some(Value::Array(auds)) if auds.iter().any(accepted) => {}
+                Some(aud) if accepted(aud) => {}
+                Some(_) => return Err(Error::InvalidClaim("aud")),
+                None => return Err(Error::MissingClaim("aud")),
+            }
+        }
+
+        let expiration = time_claim(claims, "exp")?;
+        self.check_time(expiration, time_claim(claims, "nbf")?, now)?;
+
+        #[cfg(feature = "json-schema")]
+        if let Some(ref schema) = self.schema {
+            schema.check(claims)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check the claims of a token at the time of a clock.
+    pub fn validate_with(&self, claims: &Value, clock: &impl Clock) -> Result<(), Error> {
+        self.validate(claims, clock.now())
+    }
+
+    /// Check claims of any type through their
+    /// [JwtClaims](../claims/trait.JwtClaims.html) at the given time. A
+    /// schema needs the claims as JSON, so a validation with one rejects the
+    /// claims instead of skipping it.
+    pub fn validate_claims(
+        &self,
+        claims: &impl JwtClaims,
+        now: SecondsSinceEpoch,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "json-schema")]
+        if self.schema.is_some() {
+            return Err(Error::ClaimsSchemaMismatch(
+                "claims that are not JSON can not be checked against a schema".into(),
+            ));
+        }
+
+        if let Some(ref issuer) = self.issuer {
+            match claims.issuer() {
+                Some(iss) if iss == issuer => {}
+                Some(_) => return Err(Error::InvalidClaim("iss")),
+                None => return Err(Error::MissingClaim("iss")),
+            }
+        }
+
+        if !self.audiences.is_empty() {
+            match claims.audience() {
+                [] => return Err(Error::MissingClaim("aud")),
+                auds if auds.iter().any(|aud| self.accepts(aud)) => {}
+                _ => return Err(Error::InvalidClaim("aud")),
+            }
+        }
+
+        self.check_time(claims.expiration(), claims.not_before(), now)
+    }
+
+    /// Like [validate_claims](#method.validate_claims), at the time of a
+    /// clock.
+    pub fn validate_claims_with(
+        &self,
+        claims: &impl JwtClaims,
+        clock: &impl Clock,
+    ) -> Result<(), Error> {
+        self.validate_claims(claims, clock.now())
+    }
+
+    fn accepts(&self, audience: &str) -> bool {
+        self.audiences.iter().any(|a| a == audience)
+    }
+
+    fn check_time(
+        &self,
+        expiration: Option<SecondsSinceEpoch>,
+        not_before: Option<SecondsSinceEpoch>,
+        now: SecondsSinceEpoch,
+    ) -> Result<(), Error> {
+        match expiration {
+            Some(expiration) if now >= expiration.saturating_add(self.leeway) => {
+                let by = Duration::from_secs(now - expiration);
+                return Err(Error::Expired { by });
+            }
+            None if self.require_expiration => return Err(Error::MissingClaim("exp")),
+            _ => {}
+        }
+
+        if let Some(not_before) = not_before {
+            if now.saturating_add(self.leeway) < not_before {
+                let by = Duration::from_secs(not_before - now);
+                return Err(Error::NotYetValid { by });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A compiled JSON Schema for claims. Schemas can only refer to their own
+/// definitions, not to other documents.
+#[cfg(feature = "json-schema")]
+#[derive(Clone)]
+pub struct ClaimsSchema {
+    schema: Value,
+    validator: Arc<jsonschema::Validator>,
+}
+
+#[cfg(feature = "json-schema")]
+impl ClaimsSchema {
+    pub fn new(schema: Value) -> Result<Self, Error> {
+        let validator =
+            jsonschema::validator_for(&schema).map_err(|e| Error::InvalidSchema(e.to_string()))?;
+        Ok(ClaimsSchema {
+            schema,
+            validator: Arc::new(validator),
+        })
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// The error names where the claims differ from the schema, but not the
+    /// values of the claims.
+    fn check(&self, claims: &Value) -> Result<(), Error> {
+        self.validator.validate(claims).map_err(|e| {
+            let path = e.instance_path().to_string();
+            Error::ClaimsSchemaMismatch(match path.is_empty() {
+                true => e.masked().to_string(),
+                false => alloc::format!("{} at {}", e.masked(), path),
+            })
+        })
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl fmt::Debug for ClaimsSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ClaimsSchema").field(&self.schema).finish()
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl PartialEq for ClaimsSchema {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema == other.schema
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl Eq for ClaimsSchema {}
+
+fn time_claim(claims: &Value, name: &'static str) -> Result<Option<SecondsSinceEpoch>, Error> {
+    match claims.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or(Error::InvalidClaim(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::secevent::SecurityEventClaims;
+    use crate::validation::Validation;
+
+    #[test]
+    pub fn issuer_and_audience() {
+        let validation = Validation::new().issuer("idp").audience("a").audience("b");
+        assert!(validation
+            .validate(&json!({"iss": "idp", "aud": "b"}), 0)
+            .is_ok());
+        assert!(validation
+            .validate(&json!({"iss": "idp", "aud": ["c", "a"]}), 0)
+            .is_ok());
+        assert!(matches!(
+            validation.validate(&json!({"iss": "other", "aud": "a"}), 0),
+            Err(Error::InvalidClaim("iss"))
+        ));
+        assert!(matches!(
+            validation.validate(&json!({"iss": "idp", "aud": ["c"]}), 0),
+            Err(Error::InvalidClaim("aud"))
+        ));
+        assert!(matches!(
+            validation.validate(&json!({"iss": "idp"}), 0),
+            Err(Error::MissingClaim("aud"))
+        ));
+    }
+
+    #[test]
+    pub fn time() {
+        let claims = json!({"exp": 100, "nbf": 50});
+        assert!(Validation::new().validate(&claims, 75).is_ok());
+        assert!(matches!(
+            Validation::new().validate(&claims, 100),
+            Err(Error::Expired { .. })
+        ));
+        assert!(matches!(
+            Validation::new().validate(&claims, 49),
+            Err(Error::NotYetValid { .. })
+        ));
+
+        let lenient = Validation::new().leeway(10);
+        assert!(lenient.validate(&claims, 109).is_ok());
+        assert!(lenient.validate(&claims, 40).is_ok());
+        assert!(lenient.validate(&claims, 110).is_err());
+
+        let strict = Validation::new().require_expiration(true);
+        assert!(matches!(
+            strict.validate(&json!({}), 0),
+            Err(Error::MissingClaim("exp"))
+        ));
+        assert!(matches!(
+            strict.validate(&json!({"exp": "soon"}), 0),
+            Err(Error::InvalidClaim("exp"))
+        ));
+    }
+
+    #[test]
+    pub fn typed_claims() {
+        let mut claims = RegisteredClaims {
+            issuer: Some("idp".into()),
+            audience: Some("a".into()),
+            expiration: Some(100),
+            ..Default::default()
+        };
+        let validation = Validation::new().issuer("idp").audience("a");
+        assert!(validation.validate_claims(&claims, 50).is_ok());
+        assert!(matches!(
+            validation.validate_claims(&claims, 100),
+            Err(Error::Expired { .. })
+        ));
+        assert!(matches!(
+            Validation::new().audience("b").validate_claims(&claims, 50),
+            Err(Error::InvalidClaim("aud"))
+        ));
+
+        claims.issuer = None;
+        assert!(matches!(
+            validation.validate_claims(&Claims::new(claims), 50),
+            Err(Error::MissingClaim("iss"))
+        ));
+
+        let event = SecurityEventClaims::new("idp", "1", 0);
+        assert!(matches!(
+            validation.validate_claims(&event, 50),
+            Err(Error::MissingClaim("aud"))
+        ));
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    pub fn schema() -> Result<(), Error> {
+        use crate::validation::ClaimsSchema;
+
+        let schema = ClaimsSchema::new(json!({
+            "type": "object",
+            "required": ["sub", "roles", "tenant"],
+            "properties": {
+                "sub": {"type": "string"},
+                "roles": {"type": "array", "items": {"enum": ["admin", "user"]}},
+                "tenant": {
+                    "type": "object",
+                    "required": ["id"],
+                    "properties": {"id": {"type": "integer"}}
+                }
+            }
+        }))?;
+        let validation = Validation::new().schema(schema);
+
+        let claims = json!({"sub": "someone", "roles": ["user"], "tenant": {"id": 7}, "exp": 10});
+        validation.validate(&claims, 0)?;
+        // The registered claims are checked first
+        assert!(matches!(
+            validation.validate(&claims, 10),
+            Err(Error::Expired { .. })
+        ));
+
+        let invalid = [
+            json!({"sub": 1, "roles": [], "tenant": {"id": 7}}),
+            json!({"sub": "someone", "roles": ["root"], "tenant": {"id": 7}}),
+            json!({"sub": "someone", "roles": [], "tenant": {}}),
+            json!({"sub": "someone", "roles": []}),
+        ];
+        for claims in invalid.iter() {
+            assert!(matches!(
+                validation.validate(claims, 0),
+                Err(Error::ClaimsSchemaMismatch(_))
+            ));
+        }
+        match validation.validate(
+            &json!({"sub": "secret", "roles": [], "tenant": {"id": "x"}}),
+            0,
+        ) {
+            Err(Error::ClaimsSchemaMismatch(reason)) => {
+                assert!(reason.ends_with("/tenant/id"), "{}", reason);
+                assert!(!reason.contains("\"x\""), "{}", reason);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        assert!(matches!(
+            ClaimsSchema::new(json!({"type": "nothing"})),
+            Err(Error::InvalidSchema(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Integration with [warp](https://docs.rs/warp), enabled with the `warp`
+//! feature. The [claims](fn.claims.html) filter verifies the bearer token and
+//! extracts its claims, and [recover](fn.recover.html) turns its rejections
+//! into `401 Unauthorized` and `403 Forbidden` responses with a
+//! `WWW-Authenticate` header. [authorized](fn.authorized.html) also checks
+//! the claims against a [policy](../policy/index.html).
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::AnyVerifier;
+//! use jwt::bearer::BearerAuth;
+//! use jwt::{AlgorithmType, RegisteredClaims};
+//! use warp::Filter;
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let whoami = warp::path("whoami")
+//!     .and(jwt::warp::claims(auth))
+//!     .map(|claims: RegisteredClaims| claims.subject.unwrap_or_default())
+//!     .recover(jwt::warp::recover);
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use warp::http::header::{CONTENT_TYPE, WWW_AUTHENTICATE};
+use warp::http::{HeaderMap, HeaderValue, StatusCode};
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::error::Error;
+use crate::policy::Policy;
+use crate::source::RequestParts;
+
+impl warp::reject::Reject for Rejection {}
+
+/// Verify the bearer token of the request and extract its claims.
+pub fn claims<T>(auth: BearerAuth) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+    warp::header::headers_cloned()
+        .and(query)
+        .and_then(move |headers: HeaderMap, query: String| {
+            let claims = auth.authenticate_request(&WarpParts(&headers, &query));
+            async move { claims.map_err(warp::reject::custom) }
+        })
+}
+
+struct WarpParts<'a>(&'a HeaderMap, &'a str);
+
+impl RequestParts for WarpParts<'_> {
+    fn header(&self, name: &str) -> Result<Option<&str>, Rejection> {
+        self.0
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Rejection::InvalidRequest))
+            .transpose()
+    }
+
+    fn query(&self) -> Option<&str> {
+        Some(self.1).filter(|query| !query.is_empty())
+    }
+}
+
+/// Like [claims](fn.claims.html), but reject tokens whose claims don't
+/// satisfy the predicate with `403 Forbidden`.
+pub fn claims_where<T, F>(
+    auth: BearerAuth,
+    predicate: F,
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Send + 'static,
+    F: Fn(&T) -> bool + Clone + Send + Sync + 'static,
+{
+    claims(auth).and_then(move |claims: T| {
+        let allowed = predicate(&claims);
+        async move {
+            if allowed {
+                Ok(claims)
+            } else {
+                Err(warp::reject::custom(Rejection::InsufficientScope))
+            }
+        }
+    })
+}
+
+/// Like [claims](fn.claims.html), but reject tokens whose claims don't
+/// satisfy the policy with `403 Forbidden` and a JSON body.
+pub fn authorized<T>(
+    auth: BearerAuth,
+    policy: Policy,
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    claims(auth).and_then(move |claims: Value| {
+        let authorized = policy
+            .check(&claims)
+            .map_err(Rejection::from)
+            .and_then(|_| {
+                serde_json::from_value(claims).map_err(|e| Rejection::InvalidToken(Error::Json(e)))
+            });
+        async move { authorized.map_err(warp::reject::custom) }
+    })
+}
+
+/// Respond to rejections of the filters of this module. Other rejections are
+/// passed on.
+pub async fn recover(rejection: warp::Rejection) -> Result<Response, warp::Rejection> {
+    let rejection = match rejection.find::<Rejection>() {
+        Some(rejection) => rejection,
+        None => return Err(rejection),
+    };
+
+    let status =
+        StatusCode::from_u16(rejection.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut response = match rejection.body() {
+        Some(body) => {
+            let mut response = body.into_response();
+            *response.status_mut() = status;
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            response
+        }
+        None => status.into_response(),
+    };
+    if let Some(challenge) = rejection.www_authenticate() {
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::BearerAuth;
+    use crate::error::Error;
+    use crate::policy::require_role;
+    use crate::token::signed::SignWithKey;
+    use crate::RegisteredClaims;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    pub fn filters() -> Result<(), Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token = claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)?;
+        let authorization = format!("Bearer {}", token);
+
+        let whoami = crate::warp::claims(auth.clone())
+            .map(|claims: RegisteredClaims| claims.subject.unwrap_or_default())
+            .recover(crate::warp::recover);
+        let admin = crate::warp::claims_where(auth.clone(), |claims: &RegisteredClaims| {
+            claims.subject.as_deref() == Some("admin")
+        })
+        .map(|_| "welcome")
+        .recover(crate::warp::recover);
+        let orders = crate::warp::authorized(auth, require_role("admin"))
+            .map(|_: RegisteredClaims| "created")
+            .recover(crate::warp::recover);
+
+        block_on(async {
+            let response = warp::test::request()
+                .header("authorization", &authorization)
+                .reply(&whoami)
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.body(), "someone");
+
+            let response = warp::test::request().reply(&whoami).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+            let response = warp::test::request()
+                .header("authorization", &authorization)
+                .reply(&admin)
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                response.headers()["www-authenticate"],
+                r#"Bearer error="insufficient_scope""#
+            );
+
+            let response = warp::test::request()
+                .header("authorization", &authorization)
+                .reply(&orders)
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(response.headers()["content-type"], "application/json");
+            let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(body["required"], serde_json::json!({"role": "admin"}));
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+//! Authentication of WebSocket upgrade requests, independent of any web
+//! framework. Browsers can't set the `Authorization` header of a WebSocket
+//! handshake, so the token is sent as a `Sec-WebSocket-Protocol` entry,
+//! like `bearer.<token>`, or as a query parameter, like `?access_token=<token>`.
+//! Connections outlive their tokens, so the [Session](struct.Session.html)
+//! of a connection is checked again while it is open and can be renewed with
+//! a new token sent over the socket.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::any::{AnySigner, AnyVerifier};
+//! use jwt::bearer::BearerAuth;
+//! use jwt::clock::SystemClock;
+//! use jwt::websocket::HandshakeAuth;
+//! use jwt::{AlgorithmType, RegisteredClaims, SignWithKey};
+//!
+//! let token = RegisteredClaims::default()
+//!     .sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret").unwrap())
+//!     .unwrap();
+//!
+//! let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret").unwrap());
+//! let handshake = HandshakeAuth::new(auth);
+//! let protocols = format!("chat, bearer.{}", token);
+//! let session = handshake
+//!     .authenticate::<RegisteredClaims>(Some(&protocols), None)
+//!     .unwrap();
+//! // Echo the selected protocol in the response, never the token
+//! assert_eq!(session.protocol(), Some("chat"));
+//! assert!(session.check_with(&SystemClock).is_ok());
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::bearer::{BearerAuth, Rejection};
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::clock::Clock;
+use crate::error::Error;
+use crate::source::query_value;
+
+/// The `Sec-WebSocket-Protocol` entries holding a token start with this by
+/// default.
+pub const DEFAULT_PROTOCOL_PREFIX: &str = "bearer.";
+/// The query parameter holding a token by default.
+pub const DEFAULT_QUERY_PARAMETER: &str = "access_token";
+
+/// The claims a session keeps track of, whatever type the claims are
+/// deserialized to.
+#[derive(Clone, Debug, Deserialize)]
+struct SessionClaims {
+    sub: Option<String>,
+    exp: Option<SecondsSinceEpoch>,
+}
+
+/// Verifies the token of WebSocket upgrade requests.
+#[derive(Clone)]
+pub struct HandshakeAuth {
+    auth: BearerAuth,
+    protocol_prefix: String,
+    query_parameter: String,
+}
+
+impl HandshakeAuth {
+    pub fn new(auth: BearerAuth) -> Self {
+        HandshakeAuth {
+            auth,
+            protocol_prefix: DEFAULT_PROTOCOL_PREFIX.to_owned(),
+            query_parameter: DEFAULT_QUERY_PARAMETER.to_owned(),
+        }
+    }
+
+    /// Look for the `Sec-WebSocket-Protocol` entry that starts with this,
+    /// instead of [DEFAULT_PROTOCOL_PREFIX](constant.DEFAULT_PROTOCOL_PREFIX.html).
+    pub fn protocol_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.protocol_prefix = prefix.into();
+        self
+    }
+
+    /// Look for this query parameter, instead of
+    /// [DEFAULT_QUERY_PARAMETER](constant.DEFAULT_QUERY_PARAMETER.html).
+    pub fn query_parameter(mut self, name: impl Into<String>) -> Self {
+        self.query_parameter = name.into();
+        self
+    }
+
+    /// Authenticate an upgrade request by the value of its
+    /// `Sec-WebSocket-Protocol` header and its query string, without the `?`.
+    /// A token in the protocols takes precedence.
+    pub fn authenticate<C: DeserializeOwned>(
+        &self,
+        protocols: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Session<C>, Rejection> {
+        let mut token = None;
+        let mut protocol = None;
+        for entry in protocols.into_iter().flat_map(|p| p.split(',')) {
+            let entry = entry.trim();
+            match entry.strip_prefix(self.protocol_prefix.as_str()) {
+                Some(value) if token.is_none() => token = Some(value),
+                Some(_) => return Err(Rejection::InvalidRequest),
+                None if protocol.is_none() && !entry.is_empty() => protocol = Some(entry),
+                None => {}
+            }
+        }
+
+        let token = match token {
+            Some(token) => token,
+            None => query_value(query, &self.query_parameter)?.ok_or(Rejection::Missing)?,
+        };
+        if token.is_empty() {
+            return Err(Rejection::InvalidRequest);
+        }
+
+        let (claims, tracked) = self.verify(token)?;
+        Ok(Session {
+            claims,
+            tracked,
+            protocol: protocol.map(str::to_owned),
+        })
+    }
+
+    /// Renew an open session with a new token sent over the socket. The
+    /// token must be for the same subject as the session.
+    pub fn renew<C: DeserializeOwned>(
+        &self,
+        session: &mut Session<C>,
+        token: &str,
+    ) -> Result<(), Rejection> {
+        let (claims, tracked) = self.verify(token)?;
+        if tracked.sub != session.tracked.sub {
+            return Err(Rejection::InsufficientScope);
+        }
+        session.claims = claims;
+        session.tracked = tracked;
+        Ok(())
+    }
+
+    fn verify<C: DeserializeOwned>(&self, token: &str) -> Result<(C, SessionClaims), Rejection> {
+        let claims: Value = self.auth.authenticate_token(token)?;
+        let tracked = serde_json::from_value(claims.clone()).map_err(Error::Json)?;
+        let claims = serde_json::from_value(claims).map_err(Error::Json)?;
+        Ok((claims, tracked))
+    }
+}
+
+/// The verified claims of an open connection.
+#[derive(Clone, Debug)]
+pub struct Session<C> {
+    claims: C,
+    tracked: SessionClaims,
+    protocol: Option<String>,
+}
+
+impl<C> Session<C> {
+    pub fn claims(&self) -> &C {
+        &self.claims
+    }
+
+    /// The first requested protocol that does not hold the token, to select
+    /// in the handshake response.
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// When the token of the session expires, if it does.
+    pub fn expiration(&self) -> Option<SecondsSinceEpoch> {
+        self.tracked.exp
+    }
+
+    /// Check that the token of the session has not expired, e.g. before
+    /// handling a message or on a timer set to the
+    /// [expiration](#method.expiration).
+    pub fn check_with(&self, clock: &impl Clock) -> Result<(), Rejection> {
+        let registered = RegisteredClaims {
+            expiration: self.tracked.exp,
+            ..Default::default()
+        };
+        Ok(registered.check_time_with(clock)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::algorithm::any::{AnySigner, AnyVerifier};
+    use crate::algorithm::AlgorithmType;
+    use crate::bearer::{BearerAuth, Rejection};
+    use crate::error::{Error, ErrorKind};
+    use crate::token::signed::SignWithKey;
+    use crate::websocket::HandshakeAuth;
+    use crate::RegisteredClaims;
+
+    fn handshake() -> Result<HandshakeAuth, Error> {
+        let auth = BearerAuth::new(AnyVerifier::hmac(AlgorithmType::Hs256, b"secret")?);
+        Ok(HandshakeAuth::new(auth))
+    }
+
+    fn token(claims: serde_json::Value) -> Result<String, Error> {
+        claims.sign_with_key(&AnySigner::hmac(AlgorithmType::Hs256, b"secret")?)
+    }
+
+    #[test]
+    pub fn token_locations() -> Result<(), Error> {
+        let handshake = handshake()?;
+        let token = token(json!({ "sub": "someone" }))?;
+
+        let protocols = format!("bearer.{}, chat", token);
+        let session = handshake
+            .authenticate::<RegisteredClaims>(Some(&protocols), None)
+            .unwrap();
+        assert_eq!(session.claims().subject.as_deref(), Some("someone"));
+        assert_eq!(session.protocol(), Some("chat"));
+
+        let query = format!("room=1&access_token={}", token);
+        let session = handshake
+            .authenticate::<RegisteredClaims>(None, Some(&query))
+            .unwrap();
+        assert_eq!(session.protocol(), None);
+
+        let query = format!("access_token={0}&access_token={0}", token);
+        assert!(matches!(
+            handshake.authenticate::<RegisteredClaims>(None, Some(&query)),
+            Err(Rejection::InvalidRequest)
+        ));
+        assert!(matches!(
+            handshake.authenticate::<RegisteredClaims>(Some("chat"), Some("room=1")),
+            Err(Rejection::Missing)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn revalidation() -> Result<(), Error> {
+        let handshake = handshake()?.query_parameter("token");
+        let query = format!(
+            "token={}",
+            token(json!({ "sub": "someone", "exp": 4_000_000_000u64 }))?
+        );
+        let mut session = handshake
+            .authenticate::<RegisteredClaims>(None, Some(&query))
+            .unwrap();
+
+        assert_eq!(session.expiration(), Some(4_000_000_000));
+        assert!(session.check_with(&|| 3_999_999_999).is_ok());
+        match session.check_with(&|| 4_000_000_000) {
+            Err(Rejection::InvalidToken(e)) => assert_eq!(e.kind(), ErrorKind::Expired),
+            _ => panic!("the session should have expired"),
+        }
+
+        let renewed = token(json!({ "sub": "someone", "exp": 4_000_000_100u64 }))?;
+        handshake.renew(&mut session, &renewed).unwrap();
+        assert!(session.check_with(&|| 4_000_000_000).is_ok());
+
+        let other = token(json!({ "sub": "someone else" }))?;
+        assert!(matches!(
+            handshake.renew(&mut session, &other),
+            Err(Rejection::InsufficientScope)
+        ));
+        Ok(())
+    }
+}
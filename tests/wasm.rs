@@ -0,0 +1,31 @@
+//! Run with `wasm-pack test --node -- --features js`.
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, KeyInit};
+use jwt::clock::{Clock, SystemClock};
+use jwt::{Claims, Error, RegisteredClaims, SignWithKey, VerifyWithKey};
+use sha2::Sha256;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn sign_and_verify() -> Result<(), Error> {
+    let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret")?;
+    let mut claims = BTreeMap::new();
+    claims.insert("sub", "someone");
+
+    let token = claims.sign_with_key(&key)?;
+    let verified: BTreeMap<String, String> = token.as_str().verify_with_key(&key)?;
+    assert_eq!(verified["sub"], "someone");
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn check_time_with_system_clock() -> Result<(), Error> {
+    let claims = Claims::new(RegisteredClaims {
+        expiration: Some(SystemClock.now() + 60),
+        ..Default::default()
+    });
+    claims.registered.check_time_with(&SystemClock)
+}